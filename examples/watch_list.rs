@@ -1,5 +1,5 @@
 use kite_connect::quotes::{Instrument, Ohlc};
-use kite_connect::ws::{KiteTicker, Req, Ticker};
+use kite_connect::ws::{KiteTicker, ReconnectPolicy, Req, Ticker};
 use kite_connect::{AutoAuth, KiteConnect};
 use ratatui::crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode};
 use ratatui::crossterm::execute;
@@ -30,6 +30,7 @@ struct App {
     search_results: Vec<Instrument>,
     search_cursor_position: usize,
     kt: KiteTicker,
+    connected: bool,
 }
 
 impl App {
@@ -84,7 +85,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .get_exhchange_instruments(kite_connect::orders::Exchange::NSE)
         .await?;
 
-    let (kt, rx) = kc.web_socket().await?;
+    let (mut kt, rx) = kc
+        .web_socket_with_reconnect(ReconnectPolicy::default())
+        .await?;
+    kt.set_ping_interval(Some(Duration::from_secs(30)));
 
     let mut app = App {
         screen: Screen::Search,
@@ -95,6 +99,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         search_results: Vec::with_capacity(5),
         search_cursor_position: 0,
         kt,
+        connected: true,
     };
 
     enable_raw_mode()?;
@@ -144,6 +149,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         instrument.ohlc = indices_quote.ohlc;
                     }
                 }
+                Ticker::ConnectionClosed => app.connected = false,
+                // The watched instruments were subscribed to again
+                Ticker::Reconnected => app.connected = true,
                 _ => {}
             }
         }
@@ -226,9 +234,19 @@ fn draw_watchlist_ui(f: &mut Frame, app: &App) {
     f.render_widget(watchlist_list, chunks[0]);
 
     // --- Footer ---
-    let footer_text = "Press 'q' to quit, '/' to search and add instrument.";
+    let (footer_text, footer_color) = if app.connected {
+        (
+            "Press 'q' to quit, '/' to search and add instrument.",
+            Color::Yellow,
+        )
+    } else {
+        (
+            "Disconnected, reconnecting... Press 'q' to quit.",
+            Color::Red,
+        )
+    };
     let footer = Paragraph::new(footer_text)
-        .style(Style::default().fg(Color::Yellow))
+        .style(Style::default().fg(footer_color))
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(footer, chunks[1]);
 }
@@ -262,7 +280,7 @@ fn draw_search_ui(f: &mut Frame, app: &App) {
     let results: Vec<_> = app
         .search_results
         .iter()
-        .map(|i| ListItem::new(format!("{} ({})", i.name, i.trading_symbol)))
+        .map(|i| ListItem::new(format!("{} ({}:{})", i.name, i.exchange, i.trading_symbol)))
         .collect();
 
     let result_list = List::new(results)
@@ -1,3 +1,4 @@
+use futures_util::StreamExt;
 use kite_connect::{AutoAuth, KiteConnect, ws::Req};
 use std::env;
 
@@ -21,8 +22,8 @@ async fn main() {
         kc
     };
 
-    let (mut kt, rx) = kc
-        .web_socket()
+    let (mut kt, mut ticks) = kc
+        .web_socket_stream()
         .await
         .expect("Failed to create WebSocket Connection");
 
@@ -42,7 +43,7 @@ async fn main() {
     .await
     .unwrap();
 
-    while let Ok(packet) = rx.recv() {
+    while let Some(packet) = ticks.next().await {
         println!("{:?}", packet);
     }
 }
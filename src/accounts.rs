@@ -0,0 +1,228 @@
+//! Several Kite accounts side by side, e.g. for a family or a fund trading from a few accounts.
+//!
+//! [`AccountManager`] holds an authenticated client per account, under a name of the caller's
+//! choosing. Requests to the accounts are sent concurrently and each client keeps its own
+//! connection and rate limits, so a slow or failing account doesn't hold back the others. The
+//! result of every account is returned separately, an error on one account doesn't fail the
+//! others.
+
+use futures_util::future::join_all;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::orders::{Exchange, PlaceOrderRequest, PlacedOrder, Product};
+use crate::portfolio::Positions;
+use crate::user::funds::TotalFunds;
+use crate::{Authenticated, Error, KiteConnect};
+
+/// Result of a request for every account, by account name.
+pub type AccountResults<T> = BTreeMap<String, Result<T, Error>>;
+
+/// Named authenticated clients, see the [module documentation](self).
+#[derive(Default)]
+pub struct AccountManager {
+    accounts: BTreeMap<String, KiteConnect<Authenticated>>,
+}
+
+impl AccountManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_account(
+        mut self,
+        name: impl Into<String>,
+        kite: KiteConnect<Authenticated>,
+    ) -> Self {
+        self.insert(name, kite);
+        self
+    }
+
+    /// Adds an account, returning the client it replaces if `name` was already taken.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        kite: KiteConnect<Authenticated>,
+    ) -> Option<KiteConnect<Authenticated>> {
+        self.accounts.insert(name.into(), kite)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<KiteConnect<Authenticated>> {
+        self.accounts.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&KiteConnect<Authenticated>> {
+        self.accounts.get(name)
+    }
+
+    /// Names of the accounts, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    /// Places `req` on each account of `allocations`, with its quantity multiplied by the
+    /// multiplier of the account, and returns the placed orders.
+    ///
+    /// Each placed order carries the [`idempotency_key`](PlaceOrderRequest::idempotency_key) of
+    /// `req`, if set, so a placement that timed out on an account can be retried there with
+    /// [`KiteConnect::place_order_idempotent`].
+    ///
+    /// Quantities are rounded down, pick multipliers that keep them multiples of the lot size. An
+    /// iceberg order is split again over the same number of legs. An unknown account, or a
+    /// quantity rounded down to zero, is a
+    /// [`KiteError::InputException`](crate::KiteError::InputException) for that account only.
+    ///
+    /// An account allocated more than once is an `InputException` for the whole call, before any
+    /// order is placed, as the results are keyed by account.
+    pub async fn place_order(
+        &self,
+        req: &PlaceOrderRequest,
+        allocations: &[(&str, f64)],
+    ) -> Result<AccountResults<PlacedOrder>, Error> {
+        let mut names = BTreeSet::new();
+        if let Some((name, _)) = allocations.iter().find(|(name, _)| !names.insert(*name)) {
+            return Err(Error::input_exception(format!(
+                "Account {name} is allocated more than once"
+            )));
+        }
+
+        let orders = allocations.iter().map(|&(name, multiplier)| async move {
+            let res = match (self.accounts.get(name), scale(req, multiplier)) {
+                (None, _) => Err(Error::input_exception(format!("Unknown account {name}"))),
+                (_, Err(err)) => Err(err),
+                (Some(kite), Ok(req)) => kite.place_order_poll(&req).await,
+            };
+            (name.to_owned(), res)
+        });
+
+        Ok(join_all(orders).await.into_iter().collect())
+    }
+
+    /// Returns the positions of every account.
+    pub async fn get_positions(&self) -> AccountResults<Positions> {
+        let positions = self
+            .accounts
+            .iter()
+            .map(|(name, kite)| async move { (name.clone(), kite.get_positions().await) });
+
+        join_all(positions).await.into_iter().collect()
+    }
+
+    /// Returns the funds of every account.
+    pub async fn get_funds(&self) -> AccountResults<TotalFunds> {
+        let funds = self
+            .accounts
+            .iter()
+            .map(|(name, kite)| async move { (name.clone(), kite.get_funds().await) });
+
+        join_all(funds).await.into_iter().collect()
+    }
+}
+
+fn scale(req: &PlaceOrderRequest, multiplier: f64) -> Result<PlaceOrderRequest, Error> {
+    let quantity = (req.quantity as f64 * multiplier).floor();
+    if !(quantity >= 1.0 && quantity <= u32::MAX as f64) {
+        return Err(Error::input_exception(format!(
+            "Quantity {} times {multiplier} isn't a valid quantity",
+            req.quantity
+        )));
+    }
+
+    let quantity = quantity as u32;
+    Ok(PlaceOrderRequest {
+        quantity,
+        iceberg_quantity: req.iceberg_legs.map(|legs| quantity / legs.max(1)),
+        ..req.clone()
+    })
+}
+
+/// Net position of an instrument and product over several accounts, see [`combine_positions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombinedPosition {
+    pub exchange: Exchange,
+    pub trading_symbol: String,
+    pub product: Product,
+    pub quantity: i64,
+    pub value: f64,
+    pub pnl: f64,
+    pub m2m: f64,
+    /// Accounts holding the position, flat ones included
+    pub accounts: Vec<String>,
+}
+
+/// Sums the net positions of the accounts whose request succeeded, by instrument and product, in
+/// the order they're first seen.
+pub fn combine_positions(results: &AccountResults<Positions>) -> Vec<CombinedPosition> {
+    let mut combined: Vec<CombinedPosition> = Vec::new();
+
+    for (name, positions) in results {
+        let Ok(positions) = positions else {
+            continue;
+        };
+
+        for position in &positions.net {
+            let index = match combined.iter().position(|c| {
+                c.exchange == position.exchange
+                    && c.trading_symbol == position.trading_symbol
+                    && c.product == position.product
+            }) {
+                Some(index) => index,
+                None => {
+                    combined.push(CombinedPosition {
+                        exchange: position.exchange,
+                        trading_symbol: position.trading_symbol.clone(),
+                        product: position.product,
+                        quantity: 0,
+                        value: 0.0,
+                        pnl: 0.0,
+                        m2m: 0.0,
+                        accounts: Vec::new(),
+                    });
+                    combined.len() - 1
+                }
+            };
+
+            let entry = &mut combined[index];
+            entry.quantity += position.quantity;
+            entry.value += position.value;
+            entry.pnl += position.pnl;
+            entry.m2m += position.m2m;
+            entry.accounts.push(name.clone());
+        }
+    }
+
+    combined
+}
+
+/// Funds of a segment summed over several accounts, see [`combine_funds`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CombinedSegmentFunds {
+    pub net: f64,
+    pub live_balance: f64,
+    /// Sum of the utilised margins
+    pub debits: f64,
+}
+
+/// Funds summed over several accounts, see [`combine_funds`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CombinedFunds {
+    pub equity: CombinedSegmentFunds,
+    pub commodity: CombinedSegmentFunds,
+}
+
+/// Sums the funds of the accounts whose request succeeded.
+pub fn combine_funds(results: &AccountResults<TotalFunds>) -> CombinedFunds {
+    let mut combined = CombinedFunds::default();
+
+    for funds in results.values().flatten() {
+        for (total, segment) in [
+            (&mut combined.equity, &funds.equity),
+            (&mut combined.commodity, &funds.commodity),
+        ] {
+            total.net += segment.net;
+            total.live_balance += segment.available.live_balance;
+            total.debits += segment.utilised.debits;
+        }
+    }
+
+    combined
+}
@@ -0,0 +1,416 @@
+//! Price alerts evaluated locally on WebSocket ticks.
+//!
+//! Unlike the alerts stored by Kite, rules of an [`AlertEngine`] are checked on every tick of the
+//! [`KiteConnect::web_socket`](crate::KiteConnect::web_socket) feed, so they fire as soon as the
+//! tick is received. Only subscribed instruments are checked, and [`AlertCondition::VolumeExceeds`]
+//! and [`AlertCondition::PercentChangeFromOpen`] need ticks in quote or full mode.
+//!
+//! A rule fires once when its condition becomes true and is then disarmed. A rule with
+//! [`AlertRule::rearm`] set is armed again once its condition is false again, so it fires on every
+//! crossing instead of only the first one.
+//!
+//! ```no_run
+//! # async fn run(kite: kite_connect::KiteConnect<kite_connect::Authenticated>) -> Result<(), kite_connect::Error> {
+//! use kite_connect::alert_engine::{AlertCondition, AlertEngine, AlertRule};
+//! use kite_connect::ws::Req;
+//!
+//! let (mut ticker, ticks) = kite.web_socket().await?;
+//! ticker.send(Req::Subscribe(&[408065])).await?;
+//!
+//! let engine = AlertEngine::new();
+//! engine.add(AlertRule::new(408065, AlertCondition::CrossesAbove(1500.0)));
+//!
+//! let (tx, alerts) = crossbeam_channel::unbounded();
+//! let runner = engine.clone();
+//! std::thread::spawn(move || runner.run(&ticks, &tx));
+//!
+//! // Rules can be added and removed while the engine runs
+//! engine.add(AlertRule::new(408065, AlertCondition::CrossesBelow(1400.0)).rearm());
+//!
+//! for alert in alerts {
+//!     println!("{:?} at {}", alert.condition, alert.last_price);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::alerts::Operator;
+use crate::ws::Ticker;
+
+/// Condition of an [`AlertRule`], checked on every tick of its instrument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertCondition {
+    /// The last price goes from at or below the price to above it
+    CrossesAbove(f64),
+    /// The last price goes from at or above the price to below it
+    CrossesBelow(f64),
+    /// The last price changed by at least this percentage from the day's open, e.g. `2.0` for a 2%
+    /// rise or `-2.0` for a 2% fall
+    PercentChangeFromOpen(f64),
+    /// The volume traded today exceeds this quantity
+    VolumeExceeds(u32),
+    /// The last price compares to the price with the operator, like the alerts stored by Kite
+    LastPrice(Operator, f64),
+}
+
+impl AlertCondition {
+    /// Whether the condition holds for `tick`, or `None` if the tick lacks the data to tell.
+    fn holds(&self, tick: &Ticker) -> Option<bool> {
+        let (_, last_price) = tick.last_price()?;
+
+        Some(match *self {
+            AlertCondition::CrossesAbove(price) => last_price > price,
+            AlertCondition::CrossesBelow(price) => last_price < price,
+            AlertCondition::PercentChangeFromOpen(pct) => {
+                let open = match tick {
+                    Ticker::IndicesQuote(q) => q.ohlc.open,
+                    Ticker::PartialQuote(q) => q.ohlc.open,
+                    Ticker::FullQuote(q) => q.quote.ohlc.open,
+                    Ticker::ConnectionClosed
+                    | Ticker::Reconnected
+                    | Ticker::OrderUpdate(_)
+                    | Ticker::Error(_)
+                    | Ticker::LtpQuote(_) => {
+                        return None;
+                    }
+                };
+                if open == 0.0 {
+                    return None;
+                }
+
+                let change = (last_price - open) / open * 100.0;
+                if pct >= 0.0 {
+                    change >= pct
+                } else {
+                    change <= pct
+                }
+            }
+            AlertCondition::VolumeExceeds(volume) => {
+                let volume_traded = match tick {
+                    Ticker::PartialQuote(q) => q.volume_traded,
+                    Ticker::FullQuote(q) => q.quote.volume_traded,
+                    _ => return None,
+                };
+                volume_traded > volume
+            }
+            AlertCondition::LastPrice(operator, price) => operator.evaluate(last_price, price),
+        })
+    }
+
+    /// Crossings only fire when the condition was seen false before, so that a rule added while
+    /// the price is already above the level doesn't fire right away.
+    fn is_crossing(&self) -> bool {
+        matches!(
+            self,
+            AlertCondition::CrossesAbove(_) | AlertCondition::CrossesBelow(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertRule {
+    pub instrument_token: u32,
+    pub condition: AlertCondition,
+    /// Whether the rule is armed again once its condition is false again, see the
+    /// [module documentation](self)
+    pub rearm: bool,
+}
+
+impl AlertRule {
+    /// A rule firing once.
+    pub fn new(instrument_token: u32, condition: AlertCondition) -> Self {
+        Self {
+            instrument_token,
+            condition,
+            rearm: false,
+        }
+    }
+
+    /// Makes the rule fire again every time its condition becomes true.
+    pub fn rearm(mut self) -> Self {
+        self.rearm = true;
+        self
+    }
+}
+
+/// Identifies a rule added to an [`AlertEngine`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AlertId(u64);
+
+/// A rule whose condition became true.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertFired {
+    pub id: AlertId,
+    pub instrument_token: u32,
+    pub condition: AlertCondition,
+    /// Last price of the tick that fired the rule
+    pub last_price: f64,
+}
+
+#[derive(Debug)]
+struct RuleState {
+    rule: AlertRule,
+    armed: bool,
+    /// Whether the condition held on the previous tick with the data to tell
+    last: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+struct Rules {
+    next_id: u64,
+    rules: BTreeMap<AlertId, RuleState>,
+}
+
+/// Evaluates alert rules on ticks, see the [module documentation](self).
+///
+/// Clones share the same rules, so rules can be added or removed from any clone while another one
+/// [runs](AlertEngine::run).
+#[derive(Debug, Clone, Default)]
+pub struct AlertEngine {
+    rules: Arc<Mutex<Rules>>,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an armed rule.
+    pub fn add(&self, rule: AlertRule) -> AlertId {
+        let mut rules = self.rules();
+        let id = AlertId(rules.next_id);
+        rules.next_id += 1;
+        rules.rules.insert(
+            id,
+            RuleState {
+                rule,
+                armed: true,
+                last: None,
+            },
+        );
+        id
+    }
+
+    /// Removes a rule, returning it if it existed.
+    pub fn remove(&self, id: AlertId) -> Option<AlertRule> {
+        self.rules().rules.remove(&id).map(|state| state.rule)
+    }
+
+    /// Whether the rule exists and can fire.
+    pub fn is_armed(&self, id: AlertId) -> bool {
+        self.rules().rules.get(&id).is_some_and(|state| state.armed)
+    }
+
+    /// Checks the rules of the tick's instrument, returning the ones that fired.
+    pub fn process(&self, tick: &Ticker) -> Vec<AlertFired> {
+        let Some((instrument_token, last_price)) = tick.last_price() else {
+            return Vec::new();
+        };
+
+        let mut fired = Vec::new();
+        let mut rules = self.rules();
+        let states = rules
+            .rules
+            .iter_mut()
+            .filter(|(_, state)| state.rule.instrument_token == instrument_token);
+
+        for (&id, state) in states {
+            let Some(holds) = state.rule.condition.holds(tick) else {
+                continue;
+            };
+            let last = state.last.replace(holds);
+
+            if !holds {
+                if state.rule.rearm {
+                    state.armed = true;
+                }
+                continue;
+            }
+
+            let crossed = !state.rule.condition.is_crossing() || last == Some(false);
+            if state.armed && crossed {
+                state.armed = false;
+                fired.push(AlertFired {
+                    id,
+                    instrument_token,
+                    condition: state.rule.condition,
+                    last_price,
+                });
+            }
+        }
+
+        fired
+    }
+
+    /// Processes every tick received on `ticks`, sending the fired rules to `alerts`.
+    ///
+    /// Blocks until `ticks` is disconnected or `alerts` has no receiver left.
+    pub fn run(&self, ticks: &Receiver<Ticker>, alerts: &Sender<AlertFired>) {
+        for tick in ticks {
+            for alert in self.process(&tick) {
+                if alerts.send(alert).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn rules(&self) -> MutexGuard<'_, Rules> {
+        self.rules.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::{LtpQuote, Ohlc};
+    use crate::ws::PartialQuote;
+
+    const TOKEN: u32 = 408065;
+
+    fn ltp(last_price: f64) -> Ticker {
+        Ticker::LtpQuote(LtpQuote {
+            instrument_token: TOKEN,
+            last_price,
+        })
+    }
+
+    fn quote(last_price: f64, open: f64, volume_traded: u32) -> Ticker {
+        Ticker::PartialQuote(PartialQuote {
+            instrument_token: TOKEN,
+            last_price,
+            last_traded_quantity: 1,
+            average_traded_price: last_price,
+            volume_traded,
+            total_buy_quantity: 0,
+            total_sell_quantity: 0,
+            ohlc: Ohlc {
+                open,
+                high: last_price.max(open),
+                low: last_price.min(open),
+                close: open,
+            },
+        })
+    }
+
+    /// Prices at which the engine fired, for each tick.
+    fn fires(engine: &AlertEngine, prices: &[f64]) -> Vec<f64> {
+        prices
+            .iter()
+            .flat_map(|&price| engine.process(&ltp(price)))
+            .map(|alert| alert.last_price)
+            .collect()
+    }
+
+    #[test]
+    fn test_crossing_fires_once() {
+        let engine = AlertEngine::new();
+        let id = engine.add(AlertRule::new(TOKEN, AlertCondition::CrossesAbove(100.0)));
+
+        assert_eq!(
+            fires(&engine, &[99.0, 100.0, 101.0, 102.0, 98.0, 103.0]),
+            [101.0]
+        );
+        assert!(!engine.is_armed(id));
+    }
+
+    #[test]
+    fn test_crossing_rearms() {
+        let engine = AlertEngine::new();
+        engine.add(AlertRule::new(TOKEN, AlertCondition::CrossesBelow(100.0)).rearm());
+
+        // Staying below doesn't fire again, touching the level re-arms
+        assert_eq!(
+            fires(&engine, &[101.0, 99.0, 98.0, 100.0, 99.5, 102.0, 97.0]),
+            [99.0, 99.5, 97.0]
+        );
+    }
+
+    #[test]
+    fn test_crossing_needs_a_prior_tick() {
+        let engine = AlertEngine::new();
+        engine.add(AlertRule::new(TOKEN, AlertCondition::CrossesAbove(100.0)).rearm());
+
+        // Already above when the rule is added
+        assert_eq!(fires(&engine, &[105.0, 106.0, 99.0, 101.0]), [101.0]);
+    }
+
+    #[test]
+    fn test_percent_change_and_volume() {
+        let engine = AlertEngine::new();
+        let up = engine.add(AlertRule::new(
+            TOKEN,
+            AlertCondition::PercentChangeFromOpen(2.0),
+        ));
+        let down =
+            engine.add(AlertRule::new(TOKEN, AlertCondition::PercentChangeFromOpen(-1.0)).rearm());
+        let volume = engine.add(AlertRule::new(TOKEN, AlertCondition::VolumeExceeds(1000)));
+
+        let ids =
+            |tick: Ticker| -> Vec<AlertId> { engine.process(&tick).iter().map(|a| a.id).collect() };
+
+        // Level conditions fire on the first tick they hold, LTP ticks can't be evaluated
+        assert_eq!(ids(ltp(90.0)), []);
+        assert_eq!(ids(quote(102.5, 100.0, 500)), [up]);
+        assert_eq!(ids(quote(98.9, 100.0, 1001)), [down, volume]);
+        assert_eq!(ids(quote(98.0, 100.0, 1500)), []);
+        assert_eq!(ids(quote(99.5, 100.0, 1600)), []);
+        assert_eq!(ids(quote(98.5, 100.0, 1700)), [down]);
+        assert_eq!(ids(quote(103.0, 100.0, 1800)), []);
+    }
+
+    #[test]
+    fn test_last_price_operator() {
+        let engine = AlertEngine::new();
+        engine.add(
+            AlertRule::new(
+                TOKEN,
+                AlertCondition::LastPrice(Operator::GreaterThanOrEqual, 100.0),
+            )
+            .rearm(),
+        );
+
+        // Fires on the first tick it holds, like the other level conditions
+        assert_eq!(fires(&engine, &[100.0, 101.0, 99.0, 100.0]), [100.0, 100.0]);
+    }
+
+    #[test]
+    fn test_rules_of_other_instruments() {
+        let engine = AlertEngine::new();
+        engine.add(AlertRule::new(
+            TOKEN + 1,
+            AlertCondition::CrossesAbove(100.0),
+        ));
+
+        assert!(fires(&engine, &[99.0, 101.0]).is_empty());
+        assert!(engine.process(&Ticker::ConnectionClosed).is_empty());
+    }
+
+    #[test]
+    fn test_run_with_rules_changed_at_runtime() {
+        let engine = AlertEngine::new();
+        let removed = engine.add(AlertRule::new(TOKEN, AlertCondition::CrossesAbove(100.0)));
+
+        let (tick_tx, ticks) = crossbeam_channel::unbounded();
+        let (tx, alerts) = crossbeam_channel::unbounded();
+        let runner = engine.clone();
+        let handle = std::thread::spawn(move || runner.run(&ticks, &tx));
+
+        assert_eq!(engine.remove(removed).map(|r| r.rearm), Some(false));
+        assert_eq!(engine.remove(removed), None);
+        let added = engine.add(AlertRule::new(TOKEN, AlertCondition::CrossesBelow(100.0)));
+
+        for price in [99.0, 101.0, 99.0] {
+            tick_tx.send(ltp(price)).unwrap();
+        }
+        drop(tick_tx);
+        handle.join().unwrap();
+
+        let fired: Vec<_> = alerts.iter().map(|a| (a.id, a.last_price)).collect();
+        assert_eq!(fired, [(added, 99.0)]);
+    }
+}
@@ -0,0 +1,95 @@
+//! Alerts
+//!
+//! Only the types shared with the alerts API are implemented for now.
+//!
+//! Reference: <https://kite.trade/docs/connect/v3/alerts/>
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Comparison between the watched value (left) and the alert's value (right).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Operator {
+    #[serde(rename = "<=")]
+    LessThanOrEqual,
+    #[serde(rename = ">=")]
+    GreaterThanOrEqual,
+    #[serde(rename = "<")]
+    LessThan,
+    #[serde(rename = ">")]
+    GreaterThan,
+    #[serde(rename = "==")]
+    Equal,
+}
+
+impl Operator {
+    /// Evaluates `lhs <operator> rhs`, e.g. with the last price from the WebSocket as `lhs` to
+    /// preview whether an alert would fire.
+    pub fn evaluate(&self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Operator::LessThanOrEqual => lhs <= rhs,
+            Operator::GreaterThanOrEqual => lhs >= rhs,
+            Operator::LessThan => lhs < rhs,
+            Operator::GreaterThan => lhs > rhs,
+            Operator::Equal => lhs == rhs,
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Operator::LessThanOrEqual => "<=",
+            Operator::GreaterThanOrEqual => ">=",
+            Operator::LessThan => "<",
+            Operator::GreaterThan => ">",
+            Operator::Equal => "==",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operator_evaluate() {
+        use Operator::*;
+
+        let cases = [
+            (LessThanOrEqual, [true, true, false]),
+            (GreaterThanOrEqual, [false, true, true]),
+            (LessThan, [true, false, false]),
+            (GreaterThan, [false, false, true]),
+            (Equal, [false, true, false]),
+        ];
+
+        for (operator, expected) in cases {
+            let evaluated = [99.5, 100.0, 100.5].map(|lhs| operator.evaluate(lhs, 100.0));
+            assert_eq!(evaluated, expected, "{operator}");
+        }
+    }
+
+    #[test]
+    fn test_operator_serialization() -> Result<(), Box<dyn std::error::Error>> {
+        use Operator::*;
+
+        for (operator, symbol) in [
+            (LessThanOrEqual, "<="),
+            (GreaterThanOrEqual, ">="),
+            (LessThan, "<"),
+            (GreaterThan, ">"),
+            (Equal, "=="),
+        ] {
+            assert_eq!(operator.to_string(), symbol);
+            assert_eq!(serde_json::to_string(&operator)?, format!("\"{symbol}\""));
+            assert_eq!(
+                serde_json::from_str::<Operator>(&format!("\"{symbol}\""))?,
+                operator
+            );
+        }
+
+        Ok(())
+    }
+}
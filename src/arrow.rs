@@ -0,0 +1,322 @@
+//! Conversion of candles and instruments to Arrow [`RecordBatch`]es, behind the `arrow` feature.
+//!
+//! A `RecordBatch` can be handed to Polars, DataFusion, DuckDB or written to Parquet without
+//! copying. The schemas returned by [`candles_schema`] and [`instruments_schema`] are part of the
+//! public API and only change in breaking releases.
+//!
+//! The crate doesn't depend on `parquet` itself, writing a batch to a Parquet file only takes its
+//! `ArrowWriter`:
+//!
+//! ```ignore
+//! use kite_connect::arrow::instruments_to_record_batch;
+//! use parquet::arrow::ArrowWriter;
+//!
+//! let batch = instruments_to_record_batch(&kite.get_all_instruments().await?)?;
+//! let file = std::fs::File::create("instruments.parquet")?;
+//! let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+//! writer.write(&batch)?;
+//! writer.close()?;
+//! ```
+
+use arrow_array::{
+    Array, ArrayRef, Date32Array, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray, UInt32Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Datelike, NaiveDate};
+use std::sync::Arc;
+
+use crate::Error;
+use crate::historical::{CANDLE_TIMESTAMP_FORMAT, Candle};
+use crate::quotes::{Instrument, InstrumentType};
+
+/// Timezone of candle timestamps, Kite only returns candles in IST.
+pub const CANDLE_TIMEZONE: &str = "+05:30";
+
+/// Days between 0001-01-01 (day 1 of the common era) and 1970-01-01
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Schema of the batches produced by [`candles_to_record_batch`].
+pub fn candles_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some(CANDLE_TIMEZONE.into())),
+            false,
+        ),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Int64, false),
+        Field::new("oi", DataType::Int64, true),
+    ]))
+}
+
+/// Schema of the batches produced by [`instruments_to_record_batch`].
+pub fn instruments_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("instrument_token", DataType::UInt32, false),
+        Field::new("exchange_token", DataType::Utf8, false),
+        Field::new("tradingsymbol", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("last_price", DataType::Float64, false),
+        Field::new("expiry", DataType::Date32, true),
+        Field::new("strike", DataType::Float64, false),
+        Field::new("tick_size", DataType::Float64, false),
+        Field::new("lot_size", DataType::Int64, false),
+        Field::new("instrument_type", DataType::Utf8, false),
+        Field::new("segment", DataType::Utf8, false),
+        Field::new("exchange", DataType::Utf8, false),
+    ]))
+}
+
+/// Converts candles into a [`RecordBatch`] with [`candles_schema`].
+pub fn candles_to_record_batch(candles: &[Candle]) -> Result<RecordBatch, Error> {
+    let timestamps = candles
+        .iter()
+        .map(|c| crate::utils::parse_kite_timestamp(&c.timestamp).map(|t| t.timestamp_millis()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let f64_column = |f: fn(&Candle) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from_iter_values(candles.iter().map(f)))
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(TimestampMillisecondArray::from(timestamps).with_timezone(CANDLE_TIMEZONE)),
+        f64_column(|c| c.open),
+        f64_column(|c| c.high),
+        f64_column(|c| c.low),
+        f64_column(|c| c.close),
+        Arc::new(Int64Array::from_iter_values(
+            candles.iter().map(|c| c.volume),
+        )),
+        Arc::new(Int64Array::from_iter(candles.iter().map(|c| c.oi))),
+    ];
+
+    Ok(RecordBatch::try_new(candles_schema(), columns)?)
+}
+
+/// Converts a [`RecordBatch`] with [`candles_schema`] back into candles.
+pub fn record_batch_to_candles(batch: &RecordBatch) -> Result<Vec<Candle>, Error> {
+    let ist = crate::utils::ist();
+
+    let timestamp = column::<TimestampMillisecondArray>(batch, "timestamp")?;
+    let open = column::<Float64Array>(batch, "open")?;
+    let high = column::<Float64Array>(batch, "high")?;
+    let low = column::<Float64Array>(batch, "low")?;
+    let close = column::<Float64Array>(batch, "close")?;
+    let volume = column::<Int64Array>(batch, "volume")?;
+    let oi = column::<Int64Array>(batch, "oi")?;
+
+    (0..batch.num_rows())
+        .map(|i| {
+            let timestamp = DateTime::from_timestamp_millis(timestamp.value(i))
+                .ok_or_else(|| {
+                    ArrowError::InvalidArgumentError(format!(
+                        "Timestamp {} is out of range",
+                        timestamp.value(i)
+                    ))
+                })?
+                .with_timezone(&ist);
+
+            Ok(Candle {
+                timestamp: timestamp.format(CANDLE_TIMESTAMP_FORMAT).to_string(),
+                open: open.value(i),
+                high: high.value(i),
+                low: low.value(i),
+                close: close.value(i),
+                volume: volume.value(i),
+                oi: oi.is_valid(i).then(|| oi.value(i)),
+            })
+        })
+        .collect()
+}
+
+/// Converts instruments into a [`RecordBatch`] with [`instruments_schema`].
+///
+/// Empty or unparsable `expiry` dates become nulls.
+pub fn instruments_to_record_batch(instruments: &[Instrument]) -> Result<RecordBatch, Error> {
+    let str_column = |f: fn(&Instrument) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(instruments.iter().map(f)))
+    };
+    let f64_column = |f: fn(&Instrument) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from_iter_values(instruments.iter().map(f)))
+    };
+
+    let expiry = instruments.iter().map(|i| {
+        NaiveDate::parse_from_str(&i.expiry, "%Y-%m-%d")
+            .ok()
+            .map(|d| d.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE)
+    });
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt32Array::from_iter_values(
+            instruments.iter().map(|i| i.instrument_token),
+        )),
+        str_column(|i| &i.exchange_token),
+        str_column(|i| &i.trading_symbol),
+        str_column(|i| &i.name),
+        f64_column(|i| i.last_price),
+        Arc::new(Date32Array::from_iter(expiry)),
+        f64_column(|i| i.strike),
+        f64_column(|i| i.tick_size),
+        Arc::new(Int64Array::from_iter_values(
+            instruments.iter().map(|i| i.lot_size),
+        )),
+        str_column(|i| instrument_type_str(&i.instrument_type)),
+        str_column(|i| i.segment.as_str()),
+        Arc::new(StringArray::from_iter_values(
+            instruments.iter().map(|i| i.exchange.as_str()),
+        )),
+    ];
+
+    Ok(RecordBatch::try_new(instruments_schema(), columns)?)
+}
+
+const fn instrument_type_str(instrument_type: &InstrumentType) -> &'static str {
+    match instrument_type {
+        InstrumentType::EQ => "EQ",
+        InstrumentType::FUT => "FUT",
+        InstrumentType::CE => "CE",
+        InstrumentType::PE => "PE",
+    }
+}
+
+fn column<'a, T: Array + 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T, Error> {
+    batch
+        .column_by_name(name)
+        .and_then(|c| c.as_any().downcast_ref::<T>())
+        .ok_or_else(|| {
+            ArrowError::SchemaError(format!("Missing or mistyped column `{name}`")).into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::parse_instruments;
+
+    fn candles() -> Vec<Candle> {
+        vec![
+            Candle {
+                timestamp: "2019-12-04T09:15:00+0530".into(),
+                open: 12009.9,
+                high: 12019.35,
+                low: 12001.25,
+                close: 12001.5,
+                volume: 163275,
+                oi: Some(13667775),
+            },
+            Candle {
+                timestamp: "2019-12-04T09:16:00+0530".into(),
+                open: 12001.0,
+                high: 12003.0,
+                low: 11998.25,
+                close: 12001.0,
+                volume: 105750,
+                oi: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_candles_schema() {
+        let fields: Vec<_> = candles_schema()
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), f.data_type().to_string(), f.is_nullable()))
+            .collect();
+
+        let expected = [
+            ("timestamp", "Timestamp(ms, \"+05:30\")", false),
+            ("open", "Float64", false),
+            ("high", "Float64", false),
+            ("low", "Float64", false),
+            ("close", "Float64", false),
+            ("volume", "Int64", false),
+            ("oi", "Int64", true),
+        ];
+
+        assert_eq!(
+            fields,
+            expected.map(|(n, t, null)| (n.to_string(), t.to_string(), null))
+        );
+    }
+
+    #[test]
+    fn test_instruments_schema() {
+        let fields: Vec<_> = instruments_schema()
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), f.data_type().to_string(), f.is_nullable()))
+            .collect();
+
+        let expected = [
+            ("instrument_token", "UInt32", false),
+            ("exchange_token", "Utf8", false),
+            ("tradingsymbol", "Utf8", false),
+            ("name", "Utf8", false),
+            ("last_price", "Float64", false),
+            ("expiry", "Date32", true),
+            ("strike", "Float64", false),
+            ("tick_size", "Float64", false),
+            ("lot_size", "Int64", false),
+            ("instrument_type", "Utf8", false),
+            ("segment", "Utf8", false),
+            ("exchange", "Utf8", false),
+        ];
+
+        assert_eq!(
+            fields,
+            expected.map(|(n, t, null)| (n.to_string(), t.to_string(), null))
+        );
+    }
+
+    #[test]
+    fn test_candles_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let candles = candles();
+        let batch = candles_to_record_batch(&candles)?;
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            column::<TimestampMillisecondArray>(&batch, "timestamp")?.value(0),
+            // 2019-12-04T03:45:00Z
+            1_575_431_100_000
+        );
+        assert!(column::<Int64Array>(&batch, "oi")?.is_null(1));
+
+        assert_eq!(record_batch_to_candles(&batch)?, candles);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_instruments_to_record_batch() -> Result<(), Box<dyn std::error::Error>> {
+        let instruments = parse_instruments(
+            b"instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+13238786,51714,NIFTY24DECFUT,NIFTY,0,2024-12-26,0,0.05,25,FUT,NFO-FUT,NFO
+",
+        )?;
+
+        let batch = instruments_to_record_batch(&instruments)?;
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            column::<UInt32Array>(&batch, "instrument_token")?.value(1),
+            13238786
+        );
+        assert_eq!(
+            column::<StringArray>(&batch, "instrument_type")?.value(1),
+            "FUT"
+        );
+
+        let expiry = column::<Date32Array>(&batch, "expiry")?;
+        assert!(expiry.is_null(0));
+        // 2024-12-26
+        assert_eq!(expiry.value(1), 20083);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,600 @@
+//! On-disk cache of historical candles, behind the `cache` feature.
+//!
+//! [`CandleCache`] stores candles in SQLite, keyed by instrument token, interval and trading day.
+//! [`CandleCache::get_or_fetch`] serves the days it already has and only fetches the missing ones,
+//! which keeps repeated backtests well within the 3 requests per second allowed on the historical
+//! endpoint.
+//!
+//! A day is only cached once it's over: days from today (in IST) onwards are fetched on every call,
+//! as their candles are still being formed. Days without any candles (weekends, holidays) are cached
+//! as empty, so they aren't fetched again either.
+//!
+//! ```no_run
+//! use chrono::NaiveDate;
+//! use kite_connect::{cache::CandleCache, historical::Interval};
+//! # async fn run(kite: kite_connect::KiteConnect<kite_connect::Authenticated>) -> Result<(), kite_connect::Error> {
+//! let cache = CandleCache::open("candles.sqlite")?;
+//! let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+//! let to = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+//!
+//! let candles = cache.get_or_fetch(&kite, 408065, Interval::FiveMinute, from..=to).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use chrono::{DateTime, Days, NaiveDate};
+use rusqlite::{Connection, ErrorCode, params};
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::historical::{Candle, HistoricalCandleReq, Interval};
+use crate::utils::IST_OFFSET_SECS;
+use crate::{Authenticated, Error, KiteConnect};
+
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS days (
+    instrument_token INTEGER NOT NULL,
+    interval TEXT NOT NULL,
+    day TEXT NOT NULL,
+    PRIMARY KEY (instrument_token, interval, day)
+);
+CREATE TABLE IF NOT EXISTS candles (
+    instrument_token INTEGER NOT NULL,
+    interval TEXT NOT NULL,
+    day TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    volume INTEGER NOT NULL,
+    oi INTEGER,
+    PRIMARY KEY (instrument_token, interval, timestamp)
+);
+";
+
+/// Source of the candles missing from a [`CandleCache`].
+///
+/// Implemented for [`KiteConnect<Authenticated>`], implement it yourself to cache candles from
+/// another source or to test code using the cache.
+pub trait CandleFetcher {
+    /// Fetches the candles of every day from `from` to `to`, both inclusive.
+    fn fetch_candles(
+        &self,
+        instrument_token: u32,
+        interval: Interval,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<Candle>, Error>>;
+}
+
+impl CandleFetcher for KiteConnect<Authenticated> {
+    async fn fetch_candles(
+        &self,
+        instrument_token: u32,
+        interval: Interval,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<Candle>, Error> {
+        let req = HistoricalCandleReq {
+            from: format!("{} 00:00:00", from.format(DAY_FORMAT)),
+            to: format!("{} 23:59:59", to.format(DAY_FORMAT)),
+            continuous: false,
+            oi: true,
+        };
+
+        self.get_historical_data(instrument_token, interval, req)
+            .await
+    }
+}
+
+/// SQLite backed cache of historical candles.
+#[derive(Debug)]
+pub struct CandleCache {
+    conn: Mutex<Connection>,
+}
+
+impl CandleCache {
+    /// Opens the cache at `path`, creating it if it doesn't exist.
+    ///
+    /// A corrupted or unreadable cache file is deleted and replaced with an empty cache.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        match Connection::open(path).and_then(Self::init) {
+            Ok(cache) => Ok(cache),
+            Err(e) if is_corrupt(&e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    "Recreating corrupted candle cache at {}: {e}",
+                    path.display()
+                );
+
+                for suffix in ["", "-wal", "-shm", "-journal"] {
+                    let mut file = path.as_os_str().to_owned();
+                    file.push(suffix);
+                    match std::fs::remove_file(&file) {
+                        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                        _ => {}
+                    }
+                }
+
+                Ok(Connection::open(path).and_then(Self::init)?)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Opens an empty cache that only lives in memory.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Ok(Connection::open_in_memory().and_then(Self::init)?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        let check: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        if check != "ok" {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+                Some(check),
+            ));
+        }
+
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the candles from `range`, fetching the days missing from the cache with `fetcher`.
+    ///
+    /// Missing days are fetched in as few requests as the interval's maximum range allows, and the
+    /// candles are returned in chronological order.
+    pub async fn get_or_fetch<F: CandleFetcher>(
+        &self,
+        fetcher: &F,
+        instrument_token: u32,
+        interval: Interval,
+        range: RangeInclusive<NaiveDate>,
+    ) -> Result<Vec<Candle>, Error> {
+        let (from, to) = (*range.start(), *range.end());
+        let today = today_ist();
+        let cached_days = self.cached_days(instrument_token, interval, from, to)?;
+
+        let missing: Vec<NaiveDate> = from
+            .iter_days()
+            .take_while(|day| *day <= to)
+            .filter(|day| *day >= today || !cached_days.contains(day))
+            .collect();
+
+        let mut uncached = Vec::new();
+        for (start, end) in runs(&missing, interval.max_days()) {
+            let candles = fetcher
+                .fetch_candles(instrument_token, interval, start, end)
+                .await?;
+
+            let (complete, partial): (Vec<_>, Vec<_>) = candles
+                .into_iter()
+                .filter(|c| candle_day(c).is_some_and(|day| range.contains(&day)))
+                .partition(|c| candle_day(c).is_some_and(|day| day < today));
+
+            if start < today {
+                let last_complete = end.min(today.pred_opt().unwrap_or(today));
+                self.store(instrument_token, interval, start, last_complete, &complete)?;
+            }
+            uncached.extend(partial);
+        }
+
+        let mut candles = self.load(instrument_token, interval, from, to)?;
+        candles.extend(uncached);
+        candles.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        candles.dedup_by(|a, b| a.timestamp == b.timestamp);
+
+        Ok(candles)
+    }
+
+    /// Removes the cached candles of `range`, so they're fetched again on the next call.
+    pub fn invalidate(
+        &self,
+        instrument_token: u32,
+        interval: Interval,
+        range: RangeInclusive<NaiveDate>,
+    ) -> Result<(), Error> {
+        let (from, to) = (day_str(*range.start()), day_str(*range.end()));
+        let interval = interval.to_string();
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+
+        for table in ["days", "candles"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {table} WHERE instrument_token = ?1 AND interval = ?2 AND day BETWEEN ?3 AND ?4"
+                ),
+                params![instrument_token, interval, from, to],
+            )?;
+        }
+
+        Ok(tx.commit()?)
+    }
+
+    /// Removes every cached candle.
+    pub fn clear(&self) -> Result<(), Error> {
+        Ok(self
+            .conn()
+            .execute_batch("DELETE FROM days; DELETE FROM candles;")?)
+    }
+
+    fn cached_days(
+        &self,
+        instrument_token: u32,
+        interval: Interval,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<HashSet<NaiveDate>, Error> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT day FROM days WHERE instrument_token = ?1 AND interval = ?2 AND day BETWEEN ?3 AND ?4",
+        )?;
+
+        let days = stmt
+            .query_map(
+                params![
+                    instrument_token,
+                    interval.to_string(),
+                    day_str(from),
+                    day_str(to)
+                ],
+                |row| row.get::<_, String>(0),
+            )?
+            .filter_map(|day| {
+                day.map(|day| NaiveDate::parse_from_str(&day, DAY_FORMAT).ok())
+                    .transpose()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(days)
+    }
+
+    /// Replaces the candles of every day from `from` to `to` and marks these days as cached.
+    fn store(
+        &self,
+        instrument_token: u32,
+        interval: Interval,
+        from: NaiveDate,
+        to: NaiveDate,
+        candles: &[Candle],
+    ) -> Result<(), Error> {
+        let interval = interval.to_string();
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM candles WHERE instrument_token = ?1 AND interval = ?2 AND day BETWEEN ?3 AND ?4",
+            params![instrument_token, interval, day_str(from), day_str(to)],
+        )?;
+
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT OR REPLACE INTO candles VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            for c in candles {
+                let Some(day) = candle_day(c).filter(|day| (from..=to).contains(day)) else {
+                    continue;
+                };
+
+                insert.execute(params![
+                    instrument_token,
+                    interval,
+                    day_str(day),
+                    c.timestamp,
+                    c.open,
+                    c.high,
+                    c.low,
+                    c.close,
+                    c.volume,
+                    c.oi,
+                ])?;
+            }
+
+            let mut mark = tx.prepare_cached("INSERT OR REPLACE INTO days VALUES (?1, ?2, ?3)")?;
+            for day in from.iter_days().take_while(|day| *day <= to) {
+                mark.execute(params![instrument_token, interval, day_str(day)])?;
+            }
+        }
+
+        Ok(tx.commit()?)
+    }
+
+    fn load(
+        &self,
+        instrument_token: u32,
+        interval: Interval,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<Candle>, Error> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT timestamp, open, high, low, close, volume, oi FROM candles
+             WHERE instrument_token = ?1 AND interval = ?2 AND day BETWEEN ?3 AND ?4
+             ORDER BY timestamp",
+        )?;
+
+        let candles = stmt
+            .query_map(
+                params![
+                    instrument_token,
+                    interval.to_string(),
+                    day_str(from),
+                    day_str(to)
+                ],
+                |row| {
+                    Ok(Candle {
+                        timestamp: row.get(0)?,
+                        open: row.get(1)?,
+                        high: row.get(2)?,
+                        low: row.get(3)?,
+                        close: row.get(4)?,
+                        volume: row.get(5)?,
+                        oi: row.get(6)?,
+                    })
+                },
+            )?
+            .collect::<Result<_, _>>()?;
+
+        Ok(candles)
+    }
+
+    /// Returns the number of candles in the cache.
+    pub fn len(&self) -> Result<usize, Error> {
+        let count: i64 = self
+            .conn()
+            .query_row("SELECT COUNT(*) FROM candles", [], |row| row.get(0))?;
+
+        Ok(count as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+}
+
+/// Groups sorted days into ranges of consecutive days, at most `max_days` long.
+fn runs(days: &[NaiveDate], max_days: u64) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut runs: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+
+    for &day in days {
+        match runs.last_mut() {
+            Some((start, end))
+                if end.succ_opt() == Some(day)
+                    && start.checked_add_days(Days::new(max_days)) > Some(day) =>
+            {
+                *end = day;
+            }
+            _ => runs.push((day, day)),
+        }
+    }
+
+    runs
+}
+
+fn candle_day(candle: &Candle) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(candle.timestamp.get(..10)?, DAY_FORMAT).ok()
+}
+
+fn day_str(day: NaiveDate) -> String {
+    day.format(DAY_FORMAT).to_string()
+}
+
+fn today_ist() -> NaiveDate {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+
+    DateTime::from_timestamp(now + IST_OFFSET_SECS, 0)
+        .map(|t| t.date_naive())
+        .unwrap_or_default()
+}
+
+fn is_corrupt(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Weekday};
+    use std::cell::RefCell;
+
+    /// Returns a candle at 09:15 for every weekday, and records the requested ranges.
+    #[derive(Default)]
+    struct MockFetcher {
+        calls: RefCell<Vec<(NaiveDate, NaiveDate)>>,
+    }
+
+    impl CandleFetcher for MockFetcher {
+        async fn fetch_candles(
+            &self,
+            _instrument_token: u32,
+            _interval: Interval,
+            from: NaiveDate,
+            to: NaiveDate,
+        ) -> Result<Vec<Candle>, Error> {
+            self.calls.borrow_mut().push((from, to));
+
+            Ok(from
+                .iter_days()
+                .take_while(|day| *day <= to)
+                .filter(|day| !matches!(day.weekday(), Weekday::Sat | Weekday::Sun))
+                .map(|day| Candle {
+                    timestamp: format!("{}T09:15:00+0530", day_str(day)),
+                    open: 100.0,
+                    high: 101.0,
+                    low: 99.0,
+                    close: 100.5,
+                    volume: 1000,
+                    oi: None,
+                })
+                .collect())
+        }
+    }
+
+    impl MockFetcher {
+        fn take_calls(&self) -> Vec<(NaiveDate, NaiveDate)> {
+            self.calls.take()
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch() -> Result<(), Box<dyn std::error::Error>> {
+        let cache = CandleCache::open_in_memory()?;
+        let fetcher = MockFetcher::default();
+        let interval = Interval::Day;
+
+        // 2024-01-01 is a Monday
+        let candles = cache
+            .get_or_fetch(&fetcher, 1, interval, date(2024, 1, 1)..=date(2024, 1, 10))
+            .await?;
+        assert_eq!(candles.len(), 8);
+        assert_eq!(
+            fetcher.take_calls(),
+            [(date(2024, 1, 1), date(2024, 1, 10))]
+        );
+
+        let candles = cache
+            .get_or_fetch(&fetcher, 1, interval, date(2024, 1, 5)..=date(2024, 1, 15))
+            .await?;
+        assert_eq!(candles.len(), 7);
+        assert!(candles.is_sorted_by(|a, b| a.timestamp < b.timestamp));
+        assert_eq!(candles[0].timestamp, "2024-01-05T09:15:00+0530");
+        assert_eq!(
+            fetcher.take_calls(),
+            [(date(2024, 1, 11), date(2024, 1, 15))]
+        );
+
+        // Weekends are cached as empty days
+        cache
+            .get_or_fetch(&fetcher, 1, interval, date(2024, 1, 6)..=date(2024, 1, 7))
+            .await?;
+        assert!(fetcher.take_calls().is_empty());
+
+        // Other tokens and intervals are cached separately
+        cache
+            .get_or_fetch(&fetcher, 2, interval, date(2024, 1, 1)..=date(2024, 1, 1))
+            .await?;
+        cache
+            .get_or_fetch(
+                &fetcher,
+                1,
+                Interval::Minute,
+                date(2024, 1, 1)..=date(2024, 1, 1),
+            )
+            .await?;
+        assert_eq!(fetcher.take_calls().len(), 2);
+
+        cache.invalidate(1, interval, date(2024, 1, 2)..=date(2024, 1, 3))?;
+        let candles = cache
+            .get_or_fetch(&fetcher, 1, interval, date(2024, 1, 1)..=date(2024, 1, 15))
+            .await?;
+        assert_eq!(candles.len(), 11);
+        assert_eq!(fetcher.take_calls(), [(date(2024, 1, 2), date(2024, 1, 3))]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_today_is_refetched() -> Result<(), Box<dyn std::error::Error>> {
+        let cache = CandleCache::open_in_memory()?;
+        let fetcher = MockFetcher::default();
+        let today = today_ist();
+        let yesterday = today.pred_opt().unwrap();
+
+        let first = cache
+            .get_or_fetch(&fetcher, 1, Interval::Day, yesterday..=today)
+            .await?;
+        assert_eq!(fetcher.take_calls(), [(yesterday, today)]);
+
+        let second = cache
+            .get_or_fetch(&fetcher, 1, Interval::Day, yesterday..=today)
+            .await?;
+        assert_eq!(fetcher.take_calls(), [(today, today)]);
+        assert_eq!(first, second);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_runs() {
+        let days: Vec<_> = date(2024, 1, 1)
+            .iter_days()
+            .take(150)
+            .filter(|day| *day != date(2024, 1, 10))
+            .collect();
+
+        assert_eq!(
+            runs(&days, 60),
+            [
+                (date(2024, 1, 1), date(2024, 1, 9)),
+                (date(2024, 1, 11), date(2024, 3, 10)),
+                (date(2024, 3, 11), date(2024, 5, 9)),
+                (date(2024, 5, 10), date(2024, 5, 29)),
+            ]
+        );
+        assert!(runs(&[], 60).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_persistence_and_recovery() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("candles.sqlite");
+        let fetcher = MockFetcher::default();
+        let range = date(2024, 1, 1)..=date(2024, 1, 5);
+
+        {
+            let cache = CandleCache::open(&path)?;
+            cache
+                .get_or_fetch(&fetcher, 1, Interval::Day, range.clone())
+                .await?;
+        }
+
+        let cache = CandleCache::open(&path)?;
+        assert_eq!(cache.len()?, 5);
+        cache
+            .get_or_fetch(&fetcher, 1, Interval::Day, range.clone())
+            .await?;
+        assert_eq!(fetcher.take_calls().len(), 1);
+        drop(cache);
+
+        std::fs::write(
+            &path,
+            b"definitely not an sqlite database, just some garbage bytes",
+        )?;
+        let cache = CandleCache::open(&path)?;
+        assert!(cache.is_empty()?);
+        assert_eq!(
+            cache
+                .get_or_fetch(&fetcher, 1, Interval::Day, range)
+                .await?
+                .len(),
+            5
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,532 @@
+//! Live OHLCV candles built from the ticks of the WebSocket.
+//!
+//! [`CandleAggregator`] buckets [full mode](crate::ws::ReqMode::Full) ticks by their exchange
+//! timestamp, the other modes have no timestamp and are ignored. Buckets are aligned on the
+//! 09:15 IST session open like the [historical candles](crate::historical), and
+//! [`Interval::Day`] buckets on IST midnight. The volume of a candle is the increase of
+//! `volume_traded` over its ticks.
+//!
+//! The latest bucket seen on any instrument is the clock of the aggregator: when a tick starts a
+//! new bucket, the candles of every instrument in the previous buckets are complete and emitted.
+//! Specifically:
+//! - A tick of a bucket older than the clock is late and dropped. Since `volume_traded` is
+//!   cumulative, its volume is still counted in the following candle of the instrument.
+//! - A tick older than the previous tick of the instrument in the same bucket only extends the
+//!   high and the low of the candle.
+//! - The first tick of an instrument only sets the baseline of its volume, so its first candle
+//!   doesn't count the volume traded earlier in the day.
+//! - `volume_traded` decreasing is the counter being reset at the start of a session, the whole
+//!   new `volume_traded` is then counted.
+//! - An instrument without ticks in a bucket has no candle for it. With
+//!   [`EmptyBuckets::FillWithPreviousClose`], it gets a candle at its previous close if another
+//!   instrument ticked in the bucket: buckets without any tick, e.g. overnight, are never filled.
+//!
+//! ```no_run
+//! # async fn run(kite: kite_connect::KiteConnect<kite_connect::Authenticated>) -> Result<(), kite_connect::Error> {
+//! use kite_connect::candle_aggregator::CandleAggregator;
+//! use kite_connect::historical::Interval;
+//! use kite_connect::ws::{Req, ReqMode};
+//!
+//! let (mut ticker, ticks) = kite.web_socket().await?;
+//! ticker.send(Req::Subscribe(&[408065])).await?;
+//! ticker
+//!     .send(Req::Mode {
+//!         mode: ReqMode::Full,
+//!         instrument_tokens: &[408065],
+//!     })
+//!     .await?;
+//!
+//! let (tx, candles) = crossbeam_channel::unbounded();
+//! let mut aggregator = CandleAggregator::new().with_interval(Interval::FiveMinute);
+//! std::thread::spawn(move || aggregator.run(&ticks, &tx));
+//!
+//! for live in candles {
+//!     println!("{} {:?}", live.instrument_token, live.candle);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::BTreeMap;
+
+use crate::historical::{Candle, Interval};
+use crate::utils::IST_OFFSET_SECS;
+use crate::ws::{FullQuote, Ticker};
+
+/// 09:15 IST on 1970-01-01, as a Unix timestamp.
+const SESSION_OPEN_SECS: i64 = 3 * 3600 + 45 * 60;
+const DAY_SECS: i64 = 24 * 3600;
+
+/// What to emit for the buckets in which an instrument didn't tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyBuckets {
+    /// No candle
+    #[default]
+    Skip,
+    /// A candle at the previous close with no volume, for the buckets in which other instruments
+    /// ticked
+    FillWithPreviousClose,
+}
+
+/// A completed candle of an instrument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveCandle {
+    pub instrument_token: u32,
+    pub candle: Candle,
+}
+
+/// Aggregates ticks into candles, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    interval: Interval,
+    empty_buckets: EmptyBuckets,
+    /// Start of the latest bucket seen
+    clock: Option<i64>,
+    instruments: BTreeMap<u32, InstrumentState>,
+}
+
+#[derive(Debug, Clone)]
+struct InstrumentState {
+    last_timestamp: u32,
+    last_volume: u32,
+    oi: u32,
+    bar: Option<Bar>,
+    /// Bucket and close of the last emitted candle
+    last_emitted: Option<(i64, f64)>,
+}
+
+#[derive(Debug, Clone)]
+struct Bar {
+    bucket: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: i64,
+}
+
+impl Default for CandleAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CandleAggregator {
+    /// Aggregates ticks into 1 minute candles, skipping empty buckets.
+    pub fn new() -> Self {
+        Self {
+            interval: Interval::Minute,
+            empty_buckets: EmptyBuckets::default(),
+            clock: None,
+            instruments: BTreeMap::new(),
+        }
+    }
+
+    /// Aggregates ticks into candles of `interval`.
+    pub fn with_interval(mut self, interval: Interval) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_empty_buckets(mut self, empty_buckets: EmptyBuckets) -> Self {
+        self.empty_buckets = empty_buckets;
+        self
+    }
+
+    /// Processes a tick, returning the candles it completed ordered by instrument.
+    pub fn process(&mut self, tick: &Ticker) -> Vec<LiveCandle> {
+        let Ticker::FullQuote(quote) = tick else {
+            return Vec::new();
+        };
+
+        let bucket = self.bucket_of(quote.exchange_timestamp);
+        let mut completed = Vec::new();
+        match self.clock {
+            Some(clock) if bucket < clock => return completed,
+            Some(clock) if bucket == clock => {}
+            _ => {
+                completed = self.close_clock();
+                self.clock = Some(bucket);
+            }
+        }
+
+        self.update(quote, bucket);
+        completed
+    }
+
+    /// Emits the candles still open, e.g. at the end of the session. The following ticks of
+    /// their buckets are dropped.
+    pub fn flush(&mut self) -> Vec<LiveCandle> {
+        self.close_clock()
+    }
+
+    /// Processes every tick received on `ticks`, sending the completed candles to `candles`.
+    ///
+    /// Flushes the open candles once `ticks` is disconnected. Returns then, or once `candles` has
+    /// no receiver left.
+    pub fn run(&mut self, ticks: &Receiver<Ticker>, candles: &Sender<LiveCandle>) {
+        for tick in ticks {
+            for candle in self.process(&tick) {
+                if candles.send(candle).is_err() {
+                    return;
+                }
+            }
+        }
+
+        for candle in self.flush() {
+            if candles.send(candle).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn update(&mut self, quote: &FullQuote, bucket: i64) {
+        let price = quote.quote.last_price;
+        let volume_traded = quote.quote.volume_traded;
+
+        let Some(state) = self.instruments.get_mut(&quote.quote.instrument_token) else {
+            self.instruments.insert(
+                quote.quote.instrument_token,
+                InstrumentState {
+                    last_timestamp: quote.exchange_timestamp,
+                    last_volume: volume_traded,
+                    oi: quote.oi,
+                    bar: Some(Bar::new(bucket, price, 0)),
+                    last_emitted: None,
+                },
+            );
+            return;
+        };
+
+        if state
+            .last_emitted
+            .is_some_and(|(emitted, _)| bucket <= emitted)
+        {
+            // The bucket was flushed already
+            return;
+        }
+
+        if quote.exchange_timestamp < state.last_timestamp {
+            if let Some(bar) = &mut state.bar {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+            }
+            return;
+        }
+
+        let volume = if volume_traded >= state.last_volume {
+            volume_traded - state.last_volume
+        } else {
+            volume_traded
+        };
+        state.last_timestamp = quote.exchange_timestamp;
+        state.last_volume = volume_traded;
+        state.oi = quote.oi;
+
+        match &mut state.bar {
+            Some(bar) => {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += i64::from(volume);
+            }
+            None => state.bar = Some(Bar::new(bucket, price, volume)),
+        }
+    }
+
+    /// Emits the candles of the bucket of the clock, with the empty ones if filled.
+    fn close_clock(&mut self) -> Vec<LiveCandle> {
+        let Some(clock) = self.clock else {
+            return Vec::new();
+        };
+
+        let mut completed = Vec::new();
+        for (&instrument_token, state) in &mut self.instruments {
+            let bar = match (state.bar.take(), state.last_emitted) {
+                (Some(bar), _) => bar,
+                (None, Some((emitted, close)))
+                    if emitted < clock
+                        && self.empty_buckets == EmptyBuckets::FillWithPreviousClose =>
+                {
+                    Bar::new(clock, close, 0)
+                }
+                _ => continue,
+            };
+
+            state.last_emitted = Some((bar.bucket, bar.close));
+            completed.push(LiveCandle {
+                instrument_token,
+                candle: bar.candle(state.oi),
+            });
+        }
+        completed
+    }
+
+    fn bucket_len(&self) -> i64 {
+        self.interval
+            .duration()
+            .map_or(DAY_SECS, |duration| duration.as_secs() as i64)
+    }
+
+    fn bucket_of(&self, exchange_timestamp: u32) -> i64 {
+        let anchor = match self.interval {
+            Interval::Day => -IST_OFFSET_SECS,
+            _ => SESSION_OPEN_SECS,
+        };
+        let len = self.bucket_len();
+
+        anchor + (i64::from(exchange_timestamp) - anchor).div_euclid(len) * len
+    }
+}
+
+impl Bar {
+    fn new(bucket: i64, price: f64, volume: u32) -> Self {
+        Self {
+            bucket,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: i64::from(volume),
+        }
+    }
+
+    fn candle(&self, oi: u32) -> Candle {
+        let (date, time) = crate::utils::ist_date_time(self.bucket);
+
+        Candle {
+            timestamp: format!("{date}T{time}+0530"),
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            oi: (oi > 0).then_some(i64::from(oi)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::Ohlc;
+    use crate::ws::PartialQuote;
+
+    const INFY: u32 = 408065;
+    const TCS: u32 = 2953217;
+    /// 2024-01-03 09:15:00 IST
+    const OPEN: u32 = 1_704_253_500;
+
+    fn tick(instrument_token: u32, secs: u32, last_price: f64, volume_traded: u32) -> Ticker {
+        Ticker::FullQuote(FullQuote {
+            quote: PartialQuote {
+                instrument_token,
+                last_price,
+                last_traded_quantity: 1,
+                average_traded_price: last_price,
+                volume_traded,
+                total_buy_quantity: 0,
+                total_sell_quantity: 0,
+                ohlc: Ohlc {
+                    open: last_price,
+                    high: last_price,
+                    low: last_price,
+                    close: last_price,
+                },
+            },
+            last_trade_time: OPEN + secs,
+            oi: 0,
+            oi_day_high: 0,
+            oi_day_low: 0,
+            exchange_timestamp: OPEN + secs,
+            depth: Default::default(),
+        })
+    }
+
+    /// Timestamp, OHLC and volume of each candle of `instrument_token`.
+    fn bars(candles: &[LiveCandle], instrument_token: u32) -> Vec<(&str, [f64; 4], i64)> {
+        candles
+            .iter()
+            .filter(|c| c.instrument_token == instrument_token)
+            .map(|LiveCandle { candle: c, .. }| {
+                (
+                    c.timestamp.as_str(),
+                    [c.open, c.high, c.low, c.close],
+                    c.volume,
+                )
+            })
+            .collect()
+    }
+
+    fn aggregate(aggregator: &mut CandleAggregator, ticks: &[Ticker]) -> Vec<LiveCandle> {
+        let mut candles: Vec<_> = ticks.iter().flat_map(|t| aggregator.process(t)).collect();
+        candles.extend(aggregator.flush());
+        candles
+    }
+
+    #[test]
+    fn test_minute_candles() {
+        let mut aggregator = CandleAggregator::new();
+        let candles = aggregate(
+            &mut aggregator,
+            &[
+                tick(INFY, 0, 100.0, 1000),
+                tick(INFY, 20, 102.0, 1100),
+                tick(INFY, 40, 99.0, 1150),
+                tick(INFY, 59, 101.0, 1200),
+                tick(INFY, 60, 101.5, 1260),
+                tick(INFY, 119, 100.5, 1300),
+            ],
+        );
+
+        // The first tick is the volume baseline
+        assert_eq!(
+            bars(&candles, INFY),
+            [
+                ("2024-01-03T09:15:00+0530", [100.0, 102.0, 99.0, 101.0], 200),
+                (
+                    "2024-01-03T09:16:00+0530",
+                    [101.5, 101.5, 100.5, 100.5],
+                    100
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rollover_on_any_instrument() {
+        let mut aggregator = CandleAggregator::new().with_interval(Interval::FiveMinute);
+
+        assert!(aggregator.process(&tick(INFY, 10, 100.0, 10)).is_empty());
+        assert!(aggregator.process(&tick(TCS, 290, 3800.0, 10)).is_empty());
+
+        // A tick of TCS in the next bucket completes the candle of INFY too
+        let candles = aggregator.process(&tick(TCS, 300, 3801.0, 20));
+        assert_eq!(
+            candles
+                .iter()
+                .map(|c| (c.instrument_token, c.candle.timestamp.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (INFY, "2024-01-03T09:15:00+0530"),
+                (TCS, "2024-01-03T09:15:00+0530")
+            ]
+        );
+
+        let candles = aggregator.flush();
+        assert_eq!(
+            bars(&candles, TCS),
+            [("2024-01-03T09:20:00+0530", [3801.0; 4], 10)]
+        );
+        assert!(aggregator.flush().is_empty());
+    }
+
+    #[test]
+    fn test_out_of_order_ticks() {
+        let mut aggregator = CandleAggregator::new();
+        let candles = aggregate(
+            &mut aggregator,
+            &[
+                tick(INFY, 0, 100.0, 1000),
+                tick(INFY, 30, 101.0, 1100),
+                // Older than the previous tick: only extends the range
+                tick(INFY, 20, 97.0, 1050),
+                tick(INFY, 61, 102.0, 1200),
+                // The first bucket is complete, dropped
+                tick(INFY, 59, 90.0, 1150),
+                tick(INFY, 62, 103.0, 1300),
+            ],
+        );
+
+        assert_eq!(
+            bars(&candles, INFY),
+            [
+                ("2024-01-03T09:15:00+0530", [100.0, 101.0, 97.0, 101.0], 100),
+                (
+                    "2024-01-03T09:16:00+0530",
+                    [102.0, 103.0, 102.0, 103.0],
+                    200
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_volume_reset() {
+        let mut aggregator = CandleAggregator::new().with_interval(Interval::Day);
+        let day = 24 * 3600;
+        let candles = aggregate(
+            &mut aggregator,
+            &[
+                tick(INFY, 0, 100.0, 5000),
+                tick(INFY, 3600, 101.0, 9000),
+                // The counter starts again the next day
+                tick(INFY, day, 102.0, 300),
+                tick(INFY, day + 60, 103.0, 700),
+            ],
+        );
+
+        assert_eq!(
+            bars(&candles, INFY),
+            [
+                (
+                    "2024-01-03T00:00:00+0530",
+                    [100.0, 101.0, 100.0, 101.0],
+                    4000
+                ),
+                (
+                    "2024-01-04T00:00:00+0530",
+                    [102.0, 103.0, 102.0, 103.0],
+                    700
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_buckets() {
+        let ticks = [
+            tick(INFY, 0, 100.0, 10),
+            tick(TCS, 30, 3800.0, 10),
+            tick(TCS, 150, 3805.0, 20),
+            tick(INFY, 190, 101.0, 30),
+            // Next day, not filled overnight
+            tick(INFY, 24 * 3600, 102.0, 5),
+        ];
+
+        let mut aggregator = CandleAggregator::new();
+        let candles = aggregate(&mut aggregator, &ticks);
+        assert_eq!(
+            bars(&candles, INFY),
+            [
+                ("2024-01-03T09:15:00+0530", [100.0; 4], 0),
+                ("2024-01-03T09:18:00+0530", [101.0; 4], 20),
+                ("2024-01-04T09:15:00+0530", [102.0; 4], 5),
+            ]
+        );
+
+        // Only the buckets in which the other instrument ticked, not 09:16 nor overnight
+        let mut aggregator =
+            CandleAggregator::new().with_empty_buckets(EmptyBuckets::FillWithPreviousClose);
+        let candles = aggregate(&mut aggregator, &ticks);
+        assert_eq!(
+            bars(&candles, INFY),
+            [
+                ("2024-01-03T09:15:00+0530", [100.0; 4], 0),
+                ("2024-01-03T09:17:00+0530", [100.0; 4], 0),
+                ("2024-01-03T09:18:00+0530", [101.0; 4], 20),
+                ("2024-01-04T09:15:00+0530", [102.0; 4], 5),
+            ]
+        );
+        assert_eq!(
+            bars(&candles, TCS),
+            [
+                ("2024-01-03T09:15:00+0530", [3800.0; 4], 0),
+                ("2024-01-03T09:17:00+0530", [3805.0; 4], 10),
+                ("2024-01-03T09:18:00+0530", [3805.0; 4], 0),
+                ("2024-01-04T09:15:00+0530", [3805.0; 4], 0),
+            ]
+        );
+    }
+}
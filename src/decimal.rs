@@ -0,0 +1,94 @@
+//! Decimal views of the `f64` price and amount fields, behind the `decimal` feature.
+//!
+//! Conversions go through the shortest representation that parses back to the same `f64`. A
+//! JSON number with at most 15 significant digits always comes back as the token on the wire,
+//! without the binary floating point artifacts that show up when doing arithmetic on the `f64`
+//! directly.
+//!
+//! Tokens with 16 or 17 significant digits only come back unchanged when they already are the
+//! shortest representation of their `f64`, as with `-629.2999999999993`. Otherwise the
+//! conversion returns a shorter decimal for the same `f64`, e.g. `0.10000000000000001` becomes
+//! `0.1`.
+
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Converts a value parsed from a JSON number back to the decimal that was sent, see the
+/// [module docs](self) for the limits on precision.
+///
+/// Returns `None` for values a [`Decimal`] can't hold: infinities, NaN and magnitudes beyond
+/// [`Decimal::MAX`].
+///
+/// Every price and amount field returned by the API also has a `*_decimal()` accessor built on
+/// this, e.g. [`Quote::last_price_decimal`](crate::quotes::Quote::last_price_decimal).
+pub trait ToDecimal {
+    type Output;
+
+    fn to_decimal(&self) -> Self::Output;
+}
+
+impl ToDecimal for f64 {
+    type Output = Option<Decimal>;
+
+    fn to_decimal(&self) -> Option<Decimal> {
+        // `Display` for f64 prints the shortest string that round-trips, without an exponent
+        Decimal::from_str(&self.to_string())
+            .or_else(|_| Decimal::try_from(*self))
+            .ok()
+    }
+}
+
+impl ToDecimal for Option<f64> {
+    type Output = Option<Decimal>;
+
+    fn to_decimal(&self) -> Option<Decimal> {
+        self.as_ref().and_then(ToDecimal::to_decimal)
+    }
+}
+
+/// Generates `*_decimal()` accessors for the listed `f64`/`Option<f64>` fields.
+macro_rules! decimal_accessors {
+    ($ty:ty { $($field:ident => $getter:ident -> $out:ty),* $(,)? }) => {
+        impl $ty {
+            $(
+                #[doc = concat!("`", stringify!($field), "` as a [`Decimal`](rust_decimal::Decimal), see [`ToDecimal`](crate::decimal::ToDecimal).")]
+                pub fn $getter(&self) -> $out {
+                    $crate::decimal::ToDecimal::to_decimal(&self.$field)
+                }
+            )*
+        }
+    };
+}
+
+pub(crate) use decimal_accessors;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_decimal() {
+        for token in [
+            "-629.2999999999993",
+            "1412.95",
+            "0.05",
+            "0.1",
+            "99999999.99",
+            "0.0000001",
+            "0",
+        ] {
+            let value: f64 = serde_json::from_str(token).unwrap();
+            assert_eq!(value.to_decimal(), Some(Decimal::from_str(token).unwrap()));
+        }
+
+        // 17 significant digits, the same `f64` as `0.1`
+        let value: f64 = serde_json::from_str("0.10000000000000001").unwrap();
+        assert_eq!(value.to_decimal(), Some(Decimal::from_str("0.1").unwrap()));
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1e30, -1e30] {
+            assert_eq!(value.to_decimal(), None, "{value}");
+        }
+
+        assert_eq!(None::<f64>.to_decimal(), None);
+    }
+}
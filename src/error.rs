@@ -31,6 +31,54 @@ pub enum KiteError {
     UnknownError(String, String),
 }
 
+/// How an error returned by Kite should be handled, see [`KiteError::category`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The request may succeed if retried after a short delay
+    Transient,
+    /// The request will keep failing until it's changed, don't retry it
+    Permanent,
+    /// The session is no longer valid, the user has to log in again
+    Auth,
+}
+
+impl KiteError {
+    /// Classifies the error by how it should be handled.
+    ///
+    /// | Variant | Category |
+    /// |---|---|
+    /// | `NetworkException`, `DataException` | [`ErrorCategory::Transient`] |
+    /// | `TokenException` | [`ErrorCategory::Auth`] |
+    /// | everything else, including `UnknownError` | [`ErrorCategory::Permanent`] |
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            KiteError::NetworkException(_) | KiteError::DataException(_) => {
+                ErrorCategory::Transient
+            }
+            KiteError::TokenException(_) => ErrorCategory::Auth,
+            KiteError::UserException(_)
+            | KiteError::OrderException(_)
+            | KiteError::InputException(_)
+            | KiteError::MarginException(_)
+            | KiteError::HoldingException(_)
+            | KiteError::GeneralException(_)
+            | KiteError::PermissionException(_)
+            | KiteError::UnknownError(..) => ErrorCategory::Permanent,
+        }
+    }
+
+    /// Returns `true` if the session has expired or was invalidated, and the user has to log in
+    /// again.
+    pub fn is_token_expired(&self) -> bool {
+        self.category() == ErrorCategory::Auth
+    }
+
+    /// Returns `true` if the request may succeed when retried.
+    pub fn is_retryable(&self) -> bool {
+        self.category() == ErrorCategory::Transient
+    }
+}
+
 impl From<(String, String)> for KiteError {
     fn from(value: (String, String)) -> Self {
         let (error_type, message) = value;
@@ -114,11 +162,26 @@ pub enum Error {
     InvalidAccessToken,
 
     /// Error related to IO
-    #[cfg(feature = "auto_auth")]
     IoError(std::io::Error),
 
     /// Error indicating that the request timed out.
     RequestTimeOut,
+
+    /// Error indicating that a postback's checksum doesn't match the payload.
+    InvalidChecksum,
+
+    /// Error indicating that the request was cancelled with the client's
+    /// [cancellation token](crate::KiteConnect::with_cancellation_token).
+    Cancelled,
+
+    /// Error returned by a middleware of the client set with
+    /// [`KiteConnect::with_middleware_client`](crate::KiteConnect::with_middleware_client).
+    #[cfg(feature = "middleware")]
+    Middleware(Box<dyn std::error::Error + Send + Sync>),
+
+    /// Error originating from the SQLite candle cache.
+    #[cfg(feature = "cache")]
+    Cache(rusqlite::Error),
 }
 
 impl Display for Error {
@@ -138,15 +201,31 @@ impl Display for Error {
                 f,
                 "Error indicating that the provided access token could not be converted to a header value."
             ),
-            #[cfg(feature = "auto_auth")]
             Error::IoError(e) => write!(f, "IO error: {e}"),
             Error::RequestTimeOut => write!(f, "Error indicating that the request timed out."),
+            Error::InvalidChecksum => write!(
+                f,
+                "Error indicating that a postback's checksum doesn't match the payload."
+            ),
+            Error::Cancelled => write!(f, "Error indicating that the request was cancelled."),
+            #[cfg(feature = "middleware")]
+            Error::Middleware(e) => write!(f, "Error originating from a request middleware. {e}"),
+            #[cfg(feature = "cache")]
+            Error::Cache(e) => write!(f, "Error originating from the SQLite candle cache. {e}"),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+impl Error {
+    /// Creates a locally generated [`KiteError::InputException`], mirroring the error Kite would
+    /// have returned for the same invalid input.
+    pub(crate) fn input_exception(message: impl Into<String>) -> Self {
+        Self::KiteError(KiteError::InputException(message.into()))
+    }
+}
+
 impl From<KiteError> for Error {
     fn from(value: KiteError) -> Self {
         Self::KiteError(value)
@@ -171,6 +250,30 @@ impl From<csv::Error> for Error {
     }
 }
 
+#[cfg(feature = "arrow")]
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(value: arrow_schema::ArrowError) -> Self {
+        Self::Serde(Box::new(value))
+    }
+}
+
+#[cfg(feature = "cache")]
+impl From<rusqlite::Error> for Error {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Cache(value)
+    }
+}
+
+#[cfg(feature = "middleware")]
+impl From<reqwest_middleware::Error> for Error {
+    fn from(value: reqwest_middleware::Error) -> Self {
+        match value {
+            reqwest_middleware::Error::Reqwest(e) => e.into(),
+            reqwest_middleware::Error::Middleware(e) => Self::Middleware(e.into()),
+        }
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
         if value.is_timeout() {
@@ -193,9 +296,45 @@ impl From<reqwest::header::InvalidHeaderValue> for Error {
     }
 }
 
-#[cfg(feature = "auto_auth")]
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
         Self::IoError(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_category() {
+        let m = || String::from("message");
+        let table = [
+            (KiteError::TokenException(m()), ErrorCategory::Auth),
+            (KiteError::UserException(m()), ErrorCategory::Permanent),
+            (KiteError::OrderException(m()), ErrorCategory::Permanent),
+            (KiteError::InputException(m()), ErrorCategory::Permanent),
+            (KiteError::MarginException(m()), ErrorCategory::Permanent),
+            (KiteError::HoldingException(m()), ErrorCategory::Permanent),
+            (KiteError::NetworkException(m()), ErrorCategory::Transient),
+            (KiteError::DataException(m()), ErrorCategory::Transient),
+            (KiteError::GeneralException(m()), ErrorCategory::Permanent),
+            (
+                KiteError::PermissionException(m()),
+                ErrorCategory::Permanent,
+            ),
+            (
+                KiteError::UnknownError("SomeException".into(), m()),
+                ErrorCategory::Permanent,
+            ),
+        ];
+
+        for (err, category) in table {
+            assert_eq!(err.category(), category, "{err}");
+        }
+
+        assert!(KiteError::TokenException(m()).is_token_expired());
+        assert!(!KiteError::NetworkException(m()).is_token_expired());
+        assert!(KiteError::NetworkException(m()).is_retryable());
+    }
+}
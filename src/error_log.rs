@@ -0,0 +1,173 @@
+//! Opt-in logging of failed API calls.
+//!
+//! With the `tracing` feature enabled, every [`Response::Error`](crate::Response::Error) and every
+//! HTTP-level failure is emitted as a single `ERROR` event on the `kite_connect::errors` target with
+//! the fields `endpoint`, `tag`, `error_type`, `message`, `http_status` and `elapsed_ms`.
+//!
+//! Only the fields listed above are ever logged. The `Authorization` header is never part of an
+//! event, and the API secret and access token are scrubbed from free-text fields in case Kite
+//! echoes them back.
+#![cfg_attr(not(feature = "tracing"), allow(dead_code))]
+
+use std::time::Duration;
+
+use crate::utils::AuthInfo;
+
+/// The tracing target used for error events.
+pub const ERROR_LOG_TARGET: &str = "kite_connect::errors";
+
+const REDACTED: &str = "[REDACTED]";
+
+pub(crate) struct ErrorContext<'a> {
+    endpoint: &'a str,
+    tag: Option<&'a str>,
+    auth_info: &'a AuthInfo,
+}
+
+impl<'a> ErrorContext<'a> {
+    pub(crate) fn new(endpoint: &'a str, tag: Option<&'a str>, auth_info: &'a AuthInfo) -> Self {
+        Self {
+            endpoint,
+            tag,
+            auth_info,
+        }
+    }
+
+    /// Logs a response whose body was a Kite error envelope.
+    pub(crate) fn log_kite_error(
+        &self,
+        http_status: u16,
+        error_type: &str,
+        message: &str,
+        elapsed: Duration,
+    ) {
+        self.emit(Some(http_status), Some(error_type), message, elapsed);
+    }
+
+    /// Logs a failure that happened before a Kite error envelope could be read, such as a
+    /// connection error, a timeout or an unparseable body.
+    pub(crate) fn log_http_failure(
+        &self,
+        http_status: Option<u16>,
+        message: &str,
+        elapsed: Duration,
+    ) {
+        self.emit(http_status, None, message, elapsed);
+    }
+
+    #[cfg(feature = "tracing")]
+    fn emit(
+        &self,
+        http_status: Option<u16>,
+        error_type: Option<&str>,
+        message: &str,
+        elapsed: Duration,
+    ) {
+        tracing::error!(
+            target: ERROR_LOG_TARGET,
+            endpoint = %self.redact(self.endpoint),
+            tag = self.tag,
+            error_type,
+            message = %self.redact(message),
+            http_status,
+            elapsed_ms = elapsed.as_millis() as u64,
+        );
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    fn emit(&self, _: Option<u16>, _: Option<&str>, _: &str, _: Duration) {}
+
+    /// Replaces every credential known to this client with a placeholder.
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+
+        for secret in [
+            self.auth_info.authentication_header(),
+            self.auth_info.api_secret(),
+            self.auth_info.access_token(),
+        ] {
+            if !secret.is_empty() {
+                text = text.replace(secret, REDACTED);
+            }
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth_info() -> AuthInfo {
+        let mut auth_info = AuthInfo::new("my_api_key".into(), "my_api_secret".into());
+        auth_info.update_access_token("my_access_token".into());
+        auth_info
+    }
+
+    #[test]
+    fn test_redact() {
+        let auth_info = auth_info();
+        let ctx = ErrorContext::new(
+            "POST https://api.kite.trade/orders/regular",
+            None,
+            &auth_info,
+        );
+
+        let redacted = ctx.redact("token my_api_key:my_access_token, secret my_api_secret");
+        assert_eq!(redacted, "[REDACTED], secret [REDACTED]");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_emitted_fields() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+
+        let auth_info = auth_info();
+        tracing::subscriber::with_default(subscriber, || {
+            let ctx = ErrorContext::new(
+                "POST https://api.kite.trade/orders/regular",
+                Some("strat1"),
+                &auth_info,
+            );
+            ctx.log_kite_error(
+                400,
+                "InputException",
+                "Invalid `api_key` or `access_token`: my_access_token",
+                Duration::from_millis(42),
+            );
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains(ERROR_LOG_TARGET));
+        assert!(output.contains("endpoint=POST https://api.kite.trade/orders/regular"));
+        assert!(output.contains("tag=\"strat1\""));
+        assert!(output.contains("error_type=\"InputException\""));
+        assert!(output.contains("http_status=400"));
+        assert!(output.contains("elapsed_ms=42"));
+        assert!(output.contains("[REDACTED]"));
+        assert!(!output.contains("my_access_token"));
+        assert!(!output.contains("my_api_secret"));
+    }
+}
@@ -0,0 +1,180 @@
+//! CSV export of the order book and trade book.
+//!
+//! Columns are written in the order of [`ORDERS_CSV_HEADER`] and [`TRADES_CSV_HEADER`], which only
+//! change in breaking releases. Fields are quoted only when needed, so free-text such as
+//! `status_message_raw` can safely contain commas, quotes and newlines. Kite's `YYYY-MM-DD HH:MM:SS`
+//! timestamps are written as ISO 8601 with the IST offset, e.g. `2021-05-31T09:18:57+05:30`;
+//! anything else (such as the time-only `order_timestamp` of trades) is written as received.
+
+use serde::Serialize;
+use std::io::Write;
+
+use crate::Error;
+use crate::orders::{Order, Trade};
+
+/// Header of the CSV written by [`write_orders_csv`].
+pub const ORDERS_CSV_HEADER: [&str; 25] = [
+    "order_id",
+    "parent_order_id",
+    "exchange_order_id",
+    "placed_by",
+    "variety",
+    "status",
+    "tradingsymbol",
+    "exchange",
+    "instrument_token",
+    "transaction_type",
+    "order_type",
+    "product",
+    "validity",
+    "price",
+    "trigger_price",
+    "average_price",
+    "quantity",
+    "filled_quantity",
+    "pending_quantity",
+    "cancelled_quantity",
+    "order_timestamp",
+    "exchange_timestamp",
+    "status_message",
+    "status_message_raw",
+    "tag",
+];
+
+/// Header of the CSV written by [`write_trades_csv`].
+pub const TRADES_CSV_HEADER: [&str; 13] = [
+    "trade_id",
+    "order_id",
+    "exchange_order_id",
+    "tradingsymbol",
+    "exchange",
+    "instrument_token",
+    "transaction_type",
+    "product",
+    "average_price",
+    "quantity",
+    "fill_timestamp",
+    "order_timestamp",
+    "exchange_timestamp",
+];
+
+/// Writes the orders as CSV, with [`ORDERS_CSV_HEADER`] as the header row.
+pub fn write_orders_csv<W: Write>(orders: &[Order], w: W) -> Result<(), Error> {
+    let mut wtr = csv::Writer::from_writer(w);
+    wtr.write_record(ORDERS_CSV_HEADER)?;
+
+    for o in orders {
+        wtr.write_record([
+            o.order_id.to_string(),
+            opt(&o.parent_order_id),
+            opt(&o.exchange_order_id),
+            o.placed_by.clone(),
+            enum_str(&o.variety),
+            enum_str(&o.status),
+            o.trading_symbol.clone(),
+            o.exchange.to_string(),
+            o.instrument_token.clone(),
+            enum_str(&o.transaction_type),
+            enum_str(&o.order_type),
+            enum_str(&o.product),
+            enum_str(&o.validity),
+            opt(&o.price),
+            opt(&o.trigger_price),
+            opt(&o.average_price),
+            o.quantity.to_string(),
+            o.filled_quantity.to_string(),
+            o.pending_quantity.to_string(),
+            o.cancelled_quantity.to_string(),
+            iso_timestamp(&o.order_timestamp),
+            o.exchange_timestamp
+                .as_deref()
+                .map(iso_timestamp)
+                .unwrap_or_default(),
+            opt(&o.status_message),
+            opt(&o.status_message_raw),
+            opt(&o.tag),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes the trades as CSV, with [`TRADES_CSV_HEADER`] as the header row.
+pub fn write_trades_csv<W: Write>(trades: &[Trade], w: W) -> Result<(), Error> {
+    let mut wtr = csv::Writer::from_writer(w);
+    wtr.write_record(TRADES_CSV_HEADER)?;
+
+    for t in trades {
+        wtr.write_record([
+            t.trade_id.to_string(),
+            t.order_id.to_string(),
+            opt(&t.exchange_order_id),
+            t.trading_symbol.clone(),
+            t.exchange.to_string(),
+            t.instrument_token.clone(),
+            enum_str(&t.transaction_type),
+            enum_str(&t.product),
+            t.average_price.to_string(),
+            t.quantity.to_string(),
+            iso_timestamp(&t.fill_timestamp),
+            iso_timestamp(&t.order_timestamp),
+            t.exchange_timestamp
+                .as_deref()
+                .map(iso_timestamp)
+                .unwrap_or_default(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+fn opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(T::to_string).unwrap_or_default()
+}
+
+/// The name Kite uses for the variant, e.g. `SL-M` for [`OrderType::SL_M`](crate::orders::OrderType::SL_M).
+fn enum_str<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        _ => String::new(),
+    }
+}
+
+/// Converts `YYYY-MM-DD HH:MM:SS` in IST to ISO 8601, leaving any other format untouched.
+fn iso_timestamp(ts: &str) -> String {
+    let b = ts.as_bytes();
+    let is_kite_format = b.len() == 19
+        && b.iter().enumerate().all(|(i, c)| match i {
+            4 | 7 => *c == b'-',
+            10 => *c == b' ',
+            13 | 16 => *c == b':',
+            _ => c.is_ascii_digit(),
+        });
+
+    if is_kite_format {
+        format!("{}T{}+05:30", &ts[..10], &ts[11..])
+    } else {
+        ts.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso_timestamp() {
+        assert_eq!(
+            iso_timestamp("2021-05-31 09:18:57"),
+            "2021-05-31T09:18:57+05:30"
+        );
+        assert_eq!(iso_timestamp("09:18:57"), "09:18:57");
+        assert_eq!(
+            iso_timestamp("2021-05-31T09:18:57+05:30"),
+            "2021-05-31T09:18:57+05:30"
+        );
+        assert_eq!(iso_timestamp(""), "");
+    }
+}
@@ -0,0 +1,568 @@
+//! GTT - Good Till Triggered orders
+//!
+//! Reference: <https://kite.trade/docs/connect/v3/gtt/>
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::orders::{Exchange, OrderType, Product, TransactionType, round_to_tick};
+use crate::portfolio::Position;
+
+use super::*;
+
+pub const GTT_TRIGGERS_ENDPOINT: &str = "https://api.kite.trade/gtt/triggers";
+
+/// Minimum interval between two requests of [`KiteConnect::delete_gtts`], Kite allows 10 requests
+/// per second.
+const DELETE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tick size used by [`gtt_for_position`], the tick of almost every NSE/BSE equity.
+pub const DEFAULT_TICK_SIZE: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GttType {
+    /// A single trigger placing one order
+    #[serde(rename = "single")]
+    Single,
+    /// An OCO (One Cancels Other) trigger with a lower and an upper trigger, e.g. a stop-loss
+    /// and a target
+    #[serde(rename = "two-leg")]
+    TwoLeg,
+}
+
+/// Condition checked against the last price of the instrument.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GttCondition {
+    pub exchange: Exchange,
+    /// Exchange tradingsymbol of the instrument
+    #[serde(rename = "tradingsymbol")]
+    pub trading_symbol: String,
+    /// One trigger price for [`GttType::Single`], the lower then the upper trigger price for
+    /// [`GttType::TwoLeg`]
+    pub trigger_values: Vec<f64>,
+    /// Last price of the instrument when the GTT is placed
+    pub last_price: f64,
+}
+
+/// Order placed when a trigger is hit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GttOrder {
+    pub exchange: Exchange,
+    /// Exchange tradingsymbol of the instrument
+    #[serde(rename = "tradingsymbol")]
+    pub trading_symbol: String,
+    pub transaction_type: TransactionType,
+    pub quantity: u32,
+    /// Only LIMIT orders are accepted
+    pub order_type: OrderType,
+    pub product: Product,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GttStatus {
+    Active,
+    Triggered,
+    Disabled,
+    Expired,
+    Cancelled,
+    Rejected,
+    Deleted,
+}
+
+/// Id of a GTT, as opposed to the ids of the orders it places.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct TriggerId(pub u64);
+
+impl Display for TriggerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for TriggerId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl From<u64> for TriggerId {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TriggerId> for u64 {
+    fn from(value: TriggerId) -> Self {
+        value.0
+    }
+}
+
+/// A GTT as returned by [`KiteConnect::get_gtts`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GttTrigger {
+    /// Trigger id
+    pub id: TriggerId,
+    #[serde(rename = "type")]
+    pub gtt_type: GttType,
+    pub status: GttStatus,
+    pub condition: GttCondition,
+    pub orders: Vec<GttOrder>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Timestamp after which the trigger isn't checked anymore
+    pub expires_at: String,
+}
+
+impl GttTrigger {
+    /// Whether the trigger is marked as expired, or past its `expires_at` at `now`.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        match self.status {
+            GttStatus::Expired => true,
+            GttStatus::Deleted => false,
+            // Timestamps have the same format, so they compare as strings
+            _ => self.expires_at < ist_timestamp(now),
+        }
+    }
+
+    /// Returns `trigger - last_price` for each trigger value, positive when the trigger is above
+    /// `last_price`. Divide by `last_price` for the distance as a fraction of the price.
+    pub fn distance_to_trigger(&self, last_price: f64) -> Vec<f64> {
+        self.condition
+            .trigger_values
+            .iter()
+            .map(|trigger| trigger - last_price)
+            .collect()
+    }
+
+    /// Whether a trade at `last_price` hits a trigger.
+    ///
+    /// A two-leg trigger fires at or below its lower value, or at or above its upper value. A
+    /// single trigger fires once the price reaches it from the side of the last price the GTT was
+    /// placed at, [`GttCondition::last_price`].
+    pub fn would_fire(&self, last_price: f64) -> bool {
+        match (self.gtt_type, self.condition.trigger_values.as_slice()) {
+            (GttType::TwoLeg, [lower, upper, ..]) => last_price <= *lower || last_price >= *upper,
+            (_, [trigger, ..]) if *trigger >= self.condition.last_price => last_price >= *trigger,
+            (_, [trigger, ..]) => last_price <= *trigger,
+            (_, []) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaceGttRequest {
+    pub gtt_type: GttType,
+    pub condition: GttCondition,
+    /// One order per trigger value, in the same order
+    pub orders: Vec<GttOrder>,
+}
+
+impl PlaceGttRequest {
+    /// Kite takes the condition and orders as JSON encoded form fields.
+    fn form(&self) -> Result<[(&'static str, String); 3], Error> {
+        let gtt_type = match self.gtt_type {
+            GttType::Single => "single",
+            GttType::TwoLeg => "two-leg",
+        };
+
+        Ok([
+            ("type", gtt_type.to_owned()),
+            ("condition", serde_json::to_string(&self.condition)?),
+            ("orders", serde_json::to_string(&self.orders)?),
+        ])
+    }
+}
+
+/// Builds a two-leg GTT exiting `position` at a stop-loss or a target, whichever is hit first.
+///
+/// `stop_pct` and `target_pct` are percentages of `last_price` (`2.0` is 2%): for a long position
+/// the stop-loss is below and the target above `last_price`, for a short position the other way
+/// around. The exit orders are LIMIT orders at the trigger prices for the whole open quantity,
+/// with the product of the position. Prices are rounded to [`DEFAULT_TICK_SIZE`].
+///
+/// Returns an [`KiteError::InputException`] if the position is flat, or the percentages don't give
+/// a stop-loss and a target on either side of `last_price`.
+pub fn gtt_for_position(
+    position: &Position,
+    stop_pct: f64,
+    target_pct: f64,
+    last_price: f64,
+) -> Result<PlaceGttRequest, Error> {
+    if position.quantity == 0 {
+        return Err(Error::input_exception(format!(
+            "No open quantity in {}",
+            position.trading_symbol
+        )));
+    }
+    if !(last_price.is_finite() && last_price > 0.0) {
+        return Err(Error::input_exception(format!(
+            "Invalid last price {last_price}"
+        )));
+    }
+    for (name, pct) in [("stop_pct", stop_pct), ("target_pct", target_pct)] {
+        if !(pct.is_finite() && pct > 0.0) {
+            return Err(Error::input_exception(format!(
+                "`{name}` must be a positive percentage, got {pct}"
+            )));
+        }
+    }
+
+    let long = position.quantity > 0;
+    let (lower_pct, upper_pct) = if long {
+        (stop_pct, target_pct)
+    } else {
+        (target_pct, stop_pct)
+    };
+
+    let lower = round_to_tick(last_price * (1.0 - lower_pct / 100.0), DEFAULT_TICK_SIZE);
+    let upper = round_to_tick(last_price * (1.0 + upper_pct / 100.0), DEFAULT_TICK_SIZE);
+
+    if lower <= 0.0 || lower >= last_price || upper <= last_price {
+        return Err(Error::input_exception(format!(
+            "Triggers {lower} and {upper} aren't on either side of the last price {last_price}"
+        )));
+    }
+
+    let order = |price| GttOrder {
+        exchange: position.exchange,
+        trading_symbol: position.trading_symbol.clone(),
+        transaction_type: if long {
+            TransactionType::Sell
+        } else {
+            TransactionType::Buy
+        },
+        quantity: position.quantity.unsigned_abs() as u32,
+        order_type: OrderType::Limit,
+        product: position.product,
+        price,
+    };
+
+    Ok(PlaceGttRequest {
+        gtt_type: GttType::TwoLeg,
+        condition: GttCondition {
+            exchange: position.exchange,
+            trading_symbol: position.trading_symbol.clone(),
+            trigger_values: vec![lower, upper],
+            last_price,
+        },
+        orders: vec![order(lower), order(upper)],
+    })
+}
+
+/// Formats `time` in IST, in the `YYYY-MM-DD HH:MM:SS` format of GTT timestamps.
+fn ist_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let (date, time) = crate::utils::ist_date_time(secs);
+
+    format!("{date} {time}")
+}
+
+/// Data of the responses to GTT placements, modifications and deletions.
+#[derive(Deserialize)]
+struct TriggerIdData {
+    trigger_id: TriggerId,
+}
+
+impl KiteConnect<Authenticated> {
+    /// Places a GTT and returns its trigger id.
+    pub async fn place_gtt(&self, req: &PlaceGttRequest) -> Result<TriggerId, Error> {
+        Ok(self
+            .send::<TriggerIdData>(self.client.post(GTT_TRIGGERS_ENDPOINT).form(&req.form()?))
+            .await?
+            .trigger_id)
+    }
+
+    /// Returns the GTTs of the account, active or not.
+    pub async fn get_gtts(&self) -> Result<Vec<GttTrigger>, Error> {
+        self.send(self.client.get(GTT_TRIGGERS_ENDPOINT)).await
+    }
+
+    /// Returns a single GTT.
+    pub async fn get_gtt(&self, trigger_id: TriggerId) -> Result<GttTrigger, Error> {
+        self.send(
+            self.client
+                .get(format!("{GTT_TRIGGERS_ENDPOINT}/{trigger_id}")),
+        )
+        .await
+    }
+
+    /// Replaces the condition and orders of an active GTT and returns its trigger id.
+    pub async fn modify_gtt(
+        &self,
+        trigger_id: TriggerId,
+        req: &PlaceGttRequest,
+    ) -> Result<TriggerId, Error> {
+        Ok(self
+            .send::<TriggerIdData>(
+                self.client
+                    .put(format!("{GTT_TRIGGERS_ENDPOINT}/{trigger_id}"))
+                    .form(&req.form()?),
+            )
+            .await?
+            .trigger_id)
+    }
+
+    /// Deletes a GTT and returns its trigger id.
+    pub async fn delete_gtt(&self, trigger_id: TriggerId) -> Result<TriggerId, Error> {
+        Ok(self
+            .send::<TriggerIdData>(
+                self.client
+                    .delete(format!("{GTT_TRIGGERS_ENDPOINT}/{trigger_id}")),
+            )
+            .await?
+            .trigger_id)
+    }
+
+    /// Deletes several GTTs, one at a time and at most 10 per second, returning the result of each
+    /// deletion in the order of `trigger_ids`.
+    ///
+    /// Once the client's [cancellation token](KiteConnect::with_cancellation_token) is cancelled,
+    /// the remaining deletions fail with [`Error::Cancelled`] without waiting.
+    pub async fn delete_gtts(&self, trigger_ids: &[TriggerId]) -> Vec<Result<TriggerId, Error>> {
+        let mut results = Vec::with_capacity(trigger_ids.len());
+
+        for (i, &trigger_id) in trigger_ids.iter().enumerate() {
+            if i > 0 {
+                // Cut short on cancellation, the next request then fails right away
+                let _ = self.cancellable(tokio::time::sleep(DELETE_INTERVAL)).await;
+            }
+            results.push(self.delete_gtt(trigger_id).await);
+        }
+
+        results
+    }
+
+    /// Deletes the [expired](GttTrigger::is_expired) GTTs, see [`KiteConnect::delete_gtts`].
+    ///
+    /// Returns an error if the GTTs can't be fetched, otherwise the result of each deletion.
+    pub async fn delete_expired_gtts(&self) -> Result<Vec<Result<TriggerId, Error>>, Error> {
+        let now = SystemTime::now();
+        let expired: Vec<TriggerId> = self
+            .get_gtts()
+            .await?
+            .iter()
+            .filter(|gtt| gtt.is_expired(now))
+            .map(|gtt| gtt.id)
+            .collect();
+
+        Ok(self.delete_gtts(&expired).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(quantity: i64) -> Position {
+        serde_json::from_value(serde_json::json!({
+            "tradingsymbol": "INFY",
+            "exchange": "NSE",
+            "instrument_token": 408065,
+            "product": "CNC",
+            "quantity": quantity,
+            "overnight_quantity": quantity,
+            "multiplier": 1,
+            "average_price": 1400.0,
+            "close_price": 0.0,
+            "last_price": 1412.95,
+            "value": 0.0,
+            "pnl": 0.0,
+            "m2m": 0.0,
+            "unrealised": 0.0,
+            "realised": 0.0,
+            "buy_quantity": 0,
+            "buy_price": 0.0,
+            "buy_value": 0.0,
+            "buy_m2m": 0.0,
+            "day_buy_quantity": 0,
+            "day_buy_price": 0.0,
+            "day_buy_value": 0.0,
+            "sell_quantity": 0,
+            "sell_price": 0.0,
+            "sell_value": 0.0,
+            "sell_m2m": 0.0,
+            "day_sell_quantity": 0,
+            "day_sell_price": 0.0,
+            "day_sell_value": 0.0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_gtt_for_long_position() -> Result<(), Box<dyn std::error::Error>> {
+        let req = gtt_for_position(&position(10), 2.0, 5.0, 1412.95)?;
+
+        assert_eq!(req.gtt_type, GttType::TwoLeg);
+        // 1412.95 * 0.98 = 1384.691, 1412.95 * 1.05 = 1483.5975
+        assert_eq!(req.condition.trigger_values, [1384.7, 1483.6]);
+        assert!(
+            req.orders
+                .iter()
+                .all(|o| o.transaction_type == TransactionType::Sell
+                    && o.quantity == 10
+                    && o.product == Product::CNC)
+        );
+        assert_eq!(req.orders[0].price, 1384.7);
+        assert_eq!(req.orders[1].price, 1483.6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gtt_for_short_position() -> Result<(), Box<dyn std::error::Error>> {
+        let req = gtt_for_position(&position(-25), 1.0, 3.0, 200.0)?;
+
+        // The target is the lower leg and the stop-loss the upper one
+        assert_eq!(req.condition.trigger_values, [194.0, 202.0]);
+        assert!(
+            req.orders
+                .iter()
+                .all(|o| o.transaction_type == TransactionType::Buy && o.quantity == 25)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gtt_for_position_validation() {
+        assert!(gtt_for_position(&position(0), 2.0, 5.0, 1412.95).is_err());
+        assert!(gtt_for_position(&position(10), -2.0, 5.0, 1412.95).is_err());
+        assert!(gtt_for_position(&position(10), 2.0, f64::NAN, 1412.95).is_err());
+        assert!(gtt_for_position(&position(10), 2.0, 5.0, 0.0).is_err());
+        // A 100% stop-loss on a long position would trigger at 0
+        assert!(gtt_for_position(&position(10), 100.0, 5.0, 1412.95).is_err());
+        assert!(gtt_for_position(&position(-10), 100.0, 5.0, 1412.95).is_ok());
+        // Rounds back to the last price
+        assert!(gtt_for_position(&position(10), 0.001, 5.0, 10.0).is_err());
+    }
+
+    #[test]
+    fn test_ist_timestamp() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(ist_timestamp(time), "2023-11-15 03:43:20");
+        assert_eq!(ist_timestamp(UNIX_EPOCH), "1970-01-01 05:30:00");
+        // Leap day
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_231_400);
+        assert_eq!(ist_timestamp(time), "2024-03-01 00:00:00");
+    }
+
+    #[cfg(feature = "test-utils")]
+    #[test]
+    fn test_gtt_is_expired() -> Result<(), Box<dyn std::error::Error>> {
+        let gtts: Vec<GttTrigger> =
+            serde_json::from_str::<Response<_>>(crate::test_utils::fixtures::GTTS)?
+                .into_result()?;
+        // 2023-11-15 03:43:20 IST
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let expired: Vec<_> = gtts
+            .iter()
+            .map(|gtt| (gtt.id.0, gtt.status, gtt.is_expired(now)))
+            .collect();
+        assert_eq!(
+            expired,
+            [
+                (112127, GttStatus::Active, false),
+                (105099, GttStatus::Triggered, true),
+                (98712, GttStatus::Expired, true),
+                (98713, GttStatus::Active, false),
+            ]
+        );
+        // Past its expiry a month later
+        let now = now + Duration::from_secs(60 * 86400);
+        assert!(gtts[3].is_expired(now));
+
+        Ok(())
+    }
+
+    fn trigger(gtt_type: GttType, trigger_values: Vec<f64>, last_price: f64) -> GttTrigger {
+        GttTrigger {
+            id: TriggerId(1),
+            gtt_type,
+            status: GttStatus::Active,
+            condition: GttCondition {
+                exchange: Exchange::NSE,
+                trading_symbol: "INFY".into(),
+                trigger_values,
+                last_price,
+            },
+            orders: Vec::new(),
+            created_at: "2023-11-15 09:15:00".into(),
+            updated_at: "2023-11-15 09:15:00".into(),
+            expires_at: "2024-11-15 09:15:00".into(),
+        }
+    }
+
+    #[test]
+    fn test_single_trigger_preview() {
+        // Placed as a buy stop above the price
+        let above = trigger(GttType::Single, vec![1500.0], 1412.95);
+        assert_eq!(above.distance_to_trigger(1450.0), [50.0]);
+        assert!(!above.would_fire(1499.95));
+        assert!(above.would_fire(1500.0));
+
+        // Placed as a stop-loss below the price
+        let below = trigger(GttType::Single, vec![1350.0], 1412.95);
+        assert_eq!(below.distance_to_trigger(1400.0), [-50.0]);
+        assert!(!below.would_fire(1500.0));
+        assert!(below.would_fire(1349.0));
+    }
+
+    #[test]
+    fn test_two_leg_trigger_preview() {
+        let gtt = trigger(GttType::TwoLeg, vec![1384.7, 1483.6], 1412.95);
+
+        let distances = gtt.distance_to_trigger(1400.0);
+        assert!((distances[0] + 15.3).abs() < 1e-9);
+        assert!((distances[1] - 83.6).abs() < 1e-9);
+
+        assert!(!gtt.would_fire(1400.0));
+        assert!(gtt.would_fire(1384.7));
+        assert!(gtt.would_fire(1490.0));
+    }
+
+    #[test]
+    fn test_place_gtt_form() -> Result<(), Box<dyn std::error::Error>> {
+        let req = gtt_for_position(&position(10), 2.0, 5.0, 1412.95)?;
+        let form = serde_urlencoded::to_string(req.form()?)?;
+        let decoded: Vec<(String, String)> = serde_urlencoded::from_str(&form)?;
+
+        assert_eq!(decoded[0], ("type".into(), "two-leg".into()));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&decoded[1].1)?,
+            serde_json::json!({
+                "exchange": "NSE",
+                "tradingsymbol": "INFY",
+                "trigger_values": [1384.7, 1483.6],
+                "last_price": 1412.95
+            })
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&decoded[2].1)?[0],
+            serde_json::json!({
+                "exchange": "NSE",
+                "tradingsymbol": "INFY",
+                "transaction_type": "SELL",
+                "quantity": 10,
+                "order_type": "LIMIT",
+                "product": "CNC",
+                "price": 1384.7
+            })
+        );
+
+        Ok(())
+    }
+}
@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::time::Duration;
 
 use super::*;
+use crate::orders::Exchange;
 
 pub const GET_HISTORICAL_CANDLE_ENDPOINT: &str = "https://api.kite.trade/instruments/historical/";
 
+/// Minimum interval between two requests of [`KiteConnect::get_historical_data_bulk`], Kite allows
+/// 3 requests per second on the historical endpoint.
+const BULK_INTERVAL: Duration = Duration::from_millis(334);
+
 /// The format string used for candle timestamps.
 pub const CANDLE_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z";
 
@@ -42,11 +48,239 @@ impl Display for Interval {
     }
 }
 
+impl Interval {
+    /// Returns the length of a candle, or `None` for [`Interval::Day`] whose candles span the
+    /// trading day.
+    pub fn duration(&self) -> Option<std::time::Duration> {
+        let minutes = match self {
+            Interval::Minute => 1,
+            Interval::ThreeMinute => 3,
+            Interval::FiveMinute => 5,
+            Interval::TenMinute => 10,
+            Interval::FifteenMinute => 15,
+            Interval::ThirtyMinute => 30,
+            Interval::SixtyMinute => 60,
+            Interval::Day => return None,
+        };
+
+        Some(std::time::Duration::from_secs(minutes * 60))
+    }
+
+    /// Maximum number of days Kite returns in a single historical data request of this interval.
+    pub const fn max_days(&self) -> u64 {
+        match self {
+            Interval::Minute => 60,
+            Interval::ThreeMinute | Interval::FiveMinute | Interval::TenMinute => 100,
+            Interval::FifteenMinute | Interval::ThirtyMinute => 200,
+            Interval::SixtyMinute => 400,
+            Interval::Day => 2000,
+        }
+    }
+}
+
+/// Returns whether Kite serves `days` days of `interval` candles for the instruments of
+/// `exchange` in a single request.
+///
+/// Mutual funds ([`Exchange::MF`]) have no candles, since they trade at their NAV once a day.
+/// Every other exchange serves every interval, up to [`Interval::max_days`] days per request:
+///
+/// | Interval | Days |
+/// |----------|------|
+/// | minute | 60 |
+/// | 3minute, 5minute, 10minute | 100 |
+/// | 15minute, 30minute | 200 |
+/// | 60minute | 400 |
+/// | day | 2000 |
+///
+/// Check it before [`KiteConnect::get_historical_data`] to skip a request bound to fail with a
+/// generic error, or split the range into requests of at most [`Interval::max_days`] days.
+pub const fn interval_available_for(exchange: Exchange, interval: Interval, days: u64) -> bool {
+    !matches!(exchange, Exchange::MF) && days <= interval.max_days()
+}
+
+#[cfg(feature = "chrono")]
+impl Interval {
+    /// Start of the trading session in IST.
+    const SESSION_OPEN: (u32, u32) = (9, 15);
+    /// End of the trading session in IST.
+    const SESSION_CLOSE: (u32, u32) = (15, 30);
+
+    /// Estimates the number of candles Kite returns between `from` and `to` (IST).
+    ///
+    /// Every weekday is counted as a trading day with a 09:15 to 15:30 (375 minutes) session,
+    /// holidays and special sessions such as Muhurat trading aren't accounted for. Use it to keep
+    /// requests under the 2000 candle limit.
+    pub fn candles_between(&self, from: chrono::NaiveDateTime, to: chrono::NaiveDateTime) -> usize {
+        use chrono::{Datelike, NaiveTime, Weekday};
+
+        let minutes = self.duration().map_or(0, |d| d.as_secs() as usize / 60);
+
+        let session = |(h, m)| NaiveTime::from_hms_opt(h, m, 0).unwrap_or_default();
+        let (open, close) = (session(Self::SESSION_OPEN), session(Self::SESSION_CLOSE));
+
+        from.date()
+            .iter_days()
+            .take_while(|day| *day <= to.date())
+            .filter(|day| !matches!(day.weekday(), Weekday::Sat | Weekday::Sun))
+            .map(|day| {
+                let start = day.and_time(open).max(from);
+                let end = day.and_time(close).min(to);
+                let traded = (end - start).num_minutes();
+
+                match minutes {
+                    _ if traded <= 0 => 0,
+                    0 => 1,
+                    m => (traded as usize).div_ceil(m),
+                }
+            })
+            .sum()
+    }
+}
+
+/// Returns the start of the `interval` candle containing `timestamp` (IST).
+///
+/// Intraday candles are aligned on the 09:15 open of the equity session, e.g. 09:17 is in the 09:15
+/// five minute candle and 10:20 in the 10:15 hourly candle. Timestamps before the open align to it.
+/// Daily candles start at midnight.
+#[cfg(feature = "chrono")]
+pub fn align_to_session(
+    timestamp: chrono::NaiveDateTime,
+    interval: Interval,
+) -> chrono::NaiveDateTime {
+    let Some(duration) = interval.duration() else {
+        return timestamp.date().and_time(chrono::NaiveTime::MIN);
+    };
+
+    let (h, m) = Interval::SESSION_OPEN;
+    let open = timestamp
+        .date()
+        .and_time(chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap_or_default());
+    if timestamp <= open {
+        return open;
+    }
+
+    let step = duration.as_secs() as i64;
+    let elapsed = (timestamp - open).num_seconds();
+    open + chrono::Duration::seconds(elapsed - elapsed % step)
+}
+
+/// Finds the candles missing between the first and the last of `candles`, e.g. to flag incomplete
+/// downloads before a backtest.
+///
+/// `candles` must be sorted, as returned by Kite. The expected candles are the ones in the sessions
+/// of `exchange` according to `hours`, so pass the exchange holidays to not report them as gaps.
+/// Returns the timestamp of the first missing candle of each gap and the number of missing
+/// candles, a gap spanning the close and the next open counting as one.
+#[cfg(feature = "chrono")]
+pub fn find_gaps(
+    candles: &[Candle],
+    interval: Interval,
+    exchange: crate::orders::Exchange,
+    hours: &crate::market_hours::MarketHours,
+) -> Vec<(chrono::NaiveDateTime, usize)> {
+    use chrono::Days;
+
+    let next_expected = |ts: chrono::NaiveDateTime| match interval.duration() {
+        Some(duration) => {
+            let next = ts + chrono::Duration::seconds(duration.as_secs() as i64);
+            match hours.session_bounds(exchange, next.date()) {
+                Some(session) if session.contains(next) => Some(next),
+                _ => hours.next_open(exchange, next),
+            }
+        }
+        None => (1..=366)
+            .filter_map(|days| ts.date().checked_add_days(Days::new(days)))
+            .find(|&date| hours.session_bounds(exchange, date).is_some())
+            .map(|date| date.and_time(chrono::NaiveTime::MIN)),
+    };
+
+    let timestamps: Vec<_> = candles
+        .iter()
+        .filter_map(|c| crate::utils::parse_kite_timestamp(&c.timestamp).ok())
+        .map(|ts| ts.naive_local())
+        .collect();
+
+    let mut gaps = Vec::new();
+    for pair in timestamps.windows(2) {
+        let (prev, next) = (pair[0], pair[1]);
+
+        let mut missing = 0;
+        let mut expected = next_expected(prev);
+        let first = expected;
+        while let Some(ts) = expected
+            && ts < next
+        {
+            missing += 1;
+            expected = next_expected(ts);
+        }
+
+        if let Some(first) = first
+            && missing > 0
+        {
+            gaps.push((first, missing));
+        }
+    }
+
+    gaps
+}
+
+/// Candles returned outside the requested range, see [`candles_out_of_range`].
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfRange {
+    pub from: chrono::NaiveDateTime,
+    pub to: chrono::NaiveDateTime,
+    /// Timestamp of the first candle returned
+    pub first: chrono::NaiveDateTime,
+    /// Timestamp of the last candle returned
+    pub last: chrono::NaiveDateTime,
+}
+
+/// Checks that `candles` are within the `from`/`to` range of `req`, give or take a candle.
+///
+/// Returns the range and the first and last timestamps when they're further out, which means the
+/// candles don't answer the request. Fewer candles than the range could hold (holidays, halts) or
+/// none at all are fine, as are a `from`/`to` that can't be parsed. Requests accept both the
+/// `yyyy-mm-dd hh:mm:ss` and `yyyy-mm-dd` formats.
+#[cfg(feature = "chrono")]
+pub fn candles_out_of_range(
+    candles: &[Candle],
+    req: &HistoricalCandleReq,
+    interval: Interval,
+) -> Option<OutOfRange> {
+    let parse = |s: &str| {
+        crate::utils::parse_kite_timestamp(s)
+            .ok()
+            .map(|ts| ts.naive_local())
+    };
+
+    let from = parse(&req.from)?;
+    let to = parse(&req.to)?;
+    let first = parse(&candles.first()?.timestamp)?;
+    let last = parse(&candles.last()?.timestamp)?;
+
+    let slack = interval.duration().map_or(chrono::Duration::days(1), |d| {
+        chrono::Duration::seconds(d.as_secs() as i64)
+    });
+
+    (first < from - slack || last > to + slack).then_some(OutOfRange {
+        from,
+        to,
+        first,
+        last,
+    })
+}
+
+/// Range of candles to fetch.
+///
+/// Kite reads `from` and `to` in IST, whatever the timezone of the caller: a time taken from a
+/// clock in another timezone shifts the candles returned. With the `chrono` feature,
+/// [`HistoricalCandleReq::between`] converts the range to IST.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct HistoricalCandleReq {
-    /// `yyyy-mm-dd hh:mm:ss` formatted date indicating the start date of records
+    /// `yyyy-mm-dd hh:mm:ss` formatted date indicating the start date of records, in IST
     pub from: String,
-    /// `yyyy-mm-dd hh:mm:ss` formatted date indicating the end date of records
+    /// `yyyy-mm-dd hh:mm:ss` formatted date indicating the end date of records, in IST
     pub to: String,
     /// pass `true` to get continuous data
     pub continuous: bool,
@@ -54,7 +288,34 @@ pub struct HistoricalCandleReq {
     pub oi: bool,
 }
 
-#[derive(Debug, Serialize, PartialEq, Clone)]
+#[cfg(feature = "chrono")]
+impl HistoricalCandleReq {
+    /// Creates a request for the candles between `from` and `to`, without continuous or OI data.
+    ///
+    /// The times can be in any timezone, they're converted to IST.
+    pub fn between<Tz: chrono::TimeZone>(
+        from: &chrono::DateTime<Tz>,
+        to: &chrono::DateTime<Tz>,
+    ) -> Self {
+        let ist = crate::utils::ist();
+        let format = |time: &chrono::DateTime<Tz>| {
+            time.with_timezone(&ist)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        };
+
+        Self {
+            from: format(from),
+            to: format(to),
+            continuous: false,
+            oi: false,
+        }
+    }
+}
+
+/// A candle, (de)serialized in the array form of the API:
+/// `[timestamp, open, high, low, close, volume]`, followed by `oi` when present.
+#[derive(Debug, PartialEq, Clone)]
 pub struct Candle {
     pub timestamp: String,
     pub open: f64,
@@ -65,6 +326,67 @@ pub struct Candle {
     pub oi: Option<i64>,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Candle {
+    open => open_decimal -> Option<Decimal>,
+    high => high_decimal -> Option<Decimal>,
+    low => low_decimal -> Option<Decimal>,
+    close => close_decimal -> Option<Decimal>,
+});
+
+impl Candle {
+    /// Typical price of the candle, `(high + low + close) / 3`.
+    pub fn typical_price(&self) -> f64 {
+        (self.high + self.low + self.close) / 3.0
+    }
+
+    /// Prices of the candle, e.g. for [`Ohlc::is_bullish`](crate::quotes::Ohlc::is_bullish).
+    pub fn ohlc(&self) -> crate::quotes::Ohlc {
+        crate::quotes::Ohlc {
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+        }
+    }
+}
+
+/// Volume weighted average of the typical price over all `candles`, e.g. the VWAP of a session.
+///
+/// Returns `None` if no volume was traded. See [`indicators::vwap`](crate::indicators::vwap) for
+/// the running VWAP after each candle.
+pub fn vwap(candles: &[Candle]) -> Option<f64> {
+    let (price_volume, volume) = candles.iter().fold((0.0, 0.0), |(pv, v), c| {
+        (
+            pv + c.typical_price() * c.volume as f64,
+            v + c.volume as f64,
+        )
+    });
+
+    (volume > 0.0).then(|| price_volume / volume)
+}
+
+impl Serialize for Candle {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(if self.oi.is_some() { 7 } else { 6 }))?;
+        seq.serialize_element(&self.timestamp)?;
+        seq.serialize_element(&self.open)?;
+        seq.serialize_element(&self.high)?;
+        seq.serialize_element(&self.low)?;
+        seq.serialize_element(&self.close)?;
+        seq.serialize_element(&self.volume)?;
+        if let Some(oi) = self.oi {
+            seq.serialize_element(&oi)?;
+        }
+        seq.end()
+    }
+}
+
 impl<'de> Deserialize<'de> for Candle {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -107,6 +429,15 @@ impl<'de> Deserialize<'de> for Candle {
 }
 
 impl KiteConnect<Authenticated> {
+    /// Returns the candles of `instrument_token` between `req.from` and `req.to`.
+    ///
+    /// A range without any data, e.g. only holidays or an instrument that didn't trade, returns an
+    /// empty `Vec`, not an error. Invalid requests, such as an unknown token or a range too long
+    /// for `interval`, return an `Err` with the error of Kite. Ranges with halts or holidays
+    /// return fewer candles, see [`find_gaps`] to find them.
+    ///
+    /// With the `chrono` and `tracing` features, a warning is logged when the candles returned are
+    /// outside the requested range, see [`candles_out_of_range`].
     pub async fn get_historical_data(
         &self,
         instrument_token: u32,
@@ -125,18 +456,60 @@ impl KiteConnect<Authenticated> {
             ("oi", bool_to_int_str_impl(req.oi)),
         ];
 
-        Ok(self
-            .client
-            .get(format!(
-                "{GET_HISTORICAL_CANDLE_ENDPOINT}{instrument_token}/{interval}"
-            ))
-            .query(&q)
-            .send()
-            .await?
-            .json::<Response<Candles>>()
+        let candles = self
+            .send::<Candles>(
+                self.client
+                    .get(format!(
+                        "{GET_HISTORICAL_CANDLE_ENDPOINT}{instrument_token}/{interval}"
+                    ))
+                    .query(&q),
+            )
             .await?
-            .into_result()?
-            .candles)
+            .candles;
+
+        #[cfg(all(feature = "chrono", feature = "tracing"))]
+        if let Some(out) = candles_out_of_range(&candles, &req, interval) {
+            tracing::warn!(
+                instrument_token,
+                %interval,
+                from = %out.from,
+                to = %out.to,
+                first = %out.first,
+                last = %out.last,
+                "Historical candles outside the requested range"
+            );
+        }
+
+        Ok(candles)
+    }
+
+    /// Fetches the candles of several instruments over the same range, one request at a time and
+    /// at most 3 per second, returning the result of each instrument in the order of
+    /// `instrument_tokens`.
+    ///
+    /// An error on an instrument doesn't stop the others from being fetched. Once the client's
+    /// [cancellation token](KiteConnect::with_cancellation_token) is cancelled, the remaining
+    /// instruments fail with [`Error::Cancelled`] without waiting.
+    pub async fn get_historical_data_bulk(
+        &self,
+        instrument_tokens: &[u32],
+        interval: Interval,
+        req: &HistoricalCandleReq,
+    ) -> Vec<Result<Vec<Candle>, Error>> {
+        let mut results = Vec::with_capacity(instrument_tokens.len());
+
+        for (i, &instrument_token) in instrument_tokens.iter().enumerate() {
+            if i > 0 {
+                // Cut short on cancellation, the next request then fails right away
+                let _ = self.cancellable(tokio::time::sleep(BULK_INTERVAL)).await;
+            }
+            results.push(
+                self.get_historical_data(instrument_token, interval, req.clone())
+                    .await,
+            );
+        }
+
+        results
     }
 }
 
@@ -153,6 +526,246 @@ mod tests {
         candles: Vec<Candle>,
     }
 
+    #[test]
+    fn test_interval_available_for() {
+        for (interval, max_days) in [
+            (Interval::Minute, 60),
+            (Interval::ThreeMinute, 100),
+            (Interval::FiveMinute, 100),
+            (Interval::TenMinute, 100),
+            (Interval::FifteenMinute, 200),
+            (Interval::ThirtyMinute, 200),
+            (Interval::SixtyMinute, 400),
+            (Interval::Day, 2000),
+        ] {
+            for exchange in [
+                Exchange::NSE,
+                Exchange::BSE,
+                Exchange::NFO,
+                Exchange::BFO,
+                Exchange::CDS,
+                Exchange::BCD,
+                Exchange::MCX,
+                Exchange::NCO,
+            ] {
+                assert!(
+                    interval_available_for(exchange, interval, max_days),
+                    "{exchange} {interval}"
+                );
+                assert!(
+                    !interval_available_for(exchange, interval, max_days + 1),
+                    "{exchange} {interval}"
+                );
+            }
+            assert!(
+                !interval_available_for(Exchange::MF, interval, 1),
+                "{interval}"
+            );
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_candles_between() {
+        let at = |d, h, m| {
+            chrono::NaiveDate::from_ymd_opt(2024, 1, d)
+                .and_then(|d| d.and_hms_opt(h, m, 0))
+                .unwrap()
+        };
+
+        // 2024-01-01 is a Monday
+        let (open, close) = (at(1, 9, 15), at(1, 15, 30));
+        assert_eq!(Interval::Minute.candles_between(open, close), 375);
+        assert_eq!(Interval::FiveMinute.candles_between(open, close), 75);
+        assert_eq!(Interval::SixtyMinute.candles_between(open, close), 7);
+        assert_eq!(Interval::Day.candles_between(open, close), 1);
+
+        // Whole days, partial sessions and weekends
+        assert_eq!(
+            Interval::Minute.candles_between(at(1, 0, 0), at(1, 23, 59)),
+            375
+        );
+        assert_eq!(
+            Interval::FifteenMinute.candles_between(at(1, 14, 0), at(2, 10, 15)),
+            6 + 4
+        );
+        assert_eq!(
+            Interval::Minute.candles_between(at(6, 0, 0), at(7, 23, 59)),
+            0
+        );
+        assert_eq!(Interval::Day.candles_between(at(1, 0, 0), at(14, 0, 0)), 10);
+        assert_eq!(Interval::Minute.candles_between(close, open), 0);
+    }
+
+    #[test]
+    fn test_interval_duration() {
+        use std::time::Duration;
+
+        assert_eq!(Interval::Minute.duration(), Some(Duration::from_secs(60)));
+        assert_eq!(
+            Interval::SixtyMinute.duration(),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(Interval::Day.duration(), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_req_between() {
+        use chrono::{FixedOffset, TimeZone, Utc};
+
+        // 03:45 UTC is the 09:15 IST session open
+        let req = HistoricalCandleReq::between(
+            &Utc.with_ymd_and_hms(2024, 1, 2, 3, 45, 0).unwrap(),
+            &Utc.with_ymd_and_hms(2024, 1, 2, 18, 30, 0).unwrap(),
+        );
+        assert_eq!(req.from, "2024-01-02 09:15:00");
+        assert_eq!(req.to, "2024-01-03 00:00:00");
+
+        let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+        let req = HistoricalCandleReq::between(
+            &new_york.with_ymd_and_hms(2024, 1, 1, 22, 45, 0).unwrap(),
+            &new_york.with_ymd_and_hms(2024, 1, 2, 5, 0, 0).unwrap(),
+        );
+        assert_eq!(req.from, "2024-01-02 09:15:00");
+        assert_eq!(req.to, "2024-01-02 15:30:00");
+    }
+
+    #[cfg(feature = "chrono")]
+    fn at(d: u32, h: u32, m: u32) -> chrono::NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 1, d)
+            .and_then(|d| d.and_hms_opt(h, m, 0))
+            .unwrap()
+    }
+
+    #[cfg(feature = "chrono")]
+    fn candles(timestamps: &[chrono::NaiveDateTime]) -> Vec<Candle> {
+        timestamps
+            .iter()
+            .map(|ts| Candle {
+                timestamp: ts.format("%Y-%m-%dT%H:%M:%S+0530").to_string(),
+                open: 100.0,
+                high: 100.0,
+                low: 100.0,
+                close: 100.0,
+                volume: 1,
+                oi: None,
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_align_to_session() {
+        assert_eq!(
+            align_to_session(at(1, 9, 17), Interval::FiveMinute),
+            at(1, 9, 15)
+        );
+        assert_eq!(
+            align_to_session(at(1, 9, 20), Interval::FiveMinute),
+            at(1, 9, 20)
+        );
+        assert_eq!(
+            align_to_session(at(1, 10, 20), Interval::SixtyMinute),
+            at(1, 10, 15)
+        );
+        assert_eq!(
+            align_to_session(at(1, 15, 29), Interval::ThirtyMinute),
+            at(1, 15, 15)
+        );
+        assert_eq!(
+            align_to_session(at(1, 8, 0), Interval::Minute),
+            at(1, 9, 15)
+        );
+        assert_eq!(align_to_session(at(1, 12, 34), Interval::Day), at(1, 0, 0));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_find_gaps_within_session() {
+        use crate::market_hours::MarketHours;
+        use crate::orders::Exchange;
+
+        let hours = MarketHours::new();
+        let gaps = |timestamps: &[_], interval| {
+            find_gaps(&candles(timestamps), interval, Exchange::NSE, &hours)
+        };
+
+        // 2024-01-01 is a Monday
+        assert_eq!(
+            gaps(
+                &[at(1, 9, 15), at(1, 9, 16), at(1, 9, 17)],
+                Interval::Minute
+            ),
+            []
+        );
+        assert_eq!(
+            gaps(
+                &[at(1, 9, 15), at(1, 9, 16), at(1, 9, 19), at(1, 9, 21)],
+                Interval::Minute
+            ),
+            [(at(1, 9, 17), 2), (at(1, 9, 20), 1)]
+        );
+        // The session has no break, up to the last candle before the close
+        assert_eq!(
+            gaps(&[at(1, 11, 15), at(1, 14, 15)], Interval::SixtyMinute),
+            [(at(1, 12, 15), 2)]
+        );
+        assert_eq!(
+            gaps(&[at(1, 9, 15), at(1, 15, 25)], Interval::FiveMinute),
+            [(at(1, 9, 20), 73)]
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_find_gaps_across_days() {
+        use crate::market_hours::MarketHours;
+        use crate::orders::Exchange;
+        use std::collections::HashSet;
+
+        let hours = MarketHours::new();
+        let gaps = |timestamps: &[_], interval, hours| {
+            find_gaps(&candles(timestamps), interval, Exchange::NSE, hours)
+        };
+
+        // Friday's last candle to Monday's first one
+        assert_eq!(
+            gaps(&[at(5, 15, 25), at(8, 9, 15)], Interval::FiveMinute, &hours),
+            []
+        );
+        // A gap over the close and the weekend is a single gap
+        assert_eq!(
+            gaps(&[at(5, 15, 20), at(8, 9, 20)], Interval::FiveMinute, &hours),
+            [(at(5, 15, 25), 2)]
+        );
+
+        // A missing day, unless it's a holiday
+        assert_eq!(
+            gaps(&[at(1, 15, 25), at(3, 9, 15)], Interval::FiveMinute, &hours),
+            [(at(2, 9, 15), 75)]
+        );
+        let holidays = MarketHours::new().with_holidays(HashSet::from([at(2, 0, 0).date()]));
+        assert_eq!(
+            gaps(
+                &[at(1, 15, 25), at(3, 9, 15)],
+                Interval::FiveMinute,
+                &holidays
+            ),
+            []
+        );
+
+        // Daily candles
+        assert_eq!(
+            gaps(
+                &[at(4, 0, 0), at(5, 0, 0), at(10, 0, 0)],
+                Interval::Day,
+                &hours
+            ),
+            [(at(8, 0, 0), 2)]
+        );
+    }
+
     #[test]
     fn test_candles() -> Result<(), Box<dyn std::error::Error>> {
         let json = r#"{
@@ -210,4 +823,145 @@ mod tests {
 
         Ok(())
     }
+
+    const CANDLES_JSON: &str = r#"{
+          "status": "success",
+          "data": {
+            "candles": [
+              [
+                "2019-12-04T09:15:00+0530",
+                12009.9,
+                12019.35,
+                12001.25,
+                12001.5,
+                163275,
+                13667775
+              ],
+              [
+                "2019-12-04T09:16:00+0530",
+                12001,
+                12003,
+                11998.25,
+                12001,
+                105750,
+                13667775
+              ]
+            ]
+          }
+        }"#;
+
+    #[test]
+    fn test_typical_price_and_vwap() -> Result<(), Box<dyn std::error::Error>> {
+        let candles = serde_json::from_str::<Response<Candles>>(CANDLES_JSON)?
+            .into_result()?
+            .candles;
+
+        // (12019.35 + 12001.25 + 12001.5) / 3 and (12003 + 11998.25 + 12001) / 3
+        assert!((candles[0].typical_price() - 12007.366666666667).abs() < 1e-9);
+        assert!((candles[1].typical_price() - 12000.75).abs() < 1e-9);
+
+        // (12007.3667 * 163275 + 12000.75 * 105750) / (163275 + 105750)
+        let average = vwap(&candles).unwrap();
+        assert!((average - 12004.765746677818).abs() < 1e-9, "{average}");
+
+        let no_volume: Vec<_> = candles
+            .into_iter()
+            .map(|c| Candle { volume: 0, ..c })
+            .collect();
+        assert_eq!(vwap(&no_volume), None);
+        assert_eq!(vwap(&[]), None);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_candles_out_of_range() {
+        let req = |from: &str, to: &str| HistoricalCandleReq {
+            from: from.into(),
+            to: to.into(),
+            continuous: false,
+            oi: false,
+        };
+        let intraday = candles(&[at(2, 9, 15), at(2, 9, 16), at(3, 15, 29)]);
+
+        for (from, to) in [
+            ("2024-01-02 09:15:00", "2024-01-03 15:30:00"),
+            // Holidays and halts only leave fewer candles
+            ("2024-01-01 09:15:00", "2024-01-05 15:30:00"),
+            ("2024-01-02", "2024-01-04"),
+            ("not a date", "2024-01-04"),
+        ] {
+            assert_eq!(
+                candles_out_of_range(&intraday, &req(from, to), Interval::Minute),
+                None,
+                "{from} {to}"
+            );
+        }
+        assert_eq!(
+            candles_out_of_range(&[], &req("2024-01-02", "2024-01-04"), Interval::Minute),
+            None
+        );
+
+        let out = candles_out_of_range(
+            &intraday,
+            &req("2024-01-03 09:15:00", "2024-01-03 15:30:00"),
+            Interval::Minute,
+        );
+        assert_eq!(
+            out,
+            Some(OutOfRange {
+                from: at(3, 9, 15),
+                to: at(3, 15, 30),
+                first: at(2, 9, 15),
+                last: at(3, 15, 29),
+            })
+        );
+
+        // A day of slack for daily candles
+        let daily = candles(&[at(1, 0, 0), at(2, 0, 0)]);
+        let range = req("2024-01-02", "2024-01-05");
+        assert_eq!(candles_out_of_range(&daily, &range, Interval::Day), None);
+        assert!(candles_out_of_range(&daily, &range, Interval::Minute).is_some());
+    }
+
+    #[test]
+    fn test_candle_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        for json in [
+            r#"["2019-12-04T09:15:00+0530",12009.9,12019.35,12001.25,12001.5,163275,13667775]"#,
+            r#"["2019-12-04T09:16:00+0530",12001.0,12003.0,11998.25,12001.0,105750]"#,
+        ] {
+            let candle: Candle = serde_json::from_str(json)?;
+            assert_eq!(serde_json::to_string(&candle)?, json);
+            assert_eq!(
+                serde_json::from_str::<Candle>(&serde_json::to_string(&candle)?)?,
+                candle
+            );
+        }
+
+        let candles: Vec<Candle> = serde_json::from_str::<Response<Candles>>(CANDLES_JSON)?
+            .into_result()?
+            .candles;
+        let json = serde_json::to_string(&candles)?;
+        assert_eq!(serde_json::from_str::<Vec<Candle>>(&json)?, candles);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_candle_decimal() -> Result<(), Box<dyn std::error::Error>> {
+        use std::str::FromStr;
+
+        let candle: Candle = serde_json::from_str(
+            r#"["2019-12-04T09:15:00+0530", 12009.9, 12019.35, 12001.25, 12001.5, 163275]"#,
+        )?;
+
+        assert_eq!(candle.open_decimal(), Some(Decimal::from_str("12009.9")?));
+        assert_eq!(candle.high_decimal(), Some(Decimal::from_str("12019.35")?));
+        assert_eq!(candle.low_decimal(), Some(Decimal::from_str("12001.25")?));
+        assert_eq!(candle.close_decimal(), Some(Decimal::from_str("12001.5")?));
+
+        Ok(())
+    }
 }
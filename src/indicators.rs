@@ -0,0 +1,214 @@
+//! Basic indicators over historical candles.
+//!
+//! Every function returns one value per input candle, `None` where there isn't enough data yet
+//! (or the value is undefined, e.g. VWAP before any volume was traded).
+
+use crate::historical::Candle;
+
+/// Simple moving average of the close over `period` candles.
+pub fn sma(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    let mut res = vec![None; candles.len()];
+    if period == 0 {
+        return res;
+    }
+
+    let mut sum = 0.0;
+    for (i, candle) in candles.iter().enumerate() {
+        sum += candle.close;
+        if i >= period {
+            sum -= candles[i - period].close;
+        }
+        if i + 1 >= period {
+            res[i] = Some(sum / period as f64);
+        }
+    }
+
+    res
+}
+
+/// Exponential moving average of the close, seeded with the SMA of the first `period` candles.
+pub fn ema(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    let mut res = vec![None; candles.len()];
+    if period == 0 || candles.len() < period {
+        return res;
+    }
+
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut prev = candles[..period].iter().map(|c| c.close).sum::<f64>() / period as f64;
+    res[period - 1] = Some(prev);
+
+    for (i, candle) in candles.iter().enumerate().skip(period) {
+        prev += alpha * (candle.close - prev);
+        res[i] = Some(prev);
+    }
+
+    res
+}
+
+/// Cumulative volume weighted average of the typical price (`(high + low + close) / 3`).
+///
+/// Pass the candles of a single session, VWAP is normally reset every day.
+pub fn vwap(candles: &[Candle]) -> Vec<Option<f64>> {
+    let mut price_volume = 0.0;
+    let mut volume = 0.0;
+
+    candles
+        .iter()
+        .map(|c| {
+            price_volume += c.typical_price() * c.volume as f64;
+            volume += c.volume as f64;
+
+            (volume > 0.0).then(|| price_volume / volume)
+        })
+        .collect()
+}
+
+/// Highest high over the last `period` candles.
+pub fn rolling_high(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    rolling(candles, period, |c| c.high, f64::max)
+}
+
+/// Lowest low over the last `period` candles.
+pub fn rolling_low(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    rolling(candles, period, |c| c.low, f64::min)
+}
+
+/// Close to close return of each candle, as a fraction (`0.01` is 1%).
+///
+/// On daily candles these are the daily returns.
+pub fn returns(candles: &[Candle]) -> Vec<Option<f64>> {
+    let mut res = Vec::with_capacity(candles.len());
+    res.push(None);
+
+    for w in candles.windows(2) {
+        let (prev, curr) = (w[0].close, w[1].close);
+        res.push((prev != 0.0).then(|| curr / prev - 1.0));
+    }
+
+    res.truncate(candles.len());
+    res
+}
+
+fn rolling(
+    candles: &[Candle],
+    period: usize,
+    value: impl Fn(&Candle) -> f64,
+    pick: impl Fn(f64, f64) -> f64,
+) -> Vec<Option<f64>> {
+    if period == 0 {
+        return vec![None; candles.len()];
+    }
+
+    (0..candles.len())
+        .map(|i| {
+            (i + 1 >= period).then(|| {
+                candles[i + 1 - period..=i]
+                    .iter()
+                    .map(&value)
+                    .reduce(&pick)
+                    .unwrap_or(f64::NAN)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(high: f64, low: f64, close: f64, volume: i64) -> Candle {
+        Candle {
+            timestamp: String::new(),
+            open: close,
+            high,
+            low,
+            close,
+            volume,
+            oi: None,
+        }
+    }
+
+    fn closes(closes: &[f64]) -> Vec<Candle> {
+        closes.iter().map(|&c| candle(c, c, c, 100)).collect()
+    }
+
+    fn assert_close(actual: &[Option<f64>], expected: &[Option<f64>]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            match (a, e) {
+                (Some(a), Some(e)) => assert!((a - e).abs() < 1e-9, "{actual:?} != {expected:?}"),
+                (a, e) => assert_eq!(a, e, "{actual:?} != {expected:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sma() {
+        let candles = closes(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        assert_close(
+            &sma(&candles, 3),
+            &[None, None, Some(2.0), Some(3.0), Some(4.0)],
+        );
+        assert_close(&sma(&candles, 6), &[None; 5]);
+        assert_close(&sma(&candles, 0), &[None; 5]);
+    }
+
+    #[test]
+    fn test_ema() {
+        // alpha = 0.5, seeded with (2 + 4 + 6) / 3 = 4
+        let candles = closes(&[2.0, 4.0, 6.0, 8.0, 4.0]);
+
+        assert_close(
+            &ema(&candles, 3),
+            &[None, None, Some(4.0), Some(6.0), Some(5.0)],
+        );
+        assert_close(&ema(&candles[..2], 3), &[None, None]);
+    }
+
+    #[test]
+    fn test_vwap() {
+        let candles = [
+            candle(12.0, 9.0, 9.0, 0),
+            candle(12.0, 9.0, 12.0, 100),
+            candle(21.0, 18.0, 18.0, 300),
+        ];
+
+        // typical prices: 10, 11, 19
+        assert_close(
+            &vwap(&candles),
+            &[None, Some(11.0), Some((1100.0 + 5700.0) / 400.0)],
+        );
+        assert!(vwap(&candles).iter().flatten().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn test_rolling_high_low() {
+        let candles = [
+            candle(10.0, 5.0, 8.0, 1),
+            candle(12.0, 7.0, 8.0, 1),
+            candle(11.0, 4.0, 8.0, 1),
+            candle(9.0, 6.0, 8.0, 1),
+        ];
+
+        assert_close(
+            &rolling_high(&candles, 2),
+            &[None, Some(12.0), Some(12.0), Some(11.0)],
+        );
+        assert_close(
+            &rolling_low(&candles, 3),
+            &[None, None, Some(4.0), Some(4.0)],
+        );
+    }
+
+    #[test]
+    fn test_returns() {
+        let candles = closes(&[100.0, 110.0, 99.0, 0.0, 10.0]);
+
+        assert_close(
+            &returns(&candles),
+            &[None, Some(0.1), Some(-0.1), Some(-1.0), None],
+        );
+        assert!(returns(&[]).is_empty());
+    }
+}
@@ -2,17 +2,49 @@
 
 use reqwest::Client;
 use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 use utils::AuthInfo;
 
+pub mod accounts;
+pub mod alert_engine;
+pub mod alerts;
+#[cfg(feature = "arrow")]
+pub mod arrow;
 #[cfg(feature = "auto_auth")]
 mod auto_auth;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod candle_aggregator;
+#[cfg(feature = "decimal")]
+pub mod decimal;
 mod error;
+mod error_log;
+pub mod export;
+pub mod gtt;
 pub mod historical;
+pub mod indicators;
+pub mod margins;
+pub mod market_data;
+#[cfg(feature = "chrono")]
+pub mod market_hours;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod orders;
+pub mod pnl;
 pub mod portfolio;
+pub mod postback;
 pub mod quotes;
+pub mod recorder;
 mod response;
-mod unimplemented;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+pub mod sizing;
+pub mod symbols;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod trading;
 pub mod user;
 pub(crate) mod utils;
 pub mod virtual_contract_note;
@@ -20,10 +52,14 @@ pub mod ws;
 
 #[cfg(feature = "auto_auth")]
 pub use auto_auth::AutoAuth;
-pub use error::{Error, KiteError};
+pub use error::{Error, ErrorCategory, KiteError};
 pub use response::Response;
-pub use unimplemented::*;
-pub use utils::{API_VERSION, REQUEST_TIMEOUT_SECS};
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+pub use user::login_flow::LoginFlow;
+pub use utils::{API_VERSION, KITE_API_BASE_URL, REQUEST_TIMEOUT_SECS};
+#[cfg(feature = "chrono")]
+pub use utils::{KITE_TIMESTAMP_FORMATS, parse_kite_timestamp};
 
 pub struct Authenticated;
 pub struct AuthPending;
@@ -44,6 +80,19 @@ mod sealed {
 pub struct KiteConnect<T: AuthStatus = AuthPending> {
     pub(crate) client: Client,
     pub(crate) auth_info: AuthInfo,
+    /// Overrides [`KITE_API_BASE_URL`] for every REST request when set
+    pub(crate) base_url: Option<String>,
+    /// Sends every REST request through this client instead of `client` when set
+    #[cfg(feature = "middleware")]
+    pub(crate) middleware: Option<reqwest_middleware::ClientWithMiddleware>,
+    /// Tag of every placed order whose request doesn't set one
+    pub(crate) default_order_tag: Option<String>,
+    /// Overrides [`utils::API_VERSION_STR`] in the `X-Kite-Version` header when set
+    pub(crate) api_version: Option<String>,
+    /// Cancels every in-flight and future REST request when cancelled
+    pub(crate) cancellation_token: Option<CancellationToken>,
+    /// Funds fetched within the TTL of the cache, shared by the clones of the client
+    pub(crate) funds_cache: Option<Arc<user::funds::FundsCache>>,
     _auth_status: PhantomData<T>,
 }
 
@@ -53,6 +102,109 @@ impl<T: AuthStatus> KiteConnect<T> {
     pub fn api_key(&self) -> &str {
         self.auth_info.api_key()
     }
+
+    /// Sends every REST request to `base_url` instead of [`KITE_API_BASE_URL`].
+    ///
+    /// This is mostly useful for pointing the client at a mock server in tests. The WebSocket and
+    /// login endpoints are not affected.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        let base_url: String = base_url.into();
+        self.base_url = Some(base_url.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Returns the base URL used for REST requests.
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_deref().unwrap_or(KITE_API_BASE_URL)
+    }
+
+    /// Sends every REST request through `client`, so its middleware (retries, tracing, caching,
+    /// correlation IDs, ...) applies to the Kite API calls.
+    ///
+    /// The `X-Kite-Version` and `Authorization` headers are still added to each request, unless
+    /// a middleware sets them itself. Everything else configured on the crate's own client, such as
+    /// [`REQUEST_TIMEOUT_SECS`] and the user agent, is replaced by the configuration of the
+    /// `reqwest::Client` wrapped by `client`. The WebSocket connection isn't affected.
+    ///
+    /// The client is kept when authenticating.
+    #[cfg(feature = "middleware")]
+    pub fn with_middleware_client(
+        mut self,
+        client: reqwest_middleware::ClientWithMiddleware,
+    ) -> Self {
+        self.middleware = Some(client);
+        self
+    }
+
+    /// Tags every order placed without a [`tag`](orders::PlaceOrderRequest::tag) with `tag`, e.g.
+    /// to attribute all the orders of a strategy. A tag set on the request takes precedence.
+    ///
+    /// Like any order tag, it has to be 1 to [`MAX_ORDER_TAG_LEN`](orders::MAX_ORDER_TAG_LEN)
+    /// alphanumeric characters, an [`KiteError::InputException`] is returned otherwise. The tag is
+    /// kept when authenticating.
+    pub fn with_default_order_tag(mut self, tag: String) -> Result<Self, Error> {
+        orders::validate_tag("default_order_tag", &tag)?;
+        self.default_order_tag = Some(tag);
+        Ok(self)
+    }
+
+    /// Returns the tag applied to orders placed without one.
+    pub fn default_order_tag(&self) -> Option<&str> {
+        self.default_order_tag.as_deref()
+    }
+
+    /// Sends `version` in the `X-Kite-Version` header of every REST request instead of
+    /// [`API_VERSION`], e.g. to pin a revision of the API or test against a pre-release.
+    ///
+    /// The version is kept when authenticating. A version that isn't a valid header value fails
+    /// every request.
+    pub fn with_api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = Some(version.into());
+        self
+    }
+
+    /// Returns the version sent in the `X-Kite-Version` header.
+    pub fn api_version(&self) -> &str {
+        self.api_version
+            .as_deref()
+            .unwrap_or(utils::API_VERSION_STR)
+    }
+
+    /// Cancels the REST requests of this client once `token` is cancelled, e.g. on the graceful
+    /// shutdown of a service.
+    ///
+    /// Requests in flight are dropped and return [`Error::Cancelled`], as does every request sent
+    /// afterwards. Long operations made of several requests, such as downloading
+    /// [all the instruments](KiteConnect::get_all_instruments) or
+    /// [bulk historical data](KiteConnect::get_historical_data_bulk), stop at the next chunk or
+    /// request. A request dropped after it reached Kite may still have taken effect, e.g. an
+    /// order may be placed anyway.
+    ///
+    /// To cancel a single operation, set a [child token](CancellationToken::child_token) on a
+    /// clone of the client. The token is kept when authenticating.
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Returns the token cancelling the requests of this client, if any.
+    pub fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation_token.as_ref()
+    }
+
+    /// Caches the funds returned by [`get_funds`](KiteConnect::get_funds),
+    /// [`get_equity_funds`](KiteConnect::get_equity_funds) and
+    /// [`get_commodity_funds`](KiteConnect::get_commodity_funds) for `ttl`, e.g. for a dashboard
+    /// polling them alongside quotes.
+    ///
+    /// The cache is disabled by default, as cached funds miss the changes made outside of this
+    /// client (orders placed from Kite, fills, settlements). Placing, modifying or cancelling an
+    /// order, or converting a position, with the client empties the cache. The cache is shared by
+    /// the clones of the client and kept when authenticating.
+    pub fn with_funds_cache(mut self, ttl: Duration) -> Self {
+        self.funds_cache = Some(Arc::new(user::funds::FundsCache::new(ttl)));
+        self
+    }
 }
 
 impl KiteConnect<AuthPending> {
@@ -62,6 +214,13 @@ impl KiteConnect<AuthPending> {
         Self {
             client,
             auth_info: AuthInfo::new(api_key, api_secret),
+            base_url: None,
+            #[cfg(feature = "middleware")]
+            middleware: None,
+            default_order_tag: None,
+            api_version: None,
+            cancellation_token: None,
+            funds_cache: None,
             _auth_status: PhantomData,
         }
     }
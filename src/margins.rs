@@ -0,0 +1,331 @@
+//! Margins required by orders before placing them
+//!
+//! Reference: <https://kite.trade/docs/connect/v3/margins/>
+
+use serde::{Deserialize, Serialize};
+
+use crate::orders::{Exchange, OrderType, PlaceOrderRequest, Product, TransactionType, Variety};
+
+use super::*;
+
+pub const ORDER_MARGINS_ENDPOINT: &str = "https://api.kite.trade/margins/orders";
+pub const BASKET_MARGINS_ENDPOINT: &str = "https://api.kite.trade/margins/basket";
+
+/// An order to calculate the margin of, see [`KiteConnect::get_order_margins`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginOrderReq {
+    pub exchange: Exchange,
+    /// Tradingsymbol of the instrument
+    #[serde(rename = "tradingsymbol")]
+    pub trading_symbol: String,
+    pub transaction_type: TransactionType,
+    pub variety: Variety,
+    pub product: Product,
+    pub order_type: OrderType,
+    pub quantity: u32,
+    /// Price of LIMIT and SL orders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    /// Trigger price of SL and SL-M orders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<f64>,
+}
+
+impl From<&PlaceOrderRequest> for MarginOrderReq {
+    fn from(req: &PlaceOrderRequest) -> Self {
+        Self {
+            exchange: req.exchange,
+            trading_symbol: req.trading_symbol.clone(),
+            transaction_type: req.transaction_type,
+            variety: req.variety.clone(),
+            product: req.product,
+            order_type: req.order_type,
+            quantity: req.quantity,
+            price: req.price,
+            trigger_price: req.trigger_price,
+        }
+    }
+}
+
+/// Margin required by an order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderMargin {
+    /// Segment of the order, e.g. `equity` or `commodity`
+    #[serde(rename = "type")]
+    pub margin_type: String,
+    /// Tradingsymbol of the instrument
+    #[serde(rename = "tradingsymbol")]
+    pub trading_symbol: String,
+    pub exchange: Exchange,
+    /// SPAN margin
+    pub span: f64,
+    /// Exposure margin
+    pub exposure: f64,
+    /// Option premium
+    pub option_premium: f64,
+    /// Additional margin
+    pub additional: f64,
+    /// Bracket order margin
+    pub bo: f64,
+    /// Cash credit
+    pub cash: f64,
+    /// VAR margin
+    pub var: f64,
+    /// Realised and unrealised profit and loss
+    pub pnl: MarginPnl,
+    /// Total margin required
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MarginPnl {
+    pub realised: f64,
+    pub unrealised: f64,
+}
+
+/// Margin required by a whole basket, see [`BasketMargin`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CombinedMargin {
+    /// SPAN margin
+    pub span: f64,
+    /// Exposure margin
+    pub exposure: f64,
+    /// Option premium
+    pub option_premium: f64,
+    /// Additional margin
+    pub additional: f64,
+    /// Bracket order margin
+    pub bo: f64,
+    /// Cash credit
+    pub cash: f64,
+    /// VAR margin
+    pub var: f64,
+    /// Realised and unrealised profit and loss
+    pub pnl: MarginPnl,
+    /// Total margin required
+    pub total: f64,
+}
+
+/// Margins of a basket of orders, see [`KiteConnect::get_basket_margins`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BasketMargin {
+    /// Sum of the margins of the orders taken one by one
+    pub initial: CombinedMargin,
+    /// Margin of the basket as a whole, after the benefit of hedged legs
+    #[serde(rename = "final")]
+    pub final_margin: CombinedMargin,
+    /// Margin of each order, in the order of the basket
+    pub orders: Vec<OrderMargin>,
+}
+
+impl KiteConnect<Authenticated> {
+    /// Returns the margin required by each of `orders`, in the same order.
+    ///
+    /// Each order is calculated on its own, the benefit of hedged positions isn't accounted for.
+    pub async fn get_order_margins(
+        &self,
+        orders: &[MarginOrderReq],
+    ) -> Result<Vec<OrderMargin>, Error> {
+        self.send(self.client.post(ORDER_MARGINS_ENDPOINT).json(orders))
+            .await
+    }
+
+    /// Returns the margin required by `orders` placed together, e.g. the legs of an options
+    /// strategy.
+    ///
+    /// With `consider_positions`, the open positions of the account are taken into account too,
+    /// so a leg hedging an existing position lowers the margin.
+    pub async fn get_basket_margins(
+        &self,
+        orders: &[MarginOrderReq],
+        consider_positions: bool,
+    ) -> Result<BasketMargin, Error> {
+        self.send(
+            self.client
+                .post(BASKET_MARGINS_ENDPOINT)
+                .query(&[("consider_positions", consider_positions)])
+                .json(orders),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response;
+
+    #[test]
+    fn test_order_margins() -> Result<(), Box<dyn std::error::Error>> {
+        let json = r#"{
+            "status": "success",
+            "data": [
+              {
+                "type": "equity",
+                "tradingsymbol": "INFY",
+                "exchange": "NSE",
+                "span": 0,
+                "exposure": 0,
+                "option_premium": 0,
+                "additional": 0,
+                "bo": 0,
+                "cash": 0,
+                "var": 1498,
+                "pnl": {
+                  "realised": 0,
+                  "unrealised": 0
+                },
+                "leverage": 1,
+                "charges": {
+                  "transaction_tax": 1.498,
+                  "transaction_tax_type": "stt",
+                  "exchange_turnover_charge": 0.051681,
+                  "sebi_turnover_charge": 0.001498,
+                  "brokerage": 0.01,
+                  "stamp_duty": 0.22,
+                  "gst": {
+                    "igst": 0.011372,
+                    "cgst": 0,
+                    "sgst": 0,
+                    "total": 0.011372
+                  },
+                  "total": 1.792551
+                },
+                "total": 1498
+              }
+            ]
+          }"#;
+
+        let expected = vec![OrderMargin {
+            margin_type: "equity".into(),
+            trading_symbol: "INFY".into(),
+            exchange: Exchange::NSE,
+            span: 0.0,
+            exposure: 0.0,
+            option_premium: 0.0,
+            additional: 0.0,
+            bo: 0.0,
+            cash: 0.0,
+            var: 1498.0,
+            pnl: MarginPnl {
+                realised: 0.0,
+                unrealised: 0.0,
+            },
+            total: 1498.0,
+        }];
+
+        let margins: Response<Vec<OrderMargin>> = serde_json::from_str(json)?;
+        assert_eq!(margins.into_result()?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_basket_margins() -> Result<(), Box<dyn std::error::Error>> {
+        let json = r#"{
+            "status": "success",
+            "data": {
+              "initial": {
+                "type": "",
+                "tradingsymbol": "",
+                "exchange": "",
+                "span": 233910,
+                "exposure": 47018.19,
+                "option_premium": 0,
+                "additional": 0,
+                "bo": 0,
+                "cash": 0,
+                "var": 0,
+                "pnl": { "realised": 0, "unrealised": 0 },
+                "leverage": 0,
+                "charges": null,
+                "total": 280928.19
+              },
+              "final": {
+                "type": "",
+                "tradingsymbol": "",
+                "exchange": "",
+                "span": 26310.75,
+                "exposure": 47018.19,
+                "option_premium": 1575,
+                "additional": 0,
+                "bo": 0,
+                "cash": 0,
+                "var": 0,
+                "pnl": { "realised": 0, "unrealised": 0 },
+                "leverage": 0,
+                "charges": null,
+                "total": 74903.94
+              },
+              "orders": [
+                {
+                  "type": "equity",
+                  "tradingsymbol": "NIFTY21JUN15400PE",
+                  "exchange": "NFO",
+                  "span": 0,
+                  "exposure": 0,
+                  "option_premium": 1575,
+                  "additional": 0,
+                  "bo": 0,
+                  "cash": 0,
+                  "var": 0,
+                  "pnl": { "realised": 0, "unrealised": 0 },
+                  "leverage": 1,
+                  "total": 1575
+                },
+                {
+                  "type": "equity",
+                  "tradingsymbol": "NIFTY21JUN15400CE",
+                  "exchange": "NFO",
+                  "span": 233910,
+                  "exposure": 47018.19,
+                  "option_premium": 0,
+                  "additional": 0,
+                  "bo": 0,
+                  "cash": 0,
+                  "var": 0,
+                  "pnl": { "realised": 0, "unrealised": 0 },
+                  "leverage": 1,
+                  "total": 280928.19
+                }
+              ]
+            }
+          }"#;
+
+        let basket = serde_json::from_str::<Response<BasketMargin>>(json)?.into_result()?;
+
+        assert_eq!(basket.initial.total, 280928.19);
+        assert_eq!(basket.final_margin.total, 74903.94);
+        assert_eq!(basket.final_margin.span, 26310.75);
+        assert_eq!(basket.final_margin.option_premium, 1575.0);
+        assert_eq!(basket.orders.len(), 2);
+        assert_eq!(basket.orders[0].trading_symbol, "NIFTY21JUN15400PE");
+        assert_eq!(basket.orders[1].total, basket.initial.total);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_margin_order_req() -> Result<(), Box<dyn std::error::Error>> {
+        let req = PlaceOrderRequest::builder(Variety::Regular, "INFY", Exchange::NSE)
+            .buy()
+            .quantity(1)
+            .product(Product::CNC)
+            .build()?;
+
+        assert_eq!(
+            serde_json::to_value([MarginOrderReq::from(&req)])?,
+            serde_json::json!([{
+                "exchange": "NSE",
+                "tradingsymbol": "INFY",
+                "transaction_type": "BUY",
+                "variety": "regular",
+                "product": "CNC",
+                "order_type": "MARKET",
+                "quantity": 1
+            }])
+        );
+
+        Ok(())
+    }
+}
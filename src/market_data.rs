@@ -0,0 +1,437 @@
+//! Latest prices from the WebSocket, with REST polling as a fallback.
+//!
+//! [`MarketData`] keeps the latest price of every subscribed instrument. Prices come from the
+//! ticks fed to [`MarketData::on_tick`] while the WebSocket is connected, and from the OHLC quotes
+//! endpoint, polled through [`MarketData::poll`], for the instruments the WebSocket doesn't cover:
+//! - every instrument while the WebSocket is disconnected, i.e. after [`Ticker::ConnectionClosed`]
+//!   and until the next tick or [`Ticker::Reconnected`].
+//! - the instruments subscribed beyond [`MarketData::with_max_streamed`], which are never streamed.
+//!
+//! The quotes endpoints are rate limited together, so a poll is a single request for at most
+//! [`MAX_POLLED_INSTRUMENTS`] instruments, and polls closer than the poll interval are skipped.
+//! When more instruments need polling, consecutive polls go through them in turns.
+//!
+//! ```no_run
+//! # async fn run(kite: kite_connect::KiteConnect<kite_connect::Authenticated>) -> Result<(), kite_connect::Error> {
+//! use kite_connect::market_data::MarketData;
+//! use kite_connect::ws::Req;
+//! use std::sync::Arc;
+//!
+//! let (mut ticker, ticks) = kite.web_socket().await?;
+//! let market_data = Arc::new(MarketData::new(kite));
+//!
+//! let streamed = market_data.subscribe(&[408065, 884737]);
+//! ticker.send(Req::Subscribe(&streamed)).await?;
+//!
+//! let feed = market_data.clone();
+//! std::thread::spawn(move || {
+//!     for tick in ticks {
+//!         feed.on_tick(&tick);
+//!     }
+//! });
+//!
+//! // Call regularly, e.g. every second
+//! market_data.poll().await?;
+//! if let Some(view) = market_data.latest(408065) {
+//!     println!("{} from {:?}", view.last_price, view.source);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::quotes::{Ohlc, OhlcQuote, QuoteKey};
+use crate::ws::Ticker;
+use crate::{Authenticated, Error, KiteConnect};
+
+/// Maximum number of instruments in a single OHLC quotes request.
+pub const MAX_POLLED_INSTRUMENTS: usize = 1000;
+
+/// Maximum number of instruments a single WebSocket connection can subscribe to.
+pub const MAX_STREAMED_INSTRUMENTS: usize = 3000;
+
+/// Default interval between two polls, the rate limit of the quotes endpoints.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Source of the OHLC quotes [`MarketData`] polls.
+pub trait RestQuotes {
+    /// Returns the OHLC quotes of the instruments. Unknown instruments may be left out.
+    fn ohlc_quotes(
+        &self,
+        instrument_tokens: &[u32],
+    ) -> impl Future<Output = Result<Vec<OhlcQuote>, Error>>;
+}
+
+impl RestQuotes for KiteConnect<Authenticated> {
+    async fn ohlc_quotes(&self, instrument_tokens: &[u32]) -> Result<Vec<OhlcQuote>, Error> {
+        let keys: Vec<_> = instrument_tokens
+            .iter()
+            .map(|&token| QuoteKey::Token(token))
+            .collect();
+
+        Ok(self.get_ohlc_quotes(&keys).await?.into_values().collect())
+    }
+}
+
+impl<T: RestQuotes> RestQuotes for Arc<T> {
+    async fn ohlc_quotes(&self, instrument_tokens: &[u32]) -> Result<Vec<OhlcQuote>, Error> {
+        T::ohlc_quotes(self, instrument_tokens).await
+    }
+}
+
+/// Where a [`PriceView`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    /// A WebSocket tick
+    Ticker,
+    /// A polled OHLC quote
+    Rest,
+}
+
+/// Latest known price of an instrument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceView {
+    pub last_price: f64,
+    /// Not available for ticks in LTP mode
+    pub ohlc: Option<Ohlc>,
+    pub source: PriceSource,
+    /// When the tick or the quote was received
+    pub as_of: Instant,
+}
+
+/// Latest prices of the subscribed instruments, see the [module documentation](self).
+#[derive(Debug)]
+pub struct MarketData<R> {
+    rest: R,
+    poll_interval: Duration,
+    max_streamed: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    subscribed: BTreeSet<u32>,
+    streamed: BTreeSet<u32>,
+    connected: bool,
+    ticks: HashMap<u32, PriceView>,
+    polled: HashMap<u32, PriceView>,
+    last_poll: Option<Instant>,
+    /// Instrument to resume polling from, when there are too many for one request
+    poll_cursor: u32,
+}
+
+impl<R: RestQuotes> MarketData<R> {
+    pub fn new(rest: R) -> Self {
+        Self {
+            rest,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            max_streamed: MAX_STREAMED_INSTRUMENTS,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Sets the minimum interval between two polls, [`DEFAULT_POLL_INTERVAL`] by default.
+    ///
+    /// Lower it only if nothing else calls the quotes endpoints with the same API key.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the number of instruments streamed over the WebSocket, [`MAX_STREAMED_INSTRUMENTS`]
+    /// by default. Instruments subscribed beyond it are polled.
+    pub fn with_max_streamed(mut self, max_streamed: usize) -> Self {
+        self.max_streamed = max_streamed;
+        self
+    }
+
+    /// Starts tracking the prices of `instrument_tokens`.
+    ///
+    /// Returns the instruments to subscribe to on the WebSocket, e.g. with
+    /// [`Req::Subscribe`](crate::ws::Req::Subscribe). The others are polled.
+    pub fn subscribe(&self, instrument_tokens: &[u32]) -> Vec<u32> {
+        let mut state = self.state();
+        let mut streamed = Vec::new();
+
+        for &token in instrument_tokens {
+            if !state.subscribed.insert(token) {
+                continue;
+            }
+            if state.streamed.len() < self.max_streamed {
+                state.streamed.insert(token);
+                streamed.push(token);
+            }
+        }
+
+        streamed
+    }
+
+    /// Records the price carried by `tick`, or the WebSocket disconnecting on
+    /// [`Ticker::ConnectionClosed`].
+    ///
+    /// Any other tick marks the WebSocket as connected again.
+    pub fn on_tick(&self, tick: &Ticker) {
+        let ohlc = match tick {
+            Ticker::ConnectionClosed => {
+                self.state().connected = false;
+                return;
+            }
+            Ticker::Reconnected => {
+                self.state().connected = true;
+                return;
+            }
+            Ticker::OrderUpdate(_) | Ticker::Error(_) => return,
+            Ticker::IndicesQuote(q) => Some(q.ohlc),
+            Ticker::LtpQuote(_) => None,
+            Ticker::PartialQuote(q) => Some(q.ohlc),
+            Ticker::FullQuote(q) => Some(q.quote.ohlc),
+        };
+        let Some((token, last_price)) = tick.last_price() else {
+            return;
+        };
+
+        let mut state = self.state();
+        state.connected = true;
+        state.ticks.insert(
+            token,
+            PriceView {
+                last_price,
+                ohlc,
+                source: PriceSource::Ticker,
+                as_of: Instant::now(),
+            },
+        );
+    }
+
+    /// Whether ticks are being received, i.e. no [`Ticker::ConnectionClosed`] since the last one.
+    pub fn is_connected(&self) -> bool {
+        self.state().connected
+    }
+
+    /// Returns the latest price of the instrument.
+    ///
+    /// Ticks are preferred while the WebSocket is connected, otherwise the most recent of the
+    /// last tick and the last polled quote is returned.
+    pub fn latest(&self, instrument_token: u32) -> Option<PriceView> {
+        let state = self.state();
+        let tick = state.ticks.get(&instrument_token).copied();
+
+        if state.connected && state.streamed.contains(&instrument_token) && tick.is_some() {
+            return tick;
+        }
+
+        match (tick, state.polled.get(&instrument_token).copied()) {
+            (Some(tick), Some(polled)) => Some(if tick.as_of > polled.as_of {
+                tick
+            } else {
+                polled
+            }),
+            (tick, polled) => tick.or(polled),
+        }
+    }
+
+    /// Polls the quotes of the instruments not covered by the WebSocket.
+    ///
+    /// Makes at most one request, and none if the previous poll was less than the poll interval
+    /// ago or there's nothing to poll. Returns whether a request was made.
+    pub async fn poll(&self) -> Result<bool, Error> {
+        let tokens = {
+            let mut state = self.state();
+
+            if let Some(last_poll) = state.last_poll
+                && last_poll.elapsed() < self.poll_interval
+            {
+                return Ok(false);
+            }
+
+            let tokens = state.to_poll();
+            if tokens.is_empty() {
+                return Ok(false);
+            }
+
+            // Reserved before the request, so that concurrent polls don't exceed the rate limit
+            state.last_poll = Some(Instant::now());
+            state.poll_cursor = tokens
+                .get(MAX_POLLED_INSTRUMENTS)
+                .copied()
+                .unwrap_or_default();
+
+            tokens
+                .into_iter()
+                .take(MAX_POLLED_INSTRUMENTS)
+                .collect::<Vec<_>>()
+        };
+
+        let quotes = self.rest.ohlc_quotes(&tokens).await?;
+        let as_of = Instant::now();
+
+        let mut state = self.state();
+        for q in quotes {
+            state.polled.insert(
+                q.instrument_token,
+                PriceView {
+                    last_price: q.last_price,
+                    ohlc: Some(q.ohlc),
+                    source: PriceSource::Rest,
+                    as_of,
+                },
+            );
+        }
+
+        Ok(true)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl State {
+    /// The instruments to poll, starting from the poll cursor and wrapping around.
+    fn to_poll(&self) -> Vec<u32> {
+        let polled = |token: &&u32| !self.connected || !self.streamed.contains(token);
+
+        self.subscribed
+            .range(self.poll_cursor..)
+            .chain(self.subscribed.range(..self.poll_cursor))
+            .filter(polled)
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::LtpQuote;
+
+    #[derive(Debug, Default)]
+    struct FakeRest {
+        requests: Mutex<Vec<Vec<u32>>>,
+    }
+
+    impl FakeRest {
+        fn requests(&self) -> Vec<Vec<u32>> {
+            std::mem::take(&mut *self.requests.lock().unwrap())
+        }
+    }
+
+    impl RestQuotes for FakeRest {
+        async fn ohlc_quotes(&self, instrument_tokens: &[u32]) -> Result<Vec<OhlcQuote>, Error> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(instrument_tokens.to_vec());
+
+            Ok(instrument_tokens
+                .iter()
+                .map(|&instrument_token| OhlcQuote {
+                    instrument_token,
+                    last_price: 100.0,
+                    ohlc: Ohlc {
+                        open: 99.0,
+                        high: 101.0,
+                        low: 98.0,
+                        close: 97.0,
+                    },
+                })
+                .collect())
+        }
+    }
+
+    fn ltp(instrument_token: u32, last_price: f64) -> Ticker {
+        Ticker::LtpQuote(LtpQuote {
+            instrument_token,
+            last_price,
+        })
+    }
+
+    fn market_data() -> MarketData<Arc<FakeRest>> {
+        MarketData::new(Arc::new(FakeRest::default()))
+            .with_poll_interval(Duration::ZERO)
+            .with_max_streamed(2)
+    }
+
+    #[tokio::test]
+    async fn test_overflow_is_polled() -> Result<(), Box<dyn std::error::Error>> {
+        let md = market_data();
+
+        assert_eq!(md.subscribe(&[1, 2, 3]), [1, 2]);
+        assert_eq!(md.subscribe(&[2, 4]), Vec::<u32>::new());
+
+        md.on_tick(&ltp(1, 10.0));
+        md.on_tick(&ltp(2, 20.0));
+        assert!(md.poll().await?);
+        // Streamed instruments aren't polled while connected
+        assert_eq!(md.rest.requests(), [vec![3, 4]]);
+
+        let view = md.latest(1).unwrap();
+        assert_eq!(
+            (view.last_price, view.ohlc, view.source),
+            (10.0, None, PriceSource::Ticker)
+        );
+        let view = md.latest(3).unwrap();
+        assert_eq!((view.last_price, view.source), (100.0, PriceSource::Rest));
+        assert_eq!(md.latest(5), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failover() -> Result<(), Box<dyn std::error::Error>> {
+        let md = market_data();
+        md.subscribe(&[1, 2]);
+
+        // Nothing streamed yet
+        assert!(!md.is_connected());
+        assert!(md.poll().await?);
+        assert_eq!(md.rest.requests(), [vec![1, 2]]);
+
+        md.on_tick(&ltp(1, 10.0));
+        assert_eq!(md.latest(1).unwrap().source, PriceSource::Ticker);
+        assert!(!md.poll().await?);
+        assert!(md.rest.requests().is_empty());
+
+        md.on_tick(&Ticker::ConnectionClosed);
+        assert!(md.poll().await?);
+        assert_eq!(md.rest.requests(), [vec![1, 2]]);
+        assert_eq!(md.latest(1).unwrap().source, PriceSource::Rest);
+
+        // Back to ticks once reconnected
+        md.on_tick(&Ticker::Reconnected);
+        assert!(md.is_connected());
+        md.on_tick(&ltp(1, 11.0));
+        assert_eq!(md.latest(1).unwrap().last_price, 11.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_poll_throttling() -> Result<(), Box<dyn std::error::Error>> {
+        let md = market_data().with_poll_interval(Duration::from_secs(60));
+        md.subscribe(&[1]);
+
+        assert!(md.poll().await?);
+        assert!(!md.poll().await?);
+        assert_eq!(md.rest.requests().len(), 1);
+
+        // One request per poll, going through the instruments in turns
+        let md = market_data();
+        let tokens: Vec<u32> = (1..=1500).collect();
+        md.subscribe(&tokens);
+
+        assert!(md.poll().await?);
+        assert!(md.poll().await?);
+        assert!(md.poll().await?);
+        let requests = md.rest.requests();
+        assert_eq!(requests[0], tokens[..1000]);
+        assert_eq!(requests[1][..500], tokens[1000..]);
+        assert_eq!(requests[1][500..], tokens[..500]);
+        assert_eq!(requests[2][0], 501);
+
+        Ok(())
+    }
+}
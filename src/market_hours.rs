@@ -0,0 +1,216 @@
+//! Trading sessions of each exchange, behind the `chrono` feature.
+//!
+//! Every time is in IST. The sessions are the regular ones:
+//! - NSE and BSE: 09:15 to 15:30, with a pre-open session from 09:00 to 09:08.
+//! - NFO and BFO: 09:15 to 15:30.
+//! - CDS and BCD: 09:00 to 17:00.
+//! - MCX and NCO: 09:00 to 23:30 while the US observes daylight saving time (second Sunday of March to the
+//!   first Sunday of November), 09:00 to 23:55 otherwise.
+//!
+//! Mutual funds aren't traded in sessions, so [`Exchange::MF`] is never open. Exchanges are closed
+//! on weekends, and on the holidays given to [`MarketHours::with_holidays`]: the exchanges publish
+//! them every year, and special sessions (e.g. Muhurat trading) aren't known.
+
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::collections::HashSet;
+
+use crate::orders::Exchange;
+
+/// Bounds of a trading day, see [`MarketHours::session_bounds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    /// Start and end of the pre-open session, for equity on NSE and BSE
+    pub pre_open: Option<(NaiveDateTime, NaiveDateTime)>,
+    pub open: NaiveDateTime,
+    pub close: NaiveDateTime,
+}
+
+impl Session {
+    /// Whether `datetime` is in the regular session, the close excluded.
+    pub fn contains(&self, datetime: NaiveDateTime) -> bool {
+        self.open <= datetime && datetime < self.close
+    }
+}
+
+/// Trading sessions with the caller's holidays, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct MarketHours {
+    holidays: HashSet<NaiveDate>,
+}
+
+impl MarketHours {
+    /// Market hours without any holidays.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Closes every exchange on `holidays`.
+    pub fn with_holidays(mut self, holidays: HashSet<NaiveDate>) -> Self {
+        self.holidays = holidays;
+        self
+    }
+
+    /// Returns the session of `exchange` on `date`, or `None` if it's closed for the day.
+    pub fn session_bounds(&self, exchange: Exchange, date: NaiveDate) -> Option<Session> {
+        if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) || self.holidays.contains(&date) {
+            return None;
+        }
+
+        let at = |h, m| date.and_time(NaiveTime::from_hms_opt(h, m, 0).expect("valid time"));
+
+        Some(match exchange {
+            Exchange::NSE | Exchange::BSE => Session {
+                pre_open: Some((at(9, 0), at(9, 8))),
+                open: at(9, 15),
+                close: at(15, 30),
+            },
+            Exchange::NFO | Exchange::BFO => Session {
+                pre_open: None,
+                open: at(9, 15),
+                close: at(15, 30),
+            },
+            Exchange::CDS | Exchange::BCD => Session {
+                pre_open: None,
+                open: at(9, 0),
+                close: at(17, 0),
+            },
+            Exchange::MCX | Exchange::NCO => Session {
+                pre_open: None,
+                open: at(9, 0),
+                close: if us_daylight_saving(date) {
+                    at(23, 30)
+                } else {
+                    at(23, 55)
+                },
+            },
+            Exchange::MF => return None,
+        })
+    }
+
+    /// Whether the regular session of `exchange` is open at `datetime`.
+    pub fn is_market_open(&self, exchange: Exchange, datetime: NaiveDateTime) -> bool {
+        self.session_bounds(exchange, datetime.date())
+            .is_some_and(|session| session.contains(datetime))
+    }
+
+    /// Whether `datetime` is in the pre-open session of `exchange`, the end excluded.
+    pub fn is_pre_open(&self, exchange: Exchange, datetime: NaiveDateTime) -> bool {
+        self.session_bounds(exchange, datetime.date())
+            .and_then(|session| session.pre_open)
+            .is_some_and(|(start, end)| start <= datetime && datetime < end)
+    }
+
+    /// Returns the first opening of the regular session of `exchange` strictly after `after`.
+    ///
+    /// Returns `None` if it doesn't open in the following year, e.g. for [`Exchange::MF`].
+    pub fn next_open(&self, exchange: Exchange, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=366)
+            .filter_map(|days| after.date().checked_add_days(Days::new(days)))
+            .filter_map(|date| self.session_bounds(exchange, date))
+            .map(|session| session.open)
+            .find(|&open| open > after)
+    }
+}
+
+/// Whether the US observes daylight saving time on `date`, from the second Sunday of March to the
+/// first Sunday of November.
+fn us_daylight_saving(date: NaiveDate) -> bool {
+    let start = NaiveDate::from_weekday_of_month_opt(date.year(), 3, Weekday::Sun, 2);
+    let end = NaiveDate::from_weekday_of_month_opt(date.year(), 11, Weekday::Sun, 1);
+
+    matches!((start, end), (Some(start), Some(end)) if start <= date && date < end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(datetime: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_equity_session_boundaries() {
+        let hours = MarketHours::new();
+
+        // Wednesday
+        assert!(!hours.is_market_open(Exchange::NSE, at("2024-01-03 09:14:59")));
+        assert!(hours.is_market_open(Exchange::NSE, at("2024-01-03 09:15:00")));
+        assert!(hours.is_market_open(Exchange::BSE, at("2024-01-03 15:29:59")));
+        assert!(!hours.is_market_open(Exchange::BSE, at("2024-01-03 15:30:00")));
+        assert!(hours.is_market_open(Exchange::NFO, at("2024-01-03 12:00:00")));
+
+        assert!(!hours.is_pre_open(Exchange::NSE, at("2024-01-03 08:59:59")));
+        assert!(hours.is_pre_open(Exchange::NSE, at("2024-01-03 09:00:00")));
+        assert!(!hours.is_pre_open(Exchange::NSE, at("2024-01-03 09:08:00")));
+        assert!(!hours.is_pre_open(Exchange::NFO, at("2024-01-03 09:05:00")));
+        assert!(!hours.is_market_open(Exchange::NSE, at("2024-01-03 09:05:00")));
+    }
+
+    #[test]
+    fn test_weekends_and_holidays() {
+        let hours = MarketHours::new().with_holidays(HashSet::from([date("2024-01-26")]));
+
+        // Saturday, Sunday and Republic Day
+        assert!(!hours.is_market_open(Exchange::NSE, at("2024-01-06 10:00:00")));
+        assert!(!hours.is_market_open(Exchange::MCX, at("2024-01-07 10:00:00")));
+        assert!(!hours.is_market_open(Exchange::NSE, at("2024-01-26 10:00:00")));
+        assert_eq!(
+            hours.session_bounds(Exchange::CDS, date("2024-01-26")),
+            None
+        );
+        assert!(hours.is_market_open(Exchange::NSE, at("2024-01-25 10:00:00")));
+    }
+
+    #[test]
+    fn test_next_open() {
+        let hours = MarketHours::new().with_holidays(HashSet::from([date("2024-01-29")]));
+
+        // Before the open, during the session and after the close
+        assert_eq!(
+            hours.next_open(Exchange::NSE, at("2024-01-24 09:00:00")),
+            Some(at("2024-01-24 09:15:00"))
+        );
+        assert_eq!(
+            hours.next_open(Exchange::NSE, at("2024-01-24 09:15:00")),
+            Some(at("2024-01-25 09:15:00"))
+        );
+        // Over the weekend and the Monday holiday
+        assert_eq!(
+            hours.next_open(Exchange::NSE, at("2024-01-26 16:00:00")),
+            Some(at("2024-01-30 09:15:00"))
+        );
+        assert_eq!(
+            hours.next_open(Exchange::CDS, at("2024-01-27 09:00:00")),
+            Some(at("2024-01-30 09:00:00"))
+        );
+        assert_eq!(
+            hours.next_open(Exchange::MF, at("2024-01-24 09:00:00")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_commodity_and_currency_sessions() {
+        let hours = MarketHours::new();
+        let close = |d| hours.session_bounds(Exchange::MCX, date(d)).unwrap().close;
+
+        // US daylight saving time from 2024-03-10 to 2024-11-03
+        assert_eq!(close("2024-03-08"), at("2024-03-08 23:55:00"));
+        assert_eq!(close("2024-03-11"), at("2024-03-11 23:30:00"));
+        assert_eq!(close("2024-11-01"), at("2024-11-01 23:30:00"));
+        assert_eq!(close("2024-11-04"), at("2024-11-04 23:55:00"));
+
+        assert!(hours.is_market_open(Exchange::MCX, at("2024-01-03 23:54:59")));
+        assert!(!hours.is_market_open(Exchange::MCX, at("2024-07-03 23:30:00")));
+        assert!(hours.is_market_open(Exchange::MCX, at("2024-07-03 09:00:00")));
+
+        assert!(hours.is_market_open(Exchange::CDS, at("2024-01-03 16:59:59")));
+        assert!(!hours.is_market_open(Exchange::BCD, at("2024-01-03 17:00:00")));
+        assert!(!hours.is_market_open(Exchange::MF, at("2024-01-03 12:00:00")));
+    }
+}
@@ -0,0 +1,496 @@
+//! In-memory fake of the Kite Connect REST API, behind the `mock` feature.
+//!
+//! [`FakeKite`] has the same method signatures as [`KiteConnect<Authenticated>`](crate::KiteConnect)
+//! but never touches the network. Program it with canned data and queued failures, then assert on
+//! the requests it recorded. Unlike [`test_utils`](crate::test_utils) (behind `test-utils`) it
+//! doesn't start a local HTTP server, so it works with any async runtime.
+//!
+//! ```
+//! use kite_connect::{KiteError, mock::{FakeKite, FakeMethod}};
+//! # use kite_connect::orders::*;
+//! # fn req() -> PlaceOrderRequest {
+//! #     PlaceOrderRequest::iceberg("INFY".into(), Exchange::NSE, TransactionType::Buy, OrderType::Market, 10, 2, Product::CNC).unwrap()
+//! # }
+//!
+//! # async fn run() {
+//! let kite = FakeKite::new();
+//! kite.fail_next(FakeMethod::PlaceOrder, KiteError::MarginException("Insufficient funds".into()));
+//!
+//! assert!(kite.place_order_poll(&req()).await.is_err());
+//! assert!(kite.place_order_poll(&req()).await.is_ok());
+//! assert_eq!(kite.placed_orders().len(), 1);
+//! # }
+//! ```
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::orders::{
+    ModifyCoverOrderRequest, ModifyIcebergOrderRequest, ModifyOrderRequest,
+    ModifyRegularOrderRequest, Order, OrderId, OrderStatus, PlaceOrderRequest, PlacedOrder,
+    Variety, generic_modify_order_endpoint, open_cover_order_leg, place_order_idempotent,
+};
+use crate::portfolio::{Holding, Positions};
+use crate::quotes::{LtpQuote, MixedQuotes, OhlcQuote, Quote, QuoteKey, order_quotes};
+use crate::trading::Trading;
+use crate::user::funds::TotalFunds;
+use crate::{Error, KiteError};
+
+/// The [`FakeKite`] methods failures can be queued for with [`FakeKite::fail_next`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FakeMethod {
+    /// `place_order`, `place_order_fire_and_forget`, `place_order_poll`, `place_order_verified`
+    /// and `place_order_idempotent`
+    PlaceOrder,
+    /// `modify_order`, `modify_regular_oder`, `modify_cover_order` and `modify_iceberg_order`
+    ModifyOrder,
+    /// `cancel_order` and `cancel_cover_order_leg`
+    CancelOrder,
+    /// `get_orders` and `get_order_history`
+    GetOrders,
+    GetHoldings,
+    GetPositions,
+    GetFunds,
+    /// Every quote method
+    GetQuotes,
+}
+
+/// Programmable in-memory stand-in for [`KiteConnect<Authenticated>`](crate::KiteConnect).
+#[derive(Debug, Default)]
+pub struct FakeKite {
+    state: Mutex<FakeState>,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    failures: HashMap<FakeMethod, VecDeque<KiteError>>,
+    order_ids: VecDeque<OrderId>,
+    generated_order_ids: u64,
+    placed: Vec<PlaceOrderRequest>,
+    cancelled: Vec<OrderId>,
+    orders: Vec<Order>,
+    holdings: Vec<Holding>,
+    positions: Option<Positions>,
+    funds: Option<TotalFunds>,
+    quotes: HashMap<String, Quote>,
+    ohlc_quotes: HashMap<String, OhlcQuote>,
+    ltp_quotes: HashMap<String, LtpQuote>,
+}
+
+impl FakeKite {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, FakeState> {
+        // A panic while holding the lock can only come from a failed assertion in a test
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn check(&self, method: FakeMethod) -> Result<(), Error> {
+        match self
+            .state()
+            .failures
+            .get_mut(&method)
+            .and_then(VecDeque::pop_front)
+        {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes the next call of `method` fail with `err`. Failures queue up in order.
+    pub fn fail_next(&self, method: FakeMethod, err: KiteError) -> &Self {
+        self.state()
+            .failures
+            .entry(method)
+            .or_default()
+            .push_back(err);
+        self
+    }
+
+    /// Queues the order id returned by the next successful order placement.
+    ///
+    /// Once the queue is empty, ids are generated as `FAKE000000000001`, `FAKE000000000002`, ...
+    pub fn queue_order_id(&self, order_id: impl Into<OrderId>) -> &Self {
+        self.state().order_ids.push_back(order_id.into());
+        self
+    }
+
+    /// Sets the orders returned by `get_orders` and `get_order_history`.
+    pub fn set_orders(&self, orders: Vec<Order>) -> &Self {
+        self.state().orders = orders;
+        self
+    }
+
+    pub fn set_holdings(&self, holdings: Vec<Holding>) -> &Self {
+        self.state().holdings = holdings;
+        self
+    }
+
+    pub fn set_positions(&self, positions: Positions) -> &Self {
+        self.state().positions = Some(positions);
+        self
+    }
+
+    pub fn set_funds(&self, funds: TotalFunds) -> &Self {
+        self.state().funds = Some(funds);
+        self
+    }
+
+    /// Sets the full quote returned for `key`, e.g. `"NSE:INFY"`.
+    pub fn set_quote(&self, key: impl ToString, quote: Quote) -> &Self {
+        self.state().quotes.insert(key.to_string(), quote);
+        self
+    }
+
+    pub fn set_ohlc_quote(&self, key: impl ToString, quote: OhlcQuote) -> &Self {
+        self.state().ohlc_quotes.insert(key.to_string(), quote);
+        self
+    }
+
+    pub fn set_ltp_quote(&self, key: impl ToString, quote: LtpQuote) -> &Self {
+        self.state().ltp_quotes.insert(key.to_string(), quote);
+        self
+    }
+
+    /// Returns every order placed successfully, oldest first.
+    pub fn placed_orders(&self) -> Vec<PlaceOrderRequest> {
+        self.state().placed.clone()
+    }
+
+    /// Returns the ids of every order cancelled successfully, oldest first.
+    pub fn cancelled_orders(&self) -> Vec<OrderId> {
+        self.state().cancelled.clone()
+    }
+
+    pub async fn place_order(&self, req: &PlaceOrderRequest) -> Result<(), Error> {
+        self.place_order_poll(req).await.map(|_| ())
+    }
+
+    /// Places the order like [`FakeKite::place_order`], `timeout` is ignored.
+    pub async fn place_order_fire_and_forget(
+        &self,
+        req: &PlaceOrderRequest,
+        timeout: std::time::Duration,
+    ) -> Result<(), Error> {
+        let _ = timeout;
+        self.place_order(req).await
+    }
+
+    /// Places the order with the next id queued with [`FakeKite::queue_order_id`], or a generated
+    /// one, and records `req` as is.
+    pub async fn place_order_poll(&self, req: &PlaceOrderRequest) -> Result<PlacedOrder, Error> {
+        req.validate()?;
+        self.check(FakeMethod::PlaceOrder)?;
+
+        let mut state = self.state();
+        let order_id = match state.order_ids.pop_front() {
+            Some(order_id) => order_id,
+            None => {
+                state.generated_order_ids += 1;
+                OrderId(format!("FAKE{:012}", state.generated_order_ids))
+            }
+        };
+        state.placed.push(req.clone());
+
+        Ok(PlacedOrder::new(order_id, req))
+    }
+
+    /// Places the order and returns the last order set with [`FakeKite::set_orders`] for its id,
+    /// or an `OrderException` if there's none.
+    pub async fn place_order_verified(&self, req: &PlaceOrderRequest) -> Result<Order, Error> {
+        let order_id = self.place_order_poll(req).await?.order_id;
+
+        self.get_order(&order_id).await?.ok_or_else(|| {
+            KiteError::OrderException(format!("No history found for order {order_id}")).into()
+        })
+    }
+
+    /// Places the order at most once per idempotency key, looking it up in the orders set with
+    /// [`FakeKite::set_orders`].
+    pub async fn place_order_idempotent(
+        &self,
+        req: &PlaceOrderRequest,
+    ) -> Result<PlacedOrder, Error> {
+        place_order_idempotent(self, req).await
+    }
+
+    pub async fn modify_order(
+        &self,
+        order_id: &OrderId,
+        variety: &Variety,
+        req: &ModifyOrderRequest,
+    ) -> Result<(), Error> {
+        let _ = order_id;
+        generic_modify_order_endpoint(variety)?;
+        if *variety == Variety::CO {
+            ModifyCoverOrderRequest::try_from(req)?;
+        }
+        self.check(FakeMethod::ModifyOrder)
+    }
+
+    pub async fn modify_regular_oder(
+        &self,
+        order_id: &OrderId,
+        req: &ModifyRegularOrderRequest,
+    ) -> Result<(), Error> {
+        let _ = (order_id, req);
+        self.check(FakeMethod::ModifyOrder)
+    }
+
+    pub async fn modify_cover_order(
+        &self,
+        order_id: &OrderId,
+        req: &ModifyCoverOrderRequest,
+    ) -> Result<(), Error> {
+        let _ = (order_id, req);
+        self.check(FakeMethod::ModifyOrder)
+    }
+
+    pub async fn modify_iceberg_order(
+        &self,
+        order_id: &OrderId,
+        iceberg_legs: u32,
+        req: &ModifyIcebergOrderRequest,
+    ) -> Result<(), Error> {
+        let _ = order_id;
+        req.validate(iceberg_legs)?;
+        self.check(FakeMethod::ModifyOrder)
+    }
+
+    /// Cancels the order, marking it as `CANCELLED` if it was set with [`FakeKite::set_orders`].
+    pub async fn cancel_order(&self, order_id: &OrderId, variety: &Variety) -> Result<(), Error> {
+        let _ = variety;
+        self.check(FakeMethod::CancelOrder)?;
+
+        let mut state = self.state();
+        for order in state.orders.iter_mut().filter(|o| &o.order_id == order_id) {
+            order.status = OrderStatus::Cancelled;
+        }
+        state.cancelled.push(order_id.clone());
+
+        Ok(())
+    }
+
+    /// Cancels the second leg of a cover order like [`FakeKite::cancel_order`].
+    pub async fn cancel_cover_order_leg(
+        &self,
+        order_id: &OrderId,
+        parent_order_id: &OrderId,
+    ) -> Result<(), Error> {
+        let _ = parent_order_id;
+        self.cancel_order(order_id, &Variety::CO).await
+    }
+
+    /// Cancels the open leg of the cover order among the orders set with
+    /// [`FakeKite::set_orders`].
+    pub async fn exit_cover_order(&self, parent_order_id: &OrderId) -> Result<(), Error> {
+        let orders = self.get_orders().await?;
+        let leg = open_cover_order_leg(&orders, parent_order_id)?;
+
+        self.cancel_cover_order_leg(&leg.order_id, parent_order_id)
+            .await
+    }
+
+    pub async fn get_orders(&self) -> Result<Vec<Order>, Error> {
+        self.check(FakeMethod::GetOrders)?;
+        Ok(self.state().orders.clone())
+    }
+
+    pub async fn get_order_history(&self, order_id: &OrderId) -> Result<Vec<Order>, Error> {
+        self.check(FakeMethod::GetOrders)?;
+        Ok(self
+            .state()
+            .orders
+            .iter()
+            .filter(|o| &o.order_id == order_id)
+            .cloned()
+            .collect())
+    }
+
+    pub async fn get_order(&self, order_id: &OrderId) -> Result<Option<Order>, Error> {
+        Ok(self.get_order_history(order_id).await?.pop())
+    }
+
+    pub async fn get_holdings(&self) -> Result<Vec<Holding>, Error> {
+        self.check(FakeMethod::GetHoldings)?;
+        Ok(self.state().holdings.clone())
+    }
+
+    /// Returns the positions set with [`FakeKite::set_positions`], or no positions.
+    pub async fn get_positions(&self) -> Result<Positions, Error> {
+        self.check(FakeMethod::GetPositions)?;
+        Ok(self.state().positions.clone().unwrap_or(Positions {
+            net: Vec::new(),
+            day: Vec::new(),
+        }))
+    }
+
+    /// Returns the funds set with [`FakeKite::set_funds`], or a `GeneralException` if unset.
+    pub async fn get_funds(&self) -> Result<TotalFunds, Error> {
+        self.check(FakeMethod::GetFunds)?;
+        self.state()
+            .funds
+            .ok_or_else(|| KiteError::GeneralException("FakeKite has no funds set".into()).into())
+    }
+
+    /// Returns the quotes set for the requested instruments. Unknown instruments are left out,
+    /// like Kite does.
+    pub async fn get_market_quotes<I: Serialize>(
+        &self,
+        i: &[I],
+    ) -> Result<HashMap<String, Quote>, Error> {
+        self.check(FakeMethod::GetQuotes)?;
+        Ok(pick(&self.state().quotes, i))
+    }
+
+    pub async fn get_market_quotes_ordered(
+        &self,
+        keys: &[QuoteKey],
+    ) -> Result<Vec<(String, Quote)>, Error> {
+        Ok(order_quotes(keys, &self.get_market_quotes(keys).await?))
+    }
+
+    pub async fn get_ohlc_quotes<I: Serialize>(
+        &self,
+        i: &[I],
+    ) -> Result<HashMap<String, OhlcQuote>, Error> {
+        self.check(FakeMethod::GetQuotes)?;
+        Ok(pick(&self.state().ohlc_quotes, i))
+    }
+
+    pub async fn get_ltp_quotes<I: Serialize>(
+        &self,
+        i: &[I],
+    ) -> Result<HashMap<String, LtpQuote>, Error> {
+        self.check(FakeMethod::GetQuotes)?;
+        Ok(pick(&self.state().ltp_quotes, i))
+    }
+
+    pub async fn get_mixed_quotes(
+        &self,
+        full: &[QuoteKey],
+        ltp: &[QuoteKey],
+    ) -> Result<MixedQuotes, Error> {
+        Ok(MixedQuotes {
+            full: self.get_market_quotes(full).await?,
+            ltp: self.get_ltp_quotes(ltp).await?,
+        })
+    }
+}
+
+impl Trading for FakeKite {
+    async fn place_order_poll(&self, req: &PlaceOrderRequest) -> Result<PlacedOrder, Error> {
+        FakeKite::place_order_poll(self, req).await
+    }
+
+    async fn cancel_order(&self, order_id: &OrderId, variety: &Variety) -> Result<(), Error> {
+        FakeKite::cancel_order(self, order_id, variety).await
+    }
+
+    async fn get_orders(&self) -> Result<Vec<Order>, Error> {
+        FakeKite::get_orders(self).await
+    }
+
+    async fn get_positions(&self) -> Result<Positions, Error> {
+        FakeKite::get_positions(self).await
+    }
+}
+
+/// Picks the quotes for `keys`, serialized the same way they would be in the query string.
+fn pick<I: Serialize, Q: Clone>(quotes: &HashMap<String, Q>, keys: &[I]) -> HashMap<String, Q> {
+    keys.iter()
+        .filter_map(|key| match serde_json::to_value(key).ok()? {
+            serde_json::Value::String(key) => Some(key),
+            other => Some(other.to_string()),
+        })
+        .filter_map(|key| quotes.get(&key).map(|q| (key, q.clone())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::Exchange;
+
+    fn req() -> PlaceOrderRequest {
+        crate::test_utils::market_order()
+    }
+
+    fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Error building runtime")
+            .block_on(f)
+    }
+
+    #[test]
+    fn test_place_order() {
+        let kite = FakeKite::new();
+        kite.queue_order_id("151220000000000").fail_next(
+            FakeMethod::PlaceOrder,
+            KiteError::MarginException("Insufficient funds".into()),
+        );
+
+        block_on(async {
+            assert!(matches!(
+                kite.place_order_poll(&req()).await,
+                Err(Error::KiteError(KiteError::MarginException(_)))
+            ));
+            assert_eq!(
+                kite.place_order_poll(&req()).await.unwrap().order_id,
+                "151220000000000"
+            );
+            assert_eq!(
+                kite.place_order_poll(&req()).await.unwrap().order_id,
+                "FAKE000000000001"
+            );
+
+            // Local validation still applies
+            let mut invalid = req();
+            invalid.exchange = Exchange::MCX;
+            assert!(kite.place_order_poll(&invalid).await.is_err());
+        });
+
+        assert_eq!(kite.placed_orders(), vec![req(), req()]);
+    }
+
+    #[test]
+    fn test_quotes() {
+        let kite = FakeKite::new();
+        kite.set_ltp_quote(
+            "NSE:INFY",
+            LtpQuote {
+                instrument_token: 408065,
+                last_price: 1074.35,
+            },
+        )
+        .set_ltp_quote(
+            "408065",
+            LtpQuote {
+                instrument_token: 408065,
+                last_price: 1074.35,
+            },
+        );
+
+        let ltp = block_on(kite.get_ltp_quotes(&[
+            QuoteKey::symbol(Exchange::NSE, "INFY"),
+            QuoteKey::from(408065),
+            QuoteKey::symbol(Exchange::NSE, "SBIN"),
+        ]))
+        .unwrap();
+
+        assert_eq!(ltp.len(), 2);
+        assert_eq!(ltp["NSE:INFY"].last_price, 1074.35);
+        assert!(ltp.contains_key("408065"));
+
+        kite.fail_next(
+            FakeMethod::GetQuotes,
+            KiteError::NetworkException("Too many requests".into()),
+        );
+        assert!(block_on(kite.get_ltp_quotes(&["NSE:INFY"])).is_err());
+        assert!(block_on(kite.get_funds()).is_err());
+    }
+}
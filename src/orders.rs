@@ -1,6 +1,13 @@
 use super::*;
+use crate::quotes::Instrument;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::trading::Trading;
 
 pub const PLACE_REGULAR_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/regular";
 pub const PLACE_AMO_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/amo";
@@ -9,7 +16,9 @@ pub const PLACE_ICEBERG_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/ic
 pub const PLACE_AUCTION_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/auction";
 
 pub const MODIFY_REGULAR_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/regular/";
-pub const MODIFY_COVER_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/regular/co/";
+pub const MODIFY_AMO_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/amo/";
+pub const MODIFY_COVER_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/co/";
+pub const MODIFY_ICEBERG_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/iceberg/";
 
 pub const CANCEL_REGULAR_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/regular/";
 pub const CANCEL_AMO_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/amo/";
@@ -18,6 +27,8 @@ pub const CANCEL_ICEBERG_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/i
 pub const CANCEL_AUCTION_ORDER_ENDPOINT: &str = "https://api.kite.trade/orders/auction/";
 
 pub const GET_ORDERS_ENDPOINT: &str = "https://api.kite.trade/orders";
+pub const GET_ORDER_HISTORY_ENDPOINT: &str = "https://api.kite.trade/orders/";
+pub const GET_TRADES_ENDPOINT: &str = "https://api.kite.trade/trades";
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -35,7 +46,8 @@ pub enum Variety {
 }
 
 /// Represents an exchange
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Exchange {
     /// BSE Futures & Options
     BFO,
@@ -53,11 +65,14 @@ pub enum Exchange {
     MF,
     /// NSE Futures & Options
     NFO,
+    /// NSE Commodity Derivatives
+    NCO,
 }
 
-impl Display for Exchange {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
+impl Exchange {
+    /// Name of the exchange, as used by Kite.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
             Exchange::BFO => "BFO",
             Exchange::MCX => "MCX",
             Exchange::NSE => "NSE",
@@ -66,8 +81,14 @@ impl Display for Exchange {
             Exchange::BCD => "BCD",
             Exchange::MF => "MF",
             Exchange::NFO => "NFO",
-        };
-        write!(f, "{}", s)
+            Exchange::NCO => "NCO",
+        }
+    }
+}
+
+impl Display for Exchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -88,6 +109,20 @@ pub enum Product {
     CO,
 }
 
+impl Display for Product {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Product::CNC => "CNC",
+            Product::NRML => "NRML",
+            Product::MIS => "MIS",
+            Product::MTF => "MTF",
+            Product::BO => "BO",
+            Product::CO => "CO",
+        };
+        write!(f, "{s}")
+    }
+}
+
 /// Order types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderType {
@@ -105,6 +140,18 @@ pub enum OrderType {
     SL_M,
 }
 
+impl Display for OrderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OrderType::Market => "MARKET",
+            OrderType::Limit => "LIMIT",
+            OrderType::SL => "SL",
+            OrderType::SL_M => "SL-M",
+        };
+        write!(f, "{s}")
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Validity {
@@ -120,9 +167,20 @@ pub enum TransactionType {
     Sell,
 }
 
+impl Display for TransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TransactionType::Buy => "BUY",
+            TransactionType::Sell => "SELL",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// An order to place, see [`PlaceOrderRequest::builder`] to build one checked against its variety
+/// and order type.
+///
 /// Read More: <https://zerodha.com/varsity/chapter/understanding-the-various-order-types/>
-// TODO: Some properties depend on variety, while some on OrderType. Have these type store that extra
-// metadata so it is easier to create correct request
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PlaceOrderRequest {
     #[serde(skip_serializing)]
@@ -141,47 +199,716 @@ pub struct PlaceOrderRequest {
     /// Margin product to use for the order (margins are blocked based on this)
     pub product: Product,
     /// The price to execute the order at (for LIMIT orders)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub price: Option<f64>,
     /// The price at which an order should be triggered (SL, SL-M)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub trigger_price: Option<f64>,
     /// Quantity to disclose publicly (for equity trades)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub disclosed_quantity: Option<u32>,
     /// Order validity (DAY, IOC and TTL)
     pub validity: Validity,
     /// Order life span in minutes for TTL validity orders
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub validity_ttl: Option<u32>,
     /// Total number of legs for iceberg order type (number of legs per Iceberg should be between 2 and 10)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub iceberg_legs: Option<u32>,
     /// Split quantity for each iceberg leg order (quantity/iceberg_legs)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub iceberg_quantity: Option<u32>,
     /// A unique identifier for a particular auction
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub auction_number: Option<String>,
+    /// Percentage of the last price beyond which a MARKET or SL-M order isn't filled, on the
+    /// segments supporting it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_protection: Option<u32>,
     /// An optional tag to apply to an order to identify it (alphanumeric, max 20 chars)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tag: Option<String>,
+    /// Key identifying the placement of the order across retries, see
+    /// [`KiteConnect::place_order_idempotent`]
+    ///
+    /// Kite has no field for it, so it's sent as the `tag` of the order and can't be set along
+    /// with `tag` or a [default order tag](KiteConnect::with_default_order_tag).
+    #[serde(
+        rename = "tag",
+        skip_serializing_if = "Option::is_none",
+        skip_deserializing
+    )]
+    pub idempotency_key: Option<String>,
+}
+
+/// Maximum `validity_ttl` (in minutes) accepted for After Market Orders.
+pub const MAX_AMO_VALIDITY_TTL: u32 = 365;
+/// Maximum `validity_ttl` (in minutes) accepted for every other variety.
+pub const MAX_VALIDITY_TTL: u32 = 120;
+/// Maximum length of an order `tag`.
+pub const MAX_ORDER_TAG_LEN: usize = 20;
+
+/// Checks that `tag`, sent as the `tag` of an order, is 1 to [`MAX_ORDER_TAG_LEN`] alphanumeric
+/// characters. `name` is the field the tag is set with, for the error message.
+pub(crate) fn validate_tag(name: &str, tag: &str) -> Result<(), Error> {
+    if tag.is_empty()
+        || tag.len() > MAX_ORDER_TAG_LEN
+        || !tag.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(Error::input_exception(format!(
+            "`{name}` must be 1 to {MAX_ORDER_TAG_LEN} alphanumeric characters, got {tag:?}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Generates an [`idempotency_key`](PlaceOrderRequest::idempotency_key) from the current time and
+/// a counter, unique even for keys generated at the same time.
+fn new_idempotency_key() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    fn base36(mut n: u64) -> String {
+        let mut digits = Vec::new();
+        loop {
+            digits.push(char::from_digit((n % 36) as u32, 36).unwrap_or('0'));
+            n /= 36;
+            if n == 0 {
+                break;
+            }
+        }
+        digits.iter().rev().collect()
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed) % 36u64.pow(4);
+
+    // At most 2 + 13 + 4 characters, within MAX_ORDER_TAG_LEN
+    format!(
+        "{IDEMPOTENCY_KEY_PREFIX}{}{:0>4}",
+        base36(nanos),
+        base36(count)
+    )
+}
+
+/// Prefix of the keys generated by [`new_idempotency_key`].
+const IDEMPOTENCY_KEY_PREFIX: &str = "ik";
+
+/// Whether `tag` looks like a key generated by [`new_idempotency_key`].
+fn is_generated_idempotency_key(tag: &str) -> bool {
+    tag.strip_prefix(IDEMPOTENCY_KEY_PREFIX)
+        .is_some_and(|rest| {
+            rest.len() >= 5
+                && rest
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+        })
+}
+
+impl PlaceOrderRequest {
+    /// Validates the request locally before sending it to Kite.
+    ///
+    /// Returns an [`KiteError::InputException`](crate::KiteError::InputException) describing the
+    /// first invalid field, so obviously incorrect orders don't cost an API call.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.validity == Validity::TTL {
+            let max_ttl = match self.variety {
+                Variety::AMO => MAX_AMO_VALIDITY_TTL,
+                _ => MAX_VALIDITY_TTL,
+            };
+
+            match self.validity_ttl {
+                None => {
+                    return Err(Error::input_exception(
+                        "`validity_ttl` is required when validity is TTL",
+                    ));
+                }
+                Some(ttl) if !(1..=max_ttl).contains(&ttl) => {
+                    return Err(Error::input_exception(format!(
+                        "`validity_ttl` must be between 1 and {max_ttl} minutes for {:?} orders, got {ttl}",
+                        self.variety
+                    )));
+                }
+                Some(_) => {}
+            }
+        }
+
+        if !is_valid_product_for(self.exchange, self.product) {
+            return Err(Error::input_exception(format!(
+                "Product {:?} is not available on {}",
+                self.product, self.exchange
+            )));
+        }
+
+        if let Some(market_protection) = self.market_protection {
+            if !matches!(self.order_type, OrderType::Market | OrderType::SL_M) {
+                return Err(Error::input_exception(format!(
+                    "`market_protection` is only for MARKET and SL-M orders, not {}",
+                    self.order_type
+                )));
+            }
+            if market_protection > 100 {
+                return Err(Error::input_exception(format!(
+                    "`market_protection` must be a percentage, got {market_protection}"
+                )));
+            }
+        }
+
+        if self.tag.is_some() && self.idempotency_key.is_some() {
+            return Err(Error::input_exception(
+                "`tag` and `idempotency_key` can't be both set, the key is sent as the tag",
+            ));
+        }
+
+        for (name, tag) in [
+            ("tag", &self.tag),
+            ("idempotency_key", &self.idempotency_key),
+        ] {
+            if let Some(tag) = tag {
+                validate_tag(name, tag)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Same as [`PlaceOrderRequest::validate`], also checking the quantity against the lot size
+    /// of `instrument`, e.g. looked up with [`InstrumentIndex::get`](crate::symbols::InstrumentIndex::get).
+    ///
+    /// The iceberg leg quantity has to be a multiple of the lot size too, see
+    /// [`validate_quantity`].
+    pub fn validate_for(&self, instrument: &Instrument) -> Result<(), Error> {
+        self.validate()?;
+        validate_quantity(self.quantity, instrument.lot_size)?;
+        if let Some(iceberg_quantity) = self.iceberg_quantity {
+            validate_quantity(iceberg_quantity, instrument.lot_size)?;
+        }
+
+        Ok(())
+    }
+
+    /// Rounds `price` and `trigger_price` to the nearest multiple of `tick_size`.
+    ///
+    /// Kite rejects LIMIT and SL orders whose prices aren't a multiple of the instrument's
+    /// [`Instrument::tick_size`].
+    pub fn round_prices_to_tick(&mut self, tick_size: f64) {
+        self.price = self.price.map(|p| round_to_tick(p, tick_size));
+        self.trigger_price = self.trigger_price.map(|p| round_to_tick(p, tick_size));
+    }
+
+    /// Same as [`PlaceOrderRequest::round_prices_to_tick`], using `instrument`'s tick size.
+    pub fn with_prices_rounded_for(mut self, instrument: &Instrument) -> Self {
+        self.round_prices_to_tick(instrument.tick_size);
+        self
+    }
+
+    /// Sets the limit price, e.g. to re-place an [`Order`] at a new price.
+    pub fn with_price(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Sets a newly generated [`idempotency_key`](PlaceOrderRequest::idempotency_key) unless the
+    /// request has one, see [`KiteConnect::place_order_idempotent`].
+    pub fn with_idempotency_key(mut self) -> Self {
+        if self.idempotency_key.is_none() {
+            self.idempotency_key = Some(new_idempotency_key());
+        }
+        self
+    }
+
+    /// Creates an iceberg order, splitting `total_quantity` evenly across `legs`.
+    ///
+    /// The order is created with [`Validity::Day`] and no price, set `price`/`trigger_price` on the
+    /// returned request for LIMIT and SL orders.
+    ///
+    /// Returns an [`KiteError::InputException`](crate::KiteError::InputException) if `legs` is
+    /// outside [`ICEBERG_LEGS`] or `total_quantity` isn't divisible by `legs`.
+    pub fn iceberg(
+        trading_symbol: String,
+        exchange: Exchange,
+        transaction_type: TransactionType,
+        order_type: OrderType,
+        total_quantity: u32,
+        legs: u32,
+        product: Product,
+    ) -> Result<Self, Error> {
+        let iceberg_quantity = iceberg_quantity(total_quantity, legs)?;
+
+        Ok(Self {
+            variety: Variety::IceBerg,
+            trading_symbol,
+            exchange,
+            transaction_type,
+            order_type,
+            quantity: total_quantity,
+            product,
+            price: None,
+            trigger_price: None,
+            disclosed_quantity: None,
+            validity: Validity::Day,
+            validity_ttl: None,
+            iceberg_legs: Some(legs),
+            iceberg_quantity: Some(iceberg_quantity),
+            auction_number: None,
+            market_protection: None,
+            tag: None,
+            idempotency_key: None,
+        })
+    }
+
+    /// Starts building an order of `variety` for `trading_symbol` on `exchange`, see
+    /// [`PlaceOrderRequestBuilder`].
+    pub fn builder(
+        variety: Variety,
+        trading_symbol: impl Into<String>,
+        exchange: Exchange,
+    ) -> PlaceOrderRequestBuilder {
+        PlaceOrderRequestBuilder {
+            variety,
+            trading_symbol: trading_symbol.into(),
+            exchange,
+            transaction_type: None,
+            order_type: OrderType::Market,
+            quantity: None,
+            product: None,
+            price: None,
+            trigger_price: None,
+            disclosed_quantity: None,
+            validity: Validity::Day,
+            validity_ttl: None,
+            iceberg_legs: None,
+            auction_number: None,
+            market_protection: None,
+            tag: None,
+        }
+    }
+}
+
+/// Quantity of each leg of an iceberg order of `total_quantity` split across `legs`.
+fn iceberg_quantity(total_quantity: u32, legs: u32) -> Result<u32, Error> {
+    if !ICEBERG_LEGS.contains(&legs) {
+        return Err(Error::input_exception(format!(
+            "Iceberg orders need between {} and {} legs, got {legs}",
+            ICEBERG_LEGS.start(),
+            ICEBERG_LEGS.end()
+        )));
+    }
+
+    if !total_quantity.is_multiple_of(legs) {
+        return Err(Error::input_exception(format!(
+            "Quantity {total_quantity} can't be split evenly across {legs} iceberg legs"
+        )));
+    }
+
+    Ok(total_quantity / legs)
+}
+
+/// Builds a [`PlaceOrderRequest`], created with [`PlaceOrderRequest::builder`].
+///
+/// Orders are MARKET orders valid for the day unless set otherwise. The transaction type, quantity
+/// and product have to be set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaceOrderRequestBuilder {
+    variety: Variety,
+    trading_symbol: String,
+    exchange: Exchange,
+    transaction_type: Option<TransactionType>,
+    order_type: OrderType,
+    quantity: Option<u32>,
+    product: Option<Product>,
+    price: Option<f64>,
+    trigger_price: Option<f64>,
+    disclosed_quantity: Option<u32>,
+    validity: Validity,
+    validity_ttl: Option<u32>,
+    iceberg_legs: Option<u32>,
+    auction_number: Option<String>,
+    market_protection: Option<u32>,
+    tag: Option<String>,
+}
+
+impl PlaceOrderRequestBuilder {
+    pub fn transaction_type(mut self, transaction_type: TransactionType) -> Self {
+        self.transaction_type = Some(transaction_type);
+        self
+    }
+
+    pub fn buy(self) -> Self {
+        self.transaction_type(TransactionType::Buy)
+    }
+
+    pub fn sell(self) -> Self {
+        self.transaction_type(TransactionType::Sell)
+    }
+
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = order_type;
+        self
+    }
+
+    /// Total quantity of the order, split across the legs of an iceberg order.
+    pub fn quantity(mut self, quantity: u32) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn product(mut self, product: Product) -> Self {
+        self.product = Some(product);
+        self
+    }
+
+    /// Limit price of LIMIT and SL orders.
+    pub fn price(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Trigger price of SL and SL-M orders.
+    pub fn trigger_price(mut self, trigger_price: f64) -> Self {
+        self.trigger_price = Some(trigger_price);
+        self
+    }
+
+    pub fn disclosed_quantity(mut self, disclosed_quantity: u32) -> Self {
+        self.disclosed_quantity = Some(disclosed_quantity);
+        self
+    }
+
+    pub fn validity(mut self, validity: Validity) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    /// Makes the order valid for `minutes`, setting the validity to [`Validity::TTL`].
+    pub fn validity_ttl(mut self, minutes: u32) -> Self {
+        self.validity = Validity::TTL;
+        self.validity_ttl = Some(minutes);
+        self
+    }
+
+    /// Number of legs of an iceberg order, within [`ICEBERG_LEGS`].
+    pub fn iceberg_legs(mut self, legs: u32) -> Self {
+        self.iceberg_legs = Some(legs);
+        self
+    }
+
+    pub fn auction_number(mut self, auction_number: impl Into<String>) -> Self {
+        self.auction_number = Some(auction_number.into());
+        self
+    }
+
+    /// Market protection of a MARKET or SL-M order, as a percentage of the last price.
+    pub fn market_protection(mut self, percentage: u32) -> Self {
+        self.market_protection = Some(percentage);
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Returns the request, or an [`KiteError::InputException`](crate::KiteError::InputException)
+    /// if:
+    ///
+    /// - the transaction type, product or a non-zero quantity isn't set,
+    /// - a LIMIT or SL order has no price, or an SL or SL-M order no trigger price,
+    /// - an iceberg order has no valid number of legs, or iceberg legs are set on another variety,
+    /// - an auction order has no auction number, or an auction number is set on another variety,
+    /// - or [`PlaceOrderRequest::validate`] fails.
+    pub fn build(self) -> Result<PlaceOrderRequest, Error> {
+        let missing = |field: &str| Error::input_exception(format!("`{field}` is required"));
+
+        let transaction_type = self
+            .transaction_type
+            .ok_or_else(|| missing("transaction_type"))?;
+        let product = self.product.ok_or_else(|| missing("product"))?;
+        let quantity = self
+            .quantity
+            .filter(|&quantity| quantity > 0)
+            .ok_or_else(|| missing("quantity"))?;
+
+        let needs_price = matches!(self.order_type, OrderType::Limit | OrderType::SL);
+        let needs_trigger = matches!(self.order_type, OrderType::SL | OrderType::SL_M);
+        if needs_price && self.price.is_none() {
+            return Err(Error::input_exception(format!(
+                "`price` is required for {} orders",
+                self.order_type
+            )));
+        }
+        if needs_trigger && self.trigger_price.is_none() {
+            return Err(Error::input_exception(format!(
+                "`trigger_price` is required for {} orders",
+                self.order_type
+            )));
+        }
+
+        let iceberg_quantity = match (&self.variety, self.iceberg_legs) {
+            (Variety::IceBerg, Some(legs)) => Some(iceberg_quantity(quantity, legs)?),
+            (Variety::IceBerg, None) => return Err(missing("iceberg_legs")),
+            (_, Some(_)) => {
+                return Err(Error::input_exception(
+                    "`iceberg_legs` is only for iceberg orders",
+                ));
+            }
+            (_, None) => None,
+        };
+        match (&self.variety, &self.auction_number) {
+            (Variety::Auction, None) => return Err(missing("auction_number")),
+            (Variety::Auction, Some(_)) | (_, None) => {}
+            (_, Some(_)) => {
+                return Err(Error::input_exception(
+                    "`auction_number` is only for auction orders",
+                ));
+            }
+        }
+
+        let req = PlaceOrderRequest {
+            variety: self.variety,
+            trading_symbol: self.trading_symbol,
+            exchange: self.exchange,
+            transaction_type,
+            order_type: self.order_type,
+            quantity,
+            product,
+            price: self.price,
+            trigger_price: self.trigger_price,
+            disclosed_quantity: self.disclosed_quantity,
+            validity: self.validity,
+            validity_ttl: self.validity_ttl,
+            iceberg_legs: self.iceberg_legs,
+            iceberg_quantity,
+            auction_number: self.auction_number,
+            market_protection: self.market_protection,
+            tag: self.tag,
+            idempotency_key: None,
+        };
+        req.validate()?;
+
+        Ok(req)
+    }
+}
+
+/// Builds the request to place `order` again, e.g. after it was rejected or cancelled.
+///
+/// Kite reports unset prices and disclosed quantities as `0`, they're left out of the request.
+/// The iceberg legs are read from [`Order::meta`]. A tag that's an idempotency key generated by
+/// [`KiteConnect::place_order_idempotent`] is left out, so the request can be placed again
+/// instead of matching the old order.
+///
+/// Returns an [`KiteError::InputException`](crate::KiteError::InputException) for an auction
+/// order without an auction number, or an iceberg order without its legs.
+impl TryFrom<&Order> for PlaceOrderRequest {
+    type Error = Error;
+
+    fn try_from(order: &Order) -> Result<Self, Self::Error> {
+        let (iceberg_legs, iceberg_quantity) = match order.variety {
+            Variety::IceBerg => match order.meta.as_ref().and_then(|meta| meta.iceberg) {
+                Some(iceberg) if iceberg.legs > 0 && iceberg.leg_quantity > 0 => {
+                    (Some(iceberg.legs), Some(iceberg.leg_quantity))
+                }
+                _ => {
+                    return Err(Error::input_exception(format!(
+                        "Iceberg order {} has no legs to place it again",
+                        order.order_id
+                    )));
+                }
+            },
+            _ => (None, None),
+        };
+
+        if order.variety == Variety::Auction && order.auction_number.is_none() {
+            return Err(Error::input_exception(format!(
+                "Auction order {} has no auction number to place it again",
+                order.order_id
+            )));
+        }
+
+        Ok(Self {
+            variety: order.variety.clone(),
+            trading_symbol: order.trading_symbol.clone(),
+            exchange: order.exchange,
+            transaction_type: order.transaction_type,
+            order_type: order.order_type,
+            quantity: order.quantity,
+            product: order.product,
+            price: order.price.filter(|&price| price > 0.0),
+            trigger_price: order.trigger_price.filter(|&price| price > 0.0),
+            disclosed_quantity: order.disclosed_quantity.filter(|&quantity| quantity > 0),
+            validity: order.validity,
+            validity_ttl: order.validity_ttl.filter(|&ttl| ttl > 0),
+            iceberg_legs,
+            iceberg_quantity,
+            auction_number: order.auction_number.clone(),
+            market_protection: order
+                .market_protection
+                .filter(|&market_protection| market_protection > 0.0)
+                .map(|market_protection| market_protection.round() as u32),
+            tag: order
+                .tag
+                .clone()
+                .filter(|tag| !is_generated_idempotency_key(tag)),
+            idempotency_key: None,
+        })
+    }
+}
+
+/// Allowed number of legs for an iceberg order.
+pub const ICEBERG_LEGS: std::ops::RangeInclusive<u32> = 2..=10;
+
+/// Rounds `price` to the nearest multiple of `tick_size`.
+///
+/// The result is also rounded to the number of decimals in `tick_size`, so `1412.93` at a tick of
+/// `0.05` gives exactly `1412.95` rather than `1412.9500000000000455`.
+pub fn round_to_tick(price: f64, tick_size: f64) -> f64 {
+    if tick_size <= 0.0 || !tick_size.is_finite() {
+        return price;
+    }
+
+    let rounded = (price / tick_size).round() * tick_size;
+    let decimals = tick_size
+        .to_string()
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len() as i32);
+    let scale = 10f64.powi(decimals);
+
+    (rounded * scale).round() / scale
+}
+
+/// Checks that `quantity` is a multiple of `lot_size`, which Kite requires of F&O orders.
+///
+/// Returns an [`KiteError::InputException`](crate::KiteError::InputException) naming the required
+/// multiple otherwise. A lot size that isn't positive is taken as unknown and accepts any quantity.
+pub fn validate_quantity(quantity: u32, lot_size: i64) -> Result<(), Error> {
+    if lot_size > 0 && i64::from(quantity) % lot_size != 0 {
+        return Err(Error::input_exception(format!(
+            "Quantity must be a multiple of the lot size {lot_size}, got {quantity}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns whether orders for `product` can be placed on `exchange`.
+///
+/// - CNC and MTF are only for equity delivery on NSE/BSE.
+/// - NRML is only for derivatives (F&O, currency and commodity).
+/// - MIS and CO are available on every exchange except MF.
+/// - BO has been discontinued by Kite and is never accepted.
+/// - MF orders go through the mutual fund APIs and don't take a product.
+pub const fn is_valid_product_for(exchange: Exchange, product: Product) -> bool {
+    use Exchange::*;
+
+    match product {
+        Product::CNC | Product::MTF => matches!(exchange, NSE | BSE),
+        Product::NRML => matches!(exchange, NFO | BFO | CDS | BCD | MCX | NCO),
+        Product::MIS | Product::CO => !matches!(exchange, MF),
+        Product::BO => false,
+    }
 }
 
 // TODO: Add utility functions to create order
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModifyRegularOrderRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_type: Option<OrderType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disclosed_quantity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity: Option<Validity>,
+}
+
+/// Changes to an order of any variety, see [`KiteConnect::modify_order`].
+pub type ModifyOrderRequest = ModifyRegularOrderRequest;
+
+/// Changes to an iceberg order, see [`KiteConnect::modify_iceberg_order`].
+///
+/// The new quantity is the total quantity of the order, split again across its original number
+/// of legs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModifyIcebergOrderRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub order_type: Option<OrderType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub quantity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub trigger_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub disclosed_quantity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub validity: Option<Validity>,
 }
 
+impl ModifyIcebergOrderRequest {
+    /// Checks that the new quantity, if any, splits evenly across the `iceberg_legs` of the order.
+    ///
+    /// Returns an [`KiteError::InputException`](crate::KiteError::InputException) otherwise.
+    pub fn validate(&self, iceberg_legs: u32) -> Result<(), Error> {
+        match self.quantity {
+            Some(quantity) if iceberg_legs == 0 || !quantity.is_multiple_of(iceberg_legs) => {
+                Err(Error::input_exception(format!(
+                    "Quantity {quantity} can't be split evenly across {iceberg_legs} iceberg legs"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModifyCoverOrderRequest {
     /// Unique order ID
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub order_id: Option<String>,
     /// The price to execute the order at
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub price: Option<f64>,
     /// For LIMIT Cover orders
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub trigger_price: Option<f64>,
 }
 
+/// Only the prices of a cover order can be modified.
+impl TryFrom<&ModifyOrderRequest> for ModifyCoverOrderRequest {
+    type Error = Error;
+
+    fn try_from(req: &ModifyOrderRequest) -> Result<Self, Self::Error> {
+        if req.order_type.is_some()
+            || req.quantity.is_some()
+            || req.disclosed_quantity.is_some()
+            || req.validity.is_some()
+        {
+            return Err(Error::input_exception(
+                "Only the price and trigger price of cover orders can be modified",
+            ));
+        }
+
+        Ok(Self {
+            order_id: None,
+            price: req.price,
+            trigger_price: req.trigger_price,
+        })
+    }
+}
+
+/// Status of an order.
+///
+/// Besides the final states, an order goes through interim states while the OMS and the exchange
+/// process it, see <https://kite.trade/docs/connect/v3/orders/#order-statuses>. Statuses Kite
+/// doesn't document are kept in [`OrderStatus::Other`].
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum OrderStatus {
@@ -189,19 +916,179 @@ pub enum OrderStatus {
     Cancelled,
     Rejected,
     Complete,
+    /// SL or SL-M order waiting for its trigger price
+    #[serde(rename = "TRIGGER PENDING")]
+    TriggerPending,
+    #[serde(rename = "PUT ORDER REQ RECEIVED")]
+    PutOrderReqReceived,
+    #[serde(rename = "VALIDATION PENDING")]
+    ValidationPending,
+    #[serde(rename = "OPEN PENDING")]
+    OpenPending,
+    #[serde(rename = "MODIFY VALIDATION PENDING")]
+    ModifyValidationPending,
+    #[serde(rename = "MODIFY PENDING")]
+    ModifyPending,
+    #[serde(rename = "CANCEL PENDING")]
+    CancelPending,
+    /// After Market Order waiting for the market to open
+    #[serde(rename = "AMO REQ RECEIVED")]
+    AmoReqReceived,
     #[serde(untagged)]
     Other(String),
 }
 
+impl OrderStatus {
+    /// Whether the order reached a final state: complete, cancelled or rejected.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Complete | OrderStatus::Cancelled | OrderStatus::Rejected
+        )
+    }
+
+    /// Whether the order is resting at the exchange and can still be filled: open, or waiting for
+    /// its trigger price.
+    pub fn is_open(&self) -> bool {
+        matches!(self, OrderStatus::Open | OrderStatus::TriggerPending)
+    }
+
+    /// Whether the order is in an interim state, being placed, modified or cancelled by the OMS,
+    /// or an AMO waiting for the market to open.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::PutOrderReqReceived
+                | OrderStatus::ValidationPending
+                | OrderStatus::OpenPending
+                | OrderStatus::ModifyValidationPending
+                | OrderStatus::ModifyPending
+                | OrderStatus::CancelPending
+                | OrderStatus::AmoReqReceived
+        )
+    }
+}
+
+impl Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OrderStatus::Open => "OPEN",
+            OrderStatus::Cancelled => "CANCELLED",
+            OrderStatus::Rejected => "REJECTED",
+            OrderStatus::Complete => "COMPLETE",
+            OrderStatus::TriggerPending => "TRIGGER PENDING",
+            OrderStatus::PutOrderReqReceived => "PUT ORDER REQ RECEIVED",
+            OrderStatus::ValidationPending => "VALIDATION PENDING",
+            OrderStatus::OpenPending => "OPEN PENDING",
+            OrderStatus::ModifyValidationPending => "MODIFY VALIDATION PENDING",
+            OrderStatus::ModifyPending => "MODIFY PENDING",
+            OrderStatus::CancelPending => "CANCEL PENDING",
+            OrderStatus::AmoReqReceived => "AMO REQ RECEIVED",
+            OrderStatus::Other(status) => status,
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Defines a string id wrapper, so that ids of different kinds can't be mixed up.
+macro_rules! string_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self(s.to_owned()))
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                Self(value.to_owned())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl std::borrow::Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+string_id!(
+    /// Id of an order assigned by Kite, as opposed to the [`ExchangeOrderId`] assigned by the
+    /// exchange.
+    OrderId
+);
+
+string_id!(
+    /// Id of an order assigned by the exchange, as opposed to the [`OrderId`] assigned by Kite.
+    ExchangeOrderId
+);
+
+string_id!(
+    /// Id of a trade assigned by the exchange.
+    TradeId
+);
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Order {
     /// Unique order ID
-    pub order_id: String,
+    pub order_id: OrderId,
     /// Order ID of the parent order (only applicable in case of multi-legged orders like CO)
-    pub parent_order_id: Option<String>,
+    pub parent_order_id: Option<OrderId>,
     /// Exchange generated order ID. Orders that don't reach the exchange have null IDs
-    pub exchange_order_id: Option<String>,
-    /// Indicate that the order has been modified since placement by the user
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    /// Indicate that the order has been modified since placement by the user. Missing from the
+    /// order updates of the WebSocket
+    #[serde(default)]
     pub modified: bool,
     /// ID of the user that placed the order. This may different from the user's ID for orders
     /// placed outside of Kite, for instance, by dealers at the brokerage using dealer terminals
@@ -228,6 +1115,9 @@ pub struct Order {
     pub product: Product,
     /// Order validity
     pub validity: Validity,
+    /// Number of minutes the order stays open when `validity` is TTL
+    #[serde(default)]
+    pub validity_ttl: Option<u32>,
     /// Price at which the order was placed (LIMIT orders)
     pub price: Option<f64>,
     /// Quantity ordered
@@ -243,12 +1133,21 @@ pub struct Order {
     /// Quantity to be disclosed (may be different from actual quantity) to the public exchange
     /// orderbook. Only for equities
     pub disclosed_quantity: Option<u32>,
-    /// Timestamp at which the order was registered by the API
+    /// Timestamp at which the order was registered by the API, see [`Order::order_time`]
+    #[serde(deserialize_with = "crate::utils::deserialize_nullable_string")]
     pub order_timestamp: String,
     /// Timestamp at which the order was registered by the exchange. Orders that don't reach
-    /// the exchange have null timestamps
+    /// the exchange have null timestamps, empty ones are taken as null too
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::deserialize_non_empty_string"
+    )]
     pub exchange_timestamp: Option<String>,
     /// Timestamp at which an order's state changed at the exchange
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::deserialize_non_empty_string"
+    )]
     pub exchange_update_timestamp: Option<String>,
     /// Textual description of the order's status. Failed orders come with human readable explanation
     pub status_message: Option<String>,
@@ -258,122 +1157,568 @@ pub struct Order {
     pub cancelled_quantity: u32,
     /// A unique identifier for a particular auction
     pub auction_number: Option<String>,
+    /// Market protection percentage of a MARKET or SL-M order, `0` when unset
+    pub market_protection: Option<f64>,
     /// An optional tag to apply to an order to identify it (alphanumeric, max 20 chars)
     pub tag: Option<String>,
     /// Unusable request id to avoid order duplication
     pub guid: String,
-    /// Map of arbitrary fields that the system may attach to an order.
+    /// Metadata the system may attach to an order, e.g. the legs of an iceberg order
+    #[serde(default)]
+    pub meta: Option<OrderMeta>,
+    /// Fields of the order not mapped above
     #[serde(flatten)]
-    pub meta: Option<serde_json::Value>,
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Deserialize)]
-struct Data {
-    order_id: String,
+/// The `meta` object of an [`Order`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderMeta {
+    /// Leg of an iceberg order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iceberg: Option<IcebergMeta>,
+    /// Auction an auction order was placed in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auction: Option<AuctionMeta>,
+    /// Fields of `meta` not mapped above
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
-impl KiteConnect<Authenticated> {
-    pub async fn place_order(&self, req: &PlaceOrderRequest) -> Result<(), Error> {
-        let endpoint = place_order_endpoint_url_impl(&req.variety);
-
-        match self
-            .client
-            .post(endpoint)
-            .form(req)
-            .timeout(std::time::Duration::from_millis(50))
-            .send()
-            .await
-        {
-            Ok(r) => r.json::<Response<Data>>().await?.into_result()?,
-            Err(err) => {
-                if err.is_timeout() {
-                    return Ok(());
-                } else {
-                    return Err(err.into());
-                }
-            }
-        };
+/// Leg of an iceberg order, see [`OrderMeta::iceberg`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IcebergMeta {
+    /// Number of this leg, from 1
+    pub leg: u32,
+    /// Total number of legs
+    pub legs: u32,
+    /// Quantity of each leg
+    pub leg_quantity: u32,
+    /// Quantity of the whole iceberg order
+    pub total_quantity: u32,
+    /// Quantity of the iceberg order not placed yet
+    pub remaining_quantity: u32,
+}
 
-        Ok(())
-    }
+/// Auction of an auction order, see [`OrderMeta::auction`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuctionMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auction_number: Option<String>,
+}
 
-    pub async fn place_order_poll(&self, req: &PlaceOrderRequest) -> Result<String, Error> {
-        let endpoint = place_order_endpoint_url_impl(&req.variety);
+/// One line summary of the order, e.g. for logs and CLI tools:
+///
+/// ```text
+/// BUY 50 NSE:INFY @ LIMIT 1500.00 [OPEN, filled 0/50, tag=strat1]
+/// SELL 50 NSE:INFY @ SL 1490.00 trigger 1495.00 [TRIGGER PENDING, filled 0/50]
+/// BUY 1 NSE:IOC @ MARKET [COMPLETE, filled 1/1, avg 109.40]
+/// ```
+///
+/// Prices have two decimals, unset prices are left out. The format is kept stable, but parse it
+/// at your own risk.
+impl Display for Order {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}:{} @ {}",
+            self.transaction_type,
+            self.quantity,
+            self.exchange,
+            self.trading_symbol,
+            self.order_type
+        )?;
+        if let Some(price) = self.price.filter(|&price| price > 0.0) {
+            write!(f, " {price:.2}")?;
+        }
+        if let Some(trigger_price) = self.trigger_price.filter(|&price| price > 0.0) {
+            write!(f, " trigger {trigger_price:.2}")?;
+        }
 
-        Ok(self
-            .client
-            .post(endpoint)
-            .form(req)
-            .send()
-            .await?
-            .json::<Response<Data>>()
-            .await?
-            .into_result()?
-            .order_id)
+        write!(
+            f,
+            " [{}, filled {}/{}",
+            self.status, self.filled_quantity, self.quantity
+        )?;
+        if let Some(average_price) = self.average_price.filter(|&price| price > 0.0) {
+            write!(f, ", avg {average_price:.2}")?;
+        }
+        if let Some(tag) = &self.tag {
+            write!(f, ", tag={tag}")?;
+        }
+        write!(f, "]")
     }
+}
 
-    pub async fn modify_regular_oder(
-        &self,
-        order_id: &str,
-        req: &ModifyRegularOrderRequest,
-    ) -> Result<(), Error> {
-        let _ = self
-            .client
-            .put(format!("{MODIFY_REGULAR_ORDER_ENDPOINT}{order_id}"))
-            .form(req)
-            .send()
-            .await?
-            .json::<Response<Data>>()
-            .await?
-            .into_result()?;
-
-        Ok(())
-    }
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Order {
+    price => price_decimal -> Option<Decimal>,
+    trigger_price => trigger_price_decimal -> Option<Decimal>,
+    average_price => average_price_decimal -> Option<Decimal>,
+});
 
-    pub async fn modify_cover_order(
-        &self,
-        order_id: &str,
+/// A single execution of an order. An order can be executed in several trades.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    /// Exchange generated trade ID
+    pub trade_id: TradeId,
+    /// Unique order ID
+    pub order_id: OrderId,
+    /// Exchange generated order ID
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    /// Exchange tradingsymbol of the instrument
+    #[serde(rename = "tradingsymbol")]
+    pub trading_symbol: String,
+    /// Exchange
+    pub exchange: Exchange,
+    /// The numerical identifier issued by the exchange representing the instrument
+    #[serde(deserialize_with = "crate::utils::deserialize_number_or_string")]
+    pub instrument_token: String,
+    /// BUY or SELL
+    pub transaction_type: TransactionType,
+    /// Margin product used for the order
+    pub product: Product,
+    /// Price at which the quantity was filled
+    pub average_price: f64,
+    /// Filled quantity
+    pub quantity: u32,
+    /// Timestamp at which the trade was filled at the exchange
+    pub fill_timestamp: String,
+    /// Timestamp at which the order was registered by the API
+    pub order_timestamp: String,
+    /// Timestamp at which the order was registered by the exchange
+    pub exchange_timestamp: Option<String>,
+}
+
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Trade {
+    average_price => average_price_decimal -> Option<Decimal>,
+});
+
+/// Groups `trades` by the order they filled, with an entry for every order of `orders`.
+///
+/// Orders without a trade, e.g. open or rejected ones, map to an empty `Vec`. Trades of orders not
+/// in `orders` are left out. The trades of an order keep the order of `trades`.
+pub fn reconcile_trades(orders: &[Order], trades: &[Trade]) -> HashMap<OrderId, Vec<Trade>> {
+    let mut reconciled: HashMap<OrderId, Vec<Trade>> = orders
+        .iter()
+        .map(|order| (order.order_id.clone(), Vec::new()))
+        .collect();
+
+    for trade in trades {
+        if let Some(fills) = reconciled.get_mut(&trade.order_id) {
+            fills.push(trade.clone());
+        }
+    }
+
+    reconciled
+}
+
+/// Returns the average price of `trades` weighted by their quantity, or `None` if nothing was
+/// filled.
+pub fn weighted_average_fill(trades: &[Trade]) -> Option<f64> {
+    let (value, quantity) = trades.iter().fold((0.0, 0u64), |(value, quantity), trade| {
+        (
+            value + trade.average_price * trade.quantity as f64,
+            quantity + trade.quantity as u64,
+        )
+    });
+
+    (quantity > 0).then(|| value / quantity as f64)
+}
+
+#[cfg(feature = "chrono")]
+fn parse_timestamp(timestamp: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    crate::utils::parse_kite_timestamp(timestamp).ok()
+}
+
+#[cfg(feature = "chrono")]
+impl Order {
+    /// `order_timestamp` in IST, or `None` if it's empty or can't be parsed.
+    pub fn order_time(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        parse_timestamp(&self.order_timestamp)
+    }
+
+    /// `exchange_timestamp` in IST, or `None` for orders that didn't reach the exchange.
+    pub fn exchange_time(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        parse_timestamp(self.exchange_timestamp.as_deref()?)
+    }
+
+    /// `exchange_update_timestamp` in IST, or `None` if the exchange didn't update the order.
+    pub fn exchange_update_time(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        parse_timestamp(self.exchange_update_timestamp.as_deref()?)
+    }
+
+    /// Time elapsed between the order being registered by the API and `now`, e.g. to detect
+    /// orders stuck in the OPEN state.
+    ///
+    /// Returns `None` if `order_timestamp` can't be parsed.
+    pub fn age(&self, now: chrono::DateTime<chrono::FixedOffset>) -> Option<chrono::Duration> {
+        Some(now - self.order_time()?)
+    }
+
+    /// Time between the order being registered by the API and by the exchange.
+    ///
+    /// Returns `None` for orders that didn't reach the exchange. The two timestamps come from
+    /// different clocks, so the latency can be negative.
+    pub fn exchange_latency(&self) -> Option<chrono::Duration> {
+        Some(self.exchange_time()? - self.order_time()?)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Trade {
+    /// `fill_timestamp` in IST, or `None` if it can't be parsed.
+    pub fn fill_time(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        parse_timestamp(&self.fill_timestamp)
+    }
+
+    /// Time elapsed between the trade being filled at the exchange and `now`.
+    ///
+    /// Returns `None` if `fill_timestamp` can't be parsed.
+    pub fn age(&self, now: chrono::DateTime<chrono::FixedOffset>) -> Option<chrono::Duration> {
+        Some(now - self.fill_time()?)
+    }
+}
+
+#[derive(Deserialize)]
+struct Data {
+    order_id: OrderId,
+}
+
+/// Order placed by [`KiteConnect::place_order_poll`] or [`KiteConnect::place_order_idempotent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacedOrder {
+    pub order_id: OrderId,
+    /// Key the order was placed with, pass it again to retry the placement. `None` for an order
+    /// placed without a key
+    pub idempotency_key: Option<String>,
+    /// Whether an earlier attempt had already placed the order, in which case it's returned
+    /// without placing another one
+    pub already_placed: bool,
+}
+
+impl PlacedOrder {
+    /// Order newly placed with `req`.
+    pub(crate) fn new(order_id: OrderId, req: &PlaceOrderRequest) -> Self {
+        Self {
+            order_id,
+            idempotency_key: req.idempotency_key.clone(),
+            already_placed: false,
+        }
+    }
+}
+
+/// Shared by [`KiteConnect::place_order_idempotent`] and its stand-ins.
+pub(crate) async fn place_order_idempotent<T: Trading>(
+    kite: &T,
+    req: &PlaceOrderRequest,
+) -> Result<PlacedOrder, Error> {
+    req.validate()?;
+
+    let req = match &req.idempotency_key {
+        Some(key) => {
+            let placed = kite
+                .get_orders()
+                .await?
+                .into_iter()
+                .find(|order| order.tag.as_ref() == Some(key));
+            if let Some(order) = placed {
+                return Ok(PlacedOrder {
+                    order_id: order.order_id,
+                    idempotency_key: Some(key.clone()),
+                    already_placed: true,
+                });
+            }
+            Cow::Borrowed(req)
+        }
+        None => Cow::Owned(req.clone().with_idempotency_key()),
+    };
+
+    kite.place_order_poll(&req).await
+}
+
+impl KiteConnect<Authenticated> {
+    /// Places the order, see [`KiteConnect::place_order_poll`] for its id.
+    pub async fn place_order(&self, req: &PlaceOrderRequest) -> Result<(), Error> {
+        self.place_order_poll(req).await.map(|_| ())
+    }
+
+    /// Places the order without waiting more than `timeout` for Kite to respond.
+    ///
+    /// This is best-effort: a timed out request is reported as `Ok(())`, although the order may
+    /// never have reached Kite. Check the order book, e.g. with [`KiteConnect::get_orders`],
+    /// before relying on the order being placed. Other errors, such as a failed validation or an
+    /// error returned by Kite in time, are still returned.
+    pub async fn place_order_fire_and_forget(
+        &self,
+        req: &PlaceOrderRequest,
+        timeout: std::time::Duration,
+    ) -> Result<(), Error> {
+        let req = self.with_default_tag(req)?;
+        req.validate()?;
+        let endpoint = place_order_endpoint_url_impl(&req.variety);
+
+        let res = self
+            .send_tagged::<Data>(
+                self.client.post(endpoint).form(&*req).timeout(timeout),
+                req.tag.as_deref().or(req.idempotency_key.as_deref()),
+            )
+            .await;
+
+        match res {
+            Ok(_) | Err(Error::RequestTimeOut) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Places the order and returns its id, along with the
+    /// [`idempotency_key`](PlaceOrderRequest::idempotency_key) of the request, if any.
+    ///
+    /// No key is generated, use [`KiteConnect::place_order_idempotent`] to place an order that
+    /// can be retried without risking a duplicate order.
+    pub async fn place_order_poll(&self, req: &PlaceOrderRequest) -> Result<PlacedOrder, Error> {
+        let req = self.with_default_tag(req)?;
+        req.validate()?;
+        let endpoint = place_order_endpoint_url_impl(&req.variety);
+
+        let order_id = self
+            .send_tagged::<Data>(
+                self.client.post(endpoint).form(&*req),
+                req.tag.as_deref().or(req.idempotency_key.as_deref()),
+            )
+            .await?
+            .order_id;
+        Ok(PlacedOrder::new(order_id, &req))
+    }
+
+    /// Modifies an order, routing the request to the endpoint of its `variety`.
+    ///
+    /// Cover orders only take a new `price` and `trigger_price`, the other fields of `req` have
+    /// to be `None`. Returns an [`KiteError::InputException`](crate::KiteError::InputException)
+    /// without calling Kite for a cover order changing other fields, an auction order, which
+    /// can't be modified, or an iceberg order, which is modified with
+    /// [`KiteConnect::modify_iceberg_order`].
+    pub async fn modify_order(
+        &self,
+        order_id: &OrderId,
+        variety: &Variety,
+        req: &ModifyOrderRequest,
+    ) -> Result<(), Error> {
+        let endpoint = generic_modify_order_endpoint(variety)?;
+        let request = self.client.put(format!("{endpoint}{order_id}"));
+
+        let request = match variety {
+            Variety::CO => request.form(&ModifyCoverOrderRequest::try_from(req)?),
+            _ => request.form(req),
+        };
+        let _ = self.send::<Data>(request).await?;
+
+        Ok(())
+    }
+
+    pub async fn modify_regular_oder(
+        &self,
+        order_id: &OrderId,
+        req: &ModifyRegularOrderRequest,
+    ) -> Result<(), Error> {
+        let endpoint = modify_order_endpoint(&Variety::Regular)?;
+
+        let _ = self
+            .send::<Data>(self.client.put(format!("{endpoint}{order_id}")).form(req))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn modify_cover_order(
+        &self,
+        order_id: &OrderId,
         req: &ModifyCoverOrderRequest,
     ) -> Result<(), Error> {
+        let endpoint = modify_order_endpoint(&Variety::CO)?;
+
         let _ = self
-            .client
-            .put(format!("{MODIFY_COVER_ORDER_ENDPOINT}{order_id}"))
-            .form(req)
-            .send()
-            .await?
-            .json::<Response<Data>>()
-            .await?
-            .into_result()?;
+            .send::<Data>(self.client.put(format!("{endpoint}{order_id}")).form(req))
+            .await?;
 
         Ok(())
     }
 
-    pub async fn cancel_order(&self, order_id: &str, variety: &Variety) -> Result<(), Error> {
+    /// Modifies an iceberg order placed with `iceberg_legs` legs, see
+    /// [`ModifyIcebergOrderRequest::validate`].
+    pub async fn modify_iceberg_order(
+        &self,
+        order_id: &OrderId,
+        iceberg_legs: u32,
+        req: &ModifyIcebergOrderRequest,
+    ) -> Result<(), Error> {
+        req.validate(iceberg_legs)?;
+        let endpoint = modify_order_endpoint(&Variety::IceBerg)?;
+
+        let _ = self
+            .send::<Data>(self.client.put(format!("{endpoint}{order_id}")).form(req))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn cancel_order(&self, order_id: &OrderId, variety: &Variety) -> Result<(), Error> {
         let endpoint = cancel_order_endpoint_url_impl(variety);
 
         let _ = self
-            .client
-            .delete(format!("{endpoint}{order_id}"))
-            .send()
-            .await?
-            .json::<Response<Data>>()
-            .await?
-            .into_result()?;
+            .send::<Data>(self.client.delete(format!("{endpoint}{order_id}")))
+            .await?;
         Ok(())
     }
 
-    pub async fn get_orders(&self) -> Result<Order, Error> {
-        Ok(self
-            .client
-            .get(GET_ORDERS_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+    /// Exits a cover order by cancelling its second leg, the stop-loss order `order_id` placed
+    /// for the cover order `parent_order_id`.
+    pub async fn cancel_cover_order_leg(
+        &self,
+        order_id: &OrderId,
+        parent_order_id: &OrderId,
+    ) -> Result<(), Error> {
+        let endpoint = cancel_order_endpoint_url_impl(&Variety::CO);
+
+        let _ = self
+            .send::<Data>(
+                self.client
+                    .delete(format!("{endpoint}{order_id}"))
+                    .query(&[("parent_order_id", parent_order_id)]),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Exits the cover order `parent_order_id` by cancelling its open stop-loss leg, found in the
+    /// orders of the day.
+    ///
+    /// Returns an [`KiteError::OrderException`] if the cover order has no open leg, e.g. if it was
+    /// already exited or its stop-loss was hit.
+    pub async fn exit_cover_order(&self, parent_order_id: &OrderId) -> Result<(), Error> {
+        let orders = self.get_orders().await?;
+        let leg = open_cover_order_leg(&orders, parent_order_id)?;
+
+        self.cancel_cover_order_leg(&leg.order_id, parent_order_id)
+            .await
+    }
+
+    pub async fn get_orders(&self) -> Result<Vec<Order>, Error> {
+        self.send(self.client.get(GET_ORDERS_ENDPOINT)).await
+    }
+
+    /// Returns every state the order went through, oldest first.
+    pub async fn get_order_history(&self, order_id: &OrderId) -> Result<Vec<Order>, Error> {
+        self.send(
+            self.client
+                .get(format!("{GET_ORDER_HISTORY_ENDPOINT}{order_id}")),
+        )
+        .await
+    }
+
+    /// Returns the latest state of the order, the last entry of [`KiteConnect::get_order_history`].
+    ///
+    /// Returns `None` if the order has no history.
+    pub async fn get_order(&self, order_id: &OrderId) -> Result<Option<Order>, Error> {
+        Ok(self.get_order_history(order_id).await?.pop())
+    }
+
+    /// Returns the trades executed during the day.
+    pub async fn get_trades(&self) -> Result<Vec<Trade>, Error> {
+        self.send(self.client.get(GET_TRADES_ENDPOINT)).await
+    }
+
+    /// Returns the trades the order was executed in.
+    pub async fn get_order_trades(&self, order_id: &OrderId) -> Result<Vec<Trade>, Error> {
+        self.send(
+            self.client
+                .get(format!("{GET_ORDER_HISTORY_ENDPOINT}{order_id}/trades")),
+        )
+        .await
+    }
+
+    /// Returns `req` with the [default order tag](KiteConnect::with_default_order_tag) when it
+    /// doesn't set a tag.
+    ///
+    /// The idempotency key is sent as the tag, so a request with a key is an
+    /// [`KiteError::InputException`] when there's a default tag, instead of dropping the tag.
+    fn with_default_tag<'a>(
+        &self,
+        req: &'a PlaceOrderRequest,
+    ) -> Result<Cow<'a, PlaceOrderRequest>, Error> {
+        match (&req.tag, &req.idempotency_key, &self.default_order_tag) {
+            (None, None, Some(tag)) => Ok(Cow::Owned(PlaceOrderRequest {
+                tag: Some(tag.clone()),
+                ..req.clone()
+            })),
+            (None, Some(_), Some(tag)) => Err(Error::input_exception(format!(
+                "`idempotency_key` is sent as the tag, it can't be used along with the default order tag {tag:?}"
+            ))),
+            _ => Ok(Cow::Borrowed(req)),
+        }
+    }
+
+    /// Places the order and returns its latest state from the order history.
+    ///
+    /// Kite accepts an order before the OMS validates it, so [`KiteConnect::place_order_poll`]
+    /// succeeds even for orders that are rejected right after (e.g. for insufficient margin). This
+    /// makes an extra call to [`KiteConnect::get_order_history`], so the returned [`Order`] carries
+    /// the rejection reason in `status_message`/`status_message_raw`.
+    ///
+    /// The extra call counts against the rate limit of the order history endpoint (10 requests
+    /// per second). The order may still be in a transient state such as `OPEN` or
+    /// `VALIDATION PENDING` when its history is fetched.
+    pub async fn place_order_verified(&self, req: &PlaceOrderRequest) -> Result<Order, Error> {
+        let order_id = self.place_order_poll(req).await?.order_id;
+
+        self.get_order(&order_id).await?.ok_or_else(|| {
+            KiteError::OrderException(format!("No history found for order {order_id}")).into()
+        })
+    }
+
+    /// Places the order at most once per
+    /// [`idempotency_key`](PlaceOrderRequest::idempotency_key), e.g. to retry a placement that
+    /// timed out without risking a duplicate order.
+    ///
+    /// A key is generated when `req` has none. Otherwise, the orders of the day are searched for
+    /// an order tagged with the key first, and that order is returned instead of placing another
+    /// one. To be able to retry, set the key with [`PlaceOrderRequest::with_idempotency_key`]
+    /// before the first attempt and retry with the same request.
+    ///
+    /// The key is sent as the tag of the order, so the request can't have a `tag` and can't be
+    /// placed by a client with a [default order tag](KiteConnect::with_default_order_tag). An
+    /// order only shows up in the order book once Kite has accepted it, so wait for the timeout
+    /// of the first attempt before retrying.
+    pub async fn place_order_idempotent(
+        &self,
+        req: &PlaceOrderRequest,
+    ) -> Result<PlacedOrder, Error> {
+        self.with_default_tag(req)?;
+        place_order_idempotent(self, req).await
     }
 }
 
+/// Returns the leg of the cover order `parent_order_id` that's still open.
+pub(crate) fn open_cover_order_leg<'a>(
+    orders: &'a [Order],
+    parent_order_id: &OrderId,
+) -> Result<&'a Order, Error> {
+    orders
+        .iter()
+        .find(|order| {
+            order.parent_order_id.as_ref() == Some(parent_order_id) && !order.status.is_terminal()
+        })
+        .ok_or_else(|| {
+            KiteError::OrderException(format!(
+                "Cover order {parent_order_id} has no open leg, it may already be exited"
+            ))
+            .into()
+        })
+}
+
 const fn place_order_endpoint_url_impl(variety: &Variety) -> &'static str {
     match variety {
         Variety::Regular => PLACE_REGULAR_ORDER_ENDPOINT,
@@ -394,40 +1739,41 @@ const fn cancel_order_endpoint_url_impl(variety: &Variety) -> &'static str {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_order_req() -> Result<(), Box<dyn std::error::Error>> {
-        let order_req = PlaceOrderRequest {
-            variety: Variety::Regular,
-            trading_symbol: "COROMANDEL".to_string(),
-            exchange: Exchange::NSE,
-            transaction_type: TransactionType::Buy,
-            order_type: OrderType::Market,
-            quantity: 1,
-            product: Product::CNC,
-            price: None,
-            trigger_price: None,
-            disclosed_quantity: None,
-            validity: Validity::TTL,
-            validity_ttl: Some(2),
-            iceberg_legs: None,
-            iceberg_quantity: None,
-            auction_number: None,
-            tag: Some("Nobelium".to_string()),
-        };
+/// Kite doesn't allow modifying auction orders, they can only be cancelled.
+const fn modify_order_endpoint_url_impl(variety: &Variety) -> Option<&'static str> {
+    match variety {
+        Variety::Regular => Some(MODIFY_REGULAR_ORDER_ENDPOINT),
+        Variety::AMO => Some(MODIFY_AMO_ORDER_ENDPOINT),
+        Variety::CO => Some(MODIFY_COVER_ORDER_ENDPOINT),
+        Variety::IceBerg => Some(MODIFY_ICEBERG_ORDER_ENDPOINT),
+        Variety::Auction => None,
+    }
+}
 
-        let value = serde_urlencoded::to_string(order_req)?;
-        assert_eq!(value, "tradingsymbol=COROMANDEL&exchange=NSE&transaction_type=BUY&order_type=MARKET&quantity=1&product=CNC&validity=TTL&validity_ttl=2&tag=Nobelium".to_string());
+/// Same as [`modify_order_endpoint_url_impl`], with an `InputException` for auction orders.
+pub(crate) fn modify_order_endpoint(variety: &Variety) -> Result<&'static str, Error> {
+    modify_order_endpoint_url_impl(variety).ok_or_else(|| {
+        Error::input_exception("Auction orders can't be modified, cancel and place them again")
+    })
+}
 
-        Ok(())
+/// Same as [`modify_order_endpoint`], with an `InputException` for iceberg orders too, as
+/// [`KiteConnect::modify_order`] can't check their new quantity against the legs.
+pub(crate) fn generic_modify_order_endpoint(variety: &Variety) -> Result<&'static str, Error> {
+    if *variety == Variety::IceBerg {
+        return Err(Error::input_exception(
+            "Iceberg orders are modified with `modify_iceberg_order`, which checks the quantity against the legs",
+        ));
     }
 
-    #[test]
-    fn test_orders() -> Result<(), Box<dyn std::error::Error>> {
-        let json = r#"{
+    modify_order_endpoint(variety)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDERS_JSON: &str = r#"{
           "status": "success",
           "data": [
             {
@@ -499,6 +1845,473 @@ mod tests {
           ]
         }"#;
 
+    #[test]
+    fn test_order_req() -> Result<(), Box<dyn std::error::Error>> {
+        let order_req = PlaceOrderRequest {
+            variety: Variety::Regular,
+            trading_symbol: "COROMANDEL".to_string(),
+            exchange: Exchange::NSE,
+            transaction_type: TransactionType::Buy,
+            order_type: OrderType::Market,
+            quantity: 1,
+            product: Product::CNC,
+            price: None,
+            trigger_price: None,
+            disclosed_quantity: None,
+            validity: Validity::TTL,
+            validity_ttl: Some(2),
+            iceberg_legs: None,
+            iceberg_quantity: None,
+            auction_number: None,
+            market_protection: None,
+            tag: Some("Nobelium".to_string()),
+            idempotency_key: None,
+        };
+
+        let value = serde_urlencoded::to_string(order_req)?;
+        assert_eq!(value, "tradingsymbol=COROMANDEL&exchange=NSE&transaction_type=BUY&order_type=MARKET&quantity=1&product=CNC&validity=TTL&validity_ttl=2&tag=Nobelium".to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_order_req_skips_none() -> Result<(), Box<dyn std::error::Error>> {
+        let regular = ModifyRegularOrderRequest {
+            order_type: None,
+            quantity: Some(5),
+            price: None,
+            trigger_price: None,
+            disclosed_quantity: None,
+            validity: None,
+        };
+        assert_eq!(serde_urlencoded::to_string(&regular)?, "quantity=5");
+        assert_eq!(serde_json::to_string(&regular)?, r#"{"quantity":5}"#);
+
+        let cover = ModifyCoverOrderRequest {
+            order_id: None,
+            price: None,
+            trigger_price: Some(101.5),
+        };
+        assert_eq!(serde_urlencoded::to_string(&cover)?, "trigger_price=101.5");
+        assert_eq!(serde_json::to_string(&cover)?, r#"{"trigger_price":101.5}"#);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_iceberg_order_req() -> Result<(), Box<dyn std::error::Error>> {
+        let req = ModifyIcebergOrderRequest {
+            order_type: None,
+            quantity: Some(300),
+            price: Some(1500.5),
+            trigger_price: None,
+            disclosed_quantity: None,
+            validity: None,
+        };
+        assert_eq!(
+            serde_urlencoded::to_string(&req)?,
+            "quantity=300&price=1500.5"
+        );
+
+        assert!(req.validate(3).is_ok());
+        for legs in [0, 7] {
+            assert!(matches!(
+                req.validate(legs),
+                Err(Error::KiteError(KiteError::InputException(_)))
+            ));
+        }
+        // Only the quantity has to be split across the legs
+        let price_only = ModifyIcebergOrderRequest {
+            quantity: None,
+            ..req
+        };
+        assert!(price_only.validate(7).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_place_order_req_skips_none() -> Result<(), Box<dyn std::error::Error>> {
+        let mut req = ttl_order_req(Variety::Regular, None);
+        req.validity = Validity::Day;
+
+        let value = serde_json::to_value(&req)?;
+        for key in [
+            "price",
+            "trigger_price",
+            "disclosed_quantity",
+            "validity_ttl",
+            "iceberg_legs",
+            "iceberg_quantity",
+            "auction_number",
+            "tag",
+        ] {
+            assert!(value.get(key).is_none(), "`{key}` should not be serialized");
+        }
+
+        assert_eq!(
+            serde_urlencoded::to_string(&req)?,
+            "tradingsymbol=COROMANDEL&exchange=NSE&transaction_type=BUY&order_type=MARKET&quantity=1&product=CNC&validity=DAY"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_prices_to_tick() {
+        assert_eq!(round_to_tick(1412.93, 0.05), 1412.95);
+        assert_eq!(round_to_tick(1412.92, 0.05), 1412.90);
+        assert_eq!(round_to_tick(0.17, 0.1), 0.2);
+        assert_eq!(round_to_tick(101.0, 5.0), 100.0);
+        assert_eq!(round_to_tick(123.456, 0.0025), 123.4550);
+        assert_eq!(round_to_tick(1412.93, 0.0), 1412.93);
+
+        let mut req = ttl_order_req(Variety::Regular, Some(2));
+        req.price = Some(1412.93);
+        req.trigger_price = Some(1399.01);
+        req.round_prices_to_tick(0.05);
+
+        assert_eq!(req.price, Some(1412.95));
+        assert_eq!(req.trigger_price, Some(1399.0));
+    }
+
+    #[test]
+    fn test_is_valid_product_for() {
+        let table = [
+            (Exchange::NSE, Product::CNC, true),
+            (Exchange::BSE, Product::CNC, true),
+            (Exchange::NSE, Product::MTF, true),
+            (Exchange::BSE, Product::MTF, true),
+            (Exchange::NSE, Product::MIS, true),
+            (Exchange::NFO, Product::NRML, true),
+            (Exchange::BFO, Product::NRML, true),
+            (Exchange::MCX, Product::NRML, true),
+            (Exchange::CDS, Product::NRML, true),
+            (Exchange::MCX, Product::MIS, true),
+            (Exchange::NFO, Product::CO, true),
+            (Exchange::MCX, Product::CNC, false),
+            (Exchange::NFO, Product::CNC, false),
+            (Exchange::NFO, Product::MTF, false),
+            (Exchange::CDS, Product::MTF, false),
+            (Exchange::NSE, Product::NRML, false),
+            (Exchange::BSE, Product::NRML, false),
+            (Exchange::MF, Product::MIS, false),
+            (Exchange::MF, Product::CNC, false),
+            (Exchange::NSE, Product::BO, false),
+        ];
+
+        for (exchange, product, expected) in table {
+            assert_eq!(
+                is_valid_product_for(exchange, product),
+                expected,
+                "{product:?} on {exchange}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_product() {
+        let mut req = ttl_order_req(Variety::Regular, Some(2));
+        req.exchange = Exchange::MCX;
+
+        match req.validate() {
+            Err(Error::KiteError(KiteError::InputException(msg))) => {
+                assert!(msg.contains("CNC") && msg.contains("MCX"));
+            }
+            other => panic!("expected InputException, got {other:?}"),
+        }
+    }
+
+    fn ttl_order_req(variety: Variety, validity_ttl: Option<u32>) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            variety,
+            trading_symbol: "COROMANDEL".to_string(),
+            validity: Validity::TTL,
+            validity_ttl,
+            ..crate::test_utils::market_order()
+        }
+    }
+
+    #[test]
+    fn test_validate_ttl() {
+        assert!(ttl_order_req(Variety::Regular, Some(2)).validate().is_ok());
+        assert!(ttl_order_req(Variety::AMO, Some(365)).validate().is_ok());
+
+        let missing = ttl_order_req(Variety::Regular, None).validate();
+        assert!(matches!(
+            missing,
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+
+        for (variety, ttl) in [
+            (Variety::Regular, 0),
+            (Variety::Regular, MAX_VALIDITY_TTL + 1),
+            (Variety::AMO, MAX_AMO_VALIDITY_TTL + 1),
+        ] {
+            let out_of_range = ttl_order_req(variety, Some(ttl)).validate();
+            assert!(matches!(
+                out_of_range,
+                Err(Error::KiteError(KiteError::InputException(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_tag() {
+        let mut req = ttl_order_req(Variety::Regular, Some(2));
+
+        for tag in ["strategy1", "A1234567890123456789"] {
+            req.tag = Some(tag.to_string());
+            assert!(req.validate().is_ok(), "{tag}");
+        }
+
+        for tag in ["A12345678901234567890", "mean-reversion", ""] {
+            req.tag = Some(tag.to_string());
+            assert!(
+                matches!(
+                    req.validate(),
+                    Err(Error::KiteError(KiteError::InputException(_)))
+                ),
+                "{tag}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_quantity() -> Result<(), Box<dyn std::error::Error>> {
+        assert!(validate_quantity(50, 50).is_ok());
+        assert!(validate_quantity(150, 50).is_ok());
+        assert!(validate_quantity(75, 1).is_ok());
+        assert!(validate_quantity(75, 0).is_ok());
+        match validate_quantity(75, 50) {
+            Err(Error::KiteError(KiteError::InputException(message))) => {
+                assert!(message.contains("50"), "{message}")
+            }
+            res => panic!("{res:?}"),
+        }
+
+        let nifty = Instrument {
+            instrument_token: 12683010,
+            exchange_token: "49543".into(),
+            trading_symbol: "NIFTY25JULFUT".into(),
+            name: "NIFTY".into(),
+            last_price: 0.0,
+            expiry: "2025-07-31".into(),
+            strike: 0.0,
+            tick_size: 0.1,
+            lot_size: 50,
+            instrument_type: crate::quotes::InstrumentType::FUT,
+            segment: crate::quotes::Segment::NFO_FUT,
+            exchange: Exchange::NFO.into(),
+        };
+        let mut req = PlaceOrderRequest::builder(Variety::Regular, "NIFTY25JULFUT", Exchange::NFO)
+            .buy()
+            .quantity(50)
+            .product(Product::NRML)
+            .build()?;
+        assert!(req.validate_for(&nifty).is_ok());
+
+        req.quantity = 75;
+        assert!(req.validate().is_ok());
+        assert!(matches!(
+            req.validate_for(&nifty),
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+
+        // 4 legs of 25
+        req.quantity = 100;
+        req.iceberg_legs = Some(4);
+        req.iceberg_quantity = Some(25);
+        assert!(req.validate_for(&nifty).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_status() -> Result<(), Box<dyn std::error::Error>> {
+        for (json, status) in [
+            ("OPEN", OrderStatus::Open),
+            ("CANCELLED", OrderStatus::Cancelled),
+            ("REJECTED", OrderStatus::Rejected),
+            ("COMPLETE", OrderStatus::Complete),
+            ("TRIGGER PENDING", OrderStatus::TriggerPending),
+            ("PUT ORDER REQ RECEIVED", OrderStatus::PutOrderReqReceived),
+            ("VALIDATION PENDING", OrderStatus::ValidationPending),
+            ("OPEN PENDING", OrderStatus::OpenPending),
+            (
+                "MODIFY VALIDATION PENDING",
+                OrderStatus::ModifyValidationPending,
+            ),
+            ("MODIFY PENDING", OrderStatus::ModifyPending),
+            ("CANCEL PENDING", OrderStatus::CancelPending),
+            ("AMO REQ RECEIVED", OrderStatus::AmoReqReceived),
+            ("LAPSED", OrderStatus::Other("LAPSED".into())),
+        ] {
+            assert_eq!(
+                serde_json::from_value::<OrderStatus>(json.into())?,
+                status,
+                "{json}"
+            );
+            assert_eq!(serde_json::to_value(&status)?, json);
+            assert_eq!(status.to_string(), json);
+
+            // Exactly one of the helpers holds for a documented status
+            let helpers = [status.is_terminal(), status.is_open(), status.is_pending()];
+            let expected = usize::from(!matches!(status, OrderStatus::Other(_)));
+            assert_eq!(helpers.iter().filter(|&&h| h).count(), expected, "{json}");
+        }
+
+        assert!(OrderStatus::Rejected.is_terminal());
+        assert!(OrderStatus::TriggerPending.is_open());
+        assert!(OrderStatus::AmoReqReceived.is_pending());
+        assert!(!OrderStatus::CancelPending.is_terminal());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_iceberg_order() -> Result<(), Box<dyn std::error::Error>> {
+        let req = PlaceOrderRequest::iceberg(
+            "INFY".into(),
+            Exchange::NSE,
+            TransactionType::Buy,
+            OrderType::Market,
+            1000,
+            4,
+            Product::CNC,
+        )?;
+
+        assert_eq!(req.variety, Variety::IceBerg);
+        assert_eq!(req.iceberg_legs, Some(4));
+        assert_eq!(req.iceberg_quantity, Some(250));
+
+        for (quantity, legs) in [(1000, 1), (1000, 11), (1000, 3)] {
+            let res = PlaceOrderRequest::iceberg(
+                "INFY".into(),
+                Exchange::NSE,
+                TransactionType::Buy,
+                OrderType::Market,
+                quantity,
+                legs,
+                Product::CNC,
+            );
+            assert!(matches!(
+                res,
+                Err(Error::KiteError(KiteError::InputException(_)))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_place_order_builder() -> Result<(), Box<dyn std::error::Error>> {
+        let req = PlaceOrderRequest::builder(Variety::Regular, "INFY", Exchange::NSE)
+            .buy()
+            .quantity(10)
+            .product(Product::CNC)
+            .order_type(OrderType::SL)
+            .price(1500.0)
+            .trigger_price(1495.0)
+            .validity_ttl(30)
+            .tag("strat1")
+            .build()?;
+        assert_eq!(
+            serde_urlencoded::to_string(&req)?,
+            "tradingsymbol=INFY&exchange=NSE&transaction_type=BUY&order_type=SL&quantity=10&product=CNC&price=1500.0&trigger_price=1495.0&validity=TTL&validity_ttl=30&tag=strat1"
+        );
+
+        let iceberg = PlaceOrderRequest::builder(Variety::IceBerg, "INFY", Exchange::NSE)
+            .sell()
+            .quantity(1000)
+            .product(Product::MIS)
+            .iceberg_legs(4)
+            .build()?;
+        assert_eq!(iceberg.order_type, OrderType::Market);
+        assert_eq!(iceberg.iceberg_quantity, Some(250));
+
+        let protected = PlaceOrderRequest::builder(Variety::Regular, "INFY", Exchange::NSE)
+            .buy()
+            .quantity(1)
+            .product(Product::MIS)
+            .market_protection(3)
+            .build()?;
+        assert_eq!(
+            serde_urlencoded::to_string(&protected)?,
+            "tradingsymbol=INFY&exchange=NSE&transaction_type=BUY&order_type=MARKET&quantity=1&product=MIS&validity=DAY&market_protection=3"
+        );
+
+        let market = || {
+            PlaceOrderRequest::builder(Variety::Regular, "INFY", Exchange::NSE)
+                .buy()
+                .quantity(1)
+                .product(Product::CNC)
+        };
+        let sell = |variety| {
+            PlaceOrderRequest::builder(variety, "INFY", Exchange::NSE)
+                .sell()
+                .quantity(1)
+                .product(Product::CNC)
+        };
+        for (case, builder) in [
+            (
+                "no transaction type",
+                PlaceOrderRequest::builder(Variety::Regular, "INFY", Exchange::NSE)
+                    .quantity(1)
+                    .product(Product::CNC),
+            ),
+            ("zero quantity", market().quantity(0)),
+            ("limit without price", market().order_type(OrderType::Limit)),
+            (
+                "SL without trigger",
+                market().order_type(OrderType::SL).price(100.0),
+            ),
+            ("SL-M without trigger", market().order_type(OrderType::SL_M)),
+            ("legs on a regular order", market().iceberg_legs(2)),
+            ("iceberg without legs", sell(Variety::IceBerg).quantity(100)),
+            (
+                "iceberg with 11 legs",
+                sell(Variety::IceBerg).quantity(110).iceberg_legs(11),
+            ),
+            ("auction without number", sell(Variety::Auction)),
+            (
+                "auction number on a regular order",
+                market().auction_number("22"),
+            ),
+            ("TTL out of range", market().validity_ttl(121)),
+            ("TTL without minutes", market().validity(Validity::TTL)),
+            ("NRML on NSE", market().product(Product::NRML)),
+            (
+                "market protection on a limit order",
+                market()
+                    .order_type(OrderType::Limit)
+                    .price(100.0)
+                    .market_protection(3),
+            ),
+            (
+                "market protection above 100",
+                market().market_protection(101),
+            ),
+        ] {
+            assert!(
+                matches!(
+                    builder.build(),
+                    Err(Error::KiteError(KiteError::InputException(_)))
+                ),
+                "{case}"
+            );
+        }
+
+        let auction = sell(Variety::Auction).auction_number("22").build()?;
+        assert_eq!(auction.auction_number.as_deref(), Some("22"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_orders() -> Result<(), Box<dyn std::error::Error>> {
+        let json = ORDERS_JSON;
+
         let value: Response<_> = serde_json::from_str(json)?;
 
         let expected = Response::Success {
@@ -522,6 +2335,7 @@ mod tests {
                     order_type: OrderType::Limit,
                     transaction_type: TransactionType::Buy,
                     validity: Validity::Day,
+                    validity_ttl: None,
                     product: Product::NRML,
                     quantity: 1,
                     disclosed_quantity: Some(0),
@@ -534,10 +2348,9 @@ mod tests {
                     tag: None,
                     guid: "XXXXX".into(),
                     auction_number: None,
-                    meta: Some(serde_json::json!({
-                        "market_protection": 0,
-                        "meta": {}
-                    })),
+                    market_protection: Some(0.0),
+                    meta: Some(OrderMeta::default()),
+                    extra: HashMap::new(),
                 },
                 Order {
                     placed_by: "XXXXXX".into(),
@@ -558,6 +2371,7 @@ mod tests {
                     order_type: OrderType::Limit,
                     transaction_type: TransactionType::Buy,
                     validity: Validity::Day,
+                    validity_ttl: None,
                     product: Product::CNC,
                     quantity: 1,
                     disclosed_quantity: Some(0),
@@ -570,11 +2384,9 @@ mod tests {
                     tag: None,
                     guid: "XXXXXX".into(),
                     auction_number: None,
-                    meta: Some(serde_json::json!({
-                        "market_protection": 0,
-                        // TODO: Make the values of meta, go inside the top level meta object
-                        "meta": {}
-                    })),
+                    market_protection: Some(0.0),
+                    meta: Some(OrderMeta::default()),
+                    extra: HashMap::new(),
                 },
             ],
         };
@@ -583,4 +2395,423 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_exchange_serde_matches_display() -> Result<(), serde_json::Error> {
+        for exchange in [
+            Exchange::BFO,
+            Exchange::MCX,
+            Exchange::NSE,
+            Exchange::CDS,
+            Exchange::BSE,
+            Exchange::BCD,
+            Exchange::MF,
+            Exchange::NFO,
+        ] {
+            let json = serde_json::to_string(&exchange)?;
+            assert_eq!(json, format!("\"{exchange}\""));
+            assert_eq!(serde_json::from_str::<Exchange>(&json)?, exchange);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trades() -> Result<(), Box<dyn std::error::Error>> {
+        let trades =
+            serde_json::from_str::<Response<Vec<Trade>>>(crate::test_utils::fixtures::TRADES)?
+                .into_result()?;
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(
+            trades[0],
+            Trade {
+                trade_id: "10000000".into(),
+                order_id: "200000000000000".into(),
+                exchange_order_id: Some("300000000000000".into()),
+                trading_symbol: "SBIN".into(),
+                exchange: Exchange::NSE,
+                instrument_token: "779521".into(),
+                transaction_type: TransactionType::Buy,
+                product: Product::CNC,
+                average_price: 420.65,
+                quantity: 1,
+                fill_timestamp: "2021-05-31 09:16:39".into(),
+                order_timestamp: "09:16:39".into(),
+                exchange_timestamp: Some("2021-05-31 09:16:39".into()),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_idempotency_key() {
+        let keys: Vec<_> = (0..100).map(|_| new_idempotency_key()).collect();
+
+        for key in &keys {
+            assert!(key.len() <= MAX_ORDER_TAG_LEN, "{key}");
+            assert!(key.chars().all(|c| c.is_ascii_alphanumeric()), "{key}");
+            assert!(is_generated_idempotency_key(key), "{key}");
+        }
+        let unique: std::collections::HashSet<_> = keys.iter().collect();
+        assert_eq!(unique.len(), keys.len());
+    }
+
+    #[test]
+    fn test_ids() -> Result<(), Box<dyn std::error::Error>> {
+        let order_id: OrderId = "151220000000000".parse()?;
+        assert_eq!(order_id, OrderId::from("151220000000000"));
+        assert_eq!(order_id, "151220000000000");
+        assert_eq!(order_id.to_string(), "151220000000000");
+        assert_eq!(serde_json::to_string(&order_id)?, r#""151220000000000""#);
+        assert_eq!(
+            serde_json::from_str::<TradeId>(r#""10000000""#)?,
+            TradeId::from("10000000".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reconcile_trades() -> Result<(), Box<dyn std::error::Error>> {
+        let orders = serde_json::from_str::<Response<Vec<Order>>>(ORDERS_JSON)?.into_result()?;
+        let trade = |trade_id: &str, order_id: &str, average_price, quantity| Trade {
+            trade_id: trade_id.into(),
+            order_id: order_id.into(),
+            exchange_order_id: None,
+            trading_symbol: "IOC".into(),
+            exchange: Exchange::NSE,
+            instrument_token: "415745".into(),
+            transaction_type: TransactionType::Buy,
+            product: Product::CNC,
+            average_price,
+            quantity,
+            fill_timestamp: "2021-05-31 15:20:28".into(),
+            order_timestamp: "15:20:28".into(),
+            exchange_timestamp: None,
+        };
+        let trades = [
+            trade("1", "300000000000000", 109.4, 30),
+            trade("2", "999999999999999", 1.0, 1),
+            trade("3", "300000000000000", 109.9, 10),
+        ];
+
+        let reconciled = reconcile_trades(&orders, &trades);
+
+        assert_eq!(reconciled.len(), 2);
+        assert!(reconciled["100000000000000"].is_empty());
+        let fills = &reconciled["300000000000000"];
+        assert_eq!(
+            fills
+                .iter()
+                .map(|t| t.trade_id.as_str())
+                .collect::<Vec<_>>(),
+            ["1", "3"]
+        );
+
+        // (109.4 * 30 + 109.9 * 10) / 40
+        let average = weighted_average_fill(fills).ok_or("no fills")?;
+        assert!((average - 109.525).abs() < 1e-9);
+        assert_eq!(weighted_average_fill(&[]), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_history() -> Result<(), Box<dyn std::error::Error>> {
+        let orders: serde_json::Value = serde_json::from_str(ORDERS_JSON)?;
+        let snapshot = |status: &str, filled: u32| {
+            let mut order = orders["data"][1].clone();
+            order["status"] = status.into();
+            order["quantity"] = 10.into();
+            order["filled_quantity"] = filled.into();
+            order["pending_quantity"] = (10 - filled).into();
+            order
+        };
+        let json = serde_json::json!({
+            "status": "success",
+            "data": [snapshot("OPEN", 0), snapshot("OPEN", 4), snapshot("COMPLETE", 10)]
+        });
+
+        let history = Response::<Vec<Order>>::deserialize(json)?.into_result()?;
+
+        let states: Vec<_> = history
+            .iter()
+            .map(|o| (o.status.clone(), o.filled_quantity, o.pending_quantity))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                (OrderStatus::Open, 0, 10),
+                (OrderStatus::Open, 4, 6),
+                (OrderStatus::Complete, 10, 0),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_order_decimal() -> Result<(), Box<dyn std::error::Error>> {
+        use std::str::FromStr;
+
+        let orders = serde_json::from_str::<Response<Vec<Order>>>(ORDERS_JSON)?.into_result()?;
+
+        assert_eq!(orders[1].price_decimal(), Some(Decimal::from_str("109.4")?));
+        assert_eq!(
+            orders[1].average_price_decimal(),
+            Some(Decimal::from_str("109.4")?)
+        );
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "chrono", feature = "test-utils"))]
+    #[test]
+    fn test_order_timing() -> Result<(), Box<dyn std::error::Error>> {
+        use chrono::{DateTime, Duration};
+
+        let orders = serde_json::from_str::<Response<Vec<Order>>>(ORDERS_JSON)?.into_result()?;
+        let now = DateTime::parse_from_rfc3339("2021-05-31T09:28:57+05:30")?;
+
+        assert_eq!(orders[0].age(now), Some(Duration::minutes(10)));
+        // The fixture's exchange timestamp is before the order timestamp
+        assert_eq!(orders[0].exchange_latency(), Some(Duration::seconds(-199)));
+        assert_eq!(orders[1].exchange_latency(), Some(Duration::zero()));
+
+        let mut order = orders[0].clone();
+        order.exchange_timestamp = None;
+        assert_eq!(order.exchange_latency(), None);
+        order.order_timestamp = "09:18:57".into();
+        assert_eq!(order.age(now), None);
+
+        let trades =
+            serde_json::from_str::<Response<Vec<Trade>>>(crate::test_utils::fixtures::TRADES)?
+                .into_result()?;
+        assert_eq!(trades[0].age(now), Some(Duration::seconds(738)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_meta() -> Result<(), Box<dyn std::error::Error>> {
+        let mut json: serde_json::Value = serde_json::from_str(ORDERS_JSON)?;
+        json["data"][0]["validity_ttl"] = 5.into();
+        json["data"][0]["exchange_order_type"] = "RL".into();
+        json["data"][0]["meta"] = serde_json::json!({
+            "iceberg": {
+                "leg": 2,
+                "legs": 4,
+                "leg_quantity": 250,
+                "total_quantity": 1000,
+                "remaining_quantity": 500
+            },
+            "auction": { "auction_number": "22" },
+            "demat_consent": "physical"
+        });
+        json["data"][1]["meta"] = serde_json::Value::Null;
+
+        let orders = serde_json::from_value::<Response<Vec<Order>>>(json)?.into_result()?;
+        let meta = orders[0].meta.clone().unwrap_or_default();
+        assert_eq!(
+            meta.iceberg,
+            Some(IcebergMeta {
+                leg: 2,
+                legs: 4,
+                leg_quantity: 250,
+                total_quantity: 1000,
+                remaining_quantity: 500,
+            })
+        );
+        assert_eq!(
+            meta.auction.and_then(|auction| auction.auction_number),
+            Some("22".into())
+        );
+        assert_eq!(
+            meta.extra,
+            HashMap::from([("demat_consent".into(), "physical".into())])
+        );
+        assert_eq!(orders[0].validity_ttl, Some(5));
+        assert_eq!(orders[1].validity_ttl, None);
+        // Unknown top level fields stay out of meta
+        assert_eq!(
+            orders[0].extra,
+            HashMap::from([("exchange_order_type".into(), "RL".into())])
+        );
+        assert_eq!(orders[0].market_protection, Some(0.0));
+
+        assert_eq!(orders[1].meta, None);
+        assert!(orders[1].extra.is_empty());
+
+        // Round trips
+        let value = serde_json::to_value(&orders[0])?;
+        assert_eq!(value["validity_ttl"], 5);
+        assert_eq!(value["exchange_order_type"], "RL");
+        assert_eq!(value["meta"]["iceberg"]["legs"], 4);
+        assert_eq!(serde_json::from_value::<Order>(value)?, orders[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_timestamps() -> Result<(), Box<dyn std::error::Error>> {
+        let mut json: serde_json::Value = serde_json::from_str(ORDERS_JSON)?;
+        json["data"][0]["order_timestamp"] = serde_json::Value::Null;
+        json["data"][0]["exchange_timestamp"] = "".into();
+        json["data"][0]["exchange_update_timestamp"] = serde_json::Value::Null;
+        json["data"][1]["exchange_timestamp"] = "".into();
+
+        let orders = serde_json::from_value::<Response<Vec<Order>>>(json)?.into_result()?;
+        assert_eq!(orders[0].order_timestamp, "");
+        assert_eq!(orders[0].exchange_timestamp, None);
+        assert_eq!(orders[0].exchange_update_timestamp, None);
+        assert_eq!(orders[1].order_timestamp, "2021-05-31 15:20:28");
+        assert_eq!(orders[1].exchange_timestamp, None);
+        assert_eq!(
+            orders[1].exchange_update_timestamp.as_deref(),
+            Some("2021-05-31 15:20:28")
+        );
+
+        #[cfg(feature = "chrono")]
+        {
+            let time = chrono::DateTime::parse_from_rfc3339("2021-05-31T15:20:28+05:30")?;
+            assert_eq!(orders[0].order_time(), None);
+            assert_eq!(orders[0].exchange_update_time(), None);
+            assert_eq!(orders[1].order_time(), Some(time));
+            assert_eq!(orders[1].exchange_time(), None);
+            assert_eq!(orders[1].exchange_update_time(), Some(time));
+            assert_eq!(
+                orders[1].order_time().map(|t| t.offset().local_minus_utc()),
+                Some(19800)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_place_order_req_from_order() -> Result<(), Box<dyn std::error::Error>> {
+        let orders = serde_json::from_str::<Response<Vec<Order>>>(ORDERS_JSON)?.into_result()?;
+
+        let req = PlaceOrderRequest::try_from(&orders[0])?.with_price(72.5);
+        assert_eq!(
+            req,
+            PlaceOrderRequest {
+                variety: Variety::Regular,
+                trading_symbol: "USDINR21JUNFUT".into(),
+                exchange: Exchange::CDS,
+                transaction_type: TransactionType::Buy,
+                order_type: OrderType::Limit,
+                quantity: 1,
+                product: Product::NRML,
+                price: Some(72.5),
+                trigger_price: None,
+                disclosed_quantity: None,
+                validity: Validity::Day,
+                validity_ttl: None,
+                iceberg_legs: None,
+                iceberg_quantity: None,
+                auction_number: None,
+                market_protection: None,
+                tag: None,
+                idempotency_key: None,
+            }
+        );
+
+        let mut order = orders[1].clone();
+        order.variety = Variety::IceBerg;
+        order.quantity = 1000;
+        order.validity = Validity::TTL;
+        order.validity_ttl = Some(5);
+        order.tag = Some(new_idempotency_key());
+        order.meta = serde_json::from_value(serde_json::json!({
+            "iceberg": { "leg": 1, "legs": 4, "leg_quantity": 250 }
+        }))?;
+        let req = PlaceOrderRequest::try_from(&order)?;
+        assert_eq!(req.validity_ttl, Some(5));
+        assert_eq!(req.tag, None);
+        assert_eq!(req.iceberg_legs, Some(4));
+        assert_eq!(req.iceberg_quantity, Some(250));
+        assert_eq!(req.price, Some(109.4));
+        req.validate()?;
+
+        order.meta = Some(OrderMeta::default());
+        assert!(matches!(
+            PlaceOrderRequest::try_from(&order),
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+
+        order.variety = Variety::Auction;
+        assert!(matches!(
+            PlaceOrderRequest::try_from(&order),
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+        order.auction_number = Some("22".into());
+        assert_eq!(
+            PlaceOrderRequest::try_from(&order)?
+                .auction_number
+                .as_deref(),
+            Some("22")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_display() -> Result<(), Box<dyn std::error::Error>> {
+        let orders = serde_json::from_str::<Response<Vec<Order>>>(ORDERS_JSON)?.into_result()?;
+
+        assert_eq!(
+            orders[0].to_string(),
+            "BUY 1 CDS:USDINR21JUNFUT @ LIMIT 72.00 [CANCELLED, filled 0/1]"
+        );
+        assert_eq!(
+            orders[1].to_string(),
+            "BUY 1 NSE:IOC @ LIMIT 109.40 [COMPLETE, filled 1/1, avg 109.40]"
+        );
+
+        let mut order = orders[0].clone();
+        order.transaction_type = TransactionType::Sell;
+        order.quantity = 50;
+        order.order_type = OrderType::SL_M;
+        order.price = None;
+        order.trigger_price = Some(72.25);
+        order.status = OrderStatus::TriggerPending;
+        order.tag = Some("strat1".into());
+        assert_eq!(
+            order.to_string(),
+            "SELL 50 CDS:USDINR21JUNFUT @ SL-M trigger 72.25 [TRIGGER PENDING, filled 0/50, tag=strat1]"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_order_endpoint() {
+        for (variety, endpoint) in [
+            (Variety::Regular, "https://api.kite.trade/orders/regular/"),
+            (Variety::AMO, "https://api.kite.trade/orders/amo/"),
+            (Variety::CO, "https://api.kite.trade/orders/co/"),
+            (Variety::IceBerg, "https://api.kite.trade/orders/iceberg/"),
+        ] {
+            assert_eq!(modify_order_endpoint_url_impl(&variety), Some(endpoint));
+            assert_eq!(modify_order_endpoint(&variety).ok(), Some(endpoint));
+        }
+
+        assert_eq!(modify_order_endpoint_url_impl(&Variety::Auction), None);
+        assert!(matches!(
+            modify_order_endpoint(&Variety::Auction),
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+
+        assert!(generic_modify_order_endpoint(&Variety::Regular).is_ok());
+        for variety in [Variety::IceBerg, Variety::Auction] {
+            assert!(matches!(
+                generic_modify_order_endpoint(&variety),
+                Err(Error::KiteError(KiteError::InputException(_)))
+            ));
+        }
+    }
 }
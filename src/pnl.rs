@@ -0,0 +1,360 @@
+//! Realised P&L from the trade book, matching lots first in, first out.
+//!
+//! Every trade first closes the oldest open lots of the instrument on the other side, and any
+//! remaining quantity opens a new lot. A sell without an open long lot opens a short lot, which
+//! later buys close. Positions carried from a previous day can be seeded with
+//! [`FifoPnl::with_opening_lots`].
+//!
+//! The P&L is gross: combine it with the [virtual contract note](crate::virtual_contract_note) to
+//! account for the charges. Prices aren't multiplied by the contract multiplier, so currency and
+//! commodity derivatives P&L is per unit of the quoted price.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::orders::{Exchange, Trade, TransactionType};
+
+/// A quantity bought or sold that isn't closed yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenLot {
+    pub exchange: Exchange,
+    pub trading_symbol: String,
+    /// BUY for a long lot, SELL for a short lot
+    pub transaction_type: TransactionType,
+    pub quantity: u32,
+    pub price: f64,
+}
+
+/// A lot closed by a trade on the other side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedLot {
+    pub exchange: Exchange,
+    pub trading_symbol: String,
+    /// Side of the trade that opened the lot, SELL for a short lot
+    pub opened_by: TransactionType,
+    pub quantity: u32,
+    pub buy_price: f64,
+    pub sell_price: f64,
+    /// `(sell_price - buy_price) * quantity`
+    pub pnl: f64,
+}
+
+/// FIFO lot matching of trades, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct FifoPnl {
+    open: HashMap<(Exchange, String), VecDeque<OpenLot>>,
+    closed: Vec<ClosedLot>,
+}
+
+impl FifoPnl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts from lots carried from a previous day, oldest first.
+    ///
+    /// A long and a short lot of the same instrument net against each other.
+    pub fn with_opening_lots(lots: impl IntoIterator<Item = OpenLot>) -> Self {
+        let mut pnl = Self::new();
+        for lot in lots {
+            pnl.fill(
+                lot.exchange,
+                &lot.trading_symbol,
+                lot.transaction_type,
+                lot.quantity,
+                lot.price,
+            );
+        }
+        pnl
+    }
+
+    /// Matches a single trade. Trades have to be added in the order they were filled.
+    pub fn add_trade(&mut self, trade: &Trade) {
+        self.fill(
+            trade.exchange,
+            &trade.trading_symbol,
+            trade.transaction_type,
+            trade.quantity,
+            trade.average_price,
+        );
+    }
+
+    /// Matches `trades` in the order of their fill timestamp, e.g. the whole
+    /// [trade book](crate::KiteConnect::get_trades).
+    pub fn add_trades<'a>(&mut self, trades: impl IntoIterator<Item = &'a Trade>) {
+        let mut trades: Vec<_> = trades.into_iter().collect();
+        // Stable, so that trades filled the same second keep their order
+        trades.sort_by(|a, b| a.fill_timestamp.cmp(&b.fill_timestamp));
+
+        for trade in trades {
+            self.add_trade(trade);
+        }
+    }
+
+    /// Lots closed so far, in the order they were closed.
+    pub fn closed_lots(&self) -> &[ClosedLot] {
+        &self.closed
+    }
+
+    /// Lots still open, oldest first for each instrument.
+    pub fn open_lots(&self) -> impl Iterator<Item = &OpenLot> {
+        self.open.values().flatten()
+    }
+
+    /// Total realised P&L of each instrument with closed lots.
+    pub fn realised(&self) -> HashMap<(Exchange, String), f64> {
+        let mut realised = HashMap::new();
+        for lot in &self.closed {
+            *realised
+                .entry((lot.exchange, lot.trading_symbol.clone()))
+                .or_default() += lot.pnl;
+        }
+        realised
+    }
+
+    /// Total realised P&L over every instrument.
+    pub fn total_realised(&self) -> f64 {
+        self.closed.iter().map(|lot| lot.pnl).sum()
+    }
+
+    fn fill(
+        &mut self,
+        exchange: Exchange,
+        trading_symbol: &str,
+        transaction_type: TransactionType,
+        mut quantity: u32,
+        price: f64,
+    ) {
+        let lots = self
+            .open
+            .entry((exchange, trading_symbol.to_owned()))
+            .or_default();
+
+        while quantity > 0 {
+            let Some(lot) = lots
+                .front_mut()
+                .filter(|lot| lot.transaction_type != transaction_type)
+            else {
+                break;
+            };
+
+            let matched = lot.quantity.min(quantity);
+            let (buy_price, sell_price) = match lot.transaction_type {
+                TransactionType::Buy => (lot.price, price),
+                TransactionType::Sell => (price, lot.price),
+            };
+
+            self.closed.push(ClosedLot {
+                exchange,
+                trading_symbol: trading_symbol.to_owned(),
+                opened_by: lot.transaction_type,
+                quantity: matched,
+                buy_price,
+                sell_price,
+                pnl: (sell_price - buy_price) * matched as f64,
+            });
+
+            lot.quantity -= matched;
+            quantity -= matched;
+            if lot.quantity == 0 {
+                lots.pop_front();
+            }
+        }
+
+        if quantity > 0 {
+            lots.push_back(OpenLot {
+                exchange,
+                trading_symbol: trading_symbol.to_owned(),
+                transaction_type,
+                quantity,
+                price,
+            });
+        }
+
+        if lots.is_empty() {
+            self.open.remove(&(exchange, trading_symbol.to_owned()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::{OrderId, Product, TradeId};
+
+    use TransactionType::{Buy, Sell};
+
+    fn trade(
+        trading_symbol: &str,
+        transaction_type: TransactionType,
+        quantity: u32,
+        average_price: f64,
+        fill_timestamp: &str,
+    ) -> Trade {
+        Trade {
+            trade_id: TradeId::default(),
+            order_id: OrderId::default(),
+            exchange_order_id: None,
+            trading_symbol: trading_symbol.into(),
+            exchange: Exchange::NSE,
+            instrument_token: String::new(),
+            transaction_type,
+            product: Product::CNC,
+            average_price,
+            quantity,
+            fill_timestamp: format!("2024-01-01 {fill_timestamp}"),
+            order_timestamp: fill_timestamp.into(),
+            exchange_timestamp: None,
+        }
+    }
+
+    fn closed(
+        opened_by: TransactionType,
+        quantity: u32,
+        buy_price: f64,
+        sell_price: f64,
+    ) -> (TransactionType, u32, f64, f64, f64) {
+        let pnl = (sell_price - buy_price) * quantity as f64;
+        (opened_by, quantity, buy_price, sell_price, pnl)
+    }
+
+    fn closed_lots(pnl: &FifoPnl) -> Vec<(TransactionType, u32, f64, f64, f64)> {
+        pnl.closed_lots()
+            .iter()
+            .map(|l| (l.opened_by, l.quantity, l.buy_price, l.sell_price, l.pnl))
+            .collect()
+    }
+
+    fn open_lots(pnl: &FifoPnl, trading_symbol: &str) -> Vec<(TransactionType, u32, f64)> {
+        let mut lots: Vec<_> = pnl
+            .open_lots()
+            .filter(|l| l.trading_symbol == trading_symbol)
+            .map(|l| (l.transaction_type, l.quantity, l.price))
+            .collect();
+        lots.sort_by(|a, b| a.2.total_cmp(&b.2));
+        lots
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut pnl = FifoPnl::new();
+        pnl.add_trades(&[
+            trade("INFY", Buy, 10, 100.0, "09:15:00"),
+            trade("INFY", Sell, 10, 110.0, "10:00:00"),
+        ]);
+
+        assert_eq!(closed_lots(&pnl), [closed(Buy, 10, 100.0, 110.0)]);
+        assert_eq!(pnl.total_realised(), 100.0);
+        assert_eq!(pnl.open_lots().count(), 0);
+    }
+
+    #[test]
+    fn test_partial_matches() {
+        let mut pnl = FifoPnl::new();
+        pnl.add_trades(&[
+            trade("INFY", Buy, 10, 100.0, "09:15:00"),
+            trade("INFY", Buy, 5, 104.0, "09:20:00"),
+            trade("INFY", Sell, 4, 103.0, "09:30:00"),
+            trade("INFY", Sell, 8, 101.0, "09:40:00"),
+        ]);
+
+        // The oldest lot is closed first, across several sells
+        assert_eq!(
+            closed_lots(&pnl),
+            [
+                closed(Buy, 4, 100.0, 103.0),
+                closed(Buy, 6, 100.0, 101.0),
+                closed(Buy, 2, 104.0, 101.0),
+            ]
+        );
+        assert_eq!(pnl.total_realised(), 12.0 + 6.0 - 6.0);
+        assert_eq!(open_lots(&pnl, "INFY"), [(Buy, 3, 104.0)]);
+    }
+
+    #[test]
+    fn test_short_first() {
+        let mut pnl = FifoPnl::new();
+        pnl.add_trades(&[
+            trade("SBIN", Sell, 10, 500.0, "09:15:00"),
+            trade("SBIN", Sell, 10, 505.0, "09:16:00"),
+            trade("SBIN", Buy, 15, 495.0, "11:00:00"),
+        ]);
+
+        assert_eq!(
+            closed_lots(&pnl),
+            [
+                closed(Sell, 10, 495.0, 500.0),
+                closed(Sell, 5, 495.0, 505.0),
+            ]
+        );
+        assert_eq!(pnl.total_realised(), 50.0 + 50.0);
+        assert_eq!(open_lots(&pnl, "SBIN"), [(Sell, 5, 505.0)]);
+    }
+
+    #[test]
+    fn test_flip_through_zero() {
+        let mut pnl = FifoPnl::new();
+        pnl.add_trades(&[
+            trade("INFY", Buy, 5, 100.0, "09:15:00"),
+            trade("INFY", Sell, 8, 102.0, "09:30:00"),
+            trade("INFY", Buy, 3, 101.0, "09:45:00"),
+        ]);
+
+        // The sell closes the long lot and opens a short lot with the rest
+        assert_eq!(
+            closed_lots(&pnl),
+            [closed(Buy, 5, 100.0, 102.0), closed(Sell, 3, 101.0, 102.0)]
+        );
+        assert_eq!(pnl.total_realised(), 13.0);
+        assert_eq!(pnl.open_lots().count(), 0);
+    }
+
+    #[test]
+    fn test_interleaved_instruments() {
+        let mut pnl = FifoPnl::new();
+        // Out of order, sorted by fill timestamp
+        pnl.add_trades(&[
+            trade("TCS", Sell, 2, 3900.0, "10:30:00"),
+            trade("INFY", Buy, 10, 100.0, "09:15:00"),
+            trade("TCS", Buy, 2, 3800.0, "09:20:00"),
+            trade("INFY", Sell, 5, 98.0, "09:25:00"),
+            trade("TCS", Buy, 1, 3850.0, "10:00:00"),
+            trade("INFY", Sell, 5, 99.0, "11:00:00"),
+        ]);
+
+        let realised = pnl.realised();
+        assert_eq!(realised[&(Exchange::NSE, "INFY".to_string())], -15.0);
+        assert_eq!(realised[&(Exchange::NSE, "TCS".to_string())], 200.0);
+        assert_eq!(pnl.total_realised(), 185.0);
+        assert!(open_lots(&pnl, "INFY").is_empty());
+        assert_eq!(open_lots(&pnl, "TCS"), [(Buy, 1, 3850.0)]);
+    }
+
+    #[test]
+    fn test_opening_lots() {
+        let lot = |transaction_type, quantity, price| OpenLot {
+            exchange: Exchange::NSE,
+            trading_symbol: "INFY".into(),
+            transaction_type,
+            quantity,
+            price,
+        };
+
+        let mut pnl = FifoPnl::with_opening_lots([lot(Buy, 10, 90.0), lot(Buy, 10, 95.0)]);
+        pnl.add_trades(&[
+            trade("INFY", Buy, 10, 100.0, "09:15:00"),
+            trade("INFY", Sell, 15, 105.0, "10:00:00"),
+        ]);
+
+        // Carried lots are closed before the day's buys
+        assert_eq!(
+            closed_lots(&pnl),
+            [closed(Buy, 10, 90.0, 105.0), closed(Buy, 5, 95.0, 105.0)]
+        );
+        assert_eq!(open_lots(&pnl, "INFY"), [(Buy, 5, 95.0), (Buy, 10, 100.0)]);
+
+        // Opposite opening lots net against each other
+        let pnl = FifoPnl::with_opening_lots([lot(Buy, 10, 90.0), lot(Sell, 4, 92.0)]);
+        assert_eq!(closed_lots(&pnl), [closed(Buy, 4, 90.0, 92.0)]);
+        assert_eq!(open_lots(&pnl, "INFY"), [(Buy, 6, 90.0)]);
+    }
+}
@@ -1,7 +1,13 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::historical::{Candle, HistoricalCandleReq, Interval};
 use crate::orders::{Exchange, Product, TransactionType};
+use crate::quotes::{Instrument, InstrumentType};
+use crate::user::funds::TotalFunds;
 
 use super::*;
 
@@ -41,6 +47,58 @@ pub struct Holding {
     pub short_quantity: i64,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Holding {
+    price => price_decimal -> Option<Decimal>,
+    average_price => average_price_decimal -> Option<Decimal>,
+    last_price => last_price_decimal -> Option<Decimal>,
+    close_price => close_price_decimal -> Option<Decimal>,
+    pnl => pnl_decimal -> Option<Decimal>,
+    day_change => day_change_decimal -> Option<Decimal>,
+    day_change_percentage => day_change_percentage_decimal -> Option<Decimal>,
+});
+
+impl Holding {
+    /// Whether `other` is the same holding, ignoring the live price fields (`last_price`, `pnl`,
+    /// `day_change`, ...).
+    ///
+    /// Only the instrument, the quantity and the average price are compared, e.g. to check whether
+    /// holdings changed between two snapshots.
+    pub fn same_position(&self, other: &Holding) -> bool {
+        self.trading_symbol == other.trading_symbol
+            && self.exchange == other.exchange
+            && self.quantity == other.quantity
+            && self.average_price == other.average_price
+    }
+}
+
+/// One line summary of the holding, e.g. for logs and CLI tools:
+///
+/// ```text
+/// NSE:INFY 10 @ 1400.00 [LTP 1510.00, P&L 1100.00]
+/// NSE:SBIN 16 +4 T1 @ 801.78 [LTP 762.45, P&L -629.30]
+/// ```
+///
+/// Shares bought in the last session (`t1_quantity`) are shown separately. Prices have two
+/// decimals. The format is kept stable, but parse it at your own risk.
+impl Display for Holding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{} {}",
+            self.exchange, self.trading_symbol, self.quantity
+        )?;
+        if self.t1_quantity != 0 {
+            write!(f, " +{} T1", self.t1_quantity)?;
+        }
+        write!(
+            f,
+            " @ {:.2} [LTP {:.2}, P&L {:.2}]",
+            self.average_price, self.last_price, self.pnl
+        )
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct HoldingAuction {
     #[serde(rename = "tradingsymbol")]
@@ -68,6 +126,17 @@ pub struct HoldingAuction {
     pub auction_number: String,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(HoldingAuction {
+    price => price_decimal -> Option<Decimal>,
+    average_price => average_price_decimal -> Option<Decimal>,
+    last_price => last_price_decimal -> Option<Decimal>,
+    close_price => close_price_decimal -> Option<Decimal>,
+    pnl => pnl_decimal -> Option<Decimal>,
+    day_change => day_change_decimal -> Option<Decimal>,
+    day_change_percentage => day_change_percentage_decimal -> Option<Decimal>,
+});
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Position {
     #[serde(rename = "tradingsymbol")]
@@ -102,6 +171,104 @@ pub struct Position {
     pub day_sell_value: f64,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Position {
+    average_price => average_price_decimal -> Option<Decimal>,
+    close_price => close_price_decimal -> Option<Decimal>,
+    last_price => last_price_decimal -> Option<Decimal>,
+    value => value_decimal -> Option<Decimal>,
+    pnl => pnl_decimal -> Option<Decimal>,
+    m2m => m2m_decimal -> Option<Decimal>,
+    unrealised => unrealised_decimal -> Option<Decimal>,
+    realised => realised_decimal -> Option<Decimal>,
+    buy_price => buy_price_decimal -> Option<Decimal>,
+    buy_value => buy_value_decimal -> Option<Decimal>,
+    buy_m2m => buy_m2m_decimal -> Option<Decimal>,
+    day_buy_price => day_buy_price_decimal -> Option<Decimal>,
+    day_buy_value => day_buy_value_decimal -> Option<Decimal>,
+    sell_price => sell_price_decimal -> Option<Decimal>,
+    sell_value => sell_value_decimal -> Option<Decimal>,
+    sell_m2m => sell_m2m_decimal -> Option<Decimal>,
+    day_sell_price => day_sell_price_decimal -> Option<Decimal>,
+    day_sell_value => day_sell_value_decimal -> Option<Decimal>,
+});
+
+/// One line summary of the position, e.g. for logs and CLI tools:
+///
+/// ```text
+/// NSE:INFY MIS LONG 50 @ 1500.00 [LTP 1510.00, P&L 500.00]
+/// NFO:NIFTY24DECFUT NRML SHORT 75 @ 24100.00 [LTP 24050.00, P&L 3750.00]
+/// NSE:SBIN CO FLAT [LTP 308.40, P&L -2.00]
+/// ```
+///
+/// Closed positions are FLAT and have no average price. Prices have two decimals. The format is
+/// kept stable, but parse it at your own risk.
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{} {}",
+            self.exchange, self.trading_symbol, self.product
+        )?;
+        match self.quantity {
+            0 => write!(f, " FLAT")?,
+            quantity => write!(
+                f,
+                " {} {} @ {:.2}",
+                if quantity > 0 { "LONG" } else { "SHORT" },
+                quantity.unsigned_abs(),
+                self.average_price
+            )?,
+        }
+        write!(f, " [LTP {:.2}, P&L {:.2}]", self.last_price, self.pnl)
+    }
+}
+
+impl Position {
+    /// Whether `other` is the same position, ignoring the live price fields (`last_price`, `pnl`,
+    /// `m2m`, ...).
+    ///
+    /// Only the instrument, the product, the quantity and the average price are compared.
+    pub fn same_position(&self, other: &Position) -> bool {
+        self.trading_symbol == other.trading_symbol
+            && self.exchange == other.exchange
+            && self.product == other.product
+            && self.quantity == other.quantity
+            && self.average_price == other.average_price
+    }
+
+    /// Requests converting the whole position to `new_product`.
+    ///
+    /// Kite converts the overnight and the day part of a position separately, so a position
+    /// carried over and traded today takes two requests: one for the `overnight_quantity` and one
+    /// for the net quantity traded today. Parts without a quantity are left out.
+    pub fn to_convert_requests(&self, new_product: Product) -> Vec<ConvertPositionReq> {
+        [
+            (PositionType::OverNight, self.overnight_quantity),
+            (
+                PositionType::Day,
+                self.day_buy_quantity - self.day_sell_quantity,
+            ),
+        ]
+        .into_iter()
+        .filter(|&(_, quantity)| quantity != 0)
+        .map(|(position_type, quantity)| ConvertPositionReq {
+            trading_symbol: self.trading_symbol.clone(),
+            exchange: self.exchange,
+            transaction_type: if quantity < 0 {
+                TransactionType::Sell
+            } else {
+                TransactionType::Buy
+            },
+            position_type,
+            quantity: quantity.abs(),
+            old_product: self.product,
+            new_product,
+        })
+        .collect()
+    }
+}
+
 // TODO: Find a better name
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Positions {
@@ -109,6 +276,21 @@ pub struct Positions {
     pub day: Vec<Position>,
 }
 
+impl Positions {
+    /// Positions opened and closed today: the day positions that are flat after being traded.
+    pub fn intraday_closed(&self) -> Vec<&Position> {
+        self.day
+            .iter()
+            .filter(|p| p.quantity == 0 && p.buy_quantity + p.sell_quantity != 0)
+            .collect()
+    }
+
+    /// Positions still open, whether they were opened today or carried over.
+    pub fn open_positions(&self) -> Vec<&Position> {
+        self.net.iter().filter(|p| p.quantity != 0).collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ConvertPositionReq {
     #[serde(rename = "tradingsymbol")]
@@ -121,6 +303,30 @@ pub struct ConvertPositionReq {
     pub new_product: Product,
 }
 
+impl ConvertPositionReq {
+    /// Validates the request locally before sending it to Kite.
+    ///
+    /// Returns an [`KiteError::InputException`](crate::KiteError::InputException) if the quantity
+    /// isn't positive or the product doesn't change.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.quantity <= 0 {
+            return Err(Error::input_exception(format!(
+                "Quantity to convert must be positive, got {}",
+                self.quantity
+            )));
+        }
+
+        if self.old_product == self.new_product {
+            return Err(Error::input_exception(format!(
+                "Position is already {}",
+                self.new_product
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum PositionType {
     #[serde(rename = "day")]
@@ -129,50 +335,234 @@ pub enum PositionType {
     OverNight,
 }
 
+impl Display for PositionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PositionType::Day => "day",
+            PositionType::OverNight => "overnight",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl std::str::FromStr for PositionType {
+    type Err = Error;
+
+    /// Parses `day` or `overnight`, as displayed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(PositionType::Day),
+            "overnight" => Ok(PositionType::OverNight),
+            _ => Err(Error::input_exception(format!(
+                "Unknown position type {s:?}, expected `day` or `overnight`"
+            ))),
+        }
+    }
+}
+
+/// Holdings, positions and funds captured together, for archiving and comparing the portfolio
+/// across days.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PortfolioSnapshot {
+    /// When the snapshot was requested
+    pub taken_at: SystemTime,
+    pub holdings: Vec<Holding>,
+    pub positions: Positions,
+    pub funds: TotalFunds,
+}
+
+impl PortfolioSnapshot {
+    /// Same as [`KiteConnect::portfolio_snapshot`].
+    pub async fn fetch(kite: &KiteConnect<Authenticated>) -> Result<Self, Error> {
+        kite.portfolio_snapshot().await
+    }
+
+    /// Merges holdings and open net positions into one exposure row per underlying.
+    ///
+    /// Positions are grouped under their own trading symbol, unless `instruments` (e.g. from
+    /// [`KiteConnect::get_all_instruments`]) is given, in which case derivatives are mapped to their
+    /// underlying through [`Instrument::name`]. Equities keep their trading symbol, so a holding
+    /// and an intraday position of the same stock share a row. Values are notional (`quantity *
+    /// multiplier * last_price`), so option positions count with a delta of 1.
+    pub fn exposure(&self, instruments: Option<&[Instrument]>) -> Exposure {
+        let underlying: HashMap<u32, &str> = instruments
+            .unwrap_or_default()
+            .iter()
+            .filter(|i| {
+                !i.name.is_empty()
+                    && matches!(
+                        i.instrument_type,
+                        InstrumentType::FUT | InstrumentType::CE | InstrumentType::PE
+                    )
+            })
+            .map(|i| (i.instrument_token, i.name.as_str()))
+            .collect();
+
+        let mut rows: BTreeMap<String, ExposureRow> = BTreeMap::new();
+
+        for holding in &self.holdings {
+            let quantity = holding.quantity + holding.t1_quantity;
+            let entry = rows
+                .entry(holding.trading_symbol.clone())
+                .or_insert_with(|| ExposureRow::new(&holding.trading_symbol));
+            entry.holding_quantity += quantity;
+            entry.holding_value += quantity as f64 * holding.last_price;
+        }
+
+        for position in self.positions.net.iter().filter(|p| p.quantity != 0) {
+            let symbol = underlying
+                .get(&position.instrument_token)
+                .copied()
+                .unwrap_or(&position.trading_symbol);
+            let quantity = position.quantity * position.multiplier.max(1);
+            let entry = rows
+                .entry(symbol.to_owned())
+                .or_insert_with(|| ExposureRow::new(symbol));
+            entry.position_quantity += quantity;
+            entry.position_value += quantity as f64 * position.last_price;
+        }
+
+        let rows: Vec<ExposureRow> = rows.into_values().collect();
+        let (equity, commodity) = (&self.funds.equity, &self.funds.commodity);
+
+        Exposure {
+            total_holding_value: rows.iter().map(|r| r.holding_value).sum(),
+            total_position_value: rows.iter().map(|r| r.position_value).sum(),
+            margin_utilised: equity.utilised.debits + commodity.utilised.debits,
+            rows,
+        }
+    }
+}
+
+/// Exposure to a single underlying across holdings and positions.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ExposureRow {
+    /// Trading symbol of the underlying (or of the instrument when it couldn't be mapped)
+    pub underlying: String,
+    /// Quantity held in the demat account, including T1
+    pub holding_quantity: i64,
+    /// Market value of the holdings
+    pub holding_value: f64,
+    /// Net quantity across open positions, multiplied by the lot multiplier
+    pub position_quantity: i64,
+    /// Signed notional value of the open positions
+    pub position_value: f64,
+}
+
+impl ExposureRow {
+    fn new(underlying: &str) -> Self {
+        Self {
+            underlying: underlying.to_owned(),
+            holding_quantity: 0,
+            holding_value: 0.0,
+            position_quantity: 0,
+            position_value: 0.0,
+        }
+    }
+
+    /// Net quantity of the underlying across holdings and positions
+    pub fn net_quantity(&self) -> i64 {
+        self.holding_quantity + self.position_quantity
+    }
+
+    /// Net value of the underlying across holdings and positions
+    pub fn net_value(&self) -> f64 {
+        self.holding_value + self.position_value
+    }
+}
+
+/// Per underlying exposure computed by [`PortfolioSnapshot::exposure`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Exposure {
+    /// One row per underlying, sorted by symbol
+    pub rows: Vec<ExposureRow>,
+    pub total_holding_value: f64,
+    pub total_position_value: f64,
+    /// Margin blocked across the equity and commodity segments
+    pub margin_utilised: f64,
+}
+
+impl Exposure {
+    /// Net value across holdings and positions
+    pub fn net_value(&self) -> f64 {
+        self.total_holding_value + self.total_position_value
+    }
+}
+
 impl KiteConnect<Authenticated> {
+    /// Fetches holdings, positions and funds concurrently into a single [`PortfolioSnapshot`].
+    ///
+    /// Fails if any of the three requests fail.
+    pub async fn portfolio_snapshot(&self) -> Result<PortfolioSnapshot, Error> {
+        let taken_at = SystemTime::now();
+        let (holdings, positions, funds) =
+            futures_util::try_join!(self.get_holdings(), self.get_positions(), self.get_funds())?;
+
+        Ok(PortfolioSnapshot {
+            taken_at,
+            holdings,
+            positions,
+            funds,
+        })
+    }
+
     pub async fn get_holdings(&self) -> Result<Vec<Holding>, Error> {
-        Ok(self
-            .client
-            .get(GET_HOLDINGS_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.send(self.client.get(GET_HOLDINGS_ENDPOINT)).await
     }
 
     pub async fn get_holdings_auction(&self) -> Result<Vec<HoldingAuction>, Error> {
-        Ok(self
-            .client
-            .get(GET_HOLDINGS_AUCTION_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.send(self.client.get(GET_HOLDINGS_AUCTION_ENDPOINT))
+            .await
     }
 
     pub async fn get_positions(&self) -> Result<Positions, Error> {
-        Ok(self
-            .client
-            .get(GET_PUT_POSITIONS_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.send(self.client.get(GET_PUT_POSITIONS_ENDPOINT)).await
     }
 
+    /// Converts the product of a position, e.g. an intraday MIS position to CNC.
+    ///
+    /// The request is [validated](ConvertPositionReq::validate) first. Kite answers a successful
+    /// conversion with `true`, any other success body is taken as a
+    /// success too, except an explicit `false`. A failed conversion is an `Err` with the error of
+    /// Kite.
     pub async fn convert_position(&self, req: &ConvertPositionReq) -> Result<bool, Error> {
-        Ok(self
-            .client
-            .put(GET_PUT_POSITIONS_ENDPOINT)
-            .form(req)
-            .send()
-            .await?
-            .json::<Response<bool>>()
-            .await?
-            .into_result()?)
+        req.validate()?;
+        let data = self
+            .send::<Value>(self.client.put(GET_PUT_POSITIONS_ENDPOINT).form(req))
+            .await?;
+
+        Ok(data.as_bool().unwrap_or(true))
+    }
+
+    /// Converts every open `from_product` position to `to_product`, e.g. all MIS positions to CNC
+    /// before the square off.
+    ///
+    /// The positions are converted one after the other, see [`Position::to_convert_requests`]. The
+    /// result of each conversion is returned with its request, a failed conversion doesn't stop
+    /// the others. Only failing to fetch the positions is an `Err`.
+    ///
+    /// The results are paired with their requests, rather than one result per position, as a
+    /// position carried over and traded today takes two conversions that can fail separately.
+    pub async fn convert_positions_matching(
+        &self,
+        from_product: Product,
+        to_product: Product,
+    ) -> Result<Vec<(ConvertPositionReq, Result<bool, Error>)>, Error> {
+        let positions = self.get_positions().await?;
+
+        let mut results = Vec::new();
+        for position in positions.open_positions() {
+            if position.product != from_product {
+                continue;
+            }
+
+            for req in position.to_convert_requests(to_product) {
+                let res = self.convert_position(&req).await;
+                results.push((req, res));
+            }
+        }
+
+        Ok(results)
     }
 
     /// Unimplemented
@@ -183,281 +573,87 @@ impl KiteConnect<Authenticated> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_holdings() -> Result<(), Box<dyn std::error::Error>> {
-        let json = r#"{
-          "status": "success",
-          "data": [
-            {
-              "tradingsymbol": "AARON",
-              "exchange": "NSE",
-              "instrument_token": 263681,
-              "isin": "INE721Z01010",
-              "product": "CNC",
-              "price": 0,
-              "quantity": 1,
-              "used_quantity": 0,
-              "t1_quantity": 0,
-              "realised_quantity": 1,
-              "authorised_quantity": 0,
-              "authorised_date": "2025-01-17 00:00:00",
-              "authorisation": {},
-              "opening_quantity": 1,
-              "short_quantity": 0,
-              "collateral_quantity": 0,
-              "collateral_type": "",
-              "discrepancy": false,
-              "average_price": 161,
-              "last_price": 352.95,
-              "close_price": 352.35,
-              "pnl": 191.95,
-              "day_change": 0.5999999999999659,
-              "day_change_percentage": 0.17028522775648244,
-              "mtf": {
-                "quantity": 1000,
-                "used_quantity": 0,
-                "average_price": 100,
-                "value": 100000,
-                "initial_margin": 0
-              }
-            },
-            {
-              "tradingsymbol": "SBIN",
-              "exchange": "BSE",
-              "instrument_token": 128028676,
-              "isin": "INE062A01020",
-              "product": "CNC",
-              "price": 0,
-              "quantity": 16,
-              "used_quantity": 0,
-              "t1_quantity": 0,
-              "realised_quantity": 16,
-              "authorised_quantity": 0,
-              "authorised_date": "2025-01-17 00:00:00",
-              "authorisation": {},
-              "opening_quantity": 16,
-              "short_quantity": 0,
-              "collateral_quantity": 0,
-              "collateral_type": "",
-              "discrepancy": false,
-              "average_price": 801.78125,
-              "last_price": 762.45,
-              "close_price": 766.4,
-              "pnl": -629.2999999999993,
-              "day_change": -3.949999999999932,
-              "day_change_percentage": -0.5153966597077155,
-              "mtf": {
-                "quantity": 0,
-                "used_quantity": 0,
-                "average_price": 0,
-                "value": 0,
-                "initial_margin": 0
-              }
-            }
-          ]
-        }"#;
-
-        let value: Response<_> = serde_json::from_str(json)?;
-
-        let expected = Response::Success {
-            data: vec![
-                Holding {
-                    trading_symbol: "AARON".into(),
-                    exchange: Exchange::NSE,
-                    instrument_token: 263681,
-                    isin: "INE721Z01010".into(),
-                    product: Product::CNC,
-                    price: 0.0,
-                    quantity: 1,
-                    used_quantity: 0,
-                    t1_quantity: 0,
-                    realised_quantity: 1,
-                    authorised_quantity: 0,
-                    authorised_date: "2025-01-17 00:00:00".into(),
-                    authorisation: serde_json::json!({}),
-                    opening_quantity: 1,
-                    short_quantity: 0,
-                    collateral_quantity: 0,
-                    collateral_type: Some("".into()),
-                    discrepancy: false,
-                    average_price: 161.0,
-                    last_price: 352.95,
-                    close_price: 352.35,
-                    pnl: 191.95,
-                    day_change: 0.5999999999999659,
-                    day_change_percentage: 0.17028522775648244,
-                    mtf: serde_json::json!({
-                        "quantity": 1000,
-                        "used_quantity": 0,
-                        "average_price": 100,
-                        "value": 100000,
-                        "initial_margin": 0
-                    }),
-                },
-                Holding {
-                    trading_symbol: "SBIN".into(),
-                    exchange: Exchange::BSE,
-                    instrument_token: 128028676,
-                    isin: "INE062A01020".into(),
-                    product: Product::CNC,
-                    price: 0.0,
-                    quantity: 16,
-                    used_quantity: 0,
-                    t1_quantity: 0,
-                    realised_quantity: 16,
-                    authorised_quantity: 0,
-                    authorised_date: "2025-01-17 00:00:00".into(),
-                    authorisation: serde_json::json!({}),
-                    opening_quantity: 16,
-                    short_quantity: 0,
-                    collateral_quantity: 0,
-                    collateral_type: Some("".into()),
-                    discrepancy: false,
-                    average_price: 801.78125,
-                    last_price: 762.45,
-                    close_price: 766.4,
-                    pnl: -629.2999999999993,
-                    day_change: -3.949999999999932,
-                    day_change_percentage: -0.5153966597077155,
-                    mtf: serde_json::json!({
-                        "quantity": 0,
-                        "used_quantity": 0,
-                        "average_price": 0,
-                        "value": 0,
-                        "initial_margin": 0
-                    }),
-                },
-            ],
-        };
+/// Daily candles of a holding, see [`enrich_holdings_with_history`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoldingHistory {
+    /// Daily candles over the lookback, oldest first
+    pub candles: Vec<Candle>,
+    /// Highest price over the lookback, `None` without candles
+    pub high: Option<f64>,
+    /// Lowest price over the lookback, `None` without candles
+    pub low: Option<f64>,
+    /// How far the last price is below `high`, in percent
+    pub distance_from_high: Option<f64>,
+}
 
-        assert_eq!(value, expected);
+impl HoldingHistory {
+    fn new(candles: Vec<Candle>, last_price: f64) -> Self {
+        let high = candles.iter().map(|c| c.high).reduce(f64::max);
+        let low = candles.iter().map(|c| c.low).reduce(f64::min);
 
-        Ok(())
+        Self {
+            distance_from_high: high
+                .filter(|&high| high > 0.0)
+                .map(|high| (high - last_price) / high * 100.0),
+            candles,
+            high,
+            low,
+        }
     }
+}
 
-    #[test]
-    fn test_auction_holdings() -> Result<(), Box<dyn std::error::Error>> {
-        let json = r#"{
-          "status": "success",
-          "data": [
-            {
-              "tradingsymbol": "ASHOKLEY",
-              "exchange": "NSE",
-              "instrument_token": 54282,
-              "isin": "INE208A01029",
-              "product": "CNC",
-              "price": 0,
-              "quantity": 1,
-              "t1_quantity": 0,
-              "realised_quantity": 1,
-              "authorised_quantity": 0,
-              "authorised_date": "2022-12-21 00:00:00",
-              "opening_quantity": 1,
-              "collateral_quantity": 0,
-              "collateral_type": "",
-              "discrepancy": false,
-              "average_price": 131.95,
-              "last_price": 142.5,
-              "close_price": 145.1,
-              "pnl": 10.550000000000011,
-              "day_change": -2.5999999999999943,
-              "day_change_percentage": -1.79186767746,
-              "auction_number": "20"
-            },
-            {
-              "tradingsymbol": "BHEL",
-              "exchange": "NSE",
-              "instrument_token": 112138,
-              "isin": "INE257A01026",
-              "product": "CNC",
-              "price": 0,
-              "quantity": 5,
-              "t1_quantity": 0,
-              "realised_quantity": 5,
-              "authorised_quantity": 0,
-              "authorised_date": "2022-12-21 00:00:00",
-              "opening_quantity": 5,
-              "collateral_quantity": 0,
-              "collateral_type": "",
-              "discrepancy": false,
-              "average_price": 75.95,
-              "last_price": 81.1,
-              "close_price": 84,
-              "pnl": 25.749999999999957,
-              "day_change": -2.9000000000000057,
-              "day_change_percentage": -3.4523809523809588,
-              "auction_number": "34"
-            }
-          ]
-        }"#;
+/// A holding with its daily candles, see [`enrich_holdings_with_history`].
+#[derive(Debug)]
+pub struct EnrichedHolding {
+    pub holding: Holding,
+    /// The candles, or the error fetching them
+    pub history: Result<HoldingHistory, Error>,
+}
 
-        let value: Response<_> = serde_json::from_str(json)?;
+/// Fetches the daily candles of the last `lookback_days` days (in IST, today included) of each
+/// holding, e.g. `365` for the 52-week high and low.
+///
+/// The candles are fetched with [`KiteConnect::get_historical_data_bulk`], so this takes about a
+/// second per 3 holdings. A holding whose candles can't be fetched gets the error in its
+/// [`EnrichedHolding::history`], the others are still enriched. Holdings are returned in the
+/// order of `holdings`.
+pub async fn enrich_holdings_with_history(
+    kite: &KiteConnect<Authenticated>,
+    holdings: &[Holding],
+    lookback_days: u32,
+) -> Vec<EnrichedHolding> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (from, _) = crate::utils::ist_date_time(now - i64::from(lookback_days) * 86400);
+    let (to, _) = crate::utils::ist_date_time(now);
+    let req = HistoricalCandleReq {
+        from: format!("{from} 00:00:00"),
+        to: format!("{to} 23:59:59"),
+        continuous: false,
+        oi: false,
+    };
 
-        let expected = Response::Success {
-            data: vec![
-                HoldingAuction {
-                    trading_symbol: "ASHOKLEY".into(),
-                    exchange: Exchange::NSE,
-                    instrument_token: 54282,
-                    isin: "INE208A01029".into(),
-                    product: Product::CNC,
-                    price: 0.0,
-                    quantity: 1,
-                    t1_quantity: 0,
-                    realised_quantity: 1,
-                    authorised_quantity: 0,
-                    authorised_date: "2022-12-21 00:00:00".into(),
-                    opening_quantity: 1,
-                    collateral_quantity: 0,
-                    collateral_type: Some("".into()),
-                    discrepancy: false,
-                    average_price: 131.95,
-                    last_price: 142.5,
-                    close_price: 145.1,
-                    pnl: 10.550000000000011,
-                    day_change: -2.5999999999999943,
-                    day_change_percentage: -1.79186767746,
-                    auction_number: "20".into(),
-                },
-                HoldingAuction {
-                    trading_symbol: "BHEL".into(),
-                    exchange: Exchange::NSE,
-                    instrument_token: 112138,
-                    isin: "INE257A01026".into(),
-                    product: Product::CNC,
-                    price: 0.0,
-                    quantity: 5,
-                    t1_quantity: 0,
-                    realised_quantity: 5,
-                    authorised_quantity: 0,
-                    authorised_date: "2022-12-21 00:00:00".into(),
-                    opening_quantity: 5,
-                    collateral_quantity: 0,
-                    collateral_type: Some("".into()),
-                    discrepancy: false,
-                    average_price: 75.95,
-                    last_price: 81.1,
-                    close_price: 84.0,
-                    pnl: 25.749999999999957,
-                    day_change: -2.9000000000000057,
-                    day_change_percentage: -3.4523809523809588,
-                    auction_number: "34".into(),
-                },
-            ],
-        };
+    let tokens: Vec<u32> = holdings.iter().map(|h| h.instrument_token).collect();
+    let candles = kite
+        .get_historical_data_bulk(&tokens, Interval::Day, &req)
+        .await;
 
-        assert_eq!(value, expected);
+    holdings
+        .iter()
+        .zip(candles)
+        .map(|(holding, candles)| EnrichedHolding {
+            holding: holding.clone(),
+            history: candles.map(|candles| HoldingHistory::new(candles, holding.last_price)),
+        })
+        .collect()
+}
 
-        Ok(())
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    #[test]
-    fn test_positions() -> Result<(), Box<dyn std::error::Error>> {
-        let json = r#"{
+    const POSITIONS_JSON: &str = r#"{
             "status": "success",
             "data": {
                 "net": [
@@ -624,37 +820,311 @@ mod tests {
                         "instrument_token": 779521,
                         "product": "CO",
                         "quantity": 0,
-                        "overnight_quantity": 0,
-                        "multiplier": 1,
+                        "overnight_quantity": 0,
+                        "multiplier": 1,
+                        "average_price": 0,
+                        "close_price": 0,
+                        "last_price": 308.4,
+                        "value": -2,
+                        "pnl": -2,
+                        "m2m": -2,
+                        "unrealised": -2,
+                        "realised": 0,
+                        "buy_quantity": 1,
+                        "buy_price": 311,
+                        "buy_value": 311,
+                        "buy_m2m": 311,
+                        "sell_quantity": 1,
+                        "sell_price": 309,
+                        "sell_value": 309,
+                        "sell_m2m": 309,
+                        "day_buy_quantity": 1,
+                        "day_buy_price": 311,
+                        "day_buy_value": 311,
+                        "day_sell_quantity": 1,
+                        "day_sell_price": 309,
+                        "day_sell_value": 309
+                    }
+                ]
+            }
+        }"#;
+
+    const HOLDINGS_JSON: &str = r#"{
+          "status": "success",
+          "data": [
+            {
+              "tradingsymbol": "AARON",
+              "exchange": "NSE",
+              "instrument_token": 263681,
+              "isin": "INE721Z01010",
+              "product": "CNC",
+              "price": 0,
+              "quantity": 1,
+              "used_quantity": 0,
+              "t1_quantity": 0,
+              "realised_quantity": 1,
+              "authorised_quantity": 0,
+              "authorised_date": "2025-01-17 00:00:00",
+              "authorisation": {},
+              "opening_quantity": 1,
+              "short_quantity": 0,
+              "collateral_quantity": 0,
+              "collateral_type": "",
+              "discrepancy": false,
+              "average_price": 161,
+              "last_price": 352.95,
+              "close_price": 352.35,
+              "pnl": 191.95,
+              "day_change": 0.5999999999999659,
+              "day_change_percentage": 0.17028522775648244,
+              "mtf": {
+                "quantity": 1000,
+                "used_quantity": 0,
+                "average_price": 100,
+                "value": 100000,
+                "initial_margin": 0
+              }
+            },
+            {
+              "tradingsymbol": "SBIN",
+              "exchange": "BSE",
+              "instrument_token": 128028676,
+              "isin": "INE062A01020",
+              "product": "CNC",
+              "price": 0,
+              "quantity": 16,
+              "used_quantity": 0,
+              "t1_quantity": 0,
+              "realised_quantity": 16,
+              "authorised_quantity": 0,
+              "authorised_date": "2025-01-17 00:00:00",
+              "authorisation": {},
+              "opening_quantity": 16,
+              "short_quantity": 0,
+              "collateral_quantity": 0,
+              "collateral_type": "",
+              "discrepancy": false,
+              "average_price": 801.78125,
+              "last_price": 762.45,
+              "close_price": 766.4,
+              "pnl": -629.2999999999993,
+              "day_change": -3.949999999999932,
+              "day_change_percentage": -0.5153966597077155,
+              "mtf": {
+                "quantity": 0,
+                "used_quantity": 0,
+                "average_price": 0,
+                "value": 0,
+                "initial_margin": 0
+              }
+            }
+          ]
+        }"#;
+
+    #[test]
+    fn test_holdings() -> Result<(), Box<dyn std::error::Error>> {
+        let json = HOLDINGS_JSON;
+
+        let value: Response<_> = serde_json::from_str(json)?;
+
+        let expected = Response::Success {
+            data: vec![
+                Holding {
+                    trading_symbol: "AARON".into(),
+                    exchange: Exchange::NSE,
+                    instrument_token: 263681,
+                    isin: "INE721Z01010".into(),
+                    product: Product::CNC,
+                    price: 0.0,
+                    quantity: 1,
+                    used_quantity: 0,
+                    t1_quantity: 0,
+                    realised_quantity: 1,
+                    authorised_quantity: 0,
+                    authorised_date: "2025-01-17 00:00:00".into(),
+                    authorisation: serde_json::json!({}),
+                    opening_quantity: 1,
+                    short_quantity: 0,
+                    collateral_quantity: 0,
+                    collateral_type: Some("".into()),
+                    discrepancy: false,
+                    average_price: 161.0,
+                    last_price: 352.95,
+                    close_price: 352.35,
+                    pnl: 191.95,
+                    day_change: 0.5999999999999659,
+                    day_change_percentage: 0.17028522775648244,
+                    mtf: serde_json::json!({
+                        "quantity": 1000,
+                        "used_quantity": 0,
+                        "average_price": 100,
+                        "value": 100000,
+                        "initial_margin": 0
+                    }),
+                },
+                Holding {
+                    trading_symbol: "SBIN".into(),
+                    exchange: Exchange::BSE,
+                    instrument_token: 128028676,
+                    isin: "INE062A01020".into(),
+                    product: Product::CNC,
+                    price: 0.0,
+                    quantity: 16,
+                    used_quantity: 0,
+                    t1_quantity: 0,
+                    realised_quantity: 16,
+                    authorised_quantity: 0,
+                    authorised_date: "2025-01-17 00:00:00".into(),
+                    authorisation: serde_json::json!({}),
+                    opening_quantity: 16,
+                    short_quantity: 0,
+                    collateral_quantity: 0,
+                    collateral_type: Some("".into()),
+                    discrepancy: false,
+                    average_price: 801.78125,
+                    last_price: 762.45,
+                    close_price: 766.4,
+                    pnl: -629.2999999999993,
+                    day_change: -3.949999999999932,
+                    day_change_percentage: -0.5153966597077155,
+                    mtf: serde_json::json!({
+                        "quantity": 0,
+                        "used_quantity": 0,
                         "average_price": 0,
-                        "close_price": 0,
-                        "last_price": 308.4,
-                        "value": -2,
-                        "pnl": -2,
-                        "m2m": -2,
-                        "unrealised": -2,
-                        "realised": 0,
-                        "buy_quantity": 1,
-                        "buy_price": 311,
-                        "buy_value": 311,
-                        "buy_m2m": 311,
-                        "sell_quantity": 1,
-                        "sell_price": 309,
-                        "sell_value": 309,
-                        "sell_m2m": 309,
-                        "day_buy_quantity": 1,
-                        "day_buy_price": 311,
-                        "day_buy_value": 311,
-                        "day_sell_quantity": 1,
-                        "day_sell_price": 309,
-                        "day_sell_value": 309
-                    }
-                ]
+                        "value": 0,
+                        "initial_margin": 0
+                    }),
+                },
+            ],
+        };
+
+        assert_eq!(value, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_auction_holdings() -> Result<(), Box<dyn std::error::Error>> {
+        let json = r#"{
+          "status": "success",
+          "data": [
+            {
+              "tradingsymbol": "ASHOKLEY",
+              "exchange": "NSE",
+              "instrument_token": 54282,
+              "isin": "INE208A01029",
+              "product": "CNC",
+              "price": 0,
+              "quantity": 1,
+              "t1_quantity": 0,
+              "realised_quantity": 1,
+              "authorised_quantity": 0,
+              "authorised_date": "2022-12-21 00:00:00",
+              "opening_quantity": 1,
+              "collateral_quantity": 0,
+              "collateral_type": "",
+              "discrepancy": false,
+              "average_price": 131.95,
+              "last_price": 142.5,
+              "close_price": 145.1,
+              "pnl": 10.550000000000011,
+              "day_change": -2.5999999999999943,
+              "day_change_percentage": -1.79186767746,
+              "auction_number": "20"
+            },
+            {
+              "tradingsymbol": "BHEL",
+              "exchange": "NSE",
+              "instrument_token": 112138,
+              "isin": "INE257A01026",
+              "product": "CNC",
+              "price": 0,
+              "quantity": 5,
+              "t1_quantity": 0,
+              "realised_quantity": 5,
+              "authorised_quantity": 0,
+              "authorised_date": "2022-12-21 00:00:00",
+              "opening_quantity": 5,
+              "collateral_quantity": 0,
+              "collateral_type": "",
+              "discrepancy": false,
+              "average_price": 75.95,
+              "last_price": 81.1,
+              "close_price": 84,
+              "pnl": 25.749999999999957,
+              "day_change": -2.9000000000000057,
+              "day_change_percentage": -3.4523809523809588,
+              "auction_number": "34"
             }
+          ]
         }"#;
 
         let value: Response<_> = serde_json::from_str(json)?;
 
+        let expected = Response::Success {
+            data: vec![
+                HoldingAuction {
+                    trading_symbol: "ASHOKLEY".into(),
+                    exchange: Exchange::NSE,
+                    instrument_token: 54282,
+                    isin: "INE208A01029".into(),
+                    product: Product::CNC,
+                    price: 0.0,
+                    quantity: 1,
+                    t1_quantity: 0,
+                    realised_quantity: 1,
+                    authorised_quantity: 0,
+                    authorised_date: "2022-12-21 00:00:00".into(),
+                    opening_quantity: 1,
+                    collateral_quantity: 0,
+                    collateral_type: Some("".into()),
+                    discrepancy: false,
+                    average_price: 131.95,
+                    last_price: 142.5,
+                    close_price: 145.1,
+                    pnl: 10.550000000000011,
+                    day_change: -2.5999999999999943,
+                    day_change_percentage: -1.79186767746,
+                    auction_number: "20".into(),
+                },
+                HoldingAuction {
+                    trading_symbol: "BHEL".into(),
+                    exchange: Exchange::NSE,
+                    instrument_token: 112138,
+                    isin: "INE257A01026".into(),
+                    product: Product::CNC,
+                    price: 0.0,
+                    quantity: 5,
+                    t1_quantity: 0,
+                    realised_quantity: 5,
+                    authorised_quantity: 0,
+                    authorised_date: "2022-12-21 00:00:00".into(),
+                    opening_quantity: 5,
+                    collateral_quantity: 0,
+                    collateral_type: Some("".into()),
+                    discrepancy: false,
+                    average_price: 75.95,
+                    last_price: 81.1,
+                    close_price: 84.0,
+                    pnl: 25.749999999999957,
+                    day_change: -2.9000000000000057,
+                    day_change_percentage: -3.4523809523809588,
+                    auction_number: "34".into(),
+                },
+            ],
+        };
+
+        assert_eq!(value, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_positions() -> Result<(), Box<dyn std::error::Error>> {
+        let json = POSITIONS_JSON;
+
+        let value: Response<_> = serde_json::from_str(json)?;
+
         let expected = Response::Success {
             data: Positions {
                 net: vec![
@@ -854,4 +1324,379 @@ mod tests {
 
         Ok(())
     }
+
+    fn funds_fixture() -> Result<TotalFunds, serde_json::Error> {
+        let segment = serde_json::json!({
+            "enabled": true,
+            "net": 99725.05000000002,
+            "available": {
+                "adhoc_margin": 0,
+                "cash": 245431.6,
+                "opening_balance": 245431.6,
+                "live_balance": 99725.05000000002,
+                "collateral": 0,
+                "intraday_payin": 0
+            },
+            "utilised": {
+                "debits": 145706.55,
+                "exposure": 38981.25,
+                "m2m_realised": 761.7,
+                "m2m_unrealised": 0,
+                "option_premium": 0,
+                "payout": 0,
+                "span": 101989,
+                "holding_sales": 0,
+                "turnover": 0,
+                "liquid_collateral": 0,
+                "stock_collateral": 0,
+                "delivery": 0
+            }
+        });
+
+        serde_json::from_value(serde_json::json!({
+            "equity": segment,
+            "commodity": segment
+        }))
+    }
+
+    #[test]
+    fn test_portfolio_snapshot_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let snapshot = PortfolioSnapshot {
+            taken_at: SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000),
+            holdings: vec![serde_json::from_value(serde_json::json!({
+                "tradingsymbol": "SBIN",
+                "exchange": "BSE",
+                "instrument_token": 128028676,
+                "isin": "INE062A01020",
+                "product": "CNC",
+                "price": 0,
+                "quantity": 16,
+                "used_quantity": 0,
+                "t1_quantity": 0,
+                "realised_quantity": 16,
+                "authorised_quantity": 0,
+                "authorised_date": "2025-01-17 00:00:00",
+                "authorisation": {},
+                "opening_quantity": 16,
+                "short_quantity": 0,
+                "collateral_quantity": 0,
+                "collateral_type": "",
+                "discrepancy": false,
+                "average_price": 801.78125,
+                "last_price": 762.45,
+                "close_price": 766.4,
+                "pnl": -629.2999999999993,
+                "day_change": -3.949999999999932,
+                "day_change_percentage": -0.5153966597077155,
+                "mtf": {}
+            }))?],
+            positions: Positions {
+                net: vec![],
+                day: vec![],
+            },
+            funds: funds_fixture()?,
+        };
+
+        let json = serde_json::to_string(&snapshot)?;
+        let value: PortfolioSnapshot = serde_json::from_str(&json)?;
+
+        assert_eq!(value, snapshot);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_position() -> Result<(), Box<dyn std::error::Error>> {
+        let holdings: Vec<Holding> =
+            serde_json::from_str::<Response<_>>(HOLDINGS_JSON)?.into_result()?;
+        let positions: Positions =
+            serde_json::from_str::<Response<_>>(POSITIONS_JSON)?.into_result()?;
+
+        let mut holding = holdings[0].clone();
+        holding.last_price += 10.0;
+        holding.pnl += 10.0 * holding.quantity as f64;
+        assert_ne!(holding, holdings[0]);
+        assert!(holding.same_position(&holdings[0]));
+        assert!(!holdings[1].same_position(&holdings[0]));
+        holding.quantity += 1;
+        assert!(!holding.same_position(&holdings[0]));
+
+        let mut position = positions.net[0].clone();
+        position.last_price += 10.0;
+        position.m2m += 10.0;
+        assert!(position.same_position(&positions.net[0]));
+        position.average_price += 1.0;
+        assert!(!position.same_position(&positions.net[0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() -> Result<(), Box<dyn std::error::Error>> {
+        let mut holdings: Vec<Holding> =
+            serde_json::from_str::<Response<_>>(HOLDINGS_JSON)?.into_result()?;
+        assert_eq!(
+            holdings[0].to_string(),
+            "NSE:AARON 1 @ 161.00 [LTP 352.95, P&L 191.95]"
+        );
+        holdings[1].t1_quantity = 4;
+        assert_eq!(
+            holdings[1].to_string(),
+            "BSE:SBIN 16 +4 T1 @ 801.78 [LTP 762.45, P&L -629.30]"
+        );
+
+        let positions: Positions =
+            serde_json::from_str::<Response<_>>(POSITIONS_JSON)?.into_result()?;
+        assert_eq!(
+            positions.net[0].to_string(),
+            "MCX:LEADMINI17DECFUT NRML LONG 1 @ 161.05 [LTP 161.05, P&L 0.00]"
+        );
+        assert_eq!(
+            positions.net[2].to_string(),
+            "NSE:SBIN CO FLAT [LTP 308.40, P&L -2.00]"
+        );
+
+        let mut short = positions.net[0].clone();
+        short.quantity = -3;
+        assert_eq!(
+            short.to_string(),
+            "MCX:LEADMINI17DECFUT NRML SHORT 3 @ 161.05 [LTP 161.05, P&L 0.00]"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_positions() -> Result<(), Box<dyn std::error::Error>> {
+        let positions: Positions =
+            serde_json::from_str::<Response<_>>(POSITIONS_JSON)?.into_result()?;
+
+        let symbols = |positions: Vec<&Position>| {
+            positions
+                .into_iter()
+                .map(|p| p.trading_symbol.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(symbols(positions.intraday_closed()), ["SBIN"]);
+        assert_eq!(symbols(positions.open_positions()), ["LEADMINI17DECFUT"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_position_type_from_str() -> Result<(), Error> {
+        for position_type in [PositionType::Day, PositionType::OverNight] {
+            assert_eq!(
+                position_type.to_string().parse::<PositionType>()?,
+                position_type
+            );
+            assert_eq!(
+                serde_json::to_value(&position_type).ok(),
+                Some(Value::String(position_type.to_string()))
+            );
+        }
+        assert!(matches!(
+            "Overnight".parse::<PositionType>(),
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_convert_requests() -> Result<(), Box<dyn std::error::Error>> {
+        let positions: Positions =
+            serde_json::from_str::<Response<_>>(POSITIONS_JSON)?.into_result()?;
+
+        let reqs = positions.net[0].to_convert_requests(Product::MIS);
+        assert_eq!(
+            reqs,
+            [ConvertPositionReq {
+                trading_symbol: "LEADMINI17DECFUT".into(),
+                exchange: Exchange::MCX,
+                transaction_type: TransactionType::Buy,
+                position_type: PositionType::Day,
+                quantity: 1,
+                old_product: Product::NRML,
+                new_product: Product::MIS,
+            }]
+        );
+
+        // 3 carried over, 3 more sold today
+        let gold = &positions.net[1];
+        assert_eq!(gold.trading_symbol, "GOLDGUINEA17DECFUT");
+        let reqs: Vec<_> = gold
+            .to_convert_requests(Product::MIS)
+            .into_iter()
+            .map(|r| (r.position_type, r.transaction_type, r.quantity))
+            .collect();
+        assert_eq!(
+            reqs,
+            [
+                (PositionType::OverNight, TransactionType::Buy, 3),
+                (PositionType::Day, TransactionType::Sell, 3),
+            ]
+        );
+
+        let mut short = positions.net[0].clone();
+        short.quantity = -3;
+        short.overnight_quantity = -3;
+        short.day_buy_quantity = 0;
+        let reqs = short.to_convert_requests(Product::MIS);
+        assert_eq!(reqs.len(), 1);
+        assert_eq!(reqs[0].transaction_type, TransactionType::Sell);
+        assert_eq!(reqs[0].position_type, PositionType::OverNight);
+        assert_eq!(reqs[0].quantity, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_convert_position() {
+        let req = ConvertPositionReq {
+            trading_symbol: "INFY".into(),
+            exchange: Exchange::NSE,
+            transaction_type: TransactionType::Buy,
+            position_type: PositionType::Day,
+            quantity: 10,
+            old_product: Product::MIS,
+            new_product: Product::CNC,
+        };
+        assert!(req.validate().is_ok());
+
+        for invalid in [
+            ConvertPositionReq {
+                new_product: Product::MIS,
+                ..req.clone()
+            },
+            ConvertPositionReq {
+                quantity: 0,
+                ..req.clone()
+            },
+            ConvertPositionReq {
+                quantity: -10,
+                ..req.clone()
+            },
+        ] {
+            assert!(matches!(
+                invalid.validate(),
+                Err(Error::KiteError(KiteError::InputException(_)))
+            ));
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_holding_decimal() -> Result<(), Box<dyn std::error::Error>> {
+        use std::str::FromStr;
+
+        let holdings =
+            serde_json::from_str::<Response<Vec<Holding>>>(HOLDINGS_JSON)?.into_result()?;
+
+        assert_eq!(
+            holdings[1].pnl_decimal(),
+            Some(Decimal::from_str("-629.2999999999993")?)
+        );
+        assert_eq!(
+            holdings[1].day_change_decimal(),
+            Some(Decimal::from_str("-3.949999999999932")?)
+        );
+        assert_eq!(
+            holdings[0].day_change_decimal(),
+            Some(Decimal::from_str("0.5999999999999659")?)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_portfolio_exposure() -> Result<(), Box<dyn std::error::Error>> {
+        let holdings: Vec<Holding> =
+            serde_json::from_str::<Response<_>>(HOLDINGS_JSON)?.into_result()?;
+        let mut positions: Positions =
+            serde_json::from_str::<Response<_>>(POSITIONS_JSON)?.into_result()?;
+
+        // An intraday short against the SBIN holding
+        let mut sbin = positions.net[0].clone();
+        sbin.trading_symbol = "SBIN".into();
+        sbin.exchange = Exchange::NSE;
+        sbin.instrument_token = 779521;
+        sbin.product = Product::MIS;
+        sbin.quantity = -10;
+        sbin.multiplier = 1;
+        sbin.last_price = 762.45;
+        positions.net.push(sbin);
+
+        let snapshot = PortfolioSnapshot {
+            taken_at: SystemTime::UNIX_EPOCH,
+            holdings,
+            positions,
+            funds: funds_fixture()?,
+        };
+
+        let exposure = snapshot.exposure(None);
+        let symbols: Vec<_> = exposure
+            .rows
+            .iter()
+            .map(|r| r.underlying.as_str())
+            .collect();
+        assert_eq!(symbols, ["AARON", "LEADMINI17DECFUT", "SBIN"]);
+
+        let sbin = &exposure.rows[2];
+        assert_eq!(sbin.holding_quantity, 16);
+        assert_eq!(sbin.position_quantity, -10);
+        assert_eq!(sbin.net_quantity(), 6);
+        assert!((sbin.net_value() - 6.0 * 762.45).abs() < 1e-6);
+
+        assert!((exposure.total_holding_value - (352.95 + 16.0 * 762.45)).abs() < 1e-6);
+        assert!((exposure.total_position_value - (1000.0 * 161.05 - 10.0 * 762.45)).abs() < 1e-6);
+        assert!((exposure.margin_utilised - 2.0 * 145706.55).abs() < 1e-6);
+
+        let sbin_eq = Instrument {
+            instrument_token: 779521,
+            exchange_token: "3045".into(),
+            trading_symbol: "SBIN".into(),
+            name: "STATE BANK OF INDIA".into(),
+            last_price: 0.0,
+            expiry: String::new(),
+            strike: 0.0,
+            tick_size: 0.05,
+            lot_size: 1,
+            instrument_type: crate::quotes::InstrumentType::EQ,
+            segment: crate::quotes::Segment::NSE,
+            exchange: Exchange::NSE.into(),
+        };
+        let instruments = [
+            sbin_eq,
+            Instrument {
+                instrument_token: 53496327,
+                exchange_token: "208970".into(),
+                trading_symbol: "LEADMINI17DECFUT".into(),
+                name: "LEADMINI".into(),
+                last_price: 0.0,
+                expiry: "2017-12-29".into(),
+                strike: 0.0,
+                tick_size: 0.05,
+                lot_size: 1,
+                instrument_type: crate::quotes::InstrumentType::FUT,
+                segment: crate::quotes::Segment::MCX_FUT,
+                exchange: Exchange::MCX.into(),
+            },
+        ];
+
+        let exposure = snapshot.exposure(Some(&instruments));
+        let lead = &exposure.rows[1];
+        assert_eq!(lead.underlying, "LEADMINI");
+        assert_eq!(lead.position_quantity, 1000);
+        assert_eq!(lead.holding_quantity, 0);
+
+        // The MIS position of the held equity stays in the row of the holding
+        assert_eq!(exposure.rows.len(), 3);
+        let sbin = &exposure.rows[2];
+        assert_eq!(sbin.underlying, "SBIN");
+        assert_eq!(sbin.holding_quantity, 16);
+        assert_eq!(sbin.position_quantity, -10);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,200 @@
+//! Order postbacks (webhooks).
+//!
+//! Kite `POST`s a JSON payload to the app's registered postback URL whenever an order's status
+//! changes. Use [`parse_and_verify`] on the raw request body inside any web framework handler.
+//!
+//! Read More: <https://kite.trade/docs/connect/v3/postbacks/>
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use subtle::ConstantTimeEq;
+
+use crate::Error;
+use crate::orders::{
+    Exchange, ExchangeOrderId, OrderId, OrderStatus, OrderType, Product, TransactionType, Validity,
+    Variety,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Postback {
+    /// ID of the user the order belongs to
+    pub user_id: String,
+    /// ID of the Kite Connect app the postback was sent for
+    pub app_id: u64,
+    /// `SHA-256(order_id + order_timestamp + api_secret)`
+    pub checksum: String,
+    /// Quantity that's not been filled yet
+    pub unfilled_quantity: u32,
+    /// ID of the user that placed the order
+    pub placed_by: String,
+    /// Unique order ID
+    pub order_id: OrderId,
+    /// Exchange generated order ID. Orders that don't reach the exchange have null IDs
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    /// Order ID of the parent order (only applicable in case of multi-legged orders like CO)
+    pub parent_order_id: Option<OrderId>,
+    /// Current status of the order
+    pub status: OrderStatus,
+    /// Textual description of the order's status. Failed orders come with human readable explanation
+    pub status_message: Option<String>,
+    /// Raw textual description of the failed order's status, as received from the OMS
+    pub status_message_raw: Option<String>,
+    /// Timestamp at which the order was registered by the API
+    pub order_timestamp: String,
+    /// Timestamp at which an order's state changed at the exchange
+    pub exchange_update_timestamp: Option<String>,
+    /// Timestamp at which the order was registered by the exchange
+    pub exchange_timestamp: Option<String>,
+    /// Order variety (regular, amo, co etc.)
+    pub variety: Variety,
+    /// Exchange
+    pub exchange: Exchange,
+    /// Exchange tradingsymbol of the instrument
+    #[serde(rename = "tradingsymbol")]
+    pub trading_symbol: String,
+    /// The numerical identifier issued by the exchange representing the instrument
+    pub instrument_token: u32,
+    /// Order type (MARKET, LIMIT etc.)
+    pub order_type: OrderType,
+    /// BUY or SELL
+    pub transaction_type: TransactionType,
+    /// Order validity
+    pub validity: Validity,
+    /// Margin product used for the order
+    pub product: Product,
+    /// Quantity ordered
+    pub quantity: u32,
+    /// Quantity disclosed to the public exchange orderbook
+    pub disclosed_quantity: u32,
+    /// Price at which the order was placed (LIMIT orders)
+    pub price: f64,
+    /// Trigger price (for SL, SL-M, CO orders)
+    pub trigger_price: f64,
+    /// Average price at which the order was executed
+    pub average_price: f64,
+    /// Quantity that's been filled
+    pub filled_quantity: u32,
+    /// Pending quantity to be filled
+    pub pending_quantity: u32,
+    /// Quantity that's cancelled
+    pub cancelled_quantity: u32,
+    /// Market protection percentage applied to the order
+    pub market_protection: f64,
+    /// Map of arbitrary fields that the system may attach to an order
+    pub meta: serde_json::Value,
+    /// Tag applied to the order when it was placed
+    pub tag: Option<String>,
+    /// Unusable request id to avoid order duplication
+    pub guid: String,
+}
+
+impl Postback {
+    /// Checks that the postback was sent by Kite for the app owning `api_secret`.
+    ///
+    /// The digests are compared in constant time, so the time taken doesn't leak how much of a
+    /// forged checksum is right.
+    pub fn verify(&self, api_secret: &str) -> bool {
+        let checksum = sha2::Sha256::digest(format!(
+            "{}{}{}",
+            self.order_id, self.order_timestamp, api_secret
+        ));
+
+        match hex::decode(&self.checksum) {
+            Ok(received) => checksum.as_slice().ct_eq(&received).into(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Parses a postback request body and verifies its checksum.
+///
+/// Returns [`Error::InvalidChecksum`] if the payload wasn't signed with `api_secret`, in which
+/// case the request didn't come from Kite and should be rejected.
+pub fn parse_and_verify(body: &[u8], api_secret: &str) -> Result<Postback, Error> {
+    let postback: Postback = serde_json::from_slice(body)?;
+
+    if !postback.verify(api_secret) {
+        return Err(Error::InvalidChecksum);
+    }
+
+    Ok(postback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const API_SECRET: &str = "mock_api_secret";
+
+    const POSTBACK_JSON: &str = r#"{
+        "user_id": "AB1234",
+        "unfilled_quantity": 0,
+        "app_id": 1234,
+        "checksum": "f09f24b090bc62e7c7517e8b7cb62e1c39ff9e9240d509ffe4b6cf7ae9511d4f",
+        "placed_by": "AB1234",
+        "order_id": "220303000308932",
+        "exchange_order_id": "1000000001482421",
+        "parent_order_id": null,
+        "status": "COMPLETE",
+        "status_message": null,
+        "status_message_raw": null,
+        "order_timestamp": "2022-03-03 09:24:25",
+        "exchange_update_timestamp": "2022-03-03 09:24:25",
+        "exchange_timestamp": "2022-03-03 09:24:25",
+        "variety": "regular",
+        "exchange": "NSE",
+        "tradingsymbol": "SBIN",
+        "instrument_token": 779521,
+        "order_type": "MARKET",
+        "transaction_type": "BUY",
+        "validity": "DAY",
+        "product": "CNC",
+        "quantity": 1,
+        "disclosed_quantity": 0,
+        "price": 0,
+        "trigger_price": 0,
+        "average_price": 470,
+        "filled_quantity": 1,
+        "pending_quantity": 0,
+        "cancelled_quantity": 0,
+        "market_protection": 0,
+        "meta": {},
+        "tag": null,
+        "guid": "XXXXXX"
+    }"#;
+
+    #[test]
+    fn test_valid_postback() -> Result<(), Box<dyn std::error::Error>> {
+        let mut postback = parse_and_verify(POSTBACK_JSON.as_bytes(), API_SECRET)?;
+
+        assert_eq!(postback.order_id, "220303000308932");
+        assert_eq!(postback.status, OrderStatus::Complete);
+        assert_eq!(postback.trading_symbol, "SBIN");
+        assert_eq!(postback.average_price, 470.0);
+        assert!(!postback.verify("some_other_secret"));
+
+        // The hex digest is case insensitive, a truncated or malformed one never matches
+        let checksum = postback.checksum.clone();
+        postback.checksum = checksum.to_uppercase();
+        assert!(postback.verify(API_SECRET));
+        postback.checksum = checksum[..62].to_owned();
+        assert!(!postback.verify(API_SECRET));
+        postback.checksum = format!("{}zz", &checksum[..62]);
+        assert!(!postback.verify(API_SECRET));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_postback() {
+        let tampered = POSTBACK_JSON.replace(
+            r#""order_timestamp": "2022-03-03 09:24:25""#,
+            r#""order_timestamp": "2022-03-03 09:24:26""#,
+        );
+
+        assert!(matches!(
+            parse_and_verify(tampered.as_bytes(), API_SECRET),
+            Err(Error::InvalidChecksum)
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::orders::Exchange;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt::Display;
 
 use super::*;
 
@@ -9,6 +11,51 @@ pub const GET_FULL_MARKET_QUOTES: &str = "https://api.kite.trade/quote";
 pub const GET_OHLC_QUOTES: &str = "https://api.kite.trade/quote/ohlc";
 pub const GET_LTP_QUOTES: &str = "https://api.kite.trade/quote/ltp";
 
+/// Identifies an instrument in quote requests, either as `EXCHANGE:TRADINGSYMBOL` or by its
+/// instrument token.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum QuoteKey {
+    Symbol(Exchange, String),
+    Token(u32),
+}
+
+impl QuoteKey {
+    pub fn symbol(exchange: Exchange, trading_symbol: impl Into<String>) -> Self {
+        Self::Symbol(exchange, trading_symbol.into())
+    }
+}
+
+impl From<u32> for QuoteKey {
+    fn from(value: u32) -> Self {
+        Self::Token(value)
+    }
+}
+
+impl Display for QuoteKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuoteKey::Symbol(exchange, trading_symbol) => write!(f, "{exchange}:{trading_symbol}"),
+            QuoteKey::Token(instrument_token) => write!(f, "{instrument_token}"),
+        }
+    }
+}
+
+impl Serialize for QuoteKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            QuoteKey::Symbol(..) => serializer.collect_str(self),
+            QuoteKey::Token(instrument_token) => serializer.serialize_u32(*instrument_token),
+        }
+    }
+}
+
+/// Full quotes and LTPs fetched together by [`KiteConnect::get_mixed_quotes`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct MixedQuotes {
+    pub full: HashMap<String, Quote>,
+    pub ltp: HashMap<String, LtpQuote>,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Instrument {
     pub instrument_token: u32,
@@ -22,10 +69,17 @@ pub struct Instrument {
     pub tick_size: f64,
     pub lot_size: i64,
     pub instrument_type: InstrumentType,
-    pub segment: String,
-    pub exchange: String,
+    pub segment: Segment,
+    pub exchange: InstrumentExchange,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Instrument {
+    last_price => last_price_decimal -> Option<Decimal>,
+    strike => strike_decimal -> Option<Decimal>,
+    tick_size => tick_size_decimal -> Option<Decimal>,
+});
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum InstrumentType {
     EQ,
@@ -34,6 +88,206 @@ pub enum InstrumentType {
     PE,
 }
 
+/// Segment of an instrument, as listed in the instruments dump.
+///
+/// Segments without a variant are kept as [`Segment::Other`], [`Segment::as_str`] returns the
+/// segment as listed in either case.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[allow(non_camel_case_types)]
+pub enum Segment {
+    NSE,
+    BSE,
+    #[serde(rename = "NFO-FUT")]
+    NFO_FUT,
+    #[serde(rename = "NFO-OPT")]
+    NFO_OPT,
+    #[serde(rename = "BFO-FUT")]
+    BFO_FUT,
+    #[serde(rename = "BFO-OPT")]
+    BFO_OPT,
+    #[serde(rename = "CDS-FUT")]
+    CDS_FUT,
+    #[serde(rename = "CDS-OPT")]
+    CDS_OPT,
+    #[serde(rename = "BCD-FUT")]
+    BCD_FUT,
+    #[serde(rename = "BCD-OPT")]
+    BCD_OPT,
+    #[serde(rename = "MCX-FUT")]
+    MCX_FUT,
+    #[serde(rename = "MCX-OPT")]
+    MCX_OPT,
+    #[serde(rename = "NCO-FUT")]
+    NCO_FUT,
+    #[serde(rename = "NCO-OPT")]
+    NCO_OPT,
+    /// Indices of every exchange, they can't be traded
+    INDICES,
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl Segment {
+    /// Name of the segment, as listed in the instruments dump.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Segment::NSE => "NSE",
+            Segment::BSE => "BSE",
+            Segment::NFO_FUT => "NFO-FUT",
+            Segment::NFO_OPT => "NFO-OPT",
+            Segment::BFO_FUT => "BFO-FUT",
+            Segment::BFO_OPT => "BFO-OPT",
+            Segment::CDS_FUT => "CDS-FUT",
+            Segment::CDS_OPT => "CDS-OPT",
+            Segment::BCD_FUT => "BCD-FUT",
+            Segment::BCD_OPT => "BCD-OPT",
+            Segment::MCX_FUT => "MCX-FUT",
+            Segment::MCX_OPT => "MCX-OPT",
+            Segment::NCO_FUT => "NCO-FUT",
+            Segment::NCO_OPT => "NCO-OPT",
+            Segment::INDICES => "INDICES",
+            Segment::Other(segment) => segment,
+        }
+    }
+
+    /// Exchange the instruments of the segment are traded on, `None` for indices and unknown
+    /// segments.
+    pub const fn exchange(&self) -> Option<Exchange> {
+        match self {
+            Segment::NSE => Some(Exchange::NSE),
+            Segment::BSE => Some(Exchange::BSE),
+            Segment::NFO_FUT | Segment::NFO_OPT => Some(Exchange::NFO),
+            Segment::BFO_FUT | Segment::BFO_OPT => Some(Exchange::BFO),
+            Segment::CDS_FUT | Segment::CDS_OPT => Some(Exchange::CDS),
+            Segment::BCD_FUT | Segment::BCD_OPT => Some(Exchange::BCD),
+            Segment::MCX_FUT | Segment::MCX_OPT => Some(Exchange::MCX),
+            Segment::NCO_FUT | Segment::NCO_OPT => Some(Exchange::NCO),
+            Segment::INDICES | Segment::Other(_) => None,
+        }
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<&str> for Segment {
+    fn from(value: &str) -> Self {
+        use serde::de::value::{Error as ValueError, StrDeserializer};
+
+        Segment::deserialize(StrDeserializer::<ValueError>::new(value))
+            .unwrap_or_else(|_| Segment::Other(value.to_owned()))
+    }
+}
+
+/// Exchange of an instrument, as listed in the instruments dump.
+///
+/// Indices are listed either under their exchange or with the `INDICES` segment as a suffix, e.g.
+/// `NSE-INDICES`. Exchanges without an [`Exchange`] variant are kept as
+/// [`InstrumentExchange::Other`], [`InstrumentExchange::as_str`] returns the exchange as listed in
+/// every case.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(from = "String", into = "String")]
+pub enum InstrumentExchange {
+    /// An exchange, e.g. `NSE`
+    Exchange(Exchange),
+    /// Indices of an exchange, e.g. `NSE-INDICES`
+    Indices(Exchange),
+    Other(String),
+}
+
+impl InstrumentExchange {
+    /// Name of the exchange, as listed in the instruments dump.
+    pub fn as_str(&self) -> Cow<'_, str> {
+        match self {
+            InstrumentExchange::Exchange(exchange) => Cow::Borrowed(exchange.as_str()),
+            InstrumentExchange::Indices(exchange) => Cow::Owned(format!("{exchange}-INDICES")),
+            InstrumentExchange::Other(exchange) => Cow::Borrowed(exchange),
+        }
+    }
+
+    /// Exchange of the instrument, the exchange of indices included. `None` for exchanges
+    /// without an [`Exchange`] variant.
+    pub fn exchange(&self) -> Option<Exchange> {
+        match self {
+            InstrumentExchange::Exchange(exchange) | InstrumentExchange::Indices(exchange) => {
+                Some(*exchange)
+            }
+            InstrumentExchange::Other(_) => None,
+        }
+    }
+}
+
+impl Display for InstrumentExchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl PartialEq<Exchange> for InstrumentExchange {
+    fn eq(&self, other: &Exchange) -> bool {
+        *self == InstrumentExchange::Exchange(*other)
+    }
+}
+
+impl From<Exchange> for InstrumentExchange {
+    fn from(value: Exchange) -> Self {
+        InstrumentExchange::Exchange(value)
+    }
+}
+
+impl From<&str> for InstrumentExchange {
+    fn from(value: &str) -> Self {
+        use serde::de::value::{Error as ValueError, StrDeserializer};
+
+        let parse = |value| Exchange::deserialize(StrDeserializer::<ValueError>::new(value)).ok();
+        if let Some(exchange) = parse(value) {
+            return InstrumentExchange::Exchange(exchange);
+        }
+
+        match value.split_once('-') {
+            Some((exchange, "INDICES")) if let Some(exchange) = parse(exchange) => {
+                InstrumentExchange::Indices(exchange)
+            }
+            _ => InstrumentExchange::Other(value.to_owned()),
+        }
+    }
+}
+
+impl From<String> for InstrumentExchange {
+    fn from(value: String) -> Self {
+        match InstrumentExchange::from(value.as_str()) {
+            InstrumentExchange::Other(_) => InstrumentExchange::Other(value),
+            exchange => exchange,
+        }
+    }
+}
+
+impl From<InstrumentExchange> for String {
+    fn from(value: InstrumentExchange) -> Self {
+        match value {
+            InstrumentExchange::Other(exchange) => exchange,
+            exchange => exchange.as_str().into_owned(),
+        }
+    }
+}
+
+/// Segment of index instruments (NIFTY 50, SENSEX etc.) in the lowest byte of their instrument
+/// token.
+pub const INDICES_SEGMENT: u32 = 9;
+
+/// Returns whether `instrument_token` is an index, which can't be traded and has no volume, depth
+/// or open interest.
+pub const fn is_index(instrument_token: u32) -> bool {
+    instrument_token & 0xff == INDICES_SEGMENT
+}
+
+/// Full market quote of an instrument.
+///
+/// Indices (see [`Quote::is_index`]) only come with the prices, the fields that don't apply to
+/// them are `None`.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Quote {
     /// The numerical identifier issued by the exchange representing the instrument.
@@ -41,38 +295,85 @@ pub struct Quote {
     /// The exchange timestamp of the quote packet
     pub timestamp: String,
     /// Last trade timestamp
+    #[serde(default)]
     pub last_trade_time: Option<String>,
     /// Last traded market price
     pub last_price: f64,
     /// Volume traded today
-    pub volume: i64,
+    #[serde(default)]
+    pub volume: Option<i64>,
     /// The volume weighted average price of a stock at a given time during the day. [Learn More](https://support.zerodha.com/category/trading-and-markets/general-kite/kite-mw/articles/what-does-the-average-price-on-kite-3-market-depth-mean)
-    pub average_price: f64,
+    #[serde(default)]
+    pub average_price: Option<f64>,
     /// Total quantity of buy orders pending at the exchange
-    pub buy_quantity: i64,
+    #[serde(default)]
+    pub buy_quantity: Option<i64>,
     /// Total quantity of sell orders pending at the exchange
-    pub sell_quantity: i64,
+    #[serde(default)]
+    pub sell_quantity: Option<i64>,
     /// Total number of outstanding contracts held by market participants exchange-wide (only F&O)
     #[serde(default)]
     pub open_interest: Option<f64>,
     /// Last traded quantity
-    pub last_quantity: i64,
+    #[serde(default)]
+    pub last_quantity: Option<i64>,
     pub ohlc: Ohlc,
     /// The absolute change from yesterday's close to last traded price
     pub net_change: f64,
     /// The current lower circuit limit
-    pub lower_circuit_limit: f64,
+    #[serde(default)]
+    pub lower_circuit_limit: Option<f64>,
     /// The current upper circuit limit
-    pub upper_circuit_limit: f64,
+    #[serde(default)]
+    pub upper_circuit_limit: Option<f64>,
     /// The Open Interest for a futures or options contract. [Learn More](https://zerodha.com/varsity/chapter/open-interest/)
-    pub oi: f64,
+    #[serde(default)]
+    pub oi: Option<f64>,
     /// The highest Open Interest recorded during the day
-    pub oi_day_high: f64,
+    #[serde(default)]
+    pub oi_day_high: Option<f64>,
     /// The lowest Open Interest recorded during the day
-    pub oi_day_low: f64,
-    pub depth: DepthBook,
+    #[serde(default)]
+    pub oi_day_low: Option<f64>,
+    #[serde(default)]
+    pub depth: Option<DepthBook>,
+}
+
+impl Quote {
+    /// Returns whether the quote is of an index, see [`is_index`].
+    pub const fn is_index(&self) -> bool {
+        is_index(self.instrument_token)
+    }
+
+    /// Returns whether the quote comes with a market depth that has at least one non-empty
+    /// level, e.g. to decide whether to render the order book.
+    ///
+    /// `false` both for quotes without depth (indices) and for an order book with no pending
+    /// orders, which Kite sends as levels of zeros.
+    pub fn has_depth(&self) -> bool {
+        self.depth.as_ref().is_some_and(|depth| {
+            depth
+                .buy
+                .iter()
+                .chain(&depth.sell)
+                .any(|level| level.price != 0.0 || level.quantity != 0 || level.orders != 0)
+        })
+    }
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Quote {
+    last_price => last_price_decimal -> Option<Decimal>,
+    average_price => average_price_decimal -> Option<Decimal>,
+    open_interest => open_interest_decimal -> Option<Decimal>,
+    net_change => net_change_decimal -> Option<Decimal>,
+    lower_circuit_limit => lower_circuit_limit_decimal -> Option<Decimal>,
+    upper_circuit_limit => upper_circuit_limit_decimal -> Option<Decimal>,
+    oi => oi_decimal -> Option<Decimal>,
+    oi_day_high => oi_day_high_decimal -> Option<Decimal>,
+    oi_day_low => oi_day_low_decimal -> Option<Decimal>,
+});
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub struct OhlcQuote {
     /// The numerical identifier issued by the exchange representing the instrument.
@@ -82,6 +383,11 @@ pub struct OhlcQuote {
     pub ohlc: Ohlc,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(OhlcQuote {
+    last_price => last_price_decimal -> Option<Decimal>,
+});
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub struct LtpQuote {
     /// The numerical identifier issued by the exchange representing the instrument.
@@ -90,6 +396,11 @@ pub struct LtpQuote {
     pub last_price: f64,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(LtpQuote {
+    last_price => last_price_decimal -> Option<Decimal>,
+});
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub struct Ohlc {
     /// Price at market opening
@@ -102,6 +413,41 @@ pub struct Ohlc {
     pub close: f64,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Ohlc {
+    open => open_decimal -> Option<Decimal>,
+    high => high_decimal -> Option<Decimal>,
+    low => low_decimal -> Option<Decimal>,
+    close => close_decimal -> Option<Decimal>,
+});
+
+/// The `close` of a quote is the close of the previous trading day, not the last price. Build an
+/// `Ohlc` with the last price as `close` to classify the candle of the day so far, and use
+/// [`gap`] with the quote's `close` and `open` for the opening gap.
+impl Ohlc {
+    /// Whether the candle closed above its open.
+    pub fn is_bullish(&self) -> bool {
+        self.close > self.open
+    }
+
+    /// Whether the candle closed below its open. A doji, closing at its open, is neither bullish
+    /// nor bearish.
+    pub fn is_bearish(&self) -> bool {
+        self.close < self.open
+    }
+
+    /// Size of the body of the candle, `|close - open|`.
+    pub fn body(&self) -> f64 {
+        (self.close - self.open).abs()
+    }
+}
+
+/// Returns `open - prev_close`, positive for a gap up and negative for a gap down. Divide by
+/// `prev_close` for the gap as a fraction of the price.
+pub fn gap(prev_close: f64, open: f64) -> f64 {
+    open - prev_close
+}
+
 #[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct DepthBook {
     pub buy: Vec<Depth>,
@@ -131,94 +477,214 @@ pub struct Depth {
     pub quantity: i64,
 }
 
-impl KiteConnect<Authenticated> {
-    // TODO: Optimize this function performance
-    pub async fn get_all_instruments(&self) -> Result<Vec<Instrument>, Error> {
-        let bytes = self
-            .client
-            .get(GET_INSTRUMENTS_ENDPOINT)
-            // This is a large file, give it some extra time of 30 minutes
-            .timeout(std::time::Duration::from_secs(1800))
-            .send()
-            .await?
-            .bytes()
-            .await?;
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(Depth {
+    price => price_decimal -> Option<Decimal>,
+});
+
+/// Returns the quotes of `keys` in their order, keyed by [`QuoteKey`]'s `Display`.
+///
+/// A quote is looked up by its key and, for [`QuoteKey::Token`], by instrument token too. Keys
+/// without a quote, e.g. unknown instruments Kite leaves out, are skipped.
+pub fn order_quotes(keys: &[QuoteKey], quotes: &HashMap<String, Quote>) -> Vec<(String, Quote)> {
+    keys.iter()
+        .filter_map(|key| {
+            let name = key.to_string();
+            let quote = quotes.get(&name).or_else(|| match key {
+                QuoteKey::Token(token) => quotes.values().find(|q| q.instrument_token == *token),
+                QuoteKey::Symbol(..) => None,
+            })?;
+
+            Some((name, quote.clone()))
+        })
+        .collect()
+}
+
+/// Parses an instruments CSV dump, as returned by [`KiteConnect::get_all_instruments`].
+///
+/// Useful for loading a dump cached on disk instead of downloading it again. Instruments of
+/// exchanges without an [`Exchange`] variant are kept, with [`Instrument::exchange`] returning
+/// `None`.
+pub fn parse_instruments(csv: &[u8]) -> Result<Vec<Instrument>, Error> {
+    let mut parser = InstrumentsParser::default();
+    parser.push(csv)?;
+    parser.finish()
+}
+
+/// Parses an instruments CSV dump fed chunk by chunk, e.g. as it's downloaded.
+///
+/// Only the complete rows of the chunks received so far are parsed, a row cut between two chunks
+/// is kept until the rest of it arrives. The dump is never held in memory as a whole.
+#[derive(Default)]
+struct InstrumentsParser {
+    headers: Option<csv::StringRecord>,
+    /// Bytes of the rows not complete yet
+    pending: Vec<u8>,
+    /// Length of `pending` already scanned for the end of a row
+    scanned: usize,
+    /// Whether `pending` ends within a quoted field
+    in_quotes: bool,
+    instruments: Vec<Instrument>,
+}
+
+impl InstrumentsParser {
+    fn push(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        self.pending.extend_from_slice(chunk);
+
+        // A newline within quotes is part of a field, not the end of a row
+        let mut rows_end = None;
+        for (i, &byte) in self.pending.iter().enumerate().skip(self.scanned) {
+            match byte {
+                b'"' => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => rows_end = Some(i + 1),
+                _ => {}
+            }
+        }
+        self.scanned = self.pending.len();
+
+        if let Some(rows_end) = rows_end {
+            let rows: Vec<u8> = self.pending.drain(..rows_end).collect();
+            self.scanned -= rows_end;
+            self.parse_rows(&rows)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<Instrument>, Error> {
+        let rows = std::mem::take(&mut self.pending);
+        self.parse_rows(&rows)?;
+
+        Ok(self.instruments)
+    }
 
+    /// Parses complete rows, the first of which is the header if it hasn't been read yet.
+    fn parse_rows(&mut self, rows: &[u8]) -> Result<(), Error> {
         let mut rdr = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(bytes.as_ref());
+            .has_headers(self.headers.is_none())
+            .from_reader(rows);
+        let headers = match &self.headers {
+            Some(headers) => headers.clone(),
+            None => {
+                let headers = rdr.headers()?.clone();
+                self.headers = Some(headers.clone());
+                headers
+            }
+        };
 
-        let mut instruments = Vec::new();
-        for result in rdr.deserialize() {
-            let instrument: Instrument = result?;
-            instruments.push(instrument);
+        for record in rdr.records() {
+            let record = record?;
+            let instrument: Instrument = record.deserialize(Some(&headers))?;
+            self.instruments.push(instrument);
         }
 
-        Ok(instruments)
+        Ok(())
+    }
+}
+
+impl KiteConnect<Authenticated> {
+    /// Downloads the instruments of every exchange, parsing the dump as it arrives.
+    pub async fn get_all_instruments(&self) -> Result<Vec<Instrument>, Error> {
+        self.download_instruments(GET_INSTRUMENTS_ENDPOINT.to_owned())
+            .await
     }
 
     pub async fn get_exhchange_instruments(
         &self,
         exchange: Exchange,
     ) -> Result<Vec<Instrument>, Error> {
-        let bytes = self
-            .client
-            .get(format!("{GET_INSTRUMENTS_ENDPOINT}/{exchange}"))
-            // This is a large file, give it some extra time of 30 minutes
-            .timeout(std::time::Duration::from_secs(1800))
-            .send()
-            .await?
-            .bytes()
-            .await?;
+        self.download_instruments(format!("{GET_INSTRUMENTS_ENDPOINT}/{exchange}"))
+            .await
+    }
 
-        let mut rdr = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(bytes.as_ref());
+    /// Streams the instruments dump at `url` into an [`InstrumentsParser`], so that only a chunk
+    /// of the response is buffered at a time.
+    async fn download_instruments(&self, url: String) -> Result<Vec<Instrument>, Error> {
+        let mut res = self
+            .execute(
+                self.client
+                    .get(url)
+                    // This is a large file, give it some extra time of 30 minutes
+                    .timeout(std::time::Duration::from_secs(1800)),
+            )
+            .await?;
 
-        let mut instruments = Vec::new();
-        for result in rdr.deserialize() {
-            let instrument: Instrument = result?;
-            instruments.push(instrument);
+        let mut parser = InstrumentsParser::default();
+        while let Some(chunk) = self.cancellable(res.chunk()).await?? {
+            parser.push(&chunk)?;
         }
 
-        Ok(instruments)
+        parser.finish()
     }
 
-    pub async fn get_market_quotes<I: Serialize + Copy>(
+    pub async fn get_market_quotes<I: Serialize>(
         &self,
         i: &[I],
     ) -> Result<HashMap<String, Quote>, Error> {
         self.get_quotes_impl(i, GET_FULL_MARKET_QUOTES).await
     }
 
-    pub async fn get_ohlc_quotes<I: Serialize + Copy>(
+    /// Same as [`get_market_quotes`](Self::get_market_quotes), returning the quotes in the order
+    /// of `keys`, e.g. to render a watchlist.
+    ///
+    /// See [`order_quotes`] for how the quotes are matched to the keys.
+    pub async fn get_market_quotes_ordered(
+        &self,
+        keys: &[QuoteKey],
+    ) -> Result<Vec<(String, Quote)>, Error> {
+        Ok(order_quotes(keys, &self.get_market_quotes(keys).await?))
+    }
+
+    pub async fn get_ohlc_quotes<I: Serialize>(
         &self,
         i: &[I],
     ) -> Result<HashMap<String, OhlcQuote>, Error> {
         self.get_quotes_impl(i, GET_OHLC_QUOTES).await
     }
 
-    pub async fn get_ltp_quotes<I: Serialize + Copy>(&self, i: &[I]) -> Result<LtpQuote, Error> {
+    pub async fn get_ltp_quotes<I: Serialize>(
+        &self,
+        i: &[I],
+    ) -> Result<HashMap<String, LtpQuote>, Error> {
         self.get_quotes_impl(i, GET_LTP_QUOTES).await
     }
 
+    /// Fetches full quotes for `full` and LTPs for `ltp` concurrently.
+    ///
+    /// The two endpoints are rate limited separately, so this is as fast as the slower of the
+    /// two requests. Empty sets are not requested.
+    pub async fn get_mixed_quotes(
+        &self,
+        full: &[QuoteKey],
+        ltp: &[QuoteKey],
+    ) -> Result<MixedQuotes, Error> {
+        let full_quotes = async {
+            if full.is_empty() {
+                return Ok(HashMap::new());
+            }
+            self.get_market_quotes(full).await
+        };
+        let ltp_quotes = async {
+            if ltp.is_empty() {
+                return Ok(HashMap::new());
+            }
+            self.get_ltp_quotes(ltp).await
+        };
+
+        let (full, ltp) = futures_util::try_join!(full_quotes, ltp_quotes)?;
+
+        Ok(MixedQuotes { full, ltp })
+    }
+
     async fn get_quotes_impl<I, Q>(&self, i: &[I], endpoint: &'static str) -> Result<Q, Error>
     where
-        I: Serialize + Copy,
+        I: Serialize,
         Q: for<'de> serde::de::Deserialize<'de>,
     {
         // TODO: Is this a good to be done in this function?
-        let q: Vec<_> = i.iter().map(|&i| ("i", i)).collect();
+        let q: Vec<_> = i.iter().map(|i| ("i", i)).collect();
 
-        Ok(self
-            .client
-            .get(endpoint)
-            .query(&q)
-            .send()
-            .await?
-            .json::<Response<Q>>()
-            .await?
-            .into_result()?)
+        self.send::<Q>(self.client.get(endpoint).query(&q)).await
     }
 }
 
@@ -338,24 +804,24 @@ mod tests {
                 timestamp: "2021-06-08 15:45:56".into(),
                 last_trade_time: Some("2021-06-08 15:45:52".into()),
                 last_price: 1412.95,
-                last_quantity: 5,
-                buy_quantity: 0,
-                sell_quantity: 5191,
-                volume: 7360198,
-                average_price: 1412.47,
-                oi: 0.0,
-                oi_day_high: 0.0,
-                oi_day_low: 0.0,
+                last_quantity: Some(5),
+                buy_quantity: Some(0),
+                sell_quantity: Some(5191),
+                volume: Some(7360198),
+                average_price: Some(1412.47),
+                oi: Some(0.0),
+                oi_day_high: Some(0.0),
+                oi_day_low: Some(0.0),
                 net_change: 0.0,
-                lower_circuit_limit: 1250.7,
-                upper_circuit_limit: 1528.6,
+                lower_circuit_limit: Some(1250.7),
+                upper_circuit_limit: Some(1528.6),
                 ohlc: Ohlc {
                     open: 1396.0,
                     high: 1421.75,
                     low: 1395.55,
                     close: 1389.65,
                 },
-                depth: DepthBook {
+                depth: Some(DepthBook {
                     buy: vec![
                         Depth {
                             price: 0.0,
@@ -365,7 +831,7 @@ mod tests {
                         5
                     ],
                     sell: sell_depth,
-                },
+                }),
                 open_interest: None,
             },
         );
@@ -375,6 +841,198 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_index_full_quote() -> Result<(), Box<dyn std::error::Error>> {
+        let json = r#"{
+            "status": "success",
+            "data": {
+              "NSE:NIFTY 50": {
+                "instrument_token": 256265,
+                "timestamp": "2021-06-08 15:45:56",
+                "last_price": 15740.1,
+                "net_change": -11.55,
+                "ohlc": {
+                  "open": 15773.9,
+                  "high": 15778.8,
+                  "low": 15692.55,
+                  "close": 15751.65
+                }
+              }
+            }
+          }"#;
+
+        let quotes: HashMap<String, Quote> =
+            serde_json::from_str::<Response<_>>(json)?.into_result()?;
+        let quote = &quotes["NSE:NIFTY 50"];
+
+        assert!(quote.is_index());
+        assert!(!quote.has_depth());
+        assert_eq!(quote.last_price, 15740.1);
+        assert_eq!(quote.ohlc.close, 15751.65);
+        assert_eq!(quote.last_trade_time, None);
+        assert_eq!(quote.volume, None);
+        assert_eq!(quote.depth, None);
+        assert_eq!(quote.oi, None);
+
+        assert!(!is_index(408065));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_instruments() -> Result<(), Box<dyn std::error::Error>> {
+        let instruments = parse_instruments(
+            b"instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+256265,1001,NIFTY 50,NIFTY 50,0,,0,0,0,EQ,INDICES,NSE
+13238786,51714,NIFTY24DECFUT,NIFTY,0,2024-12-26,0,0.05,25,FUT,NFO-FUT,NFO
+11570946,45199,ALUMINIUM25JANFUT,ALUMINIUM,0,2025-01-31,0,0.05,5000,FUT,NCO-FUT,NCO
+138033412,3240,SILVERM25FEB90000CE,SILVERM,0,2025-02-25,90000,0.5,1,CE,MCX-SOPT,MCX
+",
+        )?;
+
+        let parsed: Vec<_> = instruments
+            .iter()
+            .map(|i| (i.exchange.clone(), i.segment.clone()))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                (Exchange::NSE.into(), Segment::NSE),
+                (Exchange::NSE.into(), Segment::INDICES),
+                (Exchange::NFO.into(), Segment::NFO_FUT),
+                (Exchange::NCO.into(), Segment::NCO_FUT),
+                (Exchange::MCX.into(), Segment::Other("MCX-SOPT".into())),
+            ]
+        );
+        assert_eq!(instruments[3].exchange, Exchange::NCO);
+        assert_eq!(instruments[2].segment.exchange(), Some(Exchange::NFO));
+        assert_eq!(instruments[1].segment.exchange(), None);
+        assert_eq!(instruments[4].segment.as_str(), "MCX-SOPT");
+        assert_eq!(Segment::from("NFO-OPT"), Segment::NFO_OPT);
+        assert_eq!(Segment::from("NCO-FUT"), Segment::NCO_FUT);
+        assert_eq!(Segment::from("NSE-SOPT"), Segment::Other("NSE-SOPT".into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_index_instruments() -> Result<(), Box<dyn std::error::Error>> {
+        let instruments = parse_instruments(
+            b"instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+256265,1001,NIFTY 50,NIFTY 50,0,,0,0,0,EQ,INDICES,NSE-INDICES
+265,1,SENSEX,SENSEX,0,,0,0,0,EQ,INDICES,BSE
+290825,1136,MCXCOMPDEX,MCXCOMPDEX,0,,0,0,0,EQ,INDICES,NCO-INDICES
+",
+        )?;
+
+        let parsed: Vec<_> = instruments
+            .iter()
+            .map(|i| {
+                (
+                    i.trading_symbol.as_str(),
+                    i.exchange.clone(),
+                    i.segment.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                (
+                    "NIFTY 50",
+                    InstrumentExchange::Indices(Exchange::NSE),
+                    Segment::INDICES
+                ),
+                ("SENSEX", Exchange::BSE.into(), Segment::INDICES),
+                (
+                    "MCXCOMPDEX",
+                    InstrumentExchange::Indices(Exchange::NCO),
+                    Segment::INDICES
+                ),
+            ]
+        );
+        assert_eq!(instruments[0].exchange.exchange(), Some(Exchange::NSE));
+        assert_eq!(instruments[2].exchange.as_str(), "NCO-INDICES");
+        assert_eq!(
+            InstrumentExchange::from("BSX-INDICES"),
+            InstrumentExchange::Other("BSX-INDICES".into())
+        );
+        assert_eq!(InstrumentExchange::from("INDICES").exchange(), None);
+
+        // Round trips as listed
+        let value = serde_json::to_value(&instruments[0])?;
+        assert_eq!(value["exchange"], "NSE-INDICES");
+        assert_eq!(serde_json::from_value::<Instrument>(value)?, instruments[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_instruments_in_chunks() -> Result<(), Box<dyn std::error::Error>> {
+        const ROWS: usize = 50_000;
+        const CHUNK: usize = 8 * 1024;
+
+        let mut csv = String::from(
+            "instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange\n",
+        );
+        // A quoted name with a comma and a newline, cut across chunks below
+        csv.push_str("1,1,ODD,\"ODD, \nNAME\",0,,0,0.05,1,EQ,NSE,NSE\n");
+        for i in 0..ROWS {
+            csv.push_str(&format!("{i},{i},SYM{i},NAME {i},0,,0,0.05,1,EQ,NSE,NSE\n"));
+        }
+        // No newline after the last row
+        csv.push_str("2,2,LAST,LAST,0,,0,0.05,1,EQ,NSE,NSE");
+
+        let mut parser = InstrumentsParser::default();
+        for chunk in csv.as_bytes().chunks(CHUNK) {
+            parser.push(chunk)?;
+            // Only the row cut by the chunk is kept
+            assert!(parser.pending.len() < 200, "{}", parser.pending.len());
+        }
+        let instruments = parser.finish()?;
+
+        assert_eq!(instruments.len(), ROWS + 2);
+        assert_eq!(instruments[0].name, "ODD, \nNAME");
+        assert_eq!(instruments[ROWS].trading_symbol, format!("SYM{}", ROWS - 1));
+        assert_eq!(instruments[ROWS + 1].trading_symbol, "LAST");
+
+        // Byte by byte, cutting every row and quoted field
+        let prefix = &csv.as_bytes()[..=csv[..2_000].rfind('\n').ok_or("no row")?];
+        let mut parser = InstrumentsParser::default();
+        for byte in prefix.chunks(1) {
+            parser.push(byte)?;
+        }
+        assert_eq!(parser.finish()?, parse_instruments(prefix)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ohlc_classification() {
+        let ohlc = |open, close| Ohlc {
+            open,
+            high: open.max(close) + 1.0,
+            low: open.min(close) - 1.0,
+            close,
+        };
+
+        let bullish = ohlc(100.0, 104.5);
+        assert!(bullish.is_bullish() && !bullish.is_bearish());
+        assert_eq!(bullish.body(), 4.5);
+
+        let bearish = ohlc(100.0, 97.0);
+        assert!(bearish.is_bearish() && !bearish.is_bullish());
+        assert_eq!(bearish.body(), 3.0);
+
+        let doji = ohlc(100.0, 100.0);
+        assert!(!doji.is_bullish() && !doji.is_bearish());
+        assert_eq!(doji.body(), 0.0);
+
+        assert_eq!(gap(100.0, 102.0), 2.0);
+        assert_eq!(gap(100.0, 98.5), -1.5);
+    }
+
     #[test]
     fn test_ohlc_quote() -> Result<(), Box<dyn std::error::Error>> {
         let json = r#"{
@@ -442,4 +1100,51 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_quote_decimal() -> Result<(), Box<dyn std::error::Error>> {
+        use std::str::FromStr;
+
+        let json = r#"{
+            "instrument_token": 408065,
+            "last_price": 1074.35,
+            "ohlc": {
+                "open": 1085.8,
+                "high": 1085.9,
+                "low": 1070.9,
+                "close": 1075.8
+            }
+        }"#;
+
+        let quote: OhlcQuote = serde_json::from_str(json)?;
+
+        assert_eq!(
+            quote.last_price_decimal(),
+            Some(Decimal::from_str("1074.35")?)
+        );
+        assert_eq!(
+            quote.ohlc.open_decimal(),
+            Some(Decimal::from_str("1085.8")?)
+        );
+        assert_eq!(quote.ohlc.low_decimal(), Some(Decimal::from_str("1070.9")?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_key() -> Result<(), Box<dyn std::error::Error>> {
+        let keys = [
+            QuoteKey::symbol(Exchange::NSE, "INFY"),
+            QuoteKey::from(408065),
+        ];
+
+        assert_eq!(keys[0].to_string(), "NSE:INFY");
+        assert_eq!(serde_json::to_string(&keys)?, r#"["NSE:INFY",408065]"#);
+
+        let q: Vec<_> = keys.iter().map(|i| ("i", i)).collect();
+        assert_eq!(serde_urlencoded::to_string(q)?, "i=NSE%3AINFY&i=408065");
+
+        Ok(())
+    }
 }
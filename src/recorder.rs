@@ -0,0 +1,369 @@
+//! Recording of the ticks of the WebSocket to a file, and their replay.
+//!
+//! A recording is a JSON Lines file: a header line with the [`RECORDING_FORMAT`] and its
+//! [`RECORDING_VERSION`], then one [`RecordedTick`] per line. [`TickRecorder`] writes it from the
+//! ticks of [`KiteConnect::web_socket`](crate::KiteConnect::web_socket), and [`TickReplayer`]
+//! sends them back on a channel, to run a strategy offline against a recorded session.
+//!
+//! A recording cut short, e.g. by a crash while writing, ends with an incomplete line. It's
+//! ignored when replaying, every complete line before it is still replayed.
+//!
+//! ```no_run
+//! # async fn run(kite: kite_connect::KiteConnect<kite_connect::Authenticated>) -> Result<(), kite_connect::Error> {
+//! use kite_connect::recorder::{Pacing, TickRecorder, TickReplayer};
+//! use std::fs::File;
+//! use std::io::{BufReader, BufWriter};
+//!
+//! let (_ticker, ticks) = kite.web_socket().await?;
+//! let mut recorder = TickRecorder::new(BufWriter::new(File::create("ticks.jsonl")?))?;
+//! std::thread::spawn(move || {
+//!     if let Err(e) = recorder.run(&ticks) {
+//!         eprintln!("Recording failed: {e}");
+//!     }
+//! });
+//!
+//! // Later, offline
+//! let replayer = TickReplayer::new(BufReader::new(File::open("ticks.jsonl")?))?
+//!     .with_pacing(Pacing::Original);
+//! let (tx, ticks) = crossbeam_channel::unbounded();
+//! std::thread::spawn(move || replayer.run(&tx).map_err(|e| e.to_string()));
+//!
+//! for tick in ticks {
+//!     println!("{tick:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crossbeam_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+use crate::ws::Ticker;
+
+/// Format in the header of every recording.
+pub const RECORDING_FORMAT: &str = "kite_connect/ticks";
+/// Latest version of the recording format, recordings of a later version can't be replayed.
+pub const RECORDING_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+}
+
+/// A tick of a recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTick {
+    /// When the tick was received, in milliseconds since the Unix epoch
+    pub received_at: u64,
+    pub tick: Ticker,
+}
+
+/// Writes ticks to a recording, see the [module documentation](self).
+#[derive(Debug)]
+pub struct TickRecorder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> TickRecorder<W> {
+    /// Starts a recording by writing its header to `writer`.
+    ///
+    /// The recorder writes a line per tick, wrap files in a [`BufWriter`](std::io::BufWriter).
+    pub fn new(mut writer: W) -> Result<Self, Error> {
+        let header = Header {
+            format: RECORDING_FORMAT.to_owned(),
+            version: RECORDING_VERSION,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+
+        Ok(Self { writer })
+    }
+
+    /// Records `tick` as received now.
+    pub fn record(&mut self, tick: &Ticker) -> Result<(), Error> {
+        self.record_at(tick, SystemTime::now())
+    }
+
+    /// Records `tick` as received at `received_at`.
+    pub fn record_at(&mut self, tick: &Ticker, received_at: SystemTime) -> Result<(), Error> {
+        let received_at = received_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+
+        // Serialized as a whole first, so a failing tick doesn't leave half a line behind
+        let mut line = serde_json::to_vec(&RecordedTickRef { received_at, tick })?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+
+        Ok(())
+    }
+
+    /// Records every tick received on `ticks`, until it's disconnected, then flushes the writer.
+    pub fn run(&mut self, ticks: &Receiver<Ticker>) -> Result<(), Error> {
+        for tick in ticks {
+            self.record(&tick)?;
+        }
+
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Returns the underlying writer, without flushing it.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[derive(Serialize)]
+struct RecordedTickRef<'a> {
+    received_at: u64,
+    tick: &'a Ticker,
+}
+
+/// How fast [`TickReplayer::run`] sends the ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pacing {
+    /// Without waiting between ticks
+    #[default]
+    AsFastAsPossible,
+    /// Waiting between ticks as long as between their original arrivals
+    Original,
+}
+
+/// Reads the ticks of a recording, see the [module documentation](self).
+///
+/// It's an iterator over the recorded ticks, which stops at the end of the recording or at an
+/// incomplete last line.
+#[derive(Debug)]
+pub struct TickReplayer<R: BufRead> {
+    reader: R,
+    pacing: Pacing,
+    line: String,
+}
+
+impl<R: BufRead> TickReplayer<R> {
+    /// Opens a recording by reading its header from `reader`.
+    ///
+    /// Returns an [`Error::IoError`] if `reader` doesn't start with the header of a recording, or
+    /// the recording is of a version later than [`RECORDING_VERSION`].
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let header = serde_json::from_str::<Header>(&line)
+            .ok()
+            .filter(|header| header.format == RECORDING_FORMAT)
+            .ok_or_else(|| invalid_data("Not a tick recording".to_owned()))?;
+        if header.version > RECORDING_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported version {} of the tick recording, expected at most {RECORDING_VERSION}",
+                header.version
+            )));
+        }
+
+        Ok(Self {
+            reader,
+            pacing: Pacing::default(),
+            line,
+        })
+    }
+
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    /// Sends every recorded tick to `ticks`, returning the number of ticks sent.
+    ///
+    /// Returns early once `ticks` has no receiver left, or on a line that isn't a recorded tick.
+    pub fn run(self, ticks: &Sender<Ticker>) -> Result<usize, Error> {
+        let pacing = self.pacing;
+        let mut previous: Option<u64> = None;
+        let mut sent = 0;
+
+        for recorded in self {
+            let recorded = recorded?;
+
+            if pacing == Pacing::Original
+                && let Some(previous) = previous
+            {
+                let wait = recorded.received_at.saturating_sub(previous);
+                std::thread::sleep(Duration::from_millis(wait));
+            }
+            previous = Some(recorded.received_at);
+
+            if ticks.send(recorded.tick).is_err() {
+                break;
+            }
+            sent += 1;
+        }
+
+        Ok(sent)
+    }
+}
+
+impl<R: BufRead> Iterator for TickReplayer<R> {
+    type Item = Result<RecordedTick, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.line.clear();
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => None,
+            Ok(_) if !self.line.ends_with('\n') => {
+                // Incomplete last line of a recording cut short, unless only the newline is missing
+                serde_json::from_str(&self.line).ok().map(Ok)
+            }
+            Ok(_) => Some(serde_json::from_str(&self.line).map_err(Error::from)),
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::IoError(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::LtpQuote;
+    use crate::quotes::Ohlc;
+    use crate::ws::{FullQuote, PartialQuote, TickerError};
+
+    fn ticks() -> Vec<Ticker> {
+        let quote = PartialQuote {
+            instrument_token: 408065,
+            last_price: 1500.5,
+            last_traded_quantity: 10,
+            average_traded_price: 1498.25,
+            volume_traded: 123456,
+            total_buy_quantity: 100,
+            total_sell_quantity: 200,
+            ohlc: Ohlc {
+                open: 1490.0,
+                high: 1510.0,
+                low: 1485.5,
+                close: 1495.0,
+            },
+        };
+
+        vec![
+            Ticker::LtpQuote(LtpQuote {
+                instrument_token: 256265,
+                last_price: 21500.25,
+            }),
+            Ticker::PartialQuote(quote),
+            Ticker::FullQuote(FullQuote {
+                quote,
+                last_trade_time: 1_704_253_500,
+                oi: 0,
+                oi_day_high: 0,
+                oi_day_low: 0,
+                exchange_timestamp: 1_704_253_501,
+                depth: Default::default(),
+            }),
+            Ticker::Error(TickerError::UnsupportedPacket { packet_len: 12 }),
+            Ticker::ConnectionClosed,
+        ]
+    }
+
+    fn record(ticks: &[Ticker], interval_ms: u64) -> Vec<u8> {
+        let mut recorder = TickRecorder::new(Vec::new()).unwrap();
+        for (i, tick) in ticks.iter().enumerate() {
+            let received_at = UNIX_EPOCH + Duration::from_millis(1_000 + i as u64 * interval_ms);
+            recorder.record_at(tick, received_at).unwrap();
+        }
+        recorder.into_inner()
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), Error> {
+        let recording = record(&ticks(), 250);
+
+        let recorded = TickReplayer::new(recording.as_slice())?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            recorded.iter().map(|r| r.received_at).collect::<Vec<_>>(),
+            [1000, 1250, 1500, 1750, 2000]
+        );
+        assert_eq!(
+            recorded.into_iter().map(|r| r.tick).collect::<Vec<_>>(),
+            ticks()
+        );
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        assert_eq!(TickReplayer::new(recording.as_slice())?.run(&tx)?, 5);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), ticks());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_original_pacing() -> Result<(), Error> {
+        let recording = record(&ticks()[..3], 40);
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let start = std::time::Instant::now();
+        let replayer = TickReplayer::new(recording.as_slice())?.with_pacing(Pacing::Original);
+        assert_eq!(replayer.run(&tx)?, 3);
+
+        assert!(start.elapsed() >= Duration::from_millis(80));
+        assert_eq!(rx.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_recording() -> Result<(), Error> {
+        let recording = record(&ticks(), 1);
+
+        // Cut in the middle of the last tick
+        let truncated = &recording[..recording.len() - 10];
+        let recorded = TickReplayer::new(truncated)?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(recorded.len(), 4);
+
+        // Only the final newline is missing
+        let truncated = &recording[..recording.len() - 1];
+        let recorded = TickReplayer::new(truncated)?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(recorded.len(), 5);
+
+        // Only the header
+        let header_len = recording.iter().position(|&b| b == b'\n').unwrap() + 1;
+        assert_eq!(TickReplayer::new(&recording[..header_len])?.count(), 0);
+
+        // A corrupted line in the middle is an error
+        let mut corrupted = recording.clone();
+        corrupted[header_len + 2] = b'#';
+        let mut replayer = TickReplayer::new(corrupted.as_slice())?;
+        assert!(matches!(replayer.next(), Some(Err(Error::Serde(_)))));
+        assert!(matches!(replayer.next(), Some(Ok(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recording_version() {
+        let open = |header: &str| TickReplayer::new(format!("{header}\n").as_bytes()).map(|_| ());
+
+        assert!(open(r#"{"format":"kite_connect/ticks","version":1}"#).is_ok());
+        assert!(matches!(
+            open(r#"{"format":"kite_connect/ticks","version":2}"#),
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::InvalidData
+        ));
+        assert!(matches!(
+            open(r#"{"format":"something/else","version":1}"#),
+            Err(Error::IoError(_))
+        ));
+        assert!(matches!(open(""), Err(Error::IoError(_))));
+    }
+}
@@ -1,4 +1,8 @@
-use serde::{Deserialize, Serialize};
+use reqwest::{Method, RequestBuilder, StatusCode, header::HeaderValue};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::time::Instant;
+
+use crate::{AuthStatus, Error, KITE_API_BASE_URL, KiteConnect, error_log::ErrorContext};
 
 /// Represents a generic API response that can either be a success or an error.
 ///
@@ -6,6 +10,9 @@ use serde::{Deserialize, Serialize};
 ///
 /// - `Success { data }`: Indicates a successful response containing the data of type `T`.
 /// - `Error { message, error_type }`: Indicates an error response with a message and an error type.
+///
+/// Endpoints with nothing to return respond with `"data": null`, deserialize them into
+/// `Response<()>` or `Response<Option<T>>`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 #[serde(tag = "status")]
@@ -40,6 +47,195 @@ impl<T> From<Response<T>> for Result<T, crate::KiteError> {
     }
 }
 
+impl<S: AuthStatus> KiteConnect<S> {
+    /// Sends the request and unwraps Kite's `{"status": ..., "data": ...}` envelope.
+    ///
+    /// This is the shared request path for every JSON endpoint, so any behaviour that has to apply
+    /// to all of them (e.g. error logging) lives here.
+    pub(crate) async fn send<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T, Error> {
+        self.send_tagged(req, None).await
+    }
+
+    /// Same as [`KiteConnect::send`], additionally attaching the order `tag` to logged errors.
+    pub(crate) async fn send_tagged<T: DeserializeOwned>(
+        &self,
+        req: RequestBuilder,
+        tag: Option<&str>,
+    ) -> Result<T, Error> {
+        let req = self.build_request(req)?;
+        let mut url = req.url().clone();
+        // Query parameters never carry credentials, but they add a lot of noise to log lines
+        url.set_query(None);
+        let endpoint = format!("{} {url}", req.method());
+        let ctx = ErrorContext::new(&endpoint, tag, &self.auth_info);
+        let start = Instant::now();
+
+        let invalidates_funds = changes_funds(req.method(), req.url().path());
+
+        let res = self.cancellable(self.dispatch(req)).await;
+        // Whatever the outcome, the request may have changed the funds
+        if invalidates_funds && let Some(cache) = &self.funds_cache {
+            cache.invalidate();
+        }
+        let res = match res.and_then(|res| res) {
+            Ok(res) => res,
+            Err(err) => {
+                ctx.log_http_failure(None, &err.to_string(), start.elapsed());
+                return Err(err);
+            }
+        };
+
+        let status = res.status();
+        let body = self
+            .cancellable(res.bytes())
+            .await
+            .and_then(|bytes| parse_response::<T>(status, &bytes?));
+        let body = match body {
+            Ok(body) => body,
+            Err(err) => {
+                ctx.log_http_failure(Some(status.as_u16()), &err.to_string(), start.elapsed());
+                return Err(err);
+            }
+        };
+
+        if let Response::Error {
+            message,
+            error_type,
+        } = &body
+        {
+            ctx.log_kite_error(status.as_u16(), error_type, message, start.elapsed());
+        }
+
+        Ok(body.into_result()?)
+    }
+
+    /// Sends the request without interpreting the body, for endpoints that don't respond with
+    /// JSON (e.g. the instruments CSV dump).
+    pub(crate) async fn execute(&self, req: RequestBuilder) -> Result<reqwest::Response, Error> {
+        let req = self.build_request(req)?;
+        self.cancellable(self.dispatch(req)).await?
+    }
+
+    /// Runs `fut` until the [cancellation token](KiteConnect::with_cancellation_token) of the
+    /// client is cancelled, returning [`Error::Cancelled`] if it is.
+    pub(crate) async fn cancellable<F: Future>(&self, fut: F) -> Result<F::Output, Error> {
+        match &self.cancellation_token {
+            Some(token) => token.run_until_cancelled(fut).await.ok_or(Error::Cancelled),
+            None => Ok(fut.await),
+        }
+    }
+
+    /// Sends the built request through the middleware client if one is set.
+    async fn dispatch(&self, req: reqwest::Request) -> Result<reqwest::Response, Error> {
+        #[cfg(feature = "middleware")]
+        if let Some(client) = &self.middleware {
+            let mut req = req;
+            let authentication_header =
+                Some(self.auth_info.authentication_header()).filter(|header| !header.is_empty());
+
+            for (name, value) in crate::utils::default_headers(authentication_header)? {
+                if let Some(name) = name {
+                    req.headers_mut().entry(name).or_insert(value);
+                }
+            }
+
+            return Ok(client.execute(req).await?);
+        }
+
+        Ok(self.client.execute(req).await?)
+    }
+
+    /// Builds the request, pointing it at the configured base URL and API version.
+    fn build_request(&self, req: RequestBuilder) -> Result<reqwest::Request, Error> {
+        let mut req = req.build()?;
+
+        if let Some(base_url) = &self.base_url
+            && let Some(path) = req.url().as_str().strip_prefix(KITE_API_BASE_URL)
+        {
+            *req.url_mut() = format!("{base_url}{path}")
+                .parse()
+                .map_err(|e| Error::Serde(Box::new(e)))?;
+        }
+
+        if let Some(api_version) = &self.api_version {
+            req.headers_mut()
+                .insert("X-Kite-Version", HeaderValue::from_str(api_version)?);
+        }
+
+        Ok(req)
+    }
+}
+
+/// Whether a `method` request to `path` may change the funds of the account: an order being
+/// placed, modified or cancelled, or a position being converted.
+fn changes_funds(method: &Method, path: &str) -> bool {
+    *method != Method::GET && (path.starts_with("/orders/") || path == "/portfolio/positions")
+}
+
+/// Maximum number of bytes of an unparsable body quoted in the error message.
+const MAX_QUOTED_BODY_LEN: usize = 200;
+
+/// Parses the body of a JSON endpoint, reconciling the HTTP status with the body's `status`.
+///
+/// A valid envelope is returned as is, whatever the HTTP status. Otherwise, an error body (e.g.
+/// `{"status": "error", "message": ...}` without an `error_type`) or the body of a failed request
+/// that isn't JSON at all (e.g. an HTML page from a proxy) becomes a [`Response::Error`]. Its
+/// `error_type` and `message` are taken from the body when present, and derived from the HTTP
+/// status otherwise, so a bare 403 is still reported as a `TokenException`.
+fn parse_response<T: DeserializeOwned>(
+    status: StatusCode,
+    body: &[u8],
+) -> Result<Response<T>, Error> {
+    let err = match serde_json::from_slice::<Response<T>>(body) {
+        Ok(res) => return Ok(res),
+        Err(err) => err,
+    };
+
+    let mut value = serde_json::from_slice::<serde_json::Value>(body).ok();
+
+    // A success without any `data` is read as `"data": null`, like the no-op responses
+    if let Some(value) = &mut value
+        && value["status"] == "success"
+        && value.get("data").is_none()
+    {
+        value["data"] = serde_json::Value::Null;
+        if let Ok(res) = Response::<T>::deserialize(&*value) {
+            return Ok(res);
+        }
+    }
+
+    let field = |name: &str| value.as_ref().and_then(|v| v[name].as_str());
+
+    if status.is_success() && field("status") != Some("error") {
+        return Err(err.into());
+    }
+
+    let error_type = field("error_type").unwrap_or(error_type_for_status(status));
+    let message = match field("message") {
+        Some(message) => message.to_owned(),
+        None => {
+            let body = String::from_utf8_lossy(body);
+            let body: String = body.trim().chars().take(MAX_QUOTED_BODY_LEN).collect();
+            format!("HTTP {status}: {body}")
+        }
+    };
+
+    Ok(Response::Error {
+        message,
+        error_type: error_type.to_owned(),
+    })
+}
+
+/// The `error_type` Kite uses for each HTTP status, see <https://kite.trade/docs/connect/v3/exceptions/>.
+fn error_type_for_status(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        400 => "InputException",
+        403 => "TokenException",
+        429 | 502 | 503 | 504 => "NetworkException",
+        _ => "GeneralException",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,6 +243,22 @@ mod tests {
     #[derive(Debug, Deserialize, PartialEq)]
     struct EmptyType {}
 
+    #[test]
+    fn test_changes_funds() {
+        for (method, path, expected) in [
+            (Method::POST, "/orders/regular", true),
+            (Method::PUT, "/orders/co/151220000000000", true),
+            (Method::DELETE, "/orders/amo/151220000000000", true),
+            (Method::PUT, "/portfolio/positions", true),
+            (Method::GET, "/orders/151220000000000", false),
+            (Method::POST, "/margins/orders", false),
+            (Method::POST, "/gtt/triggers", false),
+            (Method::DELETE, "/session/token", false),
+        ] {
+            assert_eq!(changes_funds(&method, path), expected, "{method} {path}");
+        }
+    }
+
     #[test]
     fn test_error_response() -> Result<(), Box<dyn std::error::Error>> {
         let err_str = r#"{
@@ -66,6 +278,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_into_result_keeps_error_type() -> Result<(), Box<dyn std::error::Error>> {
+        let err_str = r#"{
+            "status": "error",
+            "message": "Incorrect `api_key` or `access_token`.",
+            "error_type": "TokenException"
+            }"#;
+
+        let res: Response<EmptyType> = serde_json::from_str(err_str)?;
+        assert!(matches!(
+            res.into_result(),
+            Err(crate::KiteError::TokenException(message))
+                if message == "Incorrect `api_key` or `access_token`."
+        ));
+
+        let res: Response<EmptyType> = serde_json::from_str(
+            r#"{"status": "error", "message": "Insufficient funds", "error_type": "MarginException"}"#,
+        )?;
+        assert!(matches!(
+            res.into_result().map_err(Error::from),
+            Err(Error::KiteError(crate::KiteError::MarginException(_)))
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_success_response() -> Result<(), Box<dyn std::error::Error>> {
         let err_str = r#"{
@@ -80,4 +318,86 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_response_status_cross_check() -> Result<(), Box<dyn std::error::Error>> {
+        let error = |error_type: &str, message: &str| Response::<EmptyType>::Error {
+            message: message.to_string(),
+            error_type: error_type.to_string(),
+        };
+
+        // 403 with a valid error body
+        let body = br#"{"status": "error", "message": "Incorrect `api_key` or `access_token`.", "error_type": "TokenException"}"#;
+        assert_eq!(
+            parse_response::<EmptyType>(StatusCode::FORBIDDEN, body)?,
+            error("TokenException", "Incorrect `api_key` or `access_token`.")
+        );
+
+        // 200 with an error status but no error type
+        let body = br#"{"status": "error", "message": "Something went wrong"}"#;
+        assert_eq!(
+            parse_response::<EmptyType>(StatusCode::OK, body)?,
+            error("GeneralException", "Something went wrong")
+        );
+
+        // Error bodies that aren't JSON fall back to the HTTP status
+        assert_eq!(
+            parse_response::<EmptyType>(StatusCode::FORBIDDEN, b"<html>Forbidden</html>")?,
+            error(
+                "TokenException",
+                "HTTP 403 Forbidden: <html>Forbidden</html>"
+            )
+        );
+        assert_eq!(
+            parse_response::<EmptyType>(StatusCode::TOO_MANY_REQUESTS, b"")?,
+            error("NetworkException", "HTTP 429 Too Many Requests: ")
+        );
+
+        // A successful request with an unexpected body is a deserialization error
+        assert!(matches!(
+            parse_response::<EmptyType>(StatusCode::OK, b"<html></html>"),
+            Err(Error::Serde(_))
+        ));
+        assert_eq!(
+            parse_response::<EmptyType>(StatusCode::OK, br#"{"status": "success", "data": {}}"#)?,
+            Response::Success { data: EmptyType {} }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_data_response() -> Result<(), Box<dyn std::error::Error>> {
+        let body = br#"{"status": "success", "data": null}"#;
+
+        let res: Response<Option<EmptyType>> = serde_json::from_slice(body)?;
+        assert_eq!(res, Response::Success { data: None });
+        assert_eq!(
+            parse_response::<Option<EmptyType>>(StatusCode::OK, body)?,
+            Response::Success { data: None }
+        );
+        assert_eq!(
+            parse_response::<()>(StatusCode::OK, body)?,
+            Response::Success { data: () }
+        );
+
+        // A missing `data` is the same as null
+        let body = br#"{"status": "success"}"#;
+        assert_eq!(
+            parse_response::<()>(StatusCode::OK, body)?,
+            Response::Success { data: () }
+        );
+        assert_eq!(
+            parse_response::<Option<EmptyType>>(StatusCode::OK, body)?,
+            Response::Success { data: None }
+        );
+
+        // but not a valid value of every type
+        assert!(matches!(
+            parse_response::<bool>(StatusCode::OK, body),
+            Err(Error::Serde(_))
+        ));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,647 @@
+//! Paper trading against live prices, behind the `simulation` feature.
+//!
+//! [`SimulatedKite`] implements [`Trading`] like [`KiteConnect<Authenticated>`], but fills orders
+//! locally at the last traded price of a [`QuoteSource`]: REST polling through [`KiteConnect`] or
+//! the ticks collected in a [`TickStore`]. It tracks the resulting positions and their P&L, and
+//! estimates the charges with the [virtual contract note](crate::virtual_contract_note).
+//!
+//! Orders are matched as follows:
+//! - MARKET orders fill at the last price as soon as they're placed.
+//! - LIMIT orders fill at the last price once it's at or better than the limit price.
+//! - SL-M orders fill at the last price once it crosses the trigger price, SL orders additionally
+//!   wait for the last price to be at or better than their limit price.
+//!
+//! Pending orders are only matched when [`SimulatedKite::match_orders`] is called, e.g. on every
+//! tick. Orders for instruments without a price are rejected, there's no margin check.
+
+use chrono::DateTime;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::orders::{
+    Exchange, Order, OrderId, OrderStatus, OrderType, PlaceOrderRequest, PlacedOrder, Product,
+    TransactionType, Variety,
+};
+use crate::portfolio::{Position, Positions};
+use crate::quotes::QuoteKey;
+use crate::symbols::SymbolResolver;
+use crate::trading::Trading;
+use crate::utils::IST_OFFSET_SECS;
+use crate::virtual_contract_note::{OrderReq, VirtualContractNote, get_virtual_contract_note};
+use crate::ws::TickStore;
+use crate::{Authenticated, Error, KiteConnect, KiteError};
+
+/// Source of the last traded prices orders are filled at.
+pub trait QuoteSource {
+    /// Returns the last traded price of the instrument, or `None` if it isn't known.
+    fn last_price(&self, instrument_token: u32)
+    -> impl Future<Output = Result<Option<f64>, Error>>;
+}
+
+impl QuoteSource for KiteConnect<Authenticated> {
+    async fn last_price(&self, instrument_token: u32) -> Result<Option<f64>, Error> {
+        Ok(self
+            .get_ltp_quotes(&[QuoteKey::Token(instrument_token)])
+            .await?
+            .into_values()
+            .next()
+            .map(|q| q.last_price))
+    }
+}
+
+impl QuoteSource for TickStore {
+    async fn last_price(&self, instrument_token: u32) -> Result<Option<f64>, Error> {
+        Ok(TickStore::last_price(self, instrument_token))
+    }
+}
+
+impl<T: QuoteSource> QuoteSource for Arc<T> {
+    async fn last_price(&self, instrument_token: u32) -> Result<Option<f64>, Error> {
+        T::last_price(self, instrument_token).await
+    }
+}
+
+/// Paper trading client, see the [module documentation](self).
+#[derive(Debug)]
+pub struct SimulatedKite<Q> {
+    quotes: Q,
+    resolver: SymbolResolver,
+    state: Mutex<SimState>,
+}
+
+#[derive(Debug, Default)]
+struct SimState {
+    order_ids: u64,
+    orders: Vec<Order>,
+    positions: Vec<SimPosition>,
+}
+
+#[derive(Debug, Clone)]
+struct SimPosition {
+    trading_symbol: String,
+    exchange: Exchange,
+    instrument_token: u32,
+    product: Product,
+    buy_quantity: i64,
+    buy_value: f64,
+    sell_quantity: i64,
+    sell_value: f64,
+    last_price: f64,
+}
+
+impl<Q: QuoteSource> SimulatedKite<Q> {
+    /// Creates a simulator filling orders at the prices of `quotes`.
+    ///
+    /// `resolver` maps the trading symbols of orders to the instrument tokens of `quotes`.
+    pub fn new(quotes: Q, resolver: SymbolResolver) -> Self {
+        Self {
+            quotes,
+            resolver,
+            state: Mutex::default(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Places the order and fills it right away if it's marketable.
+    ///
+    /// Like Kite, an order rejected after validation still returns its order id, check its status
+    /// with [`SimulatedKite::get_orders`].
+    pub async fn place_order_poll(&self, req: &PlaceOrderRequest) -> Result<PlacedOrder, Error> {
+        req.validate()?;
+
+        let instrument_token = self
+            .resolver
+            .token_for(req.exchange, &req.trading_symbol)
+            .ok_or_else(|| {
+                Error::input_exception(format!(
+                    "Invalid `tradingsymbol` {}:{}",
+                    req.exchange, req.trading_symbol
+                ))
+            })?;
+        let last_price = self.quotes.last_price(instrument_token).await?;
+
+        let mut state = self.state();
+        state.order_ids += 1;
+        let mut order = new_order(
+            OrderId(format!("SIM{:012}", state.order_ids)),
+            req,
+            instrument_token,
+        );
+
+        match last_price {
+            Some(last_price) => state.try_fill(&mut order, last_price),
+            None => {
+                order.status = OrderStatus::Rejected;
+                order.pending_quantity = 0;
+                order.status_message = Some(format!(
+                    "No price available for {}:{}",
+                    req.exchange, req.trading_symbol
+                ));
+            }
+        }
+
+        let placed = PlacedOrder::new(order.order_id.clone(), req);
+        state.orders.push(order);
+
+        Ok(placed)
+    }
+
+    /// Fills the pending orders that became marketable at the current prices.
+    ///
+    /// Returns the ids of the filled orders.
+    pub async fn match_orders(&self) -> Result<Vec<OrderId>, Error> {
+        let mut tokens: Vec<u32> = self
+            .state()
+            .orders
+            .iter()
+            .filter(|o| o.status == OrderStatus::Open)
+            .filter_map(|o| o.instrument_token.parse().ok())
+            .collect();
+        tokens.sort_unstable();
+        tokens.dedup();
+
+        let mut prices = HashMap::with_capacity(tokens.len());
+        for token in tokens {
+            if let Some(price) = self.quotes.last_price(token).await? {
+                prices.insert(token.to_string(), price);
+            }
+        }
+
+        let mut state = self.state();
+        let mut orders = std::mem::take(&mut state.orders);
+        let mut filled = Vec::new();
+
+        for order in orders.iter_mut().filter(|o| o.status == OrderStatus::Open) {
+            if let Some(&last_price) = prices.get(&order.instrument_token) {
+                state.try_fill(order, last_price);
+                if order.status == OrderStatus::Complete {
+                    filled.push(order.order_id.clone());
+                }
+            }
+        }
+
+        state.orders = orders;
+        Ok(filled)
+    }
+
+    /// Cancels a pending order.
+    pub async fn cancel_order(&self, order_id: &OrderId, variety: &Variety) -> Result<(), Error> {
+        let _ = variety;
+        let mut state = self.state();
+
+        let order = state
+            .orders
+            .iter_mut()
+            .find(|o| &o.order_id == order_id)
+            .ok_or_else(|| Error::input_exception(format!("Couldn't find order {order_id}")))?;
+
+        if order.status != OrderStatus::Open {
+            return Err(KiteError::OrderException(format!(
+                "Order {order_id} can't be cancelled as it's {:?}",
+                order.status
+            ))
+            .into());
+        }
+
+        order.status = OrderStatus::Cancelled;
+        order.cancelled_quantity = order.pending_quantity;
+        order.pending_quantity = 0;
+
+        Ok(())
+    }
+
+    pub async fn get_orders(&self) -> Result<Vec<Order>, Error> {
+        Ok(self.state().orders.clone())
+    }
+
+    /// Returns the simulated positions, marked to the current prices.
+    ///
+    /// There's no carry forward, so the net and day positions are the same.
+    pub async fn get_positions(&self) -> Result<Positions, Error> {
+        let positions = self.state().positions.clone();
+
+        let mut net = Vec::with_capacity(positions.len());
+        for mut position in positions {
+            if let Some(price) = self.quotes.last_price(position.instrument_token).await? {
+                position.last_price = price;
+            }
+            net.push(position.to_position());
+        }
+
+        Ok(Positions {
+            day: net.clone(),
+            net,
+        })
+    }
+
+    /// Returns the total P&L of the simulated positions at the current prices, before charges.
+    pub async fn pnl(&self) -> Result<f64, Error> {
+        Ok(self.get_positions().await?.net.iter().map(|p| p.pnl).sum())
+    }
+
+    /// Returns the contract note of the quantity bought and sold back in the position.
+    ///
+    /// Returns `None` if there's no such position, or charges can't be computed for its exchange
+    /// and product (only NSE and BSE equity with CNC or MIS are supported).
+    pub fn contract_note(
+        &self,
+        exchange: Exchange,
+        trading_symbol: &str,
+        product: Product,
+    ) -> Option<VirtualContractNote> {
+        self.state()
+            .positions
+            .iter()
+            .find(|p| {
+                p.exchange == exchange && p.trading_symbol == trading_symbol && p.product == product
+            })
+            .and_then(SimPosition::contract_note)
+    }
+
+    /// Returns the estimated charges of every position's closed quantity.
+    pub fn charges(&self) -> f64 {
+        self.state()
+            .positions
+            .iter()
+            .filter_map(SimPosition::contract_note)
+            .map(|note| note.net_charges)
+            .sum()
+    }
+}
+
+impl<Q: QuoteSource> Trading for SimulatedKite<Q> {
+    async fn place_order_poll(&self, req: &PlaceOrderRequest) -> Result<PlacedOrder, Error> {
+        SimulatedKite::place_order_poll(self, req).await
+    }
+
+    async fn cancel_order(&self, order_id: &OrderId, variety: &Variety) -> Result<(), Error> {
+        SimulatedKite::cancel_order(self, order_id, variety).await
+    }
+
+    async fn get_orders(&self) -> Result<Vec<Order>, Error> {
+        SimulatedKite::get_orders(self).await
+    }
+
+    async fn get_positions(&self) -> Result<Positions, Error> {
+        SimulatedKite::get_positions(self).await
+    }
+}
+
+impl SimState {
+    /// Fills `order` at `last_price` if it's marketable, updating the position.
+    fn try_fill(&mut self, order: &mut Order, last_price: f64) {
+        if !is_marketable(order, last_price) {
+            return;
+        }
+
+        let quantity = order.pending_quantity;
+        order.status = OrderStatus::Complete;
+        order.average_price = Some(last_price);
+        order.filled_quantity = quantity;
+        order.pending_quantity = 0;
+
+        let token = order.instrument_token.parse().unwrap_or_default();
+        let position = match self.positions.iter_mut().position(|p| {
+            p.exchange == order.exchange
+                && p.trading_symbol == order.trading_symbol
+                && p.product == order.product
+        }) {
+            Some(i) => &mut self.positions[i],
+            None => {
+                self.positions.push(SimPosition {
+                    trading_symbol: order.trading_symbol.clone(),
+                    exchange: order.exchange,
+                    instrument_token: token,
+                    product: order.product,
+                    buy_quantity: 0,
+                    buy_value: 0.0,
+                    sell_quantity: 0,
+                    sell_value: 0.0,
+                    last_price,
+                });
+                self.positions.last_mut().expect("Just pushed")
+            }
+        };
+
+        let value = last_price * quantity as f64;
+        match order.transaction_type {
+            TransactionType::Buy => {
+                position.buy_quantity += quantity as i64;
+                position.buy_value += value;
+            }
+            TransactionType::Sell => {
+                position.sell_quantity += quantity as i64;
+                position.sell_value += value;
+            }
+        }
+        position.last_price = last_price;
+    }
+}
+
+fn is_marketable(order: &Order, last_price: f64) -> bool {
+    let buy = order.transaction_type == TransactionType::Buy;
+    let at_or_better = |price: Option<f64>| match price {
+        Some(price) if buy => last_price <= price,
+        Some(price) => last_price >= price,
+        None => false,
+    };
+    let triggered = match order.trigger_price {
+        Some(trigger) if buy => last_price >= trigger,
+        Some(trigger) => last_price <= trigger,
+        None => false,
+    };
+
+    match order.order_type {
+        OrderType::Market => true,
+        OrderType::Limit => at_or_better(order.price),
+        OrderType::SL => triggered && at_or_better(order.price),
+        OrderType::SL_M => triggered,
+    }
+}
+
+impl SimPosition {
+    fn to_position(&self) -> Position {
+        let quantity = self.buy_quantity - self.sell_quantity;
+        let avg = |value: f64, quantity: i64| {
+            if quantity == 0 {
+                0.0
+            } else {
+                value / quantity as f64
+            }
+        };
+        let buy_price = avg(self.buy_value, self.buy_quantity);
+        let sell_price = avg(self.sell_value, self.sell_quantity);
+
+        let value = self.sell_value - self.buy_value;
+        let pnl = value + quantity as f64 * self.last_price;
+        let closed = self.buy_quantity.min(self.sell_quantity);
+        let realised = closed as f64 * (sell_price - buy_price);
+
+        Position {
+            trading_symbol: self.trading_symbol.clone(),
+            exchange: self.exchange,
+            instrument_token: self.instrument_token,
+            product: self.product,
+            quantity,
+            overnight_quantity: 0,
+            multiplier: 1,
+            average_price: match quantity {
+                q if q > 0 => buy_price,
+                q if q < 0 => sell_price,
+                _ => 0.0,
+            },
+            close_price: 0.0,
+            last_price: self.last_price,
+            value,
+            pnl,
+            m2m: pnl,
+            unrealised: pnl - realised,
+            realised,
+            buy_quantity: self.buy_quantity,
+            buy_price,
+            buy_value: self.buy_value,
+            buy_m2m: self.buy_value,
+            day_buy_quantity: self.buy_quantity,
+            day_buy_price: buy_price,
+            day_buy_value: self.buy_value,
+            sell_quantity: self.sell_quantity,
+            sell_price,
+            sell_value: self.sell_value,
+            sell_m2m: self.sell_value,
+            day_sell_quantity: self.sell_quantity,
+            day_sell_price: sell_price,
+            day_sell_value: self.sell_value,
+        }
+    }
+
+    fn contract_note(&self) -> Option<VirtualContractNote> {
+        let closed = self.buy_quantity.min(self.sell_quantity);
+        let supported = matches!(self.exchange, Exchange::NSE | Exchange::BSE)
+            && matches!(self.product, Product::CNC | Product::MIS);
+        if closed == 0 || !supported {
+            return None;
+        }
+
+        let position = self.to_position();
+        Some(get_virtual_contract_note(&OrderReq {
+            exchange: self.exchange,
+            product: self.product,
+            quantity: closed,
+            buy: position.buy_price,
+            sell: position.sell_price,
+        }))
+    }
+}
+
+fn new_order(order_id: OrderId, req: &PlaceOrderRequest, instrument_token: u32) -> Order {
+    Order {
+        order_id,
+        parent_order_id: None,
+        exchange_order_id: None,
+        modified: false,
+        placed_by: "SIMULATED".to_string(),
+        variety: req.variety.clone(),
+        status: OrderStatus::Open,
+        trading_symbol: req.trading_symbol.clone(),
+        exchange: req.exchange,
+        instrument_token: instrument_token.to_string(),
+        transaction_type: req.transaction_type,
+        order_type: req.order_type,
+        product: req.product,
+        validity: req.validity,
+        validity_ttl: req.validity_ttl,
+        price: req.price,
+        quantity: req.quantity,
+        trigger_price: req.trigger_price,
+        average_price: None,
+        pending_quantity: req.quantity,
+        filled_quantity: 0,
+        disclosed_quantity: req.disclosed_quantity,
+        order_timestamp: now_ist(),
+        exchange_timestamp: None,
+        exchange_update_timestamp: None,
+        status_message: None,
+        status_message_raw: None,
+        cancelled_quantity: 0,
+        auction_number: req.auction_number.clone(),
+        market_protection: req.market_protection.map(f64::from),
+        // Like Kite, the idempotency key is the tag of the order
+        tag: req.tag.clone().or_else(|| req.idempotency_key.clone()),
+        guid: String::new(),
+        meta: None,
+        extra: HashMap::new(),
+    }
+}
+
+/// The current time in the `YYYY-MM-DD HH:MM:SS` IST format of order timestamps.
+fn now_ist() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+
+    DateTime::from_timestamp(now + IST_OFFSET_SECS, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::{LtpQuote, parse_instruments};
+    use crate::ws::Ticker;
+
+    const INSTRUMENTS_CSV: &str = "instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+884737,3456,TATAMOTORS,TATA MOTORS,0,,0,0.05,1,EQ,NSE,NSE
+";
+
+    fn simulator() -> SimulatedKite<Arc<TickStore>> {
+        let instruments = parse_instruments(INSTRUMENTS_CSV.as_bytes()).unwrap();
+        SimulatedKite::new(
+            Arc::new(TickStore::new()),
+            SymbolResolver::new(&instruments),
+        )
+    }
+
+    fn tick(kite: &SimulatedKite<Arc<TickStore>>, instrument_token: u32, last_price: f64) {
+        kite.quotes.update(&Ticker::LtpQuote(LtpQuote {
+            instrument_token,
+            last_price,
+        }));
+    }
+
+    fn order(
+        transaction_type: TransactionType,
+        order_type: OrderType,
+        quantity: u32,
+        price: Option<f64>,
+    ) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            transaction_type,
+            order_type,
+            quantity,
+            product: Product::MIS,
+            price,
+            ..crate::test_utils::market_order()
+        }
+    }
+
+    async fn status_of<T: Trading>(kite: &T, order_id: &OrderId) -> OrderStatus {
+        let orders = kite.get_orders().await.unwrap();
+        orders
+            .into_iter()
+            .find(|o| &o.order_id == order_id)
+            .map(|o| o.status)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_market_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let kite = simulator();
+
+        tick(&kite, 408065, 1000.0);
+        let buy = kite
+            .place_order_poll(&order(TransactionType::Buy, OrderType::Market, 400, None))
+            .await?
+            .order_id;
+        assert_eq!(status_of(&kite, &buy).await, OrderStatus::Complete);
+
+        tick(&kite, 408065, 1050.0);
+        let position = &kite.get_positions().await?.net[0];
+        assert_eq!(position.quantity, 400);
+        assert_eq!(position.unrealised, 20000.0);
+        // Nothing closed yet, so nothing charged
+        assert_eq!(kite.charges(), 0.0);
+
+        tick(&kite, 408065, 1100.0);
+        kite.place_order_poll(&order(TransactionType::Sell, OrderType::Market, 400, None))
+            .await?;
+
+        let position = &kite.get_positions().await?.net[0];
+        assert_eq!(position.quantity, 0);
+        assert_eq!(position.realised, 40000.0);
+        assert_eq!(kite.pnl().await?, 40000.0);
+
+        let note = kite
+            .contract_note(Exchange::NSE, "INFY", Product::MIS)
+            .unwrap();
+        assert_eq!(note.pnl, 40000.0);
+        assert_eq!(note.brokerage, 40.0);
+        assert_eq!(kite.charges(), note.net_charges);
+        assert!(note.net_pnl < note.pnl);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pending_orders() -> Result<(), Box<dyn std::error::Error>> {
+        let kite = simulator();
+        tick(&kite, 408065, 1000.0);
+
+        let limit = kite
+            .place_order_poll(&order(
+                TransactionType::Buy,
+                OrderType::Limit,
+                10,
+                Some(990.0),
+            ))
+            .await?
+            .order_id;
+        let mut stop = order(TransactionType::Sell, OrderType::SL_M, 10, None);
+        stop.trigger_price = Some(950.0);
+        let stop = kite.place_order_poll(&stop).await?.order_id;
+
+        tick(&kite, 408065, 995.0);
+        assert!(kite.match_orders().await?.is_empty());
+
+        tick(&kite, 408065, 989.5);
+        assert_eq!(kite.match_orders().await?, vec![limit.clone()]);
+        assert_eq!(kite.get_positions().await?.net[0].average_price, 989.5);
+
+        kite.cancel_order(&stop, &Variety::Regular).await?;
+        assert_eq!(status_of(&kite, &stop).await, OrderStatus::Cancelled);
+
+        tick(&kite, 408065, 900.0);
+        assert!(kite.match_orders().await?.is_empty());
+        assert!(matches!(
+            kite.cancel_order(&limit, &Variety::Regular).await,
+            Err(Error::KiteError(KiteError::OrderException(_)))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejections() -> Result<(), Box<dyn std::error::Error>> {
+        let kite = simulator();
+
+        // No tick received yet
+        let mut req = order(TransactionType::Buy, OrderType::Market, 1, None);
+        req.trading_symbol = "TATAMOTORS".into();
+        let placed = kite
+            .place_order_poll(&req.clone().with_idempotency_key())
+            .await?;
+        assert_eq!(
+            status_of(&kite, &placed.order_id).await,
+            OrderStatus::Rejected
+        );
+        // The key is the tag of the order, as with Kite
+        let orders = kite.get_orders().await?;
+        assert!(placed.idempotency_key.is_some());
+        assert_eq!(orders[0].tag, placed.idempotency_key);
+
+        req.trading_symbol = "UNKNOWN".into();
+        assert!(matches!(
+            kite.place_order_poll(&req).await,
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+        assert!(kite.get_positions().await?.net.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+//! Position sizing from the available funds and the risk taken per trade.
+
+use crate::Error;
+use crate::orders::TransactionType;
+use crate::user::funds::SegmentFunds;
+
+/// Why [`position_size`] sized a position to zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZeroSizeReason {
+    /// The live balance of the segment isn't positive
+    NoAvailableFunds,
+    /// A single lot risks more than allowed
+    BelowOneLot {
+        /// Loss of a single lot if the stop-loss is hit
+        lot_risk: f64,
+        /// Loss allowed by the risk percentage
+        max_risk: f64,
+    },
+}
+
+/// Quantity to trade, see [`position_size`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizedPosition {
+    /// BUY if the stop-loss is below the entry, SELL if it's above
+    pub transaction_type: TransactionType,
+    /// Quantity to trade, a multiple of the lot size
+    pub quantity: u32,
+    /// Loss if the stop-loss is hit, `|entry - stop| * quantity`
+    pub risk: f64,
+    /// Value of the position over the live balance, `entry * quantity / live_balance`
+    ///
+    /// This is an estimate of the margin used by a product without leverage (CNC or NRML on
+    /// equity). Leveraged products and derivatives block less, get the exact figure from the
+    /// margin calculator of Kite before placing the order.
+    pub margin_utilisation: f64,
+    /// Set when `quantity` is zero
+    pub zero_size_reason: Option<ZeroSizeReason>,
+}
+
+/// Sizes a position so that hitting the stop-loss loses at most `risk_pct` of the live balance
+/// of `funds` (`1.0` is 1%).
+///
+/// The quantity is rounded down to a multiple of `lot_size`, use `1` for equity. A stop-loss
+/// above `entry` sizes a short position. If a single lot risks too much, or there are no funds
+/// available, the quantity is zero and [`SizedPosition::zero_size_reason`] says why.
+///
+/// Returns an [`KiteError::InputException`](crate::KiteError::InputException) if the prices
+/// aren't positive, `stop` equals `entry`, `risk_pct` isn't a positive percentage or `lot_size`
+/// is zero.
+pub fn position_size(
+    funds: &SegmentFunds,
+    entry: f64,
+    stop: f64,
+    risk_pct: f64,
+    lot_size: u32,
+) -> Result<SizedPosition, Error> {
+    for (name, price) in [("entry", entry), ("stop", stop)] {
+        if !(price.is_finite() && price > 0.0) {
+            return Err(Error::input_exception(format!(
+                "Invalid {name} price {price}"
+            )));
+        }
+    }
+    if entry == stop {
+        return Err(Error::input_exception(format!(
+            "Stop-loss {stop} is equal to the entry price"
+        )));
+    }
+    if !(risk_pct.is_finite() && risk_pct > 0.0 && risk_pct <= 100.0) {
+        return Err(Error::input_exception(format!(
+            "`risk_pct` must be a percentage above 0, got {risk_pct}"
+        )));
+    }
+    if lot_size == 0 {
+        return Err(Error::input_exception("Lot size must be at least 1"));
+    }
+
+    let transaction_type = if stop < entry {
+        TransactionType::Buy
+    } else {
+        TransactionType::Sell
+    };
+    let live_balance = funds.available.live_balance;
+    let risk_per_unit = (entry - stop).abs();
+
+    let zero = |reason| SizedPosition {
+        transaction_type,
+        quantity: 0,
+        risk: 0.0,
+        margin_utilisation: 0.0,
+        zero_size_reason: Some(reason),
+    };
+
+    if live_balance.is_nan() || live_balance <= 0.0 {
+        return Ok(zero(ZeroSizeReason::NoAvailableFunds));
+    }
+
+    let max_risk = live_balance * risk_pct / 100.0;
+    let lot_risk = risk_per_unit * lot_size as f64;
+    // Capped on absurdly tight stops rather than overflowing
+    let lots = (max_risk / lot_risk)
+        .floor()
+        .min((u32::MAX / lot_size) as f64) as u32;
+    let quantity = lots * lot_size;
+    if quantity == 0 {
+        return Ok(zero(ZeroSizeReason::BelowOneLot { lot_risk, max_risk }));
+    }
+
+    Ok(SizedPosition {
+        transaction_type,
+        quantity,
+        risk: risk_per_unit * quantity as f64,
+        margin_utilisation: entry * quantity as f64 / live_balance,
+        zero_size_reason: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::funds::{AvailableFunds, UtilisedFunds};
+    use crate::{Error, KiteError};
+
+    fn funds(live_balance: f64) -> SegmentFunds {
+        SegmentFunds {
+            enabled: true,
+            net: live_balance,
+            available: AvailableFunds {
+                cash: live_balance,
+                opening_balance: live_balance,
+                live_balance,
+                intraday_payin: 0.0,
+                adhoc_margin: 0.0,
+                collateral: 0.0,
+            },
+            utilised: UtilisedFunds {
+                unrealised: 0.0,
+                realised: 0.0,
+                debits: 0.0,
+                span: 0.0,
+                option_premium: 0.0,
+                holding_sales: 0.0,
+                exposure: 0.0,
+                liquid_collateral: 0.0,
+                delivery: 0.0,
+                stock_collateral: 0.0,
+                turnover: 0.0,
+                payout: 0.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_long_and_short() -> Result<(), Error> {
+        // 1% of 1,00,000 is 1000, risking 7 per share
+        let long = position_size(&funds(100_000.0), 100.0, 93.0, 1.0, 1)?;
+        assert_eq!(long.transaction_type, TransactionType::Buy);
+        assert_eq!(long.quantity, 142);
+        assert_eq!(long.risk, 994.0);
+        assert_eq!(long.margin_utilisation, 0.142);
+        assert_eq!(long.zero_size_reason, None);
+
+        let short = position_size(&funds(100_000.0), 100.0, 107.0, 1.0, 1)?;
+        assert_eq!(short.transaction_type, TransactionType::Sell);
+        assert_eq!(short.quantity, 142);
+
+        // Rounded down to lots of 25
+        let lots = position_size(&funds(100_000.0), 100.0, 93.0, 1.0, 25)?;
+        assert_eq!(lots.quantity, 125);
+        assert_eq!(lots.risk, 875.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_size() -> Result<(), Error> {
+        let sized = position_size(&funds(100_000.0), 100.0, 90.0, 1.0, 150)?;
+        assert_eq!(sized.quantity, 0);
+        assert_eq!(
+            sized.zero_size_reason,
+            Some(ZeroSizeReason::BelowOneLot {
+                lot_risk: 1500.0,
+                max_risk: 1000.0
+            })
+        );
+
+        let sized = position_size(&funds(-250.0), 100.0, 90.0, 1.0, 1)?;
+        assert_eq!(sized.quantity, 0);
+        assert_eq!(
+            sized.zero_size_reason,
+            Some(ZeroSizeReason::NoAvailableFunds)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        let funds = funds(100_000.0);
+
+        for (entry, stop, risk_pct, lot_size) in [
+            (100.0, 100.0, 1.0, 1),
+            (100.0, 0.0, 1.0, 1),
+            (f64::NAN, 90.0, 1.0, 1),
+            (100.0, 90.0, 0.0, 1),
+            (100.0, 90.0, 101.0, 1),
+            (100.0, 90.0, 1.0, 0),
+        ] {
+            assert!(
+                matches!(
+                    position_size(&funds, entry, stop, risk_pct, lot_size),
+                    Err(Error::KiteError(KiteError::InputException(_)))
+                ),
+                "{entry} {stop} {risk_pct} {lot_size}"
+            );
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Mapping between `EXCHANGE:TRADINGSYMBOL` and instrument tokens.
+
+use std::collections::HashMap;
+
+use crate::orders::Exchange;
+use crate::quotes::Instrument;
+
+/// Resolves `EXCHANGE:TRADINGSYMBOL` strings to instrument tokens and back.
+///
+/// Built from an instruments dump (see [`KiteConnect::get_all_instruments`](crate::KiteConnect::get_all_instruments)
+/// and [`parse_instruments`](crate::quotes::parse_instruments)). Lookups ignore case and surrounding
+/// whitespace.
+#[derive(Debug, Default, Clone)]
+pub struct SymbolResolver {
+    tokens: HashMap<String, u32>,
+    symbols: HashMap<u32, String>,
+}
+
+/// Result of a batch lookup on [`SymbolResolver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved<T, I> {
+    /// Successfully resolved values, in input order
+    pub resolved: Vec<T>,
+    /// Inputs that couldn't be resolved, in input order
+    pub unresolved: Vec<I>,
+}
+
+impl SymbolResolver {
+    pub fn new(instruments: &[Instrument]) -> Self {
+        let mut tokens = HashMap::with_capacity(instruments.len());
+        let mut symbols = HashMap::with_capacity(instruments.len());
+
+        for instrument in instruments {
+            let symbol = format!("{}:{}", instrument.exchange, instrument.trading_symbol);
+            tokens.insert(normalize(&symbol), instrument.instrument_token);
+            symbols.insert(instrument.instrument_token, symbol);
+        }
+
+        Self { tokens, symbols }
+    }
+
+    /// Returns the number of instruments known to the resolver.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns the instrument token of `trading_symbol` on `exchange`.
+    pub fn token_for(&self, exchange: Exchange, trading_symbol: &str) -> Option<u32> {
+        self.resolve(&format!("{exchange}:{}", trading_symbol.trim()))
+    }
+
+    /// Returns the instrument token for an `EXCHANGE:TRADINGSYMBOL` string, e.g. `"NSE:INFY"`.
+    pub fn resolve(&self, symbol: &str) -> Option<u32> {
+        self.tokens.get(&normalize(symbol)).copied()
+    }
+
+    /// Returns the `EXCHANGE:TRADINGSYMBOL` string of `instrument_token`.
+    pub fn symbol_for(&self, instrument_token: u32) -> Option<&str> {
+        self.symbols.get(&instrument_token).map(String::as_str)
+    }
+
+    /// Resolves every `EXCHANGE:TRADINGSYMBOL` in `symbols`, collecting the ones that are unknown.
+    pub fn tokens_for<'a, S: AsRef<str>>(&self, symbols: &'a [S]) -> Resolved<u32, &'a str> {
+        let mut res = Resolved {
+            resolved: Vec::with_capacity(symbols.len()),
+            unresolved: Vec::new(),
+        };
+
+        for symbol in symbols {
+            match self.resolve(symbol.as_ref()) {
+                Some(token) => res.resolved.push(token),
+                None => res.unresolved.push(symbol.as_ref()),
+            }
+        }
+
+        res
+    }
+
+    /// Looks up the symbol of every token in `instrument_tokens`, collecting the ones that are
+    /// unknown.
+    pub fn symbols_for(&self, instrument_tokens: &[u32]) -> Resolved<&str, u32> {
+        let mut res = Resolved {
+            resolved: Vec::with_capacity(instrument_tokens.len()),
+            unresolved: Vec::new(),
+        };
+
+        for &token in instrument_tokens {
+            match self.symbol_for(token) {
+                Some(symbol) => res.resolved.push(symbol),
+                None => res.unresolved.push(token),
+            }
+        }
+
+        res
+    }
+}
+
+impl FromIterator<Instrument> for SymbolResolver {
+    fn from_iter<T: IntoIterator<Item = Instrument>>(iter: T) -> Self {
+        Self::new(&iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+/// Instruments indexed by instrument token, e.g. to show the symbol of WebSocket ticks.
+///
+/// Built from an instruments dump like [`SymbolResolver`], but keeps the whole [`Instrument`].
+#[derive(Debug, Default, Clone)]
+pub struct InstrumentIndex {
+    instruments: Vec<Instrument>,
+    by_token: HashMap<u32, usize>,
+}
+
+impl InstrumentIndex {
+    pub fn new(instruments: Vec<Instrument>) -> Self {
+        let by_token = instruments
+            .iter()
+            .enumerate()
+            .map(|(i, instrument)| (instrument.instrument_token, i))
+            .collect();
+
+        Self {
+            instruments,
+            by_token,
+        }
+    }
+
+    /// Returns the number of instruments in the index.
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    /// Returns the instrument with `instrument_token`.
+    pub fn get(&self, instrument_token: u32) -> Option<&Instrument> {
+        self.by_token
+            .get(&instrument_token)
+            .map(|&i| &self.instruments[i])
+    }
+
+    /// Returns the exchange and trading symbol of `instrument_token`.
+    ///
+    /// Returns `None` for unknown tokens and instruments of exchanges without an [`Exchange`]
+    /// variant.
+    pub fn symbol_for(&self, instrument_token: u32) -> Option<(Exchange, &str)> {
+        let instrument = self.get(instrument_token)?;
+
+        Some((instrument.exchange.exchange()?, &instrument.trading_symbol))
+    }
+
+    /// Returns every instrument, in the order of the dump.
+    pub fn instruments(&self) -> &[Instrument] {
+        &self.instruments
+    }
+}
+
+impl FromIterator<Instrument> for InstrumentIndex {
+    fn from_iter<T: IntoIterator<Item = Instrument>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+fn normalize(symbol: &str) -> String {
+    match symbol.split_once(':') {
+        Some((exchange, trading_symbol)) => format!(
+            "{}:{}",
+            exchange.trim().to_uppercase(),
+            trading_symbol.trim().to_uppercase()
+        ),
+        None => symbol.trim().to_uppercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::{InstrumentExchange, Segment, parse_instruments};
+
+    const INSTRUMENTS_CSV: &str = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+128053508,500209,INFY,INFOSYS,0,,0,0.05,1,EQ,BSE,BSE
+779521,3045,SBIN,STATE BANK OF INDIA,0,,0,0.05,1,EQ,NSE,NSE
+256265,1001,NIFTY 50,NIFTY 50,0,,0,0,0,EQ,INDICES,NSE
+13238786,51714,NIFTY24DECFUT,NIFTY,0,2024-12-26,0,0.05,25,FUT,NFO-FUT,NFO
+11570946,45199,ALUMINIUM25JANFUT,ALUMINIUM,0,2025-01-31,0,0.05,5000,FUT,NCO-FUT,NCO
+5387276,21044,GIFTNIFTY,GIFT NIFTY,0,,0,0.5,1,EQ,NSEIX,NSEIX
+";
+
+    #[test]
+    fn test_symbol_resolver() -> Result<(), Box<dyn std::error::Error>> {
+        let resolver = SymbolResolver::new(&parse_instruments(INSTRUMENTS_CSV.as_bytes())?);
+
+        assert_eq!(resolver.len(), 7);
+        assert_eq!(resolver.resolve("NSE:INFY"), Some(408065));
+        assert_eq!(resolver.resolve("NCO:ALUMINIUM25JANFUT"), Some(11570946));
+        assert_eq!(resolver.resolve("NSEIX:GIFTNIFTY"), Some(5387276));
+        assert_eq!(resolver.resolve(" bse : infy "), Some(128053508));
+        assert_eq!(resolver.resolve("NSE:NIFTY 50"), Some(256265));
+        assert_eq!(
+            resolver.token_for(Exchange::NFO, "nifty24decfut"),
+            Some(13238786)
+        );
+        assert_eq!(resolver.token_for(Exchange::NFO, "INFY"), None);
+
+        assert_eq!(resolver.symbol_for(779521), Some("NSE:SBIN"));
+        assert_eq!(resolver.symbol_for(1), None);
+
+        let res = resolver.tokens_for(&["NSE:INFY", "NSE:UNKNOWN", "nse:sbin", "INFY"]);
+        assert_eq!(res.resolved, vec![408065, 779521]);
+        assert_eq!(res.unresolved, vec!["NSE:UNKNOWN", "INFY"]);
+
+        let res = resolver.symbols_for(&[256265, 42]);
+        assert_eq!(res.resolved, vec!["NSE:NIFTY 50"]);
+        assert_eq!(res.unresolved, vec![42]);
+
+        Ok(())
+    }
+    #[test]
+    fn test_instrument_index() -> Result<(), Box<dyn std::error::Error>> {
+        let index: InstrumentIndex = parse_instruments(INSTRUMENTS_CSV.as_bytes())?
+            .into_iter()
+            .collect();
+
+        assert_eq!(index.len(), 7);
+        assert_eq!(index.symbol_for(128053508), Some((Exchange::BSE, "INFY")));
+        assert_eq!(
+            index.symbol_for(13238786),
+            Some((Exchange::NFO, "NIFTY24DECFUT"))
+        );
+        assert_eq!(index.symbol_for(1), None);
+        assert_eq!(
+            index.get(256265).map(|i| &i.segment),
+            Some(&Segment::INDICES)
+        );
+        assert_eq!(
+            index.symbol_for(11570946),
+            Some((Exchange::NCO, "ALUMINIUM25JANFUT"))
+        );
+        // NSEIX has no `Exchange` variant
+        assert_eq!(
+            index.get(5387276).map(|i| &i.exchange),
+            Some(&InstrumentExchange::Other("NSEIX".into()))
+        );
+        assert_eq!(index.symbol_for(5387276), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! Recorded Kite Connect API responses.
+//!
+//! Each constant is the full JSON envelope (`status` + `data`) returned by the matching endpoint, as
+//! documented at <https://kite.trade/docs/connect/v3/>.
+
+/// `GET /orders`
+pub const ORDERS: &str = include_str!("fixtures/orders.json");
+/// `GET /orders/{order_id}` for the order in [`PLACE_ORDER`], rejected for insufficient margin
+pub const ORDER_HISTORY: &str = include_str!("fixtures/order_history.json");
+/// `GET /trades`
+pub const TRADES: &str = include_str!("fixtures/trades.json");
+/// `POST /orders/{variety}`
+pub const PLACE_ORDER: &str = include_str!("fixtures/place_order.json");
+/// `GET /gtt/triggers`, with active, triggered and expired triggers
+pub const GTTS: &str = include_str!("fixtures/gtts.json");
+/// `GET /portfolio/holdings`
+pub const HOLDINGS: &str = include_str!("fixtures/holdings.json");
+/// `GET /portfolio/holdings/auctions`
+pub const HOLDINGS_AUCTIONS: &str = include_str!("fixtures/holdings_auctions.json");
+/// `GET /portfolio/positions`
+pub const POSITIONS: &str = include_str!("fixtures/positions.json");
+/// `GET /quote`
+pub const QUOTE: &str = include_str!("fixtures/quote.json");
+/// `GET /quote/ohlc`
+pub const QUOTE_OHLC: &str = include_str!("fixtures/quote_ohlc.json");
+/// `GET /quote/ltp`
+pub const QUOTE_LTP: &str = include_str!("fixtures/quote_ltp.json");
+/// `GET /user/margins`
+pub const FUNDS: &str = include_str!("fixtures/funds.json");
+/// `GET /user/profile`
+pub const PROFILE: &str = include_str!("fixtures/profile.json");
+/// `POST /session/token`
+pub const SESSION_TOKEN: &str = include_str!("fixtures/session_token.json");
+/// `GET /instruments/historical/{instrument_token}/{interval}`
+pub const HISTORICAL: &str = include_str!("fixtures/historical.json");
+/// Error envelope returned for an expired or invalid `access_token`
+pub const TOKEN_EXCEPTION: &str = include_str!("fixtures/token_exception.json");
+/// Error envelope returned when an order is rejected for insufficient margin
+pub const MARGIN_EXCEPTION: &str = include_str!("fixtures/margin_exception.json");
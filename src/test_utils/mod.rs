@@ -0,0 +1,307 @@
+//! Mock Kite Connect server for testing code built on this crate.
+//!
+//! Enable the `test-utils` feature (usually as a dev-dependency) to get a [`MockKite`], which
+//! starts a local HTTP server and hands out [`KiteConnect`] clients pointed at it. Responses are
+//! mounted per endpoint, either from the recorded payloads in [`fixtures`] or from any JSON body.
+//!
+//! ```toml
+//! [dev-dependencies]
+//! kite_connect = { version = "0.1", features = ["test-utils"] }
+//! ```
+//!
+//! ```no_run
+//! use kite_connect::test_utils::MockKite;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mock = MockKite::start().await;
+//! mock.mount_orders().await;
+//!
+//! let orders = mock.kite().get_orders().await?;
+//! assert!(!orders.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::orders::{
+    Exchange, OrderType, PlaceOrderRequest, Product, TransactionType, Validity, Variety,
+};
+use crate::{AuthPending, Authenticated, KiteConnect};
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+pub mod fixtures;
+
+pub use wiremock;
+
+/// API key used by the clients returned from [`MockKite`]
+pub const MOCK_API_KEY: &str = "mock_api_key";
+/// API secret used by the clients returned from [`MockKite`]
+pub const MOCK_API_SECRET: &str = "mock_api_secret";
+/// Access token used by the clients returned from [`MockKite::kite`]
+pub const MOCK_ACCESS_TOKEN: &str = "mock_access_token";
+
+/// A regular MARKET order buying 1 share of INFY on NSE for delivery, without a tag.
+///
+/// The request isn't validated, change its fields with the struct update syntax to build the
+/// requests of a test, valid or not:
+///
+/// ```
+/// use kite_connect::{orders::PlaceOrderRequest, test_utils::market_order};
+///
+/// let req = PlaceOrderRequest {
+///     quantity: 10,
+///     ..market_order()
+/// };
+/// assert!(req.validate().is_ok());
+/// ```
+pub fn market_order() -> PlaceOrderRequest {
+    PlaceOrderRequest {
+        variety: Variety::Regular,
+        trading_symbol: "INFY".into(),
+        exchange: Exchange::NSE,
+        transaction_type: TransactionType::Buy,
+        order_type: OrderType::Market,
+        quantity: 1,
+        product: Product::CNC,
+        price: None,
+        trigger_price: None,
+        disclosed_quantity: None,
+        validity: Validity::Day,
+        validity_ttl: None,
+        iceberg_legs: None,
+        iceberg_quantity: None,
+        auction_number: None,
+        market_protection: None,
+        tag: None,
+        idempotency_key: None,
+    }
+}
+
+/// A local server that answers Kite Connect REST calls with canned responses.
+pub struct MockKite {
+    server: MockServer,
+}
+
+impl MockKite {
+    /// Starts a new mock server on a random local port.
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// Returns the underlying [`MockServer`] for custom matchers or request inspection.
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Returns an authenticated client that sends every REST request to this server.
+    pub fn kite(&self) -> KiteConnect<Authenticated> {
+        self.pending_kite()
+            .authenticate_with_access_token(MOCK_ACCESS_TOKEN.into())
+            .expect("Error in authenticate_with_access_token")
+    }
+
+    /// Returns an unauthenticated client that sends every REST request to this server.
+    pub fn pending_kite(&self) -> KiteConnect<AuthPending> {
+        KiteConnect::new(MOCK_API_KEY.into(), MOCK_API_SECRET.into())
+            .with_base_url(self.server.uri())
+    }
+
+    /// Answers `http_method` requests to `endpoint` with `status` and the JSON `body`.
+    ///
+    /// `endpoint` is the path relative to the API root, e.g. `/orders`.
+    pub async fn mount_json(&self, http_method: &str, endpoint: &str, status: u16, body: &str) {
+        Mock::given(method(http_method))
+            .and(path(endpoint))
+            .respond_with(
+                ResponseTemplate::new(status).set_body_raw(body.to_owned(), "application/json"),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers `http_method` requests to `endpoint` with a Kite error envelope.
+    pub async fn mount_error(
+        &self,
+        http_method: &str,
+        endpoint: &str,
+        status: u16,
+        error_type: &str,
+        message: &str,
+    ) {
+        let body = serde_json::json!({
+            "status": "error",
+            "message": message,
+            "data": null,
+            "error_type": error_type,
+        });
+
+        self.mount_json(http_method, endpoint, status, &body.to_string())
+            .await;
+    }
+
+    /// Mounts [`fixtures::ORDERS`] on `GET /orders`.
+    pub async fn mount_orders(&self) {
+        self.mount_json("GET", "/orders", 200, fixtures::ORDERS)
+            .await;
+    }
+
+    /// Mounts [`fixtures::TRADES`] on `GET /trades`.
+    pub async fn mount_trades(&self) {
+        self.mount_json("GET", "/trades", 200, fixtures::TRADES)
+            .await;
+    }
+
+    /// Mounts [`fixtures::ORDER_HISTORY`] on `GET /orders/{order_id}`.
+    pub async fn mount_order_history(&self, order_id: &str) {
+        self.mount_json(
+            "GET",
+            &format!("/orders/{order_id}"),
+            200,
+            fixtures::ORDER_HISTORY,
+        )
+        .await;
+    }
+
+    /// Mounts [`fixtures::PLACE_ORDER`] on `POST /orders/{variety}` for every variety.
+    pub async fn mount_place_order(&self) {
+        Mock::given(method("POST"))
+            .and(wiremock::matchers::path_regex(r"^/orders/[a-z]+$"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(fixtures::PLACE_ORDER, "application/json"),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mounts [`fixtures::GTTS`] on `GET /gtt/triggers`.
+    pub async fn mount_gtts(&self) {
+        self.mount_json("GET", "/gtt/triggers", 200, fixtures::GTTS)
+            .await;
+    }
+
+    /// Answers `DELETE /gtt/triggers/{trigger_id}` with a successful deletion.
+    pub async fn mount_delete_gtt(&self, trigger_id: u64) {
+        let body = serde_json::json!({
+            "status": "success",
+            "data": { "trigger_id": trigger_id },
+        });
+
+        self.mount_json(
+            "DELETE",
+            &format!("/gtt/triggers/{trigger_id}"),
+            200,
+            &body.to_string(),
+        )
+        .await;
+    }
+
+    /// Mounts [`fixtures::HOLDINGS`] on `GET /portfolio/holdings`.
+    pub async fn mount_holdings(&self) {
+        self.mount_json("GET", "/portfolio/holdings", 200, fixtures::HOLDINGS)
+            .await;
+    }
+
+    /// Mounts [`fixtures::HOLDINGS_AUCTIONS`] on `GET /portfolio/holdings/auctions`.
+    pub async fn mount_holdings_auctions(&self) {
+        self.mount_json(
+            "GET",
+            "/portfolio/holdings/auctions",
+            200,
+            fixtures::HOLDINGS_AUCTIONS,
+        )
+        .await;
+    }
+
+    /// Mounts [`fixtures::POSITIONS`] on `GET /portfolio/positions`.
+    pub async fn mount_positions(&self) {
+        self.mount_json("GET", "/portfolio/positions", 200, fixtures::POSITIONS)
+            .await;
+    }
+
+    /// Mounts [`fixtures::QUOTE`] on `GET /quote`.
+    pub async fn mount_quote(&self) {
+        self.mount_json("GET", "/quote", 200, fixtures::QUOTE).await;
+    }
+
+    /// Mounts [`fixtures::QUOTE_OHLC`] on `GET /quote/ohlc`.
+    pub async fn mount_quote_ohlc(&self) {
+        self.mount_json("GET", "/quote/ohlc", 200, fixtures::QUOTE_OHLC)
+            .await;
+    }
+
+    /// Mounts [`fixtures::QUOTE_LTP`] on `GET /quote/ltp`.
+    pub async fn mount_quote_ltp(&self) {
+        self.mount_json("GET", "/quote/ltp", 200, fixtures::QUOTE_LTP)
+            .await;
+    }
+
+    /// Mounts [`fixtures::FUNDS`] on `GET /user/margins`.
+    pub async fn mount_funds(&self) {
+        self.mount_json("GET", "/user/margins", 200, fixtures::FUNDS)
+            .await;
+    }
+
+    /// Mounts [`fixtures::PROFILE`] on `GET /user/profile`.
+    pub async fn mount_profile(&self) {
+        self.mount_json("GET", "/user/profile", 200, fixtures::PROFILE)
+            .await;
+    }
+
+    /// Mounts [`fixtures::SESSION_TOKEN`] on `POST /session/token`.
+    pub async fn mount_session_token(&self) {
+        self.mount_json("POST", "/session/token", 200, fixtures::SESSION_TOKEN)
+            .await;
+    }
+
+    /// Mounts [`fixtures::HISTORICAL`] on `GET /instruments/historical/{instrument_token}/{interval}`.
+    pub async fn mount_historical(
+        &self,
+        instrument_token: u32,
+        interval: crate::historical::Interval,
+    ) {
+        self.mount_json(
+            "GET",
+            &format!("/instruments/historical/{instrument_token}/{interval}"),
+            200,
+            fixtures::HISTORICAL,
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response;
+
+    #[test]
+    fn test_fixtures_parse() -> Result<(), Box<dyn std::error::Error>> {
+        for fixture in [
+            fixtures::ORDERS,
+            fixtures::ORDER_HISTORY,
+            fixtures::TRADES,
+            fixtures::PLACE_ORDER,
+            fixtures::GTTS,
+            fixtures::HOLDINGS,
+            fixtures::HOLDINGS_AUCTIONS,
+            fixtures::POSITIONS,
+            fixtures::QUOTE,
+            fixtures::QUOTE_OHLC,
+            fixtures::QUOTE_LTP,
+            fixtures::FUNDS,
+            fixtures::PROFILE,
+            fixtures::SESSION_TOKEN,
+            fixtures::HISTORICAL,
+            fixtures::TOKEN_EXCEPTION,
+            fixtures::MARGIN_EXCEPTION,
+        ] {
+            let _: Response<serde_json::Value> = serde_json::from_str(fixture)?;
+        }
+
+        Ok(())
+    }
+}
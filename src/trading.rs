@@ -0,0 +1,48 @@
+//! Order placement API shared by the real client and its stand-ins.
+//!
+//! Write strategies against [`Trading`] to run them unchanged against
+//! [`KiteConnect<Authenticated>`], a paper-trading client or a test fake.
+
+use crate::orders::{Order, OrderId, PlaceOrderRequest, PlacedOrder, Variety};
+use crate::portfolio::Positions;
+use crate::{Authenticated, Error, KiteConnect};
+
+/// Places and cancels orders and reports the resulting positions.
+pub trait Trading {
+    /// Places an order and returns its order id, see [`KiteConnect::place_order_poll`].
+    fn place_order_poll(
+        &self,
+        req: &PlaceOrderRequest,
+    ) -> impl Future<Output = Result<PlacedOrder, Error>>;
+
+    /// Cancels a pending order, see [`KiteConnect::cancel_order`].
+    fn cancel_order(
+        &self,
+        order_id: &OrderId,
+        variety: &Variety,
+    ) -> impl Future<Output = Result<(), Error>>;
+
+    /// Returns the day's orders, see [`KiteConnect::get_orders`].
+    fn get_orders(&self) -> impl Future<Output = Result<Vec<Order>, Error>>;
+
+    /// Returns the current positions, see [`KiteConnect::get_positions`].
+    fn get_positions(&self) -> impl Future<Output = Result<Positions, Error>>;
+}
+
+impl Trading for KiteConnect<Authenticated> {
+    async fn place_order_poll(&self, req: &PlaceOrderRequest) -> Result<PlacedOrder, Error> {
+        KiteConnect::place_order_poll(self, req).await
+    }
+
+    async fn cancel_order(&self, order_id: &OrderId, variety: &Variety) -> Result<(), Error> {
+        KiteConnect::cancel_order(self, order_id, variety).await
+    }
+
+    async fn get_orders(&self) -> Result<Vec<Order>, Error> {
+        KiteConnect::get_orders(self).await
+    }
+
+    async fn get_positions(&self) -> Result<Positions, Error> {
+        KiteConnect::get_positions(self).await
+    }
+}
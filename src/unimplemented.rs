@@ -1,15 +0,0 @@
-pub mod gtt {
-    //! GTT - Good Till Triggered orders (Unimplemented)
-    //!
-    //! This is a placeholder module as the GTT is unimplemented for now
-    //!
-    //! Reference: <https://kite.trade/docs/connect/v3/gtt/>
-}
-
-pub mod alerts {
-    //! Alerts (Unimplemented)
-    //!
-    //! This is a placeholder module as the Alerts is unimplemented for now
-    //!
-    //! Reference: <https://kite.trade/docs/connect/v3/alerts/>
-}
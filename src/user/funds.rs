@@ -1,4 +1,9 @@
 use crate::user::*;
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 pub const USER_FUNDS_ENDPOINT: &str = "https://api.kite.trade/user/margins";
 pub const USER_EQUITY_FUNDS_ENDPOINT: &str = "https://api.kite.trade/user/margins/equity";
@@ -20,6 +25,11 @@ pub struct SegmentFunds {
     pub utilised: UtilisedFunds,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(SegmentFunds {
+    net => net_decimal -> Option<Decimal>,
+});
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct AvailableFunds {
     /// Raw cash balance in the account available for trading (also includes `intraday_payin`)
@@ -36,6 +46,16 @@ pub struct AvailableFunds {
     pub collateral: f64,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(AvailableFunds {
+    cash => cash_decimal -> Option<Decimal>,
+    opening_balance => opening_balance_decimal -> Option<Decimal>,
+    live_balance => live_balance_decimal -> Option<Decimal>,
+    intraday_payin => intraday_payin_decimal -> Option<Decimal>,
+    adhoc_margin => adhoc_margin_decimal -> Option<Decimal>,
+    collateral => collateral_decimal -> Option<Decimal>,
+});
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct UtilisedFunds {
     /// Un-booked (open) intraday profits and losses
@@ -66,38 +86,108 @@ pub struct UtilisedFunds {
     pub payout: f64,
 }
 
+#[cfg(feature = "decimal")]
+crate::decimal::decimal_accessors!(UtilisedFunds {
+    unrealised => unrealised_decimal -> Option<Decimal>,
+    realised => realised_decimal -> Option<Decimal>,
+    debits => debits_decimal -> Option<Decimal>,
+    span => span_decimal -> Option<Decimal>,
+    option_premium => option_premium_decimal -> Option<Decimal>,
+    holding_sales => holding_sales_decimal -> Option<Decimal>,
+    exposure => exposure_decimal -> Option<Decimal>,
+    liquid_collateral => liquid_collateral_decimal -> Option<Decimal>,
+    delivery => delivery_decimal -> Option<Decimal>,
+    stock_collateral => stock_collateral_decimal -> Option<Decimal>,
+    turnover => turnover_decimal -> Option<Decimal>,
+    payout => payout_decimal -> Option<Decimal>,
+});
+
+/// Funds fetched by a client with [`KiteConnect::with_funds_cache`].
+pub(crate) struct FundsCache {
+    ttl: Duration,
+    entries: Mutex<FundsCacheEntries>,
+}
+
+#[derive(Default)]
+struct FundsCacheEntries {
+    /// Bumped on every invalidation, so that funds fetched before it aren't cached after it
+    generation: u64,
+    total: Option<(Instant, TotalFunds)>,
+    equity: Option<(Instant, SegmentFunds)>,
+    commodity: Option<(Instant, SegmentFunds)>,
+}
+
+type CacheSlot<T> = fn(&mut FundsCacheEntries) -> &mut Option<(Instant, T)>;
+
+impl FundsCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Drops every cached funds.
+    pub(crate) fn invalidate(&self) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        *entries = FundsCacheEntries {
+            generation: entries.generation + 1,
+            ..Default::default()
+        };
+    }
+
+    /// Returns the funds of `slot` if they're fresh, or the generation to [store](Self::store)
+    /// them with once fetched.
+    fn lookup<T: Copy>(&self, slot: CacheSlot<T>) -> Result<T, u64> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match *slot(&mut entries) {
+            Some((fetched_at, funds)) if fetched_at.elapsed() < self.ttl => Ok(funds),
+            _ => Err(entries.generation),
+        }
+    }
+
+    fn store<T>(&self, slot: CacheSlot<T>, generation: u64, funds: T) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.generation == generation {
+            *slot(&mut entries) = Some((Instant::now(), funds));
+        }
+    }
+}
+
 impl KiteConnect<Authenticated> {
     pub async fn get_funds(&self) -> Result<TotalFunds, Error> {
-        Ok(self
-            .client
-            .get(USER_FUNDS_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.get_funds_cached(USER_FUNDS_ENDPOINT, |e| &mut e.total)
+            .await
     }
 
     pub async fn get_equity_funds(&self) -> Result<SegmentFunds, Error> {
-        Ok(self
-            .client
-            .get(USER_EQUITY_FUNDS_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.get_funds_cached(USER_EQUITY_FUNDS_ENDPOINT, |e| &mut e.equity)
+            .await
     }
 
     pub async fn get_commodity_funds(&self) -> Result<SegmentFunds, Error> {
-        Ok(self
-            .client
-            .get(USER_COMMODITY_FUNDS_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.get_funds_cached(USER_COMMODITY_FUNDS_ENDPOINT, |e| &mut e.commodity)
+            .await
+    }
+
+    /// Fetches the funds at `endpoint`, through the funds cache if the client has one.
+    async fn get_funds_cached<T: DeserializeOwned + Copy>(
+        &self,
+        endpoint: &str,
+        slot: CacheSlot<T>,
+    ) -> Result<T, Error> {
+        let Some(cache) = &self.funds_cache else {
+            return self.send(self.client.get(endpoint)).await;
+        };
+
+        let generation = match cache.lookup(slot) {
+            Ok(funds) => return Ok(funds),
+            Err(generation) => generation,
+        };
+        let funds = self.send(self.client.get(endpoint)).await?;
+        cache.store(slot, generation, funds);
+
+        Ok(funds)
     }
 }
 
@@ -105,9 +195,7 @@ impl KiteConnect<Authenticated> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_funds() -> Result<(), Box<dyn std::error::Error>> {
-        let json = r#"{
+    const FUNDS_JSON: &str = r#"{
             "status": "success",
             "data": {
               "equity": {
@@ -164,6 +252,11 @@ mod tests {
               }
             }
           }"#;
+    use crate::Response;
+
+    #[test]
+    fn test_funds() -> Result<(), Box<dyn std::error::Error>> {
+        let json = FUNDS_JSON;
 
         let expected = TotalFunds {
             equity: SegmentFunds {
@@ -225,4 +318,18 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_funds_decimal() -> Result<(), Box<dyn std::error::Error>> {
+        use std::str::FromStr;
+
+        let funds = serde_json::from_str::<Response<TotalFunds>>(FUNDS_JSON)?.into_result()?;
+        let expected = Some(Decimal::from_str("99725.05000000002")?);
+
+        assert_eq!(funds.equity.net_decimal(), expected);
+        assert_eq!(funds.equity.available.live_balance_decimal(), expected);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,149 @@
+//! The login flow of Kite Connect, step by step.
+//!
+//! [`LoginFlow`] walks through the steps of <https://kite.trade/docs/connect/v3/user/>: send the
+//! user to [`LoginFlow::login_url`], read the `request_token` Kite redirects back with (see
+//! [`request_token_from_redirect`]), then [`LoginFlow::complete`] it for an access token. Unlike
+//! `AutoAuth` (`auto_auth` feature) it doesn't listen for the redirect itself, so it fits a web app
+//! that handles the redirect URL in its own routes. Persist the returned [`SessionToken`] to skip
+//! the login until the access token expires, see
+//! [`KiteConnect::authenticate_with_access_token`].
+
+use reqwest::Url;
+
+use super::LOGIN_ENDPOINT;
+use super::session_token::SessionToken;
+use crate::{AuthPending, Authenticated, Error, KiteConnect};
+
+/// A pending login, see the [module documentation](self).
+pub struct LoginFlow {
+    kite: KiteConnect<AuthPending>,
+}
+
+impl LoginFlow {
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        Self {
+            kite: KiteConnect::new(api_key, api_secret),
+        }
+    }
+
+    /// Returns the URL of the Kite login page to send the user to.
+    pub fn login_url(&self) -> String {
+        format!("{LOGIN_ENDPOINT}{}", self.kite.api_key())
+    }
+
+    /// Exchanges `request_token` for an access token, returning the authenticated client and the
+    /// session of the user.
+    ///
+    /// A request token can be exchanged once and only for a few minutes after the login.
+    pub async fn complete(
+        self,
+        request_token: &str,
+    ) -> Result<(KiteConnect<Authenticated>, SessionToken), Error> {
+        let session = self.kite.generate_session_token(request_token).await?;
+        let kite = self
+            .kite
+            .authenticate_with_access_token(session.access_token.clone())?;
+
+        Ok((kite, session))
+    }
+}
+
+/// Starts the flow from a configured client, e.g. one with a
+/// [base URL](KiteConnect::with_base_url) or a default order tag.
+impl From<KiteConnect<AuthPending>> for LoginFlow {
+    fn from(kite: KiteConnect<AuthPending>) -> Self {
+        Self { kite }
+    }
+}
+
+/// Returns the `request_token` of the URL Kite redirects to after the login.
+///
+/// `redirect_url` is either the full URL or its path and query, e.g.
+/// `/callback?action=login&status=success&request_token=...`. Returns a
+/// [`KiteError::InputException`](crate::KiteError::InputException) if the login didn't succeed
+/// or the URL has no request token.
+pub fn request_token_from_redirect(redirect_url: &str) -> Result<String, Error> {
+    let url = Url::parse(redirect_url)
+        .or_else(|_| Url::parse("http://localhost")?.join(redirect_url))
+        .map_err(|e| Error::input_exception(format!("Invalid redirect URL: {e}")))?;
+
+    let mut request_token = None;
+    for (key, value) in url.query_pairs() {
+        match &*key {
+            "status" if value != "success" => {
+                return Err(Error::input_exception(format!("Login status is {value}")));
+            }
+            "request_token" if !value.is_empty() => request_token = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+
+    request_token.ok_or_else(|| Error::input_exception("No request token in the redirect URL"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KiteError;
+
+    #[test]
+    fn test_login_url() {
+        let flow = LoginFlow::new("abc123".into(), "secret".into());
+
+        assert_eq!(
+            flow.login_url(),
+            "https://kite.zerodha.com/connect/login?v=3&api_key=abc123"
+        );
+    }
+
+    #[test]
+    fn test_request_token_from_redirect() -> Result<(), Error> {
+        assert_eq!(
+            request_token_from_redirect(
+                "https://example.com/callback?action=login&status=success&request_token=tok1"
+            )?,
+            "tok1"
+        );
+        assert_eq!(
+            request_token_from_redirect("/callback?request_token=tok2&action=login")?,
+            "tok2"
+        );
+
+        for url in [
+            "/callback?action=login&status=cancelled&request_token=tok",
+            "/callback?action=login&status=success",
+            "/callback?request_token=",
+        ] {
+            assert!(
+                matches!(
+                    request_token_from_redirect(url),
+                    Err(Error::KiteError(KiteError::InputException(_)))
+                ),
+                "{url}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "test-utils")]
+    #[tokio::test]
+    async fn test_complete() -> Result<(), Error> {
+        use crate::test_utils::MockKite;
+
+        let mock = MockKite::start().await;
+        mock.mount_session_token().await;
+
+        let flow = LoginFlow::from(mock.pending_kite());
+        let (kite, session) = flow.complete("request_token").await?;
+
+        assert_eq!(session.user_id, "XX0000");
+        assert_eq!(kite.access_token(), session.access_token);
+
+        let requests = mock.server().received_requests().await.unwrap_or_default();
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains("request_token=request_token"), "{body}");
+
+        Ok(())
+    }
+}
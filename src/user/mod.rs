@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 
-use crate::{AuthPending, Authenticated, Error, KiteConnect, Response};
+use crate::{AuthPending, Authenticated, Error, KiteConnect};
 
 pub mod funds;
+pub mod login_flow;
 pub mod profile;
 pub mod session_token;
 
@@ -71,6 +72,13 @@ impl KiteConnect<AuthPending> {
         Ok(KiteConnect {
             client,
             auth_info: self.auth_info,
+            base_url: self.base_url,
+            #[cfg(feature = "middleware")]
+            middleware: self.middleware,
+            default_order_tag: self.default_order_tag,
+            api_version: self.api_version,
+            cancellation_token: self.cancellation_token,
+            funds_cache: self.funds_cache,
             _auth_status: std::marker::PhantomData,
         })
     }
@@ -119,6 +127,13 @@ impl KiteConnect<AuthPending> {
         Ok(KiteConnect {
             client,
             auth_info: self.auth_info,
+            base_url: self.base_url,
+            #[cfg(feature = "middleware")]
+            middleware: self.middleware,
+            default_order_tag: self.default_order_tag,
+            api_version: self.api_version,
+            cancellation_token: self.cancellation_token,
+            funds_cache: self.funds_cache,
             _auth_status: std::marker::PhantomData,
         })
     }
@@ -35,20 +35,14 @@ pub struct UserProfile {
 
 impl KiteConnect<Authenticated> {
     pub async fn get_user_profile(&self) -> Result<UserProfile, Error> {
-        Ok(self
-            .client
-            .get(USER_PROFILE_ENDPOINT)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.send(self.client.get(USER_PROFILE_ENDPOINT)).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Response;
 
     #[test]
     fn test_user_profile() -> Result<(), Box<dyn std::error::Error>> {
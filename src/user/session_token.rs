@@ -67,21 +67,15 @@ impl KiteConnect<AuthPending> {
             checksum: &checksum_hex,
         };
 
-        Ok(self
-            .client
-            .post(SESSION_TOKEN_ENDPOINT)
-            .form(&req)
-            .send()
-            .await?
-            .json::<Response<_>>()
-            .await?
-            .into_result()?)
+        self.send(self.client.post(SESSION_TOKEN_ENDPOINT).form(&req))
+            .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Response;
 
     #[test]
     fn test_session_token() -> Result<(), Box<dyn std::error::Error>> {
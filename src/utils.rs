@@ -13,6 +13,9 @@ use serde::{Deserialize, Deserializer};
 /// value before initializing [KiteConnect](super::KiteConnect).
 pub static mut REQUEST_TIMEOUT_SECS: u64 = 1;
 
+/// The base URL of every Kite Connect REST endpoint.
+pub const KITE_API_BASE_URL: &str = "https://api.kite.trade";
+
 pub const API_VERSION: u8 = 3;
 pub const API_VERSION_STR: &str = "3";
 
@@ -61,9 +64,10 @@ impl AuthInfo {
     }
 }
 
-pub fn default_client_builder(
+/// Headers sent with every REST request: `X-Kite-Version` and, once authenticated, `Authorization`.
+pub(crate) fn default_headers(
     authentication_header_value: Option<&str>,
-) -> Result<Client, crate::Error> {
+) -> Result<HeaderMap, crate::Error> {
     let mut default_headers = HeaderMap::new();
     default_headers.insert("X-Kite-Version", HeaderValue::from_static(API_VERSION_STR));
 
@@ -73,8 +77,14 @@ pub fn default_client_builder(
         default_headers.insert("Authorization", auth_value);
     }
 
+    Ok(default_headers)
+}
+
+pub fn default_client_builder(
+    authentication_header_value: Option<&str>,
+) -> Result<Client, crate::Error> {
     Ok(ClientBuilder::new()
-        .default_headers(default_headers)
+        .default_headers(default_headers(authentication_header_value)?)
         .user_agent(APP_USER_AGENT)
         .timeout(Duration::from_secs(unsafe { REQUEST_TIMEOUT_SECS }))
         .build()?)
@@ -88,6 +98,17 @@ where
     Ok(opt.unwrap_or_default())
 }
 
+/// Deserializes both null and the empty string as `None`.
+pub(crate) fn deserialize_non_empty_string<'de, D>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    Ok(opt.filter(|s| !s.is_empty()))
+}
+
 pub(crate) fn deserialize_number_or_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -142,3 +163,110 @@ where
 
     deserializer.deserialize_any(NumberOrStringVisitor)
 }
+
+/// Offset of IST from UTC.
+pub(crate) const IST_OFFSET_SECS: i64 = 5 * 3600 + 30 * 60;
+
+/// IST, the timezone of every timestamp sent by Kite.
+#[cfg(feature = "chrono")]
+pub(crate) fn ist() -> chrono::FixedOffset {
+    chrono::FixedOffset::east_opt(IST_OFFSET_SECS as i32).expect("IST is a valid offset")
+}
+
+/// Splits the Unix timestamp `unix_secs` into its `YYYY-MM-DD` date and `HH:MM:SS` time in IST,
+/// without pulling in `chrono`.
+pub(crate) fn ist_date_time(unix_secs: i64) -> (String, String) {
+    let secs = unix_secs + IST_OFFSET_SECS;
+    let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Civil date from the days since the epoch, see
+    // <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (
+        format!("{year:04}-{month:02}-{day:02}"),
+        format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        ),
+    )
+}
+
+/// Formats of the timestamps sent by Kite, tried in this order by [`parse_kite_timestamp`]:
+/// orders and quotes, candles, and dates such as expiries.
+#[cfg(feature = "chrono")]
+pub const KITE_TIMESTAMP_FORMATS: [&str; 3] =
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%d"];
+
+/// Parses a timestamp in any of the [`KITE_TIMESTAMP_FORMATS`].
+///
+/// Timestamps without an offset are in IST, and dates are taken at midnight. Returns an
+/// [`Error::Serde`](crate::Error::Serde) listing the formats tried if none matches.
+#[cfg(feature = "chrono")]
+pub fn parse_kite_timestamp(
+    timestamp: &str,
+) -> Result<chrono::DateTime<chrono::FixedOffset>, crate::Error> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+
+    let [date_time, with_offset, date] = KITE_TIMESTAMP_FORMATS;
+    let ist = ist();
+
+    let parsed = NaiveDateTime::parse_from_str(timestamp, date_time)
+        .ok()
+        .and_then(|ts| ts.and_local_timezone(ist).single())
+        .or_else(|| DateTime::parse_from_str(timestamp, with_offset).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(timestamp, date)
+                .ok()
+                .and_then(|d| d.and_time(NaiveTime::MIN).and_local_timezone(ist).single())
+        });
+
+    parsed.ok_or_else(|| {
+        crate::Error::Serde(
+            format!(
+                "Invalid timestamp {timestamp:?}, expected one of the formats {}",
+                KITE_TIMESTAMP_FORMATS.join(", ")
+            )
+            .into(),
+        )
+    })
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    #[test]
+    fn test_parse_kite_timestamp() -> Result<(), Box<dyn std::error::Error>> {
+        for (timestamp, expected) in [
+            ("2021-05-31 09:18:57", "2021-05-31T09:18:57+05:30"),
+            ("2017-12-15T09:15:00+0530", "2017-12-15T09:15:00+05:30"),
+            ("2018-08-09 00:00:00", "2018-08-09T00:00:00+05:30"),
+            ("2025-07-31", "2025-07-31T00:00:00+05:30"),
+        ] {
+            assert_eq!(
+                parse_kite_timestamp(timestamp)?,
+                DateTime::parse_from_rfc3339(expected)?,
+                "{timestamp}"
+            );
+        }
+
+        for timestamp in ["", "09:18:57", "31-05-2021 09:18:57"] {
+            let err = parse_kite_timestamp(timestamp).unwrap_err().to_string();
+            assert!(err.contains("%Y-%m-%dT%H:%M:%S%z"), "{err}");
+        }
+
+        Ok(())
+    }
+}
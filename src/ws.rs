@@ -1,13 +1,22 @@
+use crate::orders::Order;
 use crate::quotes::{Depth, DepthBook, LtpQuote, Ohlc, OhlcQuote};
+use crate::symbols::{Resolved, SymbolResolver};
 use byteorder::{BigEndian, ReadBytesExt};
 use crossbeam_channel::{Receiver, Sender};
 use futures_util::{
-    SinkExt, StreamExt,
+    SinkExt, Stream, StreamExt,
     stream::{SplitSink, SplitStream},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Cursor, Seek, SeekFrom};
-use tokio::{net::TcpStream, task::JoinHandle};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, Weak};
+use std::time::Duration;
+use tokio::{
+    net::TcpStream,
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
 use tokio_tungstenite::tungstenite::{Bytes, Message};
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
 
@@ -15,21 +24,76 @@ use super::*;
 
 pub const KITE_WEB_SOCKET_ENDPOINT: &str = "wss://ws.kite.trade/";
 
+type WriteStream = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
+type ReadStream = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
+
 pub struct KiteTicker {
     handle: JoinHandle<()>,
-    write_stream: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
+    connection: Arc<Connection>,
+    /// Task sending the pings of [`KiteTicker::set_ping_interval`]
+    pinger: Option<JoinHandle<()>>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Write half of the WebSocket, shared with the reading task which swaps it on a reconnect.
+struct Connection {
+    write_stream: tokio::sync::Mutex<WriteStream>,
+    /// Subscriptions of [`KiteTicker::subscriptions`], restored by the reading task on a reconnect
+    subscriptions: Mutex<Subscriptions>,
+}
+
+impl Connection {
+    fn subscriptions(&self) -> MutexGuard<'_, Subscriptions> {
+        self.subscriptions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Ticker {
     ConnectionClosed,
+    /// The WebSocket reconnected after a [`Ticker::ConnectionClosed`] and the tracked
+    /// subscriptions were sent again, see [`KiteConnect::web_socket_with_reconnect`]
+    Reconnected,
     IndicesQuote(OhlcQuote),
     LtpQuote(LtpQuote),
     PartialQuote(PartialQuote),
     FullQuote(FullQuote),
+    /// An order of the user changed, e.g. got filled. Sent for the orders placed by the app of the
+    /// client, like a [postback](crate::postback)
+    OrderUpdate(Box<Order>),
+    /// A packet of a binary message couldn't be decoded and was skipped
+    Error(TickerError),
+}
+
+/// Why a packet of a binary message couldn't be decoded, see [`Ticker::Error`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum TickerError {
+    /// The packet has a length matching no known mode. It's skipped and the following packets are
+    /// still decoded.
+    UnsupportedPacket { packet_len: u16 },
+    /// The packet is longer than the rest of the message. The rest of the message is dropped.
+    TruncatedPacket { packet_len: u16, remaining: usize },
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+impl std::fmt::Display for TickerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TickerError::UnsupportedPacket { packet_len } => {
+                write!(f, "Unsupported packet length {packet_len}")
+            }
+            TickerError::TruncatedPacket {
+                packet_len,
+                remaining,
+            } => write!(
+                f,
+                "Packet of length {packet_len} with only {remaining} bytes left in the message"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TickerError {}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub struct PartialQuote {
     pub instrument_token: u32,
     pub last_price: f64,
@@ -41,7 +105,7 @@ pub struct PartialQuote {
     pub ohlc: Ohlc,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FullQuote {
     pub quote: PartialQuote,
     pub last_trade_time: u32,
@@ -52,6 +116,61 @@ pub struct FullQuote {
     pub depth: DepthBook,
 }
 
+impl Ticker {
+    /// Returns the instrument token of the tick, if it carries one.
+    pub fn instrument_token(&self) -> Option<u32> {
+        self.last_price().map(|(token, _)| token)
+    }
+
+    /// Returns the instrument token and last traded price of the tick, if it carries one.
+    pub fn last_price(&self) -> Option<(u32, f64)> {
+        match self {
+            Ticker::ConnectionClosed
+            | Ticker::Reconnected
+            | Ticker::OrderUpdate(_)
+            | Ticker::Error(_) => None,
+            Ticker::IndicesQuote(q) => Some((q.instrument_token, q.last_price)),
+            Ticker::LtpQuote(q) => Some((q.instrument_token, q.last_price)),
+            Ticker::PartialQuote(q) => Some((q.instrument_token, q.last_price)),
+            Ticker::FullQuote(q) => Some((q.quote.instrument_token, q.quote.last_price)),
+        }
+    }
+}
+
+/// Latest traded price of every instrument seen on the WebSocket.
+///
+/// Feed it every [`Ticker`] received from [`KiteConnect::web_socket`]. It can be shared between
+/// threads, e.g. behind an `Arc`, and read while ticks keep coming in.
+#[derive(Debug, Default)]
+pub struct TickStore {
+    prices: RwLock<HashMap<u32, f64>>,
+}
+
+impl TickStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the last traded price carried by `tick`.
+    pub fn update(&self, tick: &Ticker) {
+        if let Some((token, price)) = tick.last_price() {
+            self.prices
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(token, price);
+        }
+    }
+
+    /// Returns the latest traded price of the instrument, if a tick was received for it.
+    pub fn last_price(&self, instrument_token: u32) -> Option<f64> {
+        self.prices
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&instrument_token)
+            .copied()
+    }
+}
+
 pub enum Req<'a> {
     Subscribe(&'a [u32]),
     Unsubscribe(&'a [u32]),
@@ -69,46 +188,236 @@ pub enum ReqMode {
     Full,
 }
 
-impl KiteTicker {
-    pub async fn send(&mut self, req: Req<'_>) -> Result<(), Error> {
-        let msg = match req {
-            Req::Subscribe(instrument_tokens) => Message::Text(
-                serde_json::json!({
-                    "a": "subscribe",
-                    "v": instrument_tokens
-                })
-                .to_string()
-                .into(),
-            ),
-            Req::Unsubscribe(instrument_token) => Message::Text(
-                serde_json::json!({
-                    "a": "unsubscribe",
-                    "v": instrument_token
-                })
-                .to_string()
-                .into(),
-            ),
+impl Req<'_> {
+    fn to_message(&self) -> Message {
+        let value = match self {
+            Req::Subscribe(instrument_tokens) => serde_json::json!({
+                "a": "subscribe",
+                "v": instrument_tokens
+            }),
+            Req::Unsubscribe(instrument_tokens) => serde_json::json!({
+                "a": "unsubscribe",
+                "v": instrument_tokens
+            }),
             Req::Mode {
                 mode,
                 instrument_tokens,
-            } => Message::Text(
-                serde_json::json!({
-                    "a": "mode",
-                    "v": [mode, instrument_tokens]
-                })
-                .to_string()
-                .into(),
-            ),
+            } => serde_json::json!({
+                "a": "mode",
+                "v": [mode, instrument_tokens]
+            }),
         };
 
-        self.send_raw(msg).await
+        Message::Text(value.to_string().into())
+    }
+}
+
+/// Instruments subscribed to through [`KiteTicker::send`], along with their streaming mode.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Subscriptions(BTreeMap<u32, ReqMode>);
+
+impl Subscriptions {
+    fn track(&mut self, req: &Req<'_>) {
+        match req {
+            // Kite streams newly subscribed instruments in quote mode
+            Req::Subscribe(instrument_tokens) => {
+                for &token in instrument_tokens.iter() {
+                    self.0.entry(token).or_insert(ReqMode::Quote);
+                }
+            }
+            Req::Unsubscribe(instrument_tokens) => {
+                for token in instrument_tokens.iter() {
+                    self.0.remove(token);
+                }
+            }
+            Req::Mode {
+                mode,
+                instrument_tokens,
+            } => {
+                for &token in instrument_tokens.iter() {
+                    self.0.insert(token, *mode);
+                }
+            }
+        }
+    }
+
+    fn tokens(&self) -> Vec<u32> {
+        self.0.keys().copied().collect()
+    }
+
+    fn tokens_in(&self, mode: ReqMode) -> Vec<u32> {
+        self.0
+            .iter()
+            .filter(|&(_, &m)| m == mode)
+            .map(|(&token, _)| token)
+            .collect()
+    }
+
+    /// The subscribe and mode requests restoring every tracked instrument.
+    fn resubscribe_messages(&self) -> Vec<Message> {
+        let tokens = self.tokens();
+        if tokens.is_empty() {
+            return Vec::new();
+        }
+
+        let mut messages = vec![Req::Subscribe(&tokens).to_message()];
+        for mode in [ReqMode::Ltp, ReqMode::Quote, ReqMode::Full] {
+            let instrument_tokens = self.tokens_in(mode);
+            if !instrument_tokens.is_empty() {
+                messages.push(
+                    Req::Mode {
+                        mode,
+                        instrument_tokens: &instrument_tokens,
+                    }
+                    .to_message(),
+                );
+            }
+        }
+
+        messages
     }
+}
+
+/// How [`KiteTicker`] reconnects after the WebSocket drops, see
+/// [`KiteConnect::web_socket_with_reconnect`].
+///
+/// The delay before each attempt doubles from `initial_backoff` up to `max_backoff`. The default
+/// mirrors the official clients: up to 50 attempts, from 1 second up to a minute apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Attempts after a disconnection before giving up, `None` to retry forever
+    pub max_attempts: Option<u32>,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
 
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(50),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before the `attempt`th attempt, counting from zero.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+impl KiteTicker {
+    /// Sends `req` and keeps track of the subscribed instruments and their modes.
+    pub async fn send(&mut self, req: Req<'_>) -> Result<(), Error> {
+        // Tracked while the write half is locked, so a reconnect resubscribes either before or
+        // after this request, never in between
+        let mut write_stream = self.connection.write_stream.lock().await;
+        write_stream.send(req.to_message()).await?;
+        self.connection.subscriptions().track(&req);
+        Ok(())
+    }
+
+    /// Sends a raw message. Subscriptions made this way aren't tracked by [`KiteTicker::subscriptions`]
+    /// and aren't restored on a reconnect.
     pub async fn send_raw(&mut self, req: Message) -> Result<(), Error> {
-        self.write_stream.send(req).await?;
+        self.connection.write_stream.lock().await.send(req).await?;
+        Ok(())
+    }
+
+    /// Returns the currently subscribed instrument tokens with their streaming mode.
+    pub fn subscriptions(&self) -> BTreeMap<u32, ReqMode> {
+        self.connection.subscriptions().0.clone()
+    }
+
+    /// Unsubscribes from every instrument currently subscribed to.
+    pub async fn unsubscribe_all(&mut self) -> Result<(), Error> {
+        let tokens = self.connection.subscriptions().tokens();
+        if tokens.is_empty() {
+            return Ok(());
+        }
+
+        self.send(Req::Unsubscribe(&tokens)).await
+    }
+
+    /// Re-sends the subscribe and mode requests for every tracked instrument, e.g. after a
+    /// manual reconnect.
+    pub async fn resubscribe_all(&mut self) -> Result<(), Error> {
+        let messages = self.connection.subscriptions().resubscribe_messages();
+        for message in messages {
+            self.send_raw(message).await?;
+        }
+
         Ok(())
     }
 
+    /// Subscribes to `symbols` (e.g. `"NSE:INFY"`) in `mode`, resolving them through `resolver`.
+    ///
+    /// Returns the symbols that couldn't be resolved, which are skipped.
+    pub async fn subscribe_symbols<'a, S: AsRef<str>>(
+        &mut self,
+        resolver: &SymbolResolver,
+        symbols: &'a [S],
+        mode: ReqMode,
+    ) -> Result<Vec<&'a str>, Error> {
+        let Resolved {
+            resolved,
+            unresolved,
+        } = resolver.tokens_for(symbols);
+
+        if !resolved.is_empty() {
+            self.send(Req::Subscribe(&resolved)).await?;
+            self.send(Req::Mode {
+                mode,
+                instrument_tokens: &resolved,
+            })
+            .await?;
+        }
+
+        Ok(unresolved)
+    }
+
+    /// Sends a ping every `interval` to keep the WebSocket alive while no ticks come in, e.g.
+    /// outside market hours, or stops pinging with `None`. Pinging is off by default.
+    ///
+    /// Pings of the server are always answered. Pings fail while a
+    /// [reconnecting](KiteConnect::web_socket_with_reconnect) WebSocket is disconnected, which is
+    /// logged as a warning with the `tracing` feature, and stop when the ticker is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a Tokio runtime.
+    pub fn set_ping_interval(&mut self, interval: Option<Duration>) {
+        if let Some(pinger) = self.pinger.take() {
+            pinger.abort();
+        }
+
+        let Some(interval) = interval else {
+            return;
+        };
+        let connection = Arc::downgrade(&self.connection);
+        self.pinger = Some(tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + interval;
+            let mut ticks = tokio::time::interval_at(start, interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                ticks.tick().await;
+                let Some(connection) = connection.upgrade() else {
+                    break;
+                };
+                let mut write_stream = connection.write_stream.lock().await;
+                if let Err(_err) = write_stream.send(Message::Ping(Bytes::new())).await {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("Failed to ping the WebSocket: {_err}");
+                }
+            }
+        }));
+    }
+
     pub async fn wait_handle(self) {
         let _ = self.handle.await;
     }
@@ -116,94 +425,337 @@ impl KiteTicker {
 
 impl KiteConnect<Authenticated> {
     pub async fn web_socket(&self) -> Result<(KiteTicker, Receiver<Ticker>), Error> {
-        let endpoint = format!(
+        let (tx, rx) = crossbeam_channel::unbounded();
+        Ok((connect(self.web_socket_endpoint(), tx, None).await?, rx))
+    }
+
+    /// Same as [`web_socket`](Self::web_socket), but the ticks come as a [`Stream`], e.g. for
+    /// `while let Some(tick) = ticks.next().await` or `select!` in async code.
+    ///
+    /// The stream ends once the WebSocket is closed, after the last [`Ticker::ConnectionClosed`].
+    pub async fn web_socket_stream(
+        &self,
+    ) -> Result<
+        (
+            KiteTicker,
+            impl Stream<Item = Ticker> + Send + Unpin + 'static,
+        ),
+        Error,
+    > {
+        connect_stream(self.web_socket_endpoint()).await
+    }
+
+    /// Same as [`web_socket`](Self::web_socket), but the WebSocket reconnects when it drops.
+    ///
+    /// After a [`Ticker::ConnectionClosed`] the connection is retried following `policy`. Once
+    /// reconnected, the instruments tracked by [`KiteTicker::subscriptions`] are subscribed to
+    /// again in their modes, a [`Ticker::Reconnected`] is sent and ticks resume on the same
+    /// receiver. Requests sent while disconnected fail. When the attempts run out, or the
+    /// [`KiteTicker`] was dropped, the ticks stop after the last [`Ticker::ConnectionClosed`].
+    pub async fn web_socket_with_reconnect(
+        &self,
+        policy: ReconnectPolicy,
+    ) -> Result<(KiteTicker, Receiver<Ticker>), Error> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        Ok((
+            connect(self.web_socket_endpoint(), tx, Some(policy)).await?,
+            rx,
+        ))
+    }
+
+    /// Same as [`web_socket`](Self::web_socket), but ticks are broadcast to every
+    /// [`Receiver`](broadcast::Receiver) subscribed to the returned [`Sender`](broadcast::Sender),
+    /// so that several consumers (a UI, a recorder and a strategy, say) each get every tick.
+    ///
+    /// Receivers only get the ticks sent after they subscribed, and ticks sent while nobody is
+    /// subscribed are dropped. The channel holds the last `capacity` ticks: a receiver falling
+    /// further behind gets [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) with the
+    /// number of ticks it missed and resumes from the oldest tick still held, without slowing
+    /// down the other receivers.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub async fn web_socket_broadcast(
+        &self,
+        capacity: usize,
+    ) -> Result<(KiteTicker, broadcast::Sender<Ticker>), Error> {
+        let (tx, _) = broadcast::channel(capacity);
+        Ok((
+            connect(self.web_socket_endpoint(), tx.clone(), None).await?,
+            tx,
+        ))
+    }
+
+    fn web_socket_endpoint(&self) -> String {
+        format!(
             "{KITE_WEB_SOCKET_ENDPOINT}?api_key={}&access_token={}",
             self.api_key(),
             self.access_token()
-        );
+        )
+    }
+}
 
-        let (socket, _) = connect_async(endpoint).await?;
-        let (write, read) = socket.split();
+async fn connect(
+    endpoint: String,
+    tx: impl TickSink,
+    policy: Option<ReconnectPolicy>,
+) -> Result<KiteTicker, Error> {
+    let (socket, _) = connect_async(&endpoint).await?;
+    let (write, read) = socket.split();
 
-        let (tx, rx) = crossbeam_channel::unbounded();
+    let connection = Arc::new(Connection {
+        write_stream: tokio::sync::Mutex::new(write),
+        subscriptions: Mutex::default(),
+    });
+    let reconnect = policy.map(|policy| Reconnect {
+        endpoint,
+        policy,
+        connection: Arc::downgrade(&connection),
+    });
 
-        let handle = tokio::spawn(async move { handle_read_stream(read, tx).await });
+    let handle = tokio::spawn(async move {
+        let mut read = read;
+        loop {
+            handle_read_stream(read, &tx).await;
 
-        Ok((
-            KiteTicker {
-                handle,
-                write_stream: write,
-            },
-            rx,
-        ))
+            let Some(reconnect) = &reconnect else {
+                break;
+            };
+            let Some(new_read) = reconnect.reconnect().await else {
+                break;
+            };
+            if tx.send(Ticker::Reconnected).is_err() {
+                break;
+            }
+            read = new_read;
+        }
+    });
+
+    Ok(KiteTicker {
+        handle,
+        connection,
+        pinger: None,
+    })
+}
+
+async fn connect_stream(
+    endpoint: String,
+) -> Result<
+    (
+        KiteTicker,
+        impl Stream<Item = Ticker> + Send + Unpin + 'static,
+    ),
+    Error,
+> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let ticker = connect(endpoint, tx, None).await?;
+
+    Ok((
+        ticker,
+        futures_util::stream::poll_fn(move |cx| rx.poll_recv(cx)),
+    ))
+}
+
+struct Reconnect {
+    endpoint: String,
+    policy: ReconnectPolicy,
+    /// Weak so that dropping the [`KiteTicker`] stops the reconnects
+    connection: Weak<Connection>,
+}
+
+impl Reconnect {
+    /// Reconnects and resubscribes, returning the new read half, or `None` once the attempts run
+    /// out.
+    async fn reconnect(&self) -> Option<ReadStream> {
+        let mut attempt = 0;
+        while self.policy.max_attempts.is_none_or(|max| attempt < max) {
+            tokio::time::sleep(self.policy.backoff(attempt)).await;
+            attempt += 1;
+
+            let connection = self.connection.upgrade()?;
+            let (write, read) = match connect_async(&self.endpoint).await {
+                Ok((socket, _)) => socket.split(),
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("Failed to reconnect the WebSocket (attempt {attempt}): {_err}");
+                    continue;
+                }
+            };
+
+            let mut write_stream = connection.write_stream.lock().await;
+            *write_stream = write;
+
+            let messages = connection.subscriptions().resubscribe_messages();
+            let mut resubscribed = Ok(());
+            for message in messages {
+                resubscribed = write_stream.send(message).await;
+                if resubscribed.is_err() {
+                    break;
+                }
+            }
+
+            match resubscribed {
+                Ok(()) => return Some(read),
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("Failed to resubscribe after reconnecting: {_err}");
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Channel the decoded ticks are sent to
+trait TickSink: Send + Sync + 'static {
+    type Error: std::fmt::Display;
+
+    fn send(&self, tick: Ticker) -> Result<(), Self::Error>;
+}
+
+impl TickSink for Sender<Ticker> {
+    type Error = crossbeam_channel::SendError<Ticker>;
+
+    fn send(&self, tick: Ticker) -> Result<(), Self::Error> {
+        Sender::send(self, tick)
     }
 }
 
-async fn handle_read_stream(
-    mut read: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
-    tx: Sender<Ticker>,
-) {
+impl TickSink for mpsc::UnboundedSender<Ticker> {
+    type Error = mpsc::error::SendError<Ticker>;
+
+    fn send(&self, tick: Ticker) -> Result<(), Self::Error> {
+        mpsc::UnboundedSender::send(self, tick)
+    }
+}
+
+impl TickSink for broadcast::Sender<Ticker> {
+    type Error = std::convert::Infallible;
+
+    fn send(&self, tick: Ticker) -> Result<(), Self::Error> {
+        // Having no receivers isn't an error, they may subscribe later
+        let _ = broadcast::Sender::send(self, tick);
+        Ok(())
+    }
+}
+
+/// Decodes the messages of `read` until the WebSocket is closed, sending a single
+/// [`Ticker::ConnectionClosed`] however the connection ended.
+async fn handle_read_stream(mut read: ReadStream, tx: &impl TickSink) {
     use tokio_tungstenite::tungstenite::Error;
 
     while let Some(msg) = read.next().await {
         match msg {
             Ok(msg) => match msg {
-                Message::Binary(bytes) => decode_n_send_bytes(bytes, &tx),
-                Message::Text(_bytes) => { /* TODO */ }
-                Message::Ping(_) | Message::Pong(_) => { /* TODO: Verify if we need to send Ping-Pong manually */
-                }
-                Message::Close(_) => {
-                    if let Err(e) = tx.send(Ticker::ConnectionClosed) {
-                        eprintln!(
-                            "Trying to send \"Connection Closed\" message to already closed channel: {e}"
-                        )
-                    }
-                }
+                Message::Binary(bytes) => decode_n_send_bytes(bytes, tx),
+                Message::Text(text) => decode_n_send_text(&text, tx),
+                // Tungstenite answers pings itself, with the next read or write
+                Message::Ping(_) | Message::Pong(_) => {}
+                Message::Close(_) => break,
                 _ => unreachable!(),
             },
             Err(err) => match err {
-                Error::AlreadyClosed | Error::ConnectionClosed => {
-                    if let Err(e) = tx.send(Ticker::ConnectionClosed) {
-                        eprintln!(
-                            "Trying to send \"Connection Closed\" message to already closed channel: {e}"
-                        )
-                    }
-                    break;
-                }
+                Error::AlreadyClosed | Error::ConnectionClosed => break,
                 _ => eprintln!("Error while sending message to channel: {err}"),
             },
         }
     }
+
+    if let Err(e) = tx.send(Ticker::ConnectionClosed) {
+        eprintln!("Trying to send \"Connection Closed\" message to already closed channel: {e}")
+    }
+}
+
+/// Text message of the WebSocket, e.g. `{"type": "order", "data": {...}}`.
+///
+/// Refer: <https://kite.trade/docs/connect/v3/websocket/#postbacks-and-non-binary-updates>
+#[derive(Deserialize)]
+struct TextMessage {
+    #[serde(rename = "type")]
+    message_type: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+/// Sends the order updates among the text messages, the other messages (errors and broker
+/// messages) are ignored.
+fn decode_n_send_text(text: &str, tx: &impl TickSink) {
+    let message = match serde_json::from_str::<TextMessage>(text) {
+        Ok(message) if message.message_type == "order" => message,
+        Ok(_) => return,
+        Err(_err) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("Failed to decode text message: {_err}");
+            return;
+        }
+    };
+
+    match serde_json::from_value::<Order>(message.data) {
+        Ok(order) => {
+            if let Err(_err) = tx.send(Ticker::OrderUpdate(Box::new(order))) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("Trying to send Order Update to channel which is closed: {_err}");
+            }
+        }
+        Err(_err) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("Failed to decode order update: {_err}");
+        }
+    }
 }
 
 // TODO: Support parallel decoding for multiple packets
-fn decode_n_send_bytes(bytes: Bytes, tx: &Sender<Ticker>) {
+fn decode_n_send_bytes(bytes: Bytes, tx: &impl TickSink) {
     if bytes.len() < 2 {
         return;
     }
 
+    let len = bytes.len();
     let mut cursor = Cursor::new(bytes);
 
-    // TODO: Should we unwrap this?
     let total_packets = cursor.read_u16::<BigEndian>().unwrap();
 
     for _ in 0..total_packets {
-        let packet_len = cursor.read_u16::<BigEndian>().unwrap();
+        let remaining = len.saturating_sub(cursor.position() as usize + 2);
+        let Ok(packet_len) = cursor.read_u16::<BigEndian>() else {
+            return;
+        };
+
+        // A bogus length would desync every following packet, so the rest of the message is dropped
+        if packet_len as usize > remaining {
+            send_error(
+                TickerError::TruncatedPacket {
+                    packet_len,
+                    remaining,
+                },
+                tx,
+            );
+            return;
+        }
+
+        let end = cursor.position() + packet_len as u64;
 
         match packet_len {
             8 => send_ltp_quote_packet(&mut cursor, tx),
             28 | 32 => send_indices_quote_packet(&mut cursor, packet_len, tx),
             44 | 184 => send_quote_n_full_packet(&mut cursor, packet_len, tx),
-            _ => {
-                eprintln!("Got unsupported packet length {packet_len}. Skipping this packet");
-                cursor.seek(SeekFrom::Current(packet_len as i64)).unwrap();
-            }
+            _ => send_error(TickerError::UnsupportedPacket { packet_len }, tx),
         }
+
+        cursor.set_position(end);
+    }
+}
+
+fn send_error(err: TickerError, tx: &impl TickSink) {
+    if let Err(err) = tx.send(Ticker::Error(err)) {
+        eprintln!("Trying to send Error to channel which is closed: {err}")
     }
 }
 
 // Refer: https://github.com/zerodha/pykiteconnect/blob/6b7b7621e575411921b506203b526bf275a702c7/kiteconnect/ticker.py#L740
-fn send_ltp_quote_packet(cursor: &mut Cursor<Bytes>, tx: &Sender<Ticker>) {
+fn send_ltp_quote_packet(cursor: &mut Cursor<Bytes>, tx: &impl TickSink) {
     let instrument_token = cursor.read_u32::<BigEndian>().unwrap();
     let last_price = cursor.read_u32::<BigEndian>().unwrap();
 
@@ -219,7 +771,7 @@ fn send_ltp_quote_packet(cursor: &mut Cursor<Bytes>, tx: &Sender<Ticker>) {
 }
 
 // Refer: https://kite.trade/docs/connect/v3/websocket/#index-packet-structure
-fn send_indices_quote_packet(cursor: &mut Cursor<Bytes>, packet_len: u16, tx: &Sender<Ticker>) {
+fn send_indices_quote_packet(cursor: &mut Cursor<Bytes>, packet_len: u16, tx: &impl TickSink) {
     let instrument_token = cursor.read_u32::<BigEndian>().unwrap();
     let last_price = cursor.read_u32::<BigEndian>().unwrap();
     let high_of_day = cursor.read_u32::<BigEndian>().unwrap();
@@ -255,7 +807,7 @@ fn send_indices_quote_packet(cursor: &mut Cursor<Bytes>, packet_len: u16, tx: &S
 }
 
 // Refer: https://github.com/zerodha/pykiteconnect/blob/6b7b7621e575411921b506203b526bf275a702c7/kiteconnect/ticker.py#L780
-fn send_quote_n_full_packet(cursor: &mut Cursor<Bytes>, packet_len: u16, tx: &Sender<Ticker>) {
+fn send_quote_n_full_packet(cursor: &mut Cursor<Bytes>, packet_len: u16, tx: &impl TickSink) {
     let instrument_token = cursor.read_u32::<BigEndian>().unwrap();
 
     let divisor = get_divisor(instrument_token);
@@ -348,3 +900,396 @@ const fn get_divisor(instrument_token: u32) -> f64 {
         _ => 100.0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriptions() {
+        let mut subscriptions = Subscriptions::default();
+
+        subscriptions.track(&Req::Subscribe(&[408065, 884737, 256265]));
+        subscriptions.track(&Req::Mode {
+            mode: ReqMode::Full,
+            instrument_tokens: &[884737],
+        });
+        subscriptions.track(&Req::Unsubscribe(&[256265]));
+        // Re-subscribing must not reset the mode
+        subscriptions.track(&Req::Subscribe(&[884737]));
+
+        assert_eq!(
+            subscriptions.0,
+            BTreeMap::from([(408065, ReqMode::Quote), (884737, ReqMode::Full)])
+        );
+        assert_eq!(subscriptions.tokens_in(ReqMode::Full), vec![884737]);
+
+        let tokens = subscriptions.tokens();
+        assert_eq!(
+            Req::Unsubscribe(&tokens).to_message(),
+            Message::Text(r#"{"a":"unsubscribe","v":[408065,884737]}"#.into())
+        );
+
+        subscriptions.track(&Req::Unsubscribe(&tokens));
+        assert!(subscriptions.tokens().is_empty());
+    }
+
+    #[test]
+    fn test_reconnect_policy() {
+        let policy = ReconnectPolicy::default();
+
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(8));
+        assert_eq!(policy.backoff(10), Duration::from_secs(60));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() -> Result<(), Box<dyn std::error::Error>> {
+        use tokio::time::timeout;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("ws://{}", listener.local_addr()?);
+        let (received_tx, mut received) = tokio::sync::mpsc::unbounded_channel();
+
+        // Drops the first connection after the subscriptions, sends a tick on the second one, then
+        // goes away for good
+        let server = tokio::spawn(async move {
+            for expected in [2, 3] {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                for _ in 0..expected {
+                    if let Some(Ok(Message::Text(text))) = socket.next().await {
+                        received_tx.send(text.to_string()).unwrap();
+                    }
+                }
+                if expected == 3 {
+                    let mut frame = 1u16.to_be_bytes().to_vec();
+                    frame.extend(ltp_packet(408065, 150_000));
+                    socket.send(Message::Binary(frame.into())).await.unwrap();
+                }
+                socket.close(None).await.unwrap();
+            }
+        });
+
+        let (tx, mut rx) = broadcast::channel(16);
+        let policy = ReconnectPolicy {
+            max_attempts: Some(2),
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        };
+        let mut ticker = connect(endpoint, tx, Some(policy)).await?;
+        ticker.send(Req::Subscribe(&[408065, 884737])).await?;
+        ticker
+            .send(Req::Mode {
+                mode: ReqMode::Full,
+                instrument_tokens: &[884737],
+            })
+            .await?;
+
+        let mut ticks = Vec::new();
+        for _ in 0..4 {
+            ticks.push(timeout(Duration::from_secs(5), rx.recv()).await??);
+        }
+        assert_eq!(
+            ticks,
+            [
+                Ticker::ConnectionClosed,
+                Ticker::Reconnected,
+                Ticker::LtpQuote(LtpQuote {
+                    instrument_token: 408065,
+                    last_price: 1500.0,
+                }),
+                Ticker::ConnectionClosed,
+            ]
+        );
+
+        let mut messages = Vec::new();
+        while let Ok(message) = received.try_recv() {
+            messages.push(message);
+        }
+        assert_eq!(
+            messages,
+            [
+                r#"{"a":"subscribe","v":[408065,884737]}"#,
+                r#"{"a":"mode","v":["full",[884737]]}"#,
+                // Resubscribed after the reconnect
+                r#"{"a":"subscribe","v":[408065,884737]}"#,
+                r#"{"a":"mode","v":["quote",[408065]]}"#,
+                r#"{"a":"mode","v":["full",[884737]]}"#,
+            ]
+        );
+
+        // The server is gone, the attempts run out
+        server.await?;
+        timeout(Duration::from_secs(5), ticker.wait_handle()).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tick_stream() -> Result<(), Box<dyn std::error::Error>> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("ws://{}", listener.local_addr()?);
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut frame = 2u16.to_be_bytes().to_vec();
+            frame.extend(ltp_packet(408065, 150_000));
+            frame.extend(ltp_packet(884737, 90_050));
+            socket.send(Message::Binary(frame.into())).await.unwrap();
+            socket.close(None).await.unwrap();
+        });
+
+        let (_ticker, ticks) = connect_stream(endpoint).await?;
+        let ticks: Vec<_> =
+            tokio::time::timeout(Duration::from_secs(5), ticks.collect::<Vec<_>>()).await?;
+        assert_eq!(
+            ticks,
+            [
+                Ticker::LtpQuote(LtpQuote {
+                    instrument_token: 408065,
+                    last_price: 1500.0,
+                }),
+                Ticker::LtpQuote(LtpQuote {
+                    instrument_token: 884737,
+                    last_price: 900.5,
+                }),
+                Ticker::ConnectionClosed,
+            ]
+        );
+        server.await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ping_pong() -> Result<(), Box<dyn std::error::Error>> {
+        use tokio::time::timeout;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("ws://{}", listener.local_addr()?);
+        let (received_tx, mut received) = tokio::sync::mpsc::unbounded_channel();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            socket
+                .send(Message::Ping(Bytes::from_static(b"heartbeat")))
+                .await
+                .unwrap();
+            while let Some(Ok(message)) = socket.next().await {
+                if received_tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let (mut ticker, _ticks) = connect_stream(endpoint).await?;
+
+        // Answered without any ping from the client
+        let reply = timeout(Duration::from_secs(5), received.recv()).await?;
+        assert_eq!(reply, Some(Message::Pong(Bytes::from_static(b"heartbeat"))));
+        assert!(received.try_recv().is_err());
+
+        ticker.set_ping_interval(Some(Duration::from_millis(10)));
+        for _ in 0..2 {
+            let ping = timeout(Duration::from_secs(5), received.recv()).await?;
+            assert_eq!(ping, Some(Message::Ping(Bytes::new())));
+        }
+
+        ticker.set_ping_interval(None);
+        drop(ticker);
+        server.abort();
+
+        Ok(())
+    }
+
+    fn ltp_packet(instrument_token: u32, last_price: u32) -> Vec<u8> {
+        let mut packet = 8u16.to_be_bytes().to_vec();
+        packet.extend(instrument_token.to_be_bytes());
+        packet.extend(last_price.to_be_bytes());
+        packet
+    }
+
+    fn decode(frame: Vec<u8>) -> Vec<Ticker> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        decode_n_send_bytes(Bytes::from(frame), &tx);
+        drop(tx);
+        rx.iter().collect()
+    }
+
+    #[test]
+    fn test_decode_broadcast() {
+        let (tx, mut ui) = broadcast::channel(2);
+        let mut recorder = tx.subscribe();
+
+        let mut frame = 3u16.to_be_bytes().to_vec();
+        for last_price in [150_000, 150_100, 150_200] {
+            frame.extend(ltp_packet(408065, last_price));
+        }
+        decode_n_send_bytes(Bytes::from(frame.clone()), &tx);
+
+        let tick = |last_price| {
+            Ticker::LtpQuote(LtpQuote {
+                instrument_token: 408065,
+                last_price,
+            })
+        };
+
+        // Each receiver gets every tick still held, the oldest one was overwritten
+        for rx in [&mut ui, &mut recorder] {
+            assert_eq!(
+                rx.try_recv(),
+                Err(broadcast::error::TryRecvError::Lagged(1))
+            );
+            assert_eq!(rx.try_recv(), Ok(tick(1501.0)));
+            assert_eq!(rx.try_recv(), Ok(tick(1502.0)));
+        }
+
+        // Without receivers the ticks are dropped
+        drop((ui, recorder));
+        decode_n_send_bytes(Bytes::from(frame), &tx);
+        assert_eq!(
+            tx.subscribe().try_recv(),
+            Err(broadcast::error::TryRecvError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_decode_order_update() {
+        let text = r#"{
+            "type": "order",
+            "data": {
+                "account_id": "AB1234",
+                "unfilled_quantity": 0,
+                "checksum": "",
+                "placed_by": "AB1234",
+                "order_id": "230808000000001",
+                "exchange_order_id": "1300000000000001",
+                "parent_order_id": null,
+                "status": "COMPLETE",
+                "status_message": null,
+                "status_message_raw": null,
+                "order_timestamp": "2023-08-08 09:15:02",
+                "exchange_update_timestamp": "2023-08-08 09:15:02",
+                "exchange_timestamp": "2023-08-08 09:15:02",
+                "variety": "regular",
+                "exchange": "NSE",
+                "tradingsymbol": "INFY",
+                "instrument_token": 408065,
+                "order_type": "MARKET",
+                "transaction_type": "BUY",
+                "validity": "DAY",
+                "product": "CNC",
+                "quantity": 1,
+                "disclosed_quantity": 0,
+                "price": 0,
+                "trigger_price": 0,
+                "average_price": 1400.5,
+                "filled_quantity": 1,
+                "pending_quantity": 0,
+                "cancelled_quantity": 0,
+                "market_protection": 0,
+                "meta": {},
+                "tag": null,
+                "guid": "XXXXXX"
+            }
+        }"#;
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        decode_n_send_text(text, &tx);
+        // Other messages are ignored
+        decode_n_send_text(r#"{"type": "message", "data": "Market is closed"}"#, &tx);
+        decode_n_send_text(r#"{"type": "error", "data": "Invalid mode"}"#, &tx);
+        decode_n_send_text("not json", &tx);
+        decode_n_send_text(r#"{"type": "order", "data": {"order_id": "1"}}"#, &tx);
+        drop(tx);
+
+        let ticks: Vec<_> = rx.iter().collect();
+        let [Ticker::OrderUpdate(order)] = &ticks[..] else {
+            panic!("{ticks:?}");
+        };
+        assert_eq!(order.order_id, "230808000000001");
+        assert_eq!(order.status, crate::orders::OrderStatus::Complete);
+        assert_eq!(order.instrument_token, "408065");
+        assert_eq!(order.average_price, Some(1400.5));
+        assert_eq!(order.filled_quantity, 1);
+        assert!(!order.modified);
+        assert_eq!(order.extra.get("account_id"), Some(&"AB1234".into()));
+        assert_eq!(ticks[0].instrument_token(), None);
+    }
+
+    #[test]
+    fn test_decode_unsupported_packet() {
+        let mut frame = 3u16.to_be_bytes().to_vec();
+        frame.extend(ltp_packet(408065, 150_000));
+        frame.extend(12u16.to_be_bytes());
+        frame.extend([0xff; 12]);
+        frame.extend(ltp_packet(884737, 90_050));
+
+        assert_eq!(
+            decode(frame),
+            [
+                Ticker::LtpQuote(LtpQuote {
+                    instrument_token: 408065,
+                    last_price: 1500.0,
+                }),
+                Ticker::Error(TickerError::UnsupportedPacket { packet_len: 12 }),
+                Ticker::LtpQuote(LtpQuote {
+                    instrument_token: 884737,
+                    last_price: 900.5,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated_packet() {
+        let mut frame = 3u16.to_be_bytes().to_vec();
+        frame.extend(ltp_packet(408065, 150_000));
+        // Claims more bytes than the frame has left
+        frame.extend(500u16.to_be_bytes());
+        frame.extend(ltp_packet(884737, 90_050));
+
+        assert_eq!(
+            decode(frame),
+            [
+                Ticker::LtpQuote(LtpQuote {
+                    instrument_token: 408065,
+                    last_price: 1500.0,
+                }),
+                Ticker::Error(TickerError::TruncatedPacket {
+                    packet_len: 500,
+                    remaining: 10,
+                }),
+            ]
+        );
+
+        // Fewer packets than announced
+        let mut frame = 2u16.to_be_bytes().to_vec();
+        frame.extend(ltp_packet(408065, 150_000));
+        assert_eq!(decode(frame).len(), 1);
+    }
+
+    #[test]
+    fn test_tick_store() {
+        let store = TickStore::new();
+
+        store.update(&Ticker::LtpQuote(LtpQuote {
+            instrument_token: 408065,
+            last_price: 1500.0,
+        }));
+        store.update(&Ticker::ConnectionClosed);
+        store.update(&Ticker::Reconnected);
+        store.update(&Ticker::LtpQuote(LtpQuote {
+            instrument_token: 408065,
+            last_price: 1501.5,
+        }));
+
+        assert_eq!(store.last_price(408065), Some(1501.5));
+        assert_eq!(store.last_price(884737), None);
+        assert_eq!(Ticker::ConnectionClosed.instrument_token(), None);
+    }
+}
@@ -0,0 +1,157 @@
+use kite_connect::{
+    Error, KiteError,
+    accounts::{AccountManager, combine_funds, combine_positions},
+    orders::PlaceOrderRequest,
+    test_utils::{self, MockKite},
+};
+
+fn market_order(quantity: u32) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+        quantity,
+        ..test_utils::market_order()
+    }
+}
+
+fn body_of(request: &wiremock::Request) -> String {
+    String::from_utf8_lossy(&request.body).into_owned()
+}
+
+#[tokio::test]
+async fn test_place_order_across_accounts() -> Result<(), Box<dyn std::error::Error>> {
+    let (alice, bob, carol) = (
+        MockKite::start().await,
+        MockKite::start().await,
+        MockKite::start().await,
+    );
+    alice.mount_place_order().await;
+    bob.mount_place_order().await;
+    carol
+        .mount_error(
+            "POST",
+            "/orders/regular",
+            400,
+            "MarginException",
+            "Insufficient funds",
+        )
+        .await;
+
+    let accounts = AccountManager::new()
+        .with_account("alice", alice.kite())
+        .with_account("bob", bob.kite())
+        .with_account("carol", carol.kite());
+    assert_eq!(
+        accounts.names().collect::<Vec<_>>(),
+        ["alice", "bob", "carol"]
+    );
+
+    // Nothing is placed when an account is allocated twice
+    let res = accounts
+        .place_order(&market_order(10), &[("alice", 1.0), ("alice", 0.05)])
+        .await;
+    assert!(matches!(
+        res,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    let req = market_order(10).with_idempotency_key();
+    let results = accounts
+        .place_order(
+            &req,
+            &[("alice", 1.0), ("bob", 2.5), ("carol", 1.0), ("dave", 1.0)],
+        )
+        .await?;
+
+    assert_eq!(results.len(), 4);
+    assert!(results["alice"].is_ok());
+    let bob_order = results["bob"].as_ref().map_err(|err| err.to_string())?;
+    assert_eq!(bob_order.order_id, "151220000000000");
+    // The key to retry the placement on the account
+    assert_eq!(bob_order.idempotency_key, req.idempotency_key);
+    assert!(matches!(
+        results["carol"],
+        Err(Error::KiteError(KiteError::MarginException(_)))
+    ));
+    assert!(matches!(
+        results["dave"],
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    // A small allocation rounds down to no quantity
+    let results = accounts
+        .place_order(&market_order(10), &[("alice", 0.05)])
+        .await?;
+    assert!(matches!(
+        results["alice"],
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    let alice_requests = alice.server().received_requests().await.unwrap_or_default();
+    let bob_requests = bob.server().received_requests().await.unwrap_or_default();
+    assert_eq!(alice_requests.len(), 1);
+    assert!(body_of(&alice_requests[0]).contains("&quantity=10&"));
+    assert_eq!(bob_requests.len(), 1);
+    assert!(body_of(&bob_requests[0]).contains("&quantity=25&"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_combine_accounts() -> Result<(), Box<dyn std::error::Error>> {
+    let (alice, bob, carol) = (
+        MockKite::start().await,
+        MockKite::start().await,
+        MockKite::start().await,
+    );
+    for mock in [&alice, &bob] {
+        mock.mount_positions().await;
+        mock.mount_funds().await;
+    }
+    carol
+        .mount_error(
+            "GET",
+            "/portfolio/positions",
+            403,
+            "TokenException",
+            "Expired",
+        )
+        .await;
+    carol
+        .mount_error("GET", "/user/margins", 403, "TokenException", "Expired")
+        .await;
+
+    let accounts = AccountManager::new()
+        .with_account("alice", alice.kite())
+        .with_account("bob", bob.kite())
+        .with_account("carol", carol.kite());
+
+    let positions = accounts.get_positions().await;
+    assert!(positions["alice"].is_ok() && positions["bob"].is_ok());
+    assert!(matches!(
+        positions["carol"],
+        Err(Error::KiteError(KiteError::TokenException(_)))
+    ));
+
+    let combined = combine_positions(&positions);
+    assert_eq!(combined.len(), 3);
+    let lead = combined
+        .iter()
+        .find(|p| p.trading_symbol == "LEADMINI17DECFUT")
+        .unwrap();
+    assert_eq!(lead.quantity, 2);
+    assert_eq!(lead.value, -322100.0);
+    assert_eq!(lead.accounts, ["alice", "bob"]);
+    let gold = combined
+        .iter()
+        .find(|p| p.trading_symbol == "GOLDGUINEA17DECFUT")
+        .unwrap();
+    assert_eq!(gold.pnl, 1602.0);
+
+    let funds = accounts.get_funds().await;
+    assert!(funds["carol"].is_err());
+    let combined = combine_funds(&funds);
+    assert!((combined.equity.net - 2.0 * 99725.05).abs() < 1e-6);
+    assert!((combined.equity.debits - 2.0 * 145706.55).abs() < 1e-6);
+    assert!((combined.commodity.live_balance - 2.0 * 100661.7).abs() < 1e-6);
+
+    Ok(())
+}
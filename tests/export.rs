@@ -0,0 +1,37 @@
+use kite_connect::{
+    export::{write_orders_csv, write_trades_csv},
+    orders::{Order, Trade},
+    test_utils::fixtures,
+};
+use serde::de::DeserializeOwned;
+
+fn data<T: DeserializeOwned>(fixture: &str) -> Result<T, Box<dyn std::error::Error>> {
+    let mut envelope: serde_json::Value = serde_json::from_str(fixture)?;
+    Ok(serde_json::from_value(envelope["data"].take())?)
+}
+
+#[test]
+fn test_write_orders_csv() -> Result<(), Box<dyn std::error::Error>> {
+    // The rejected order from the history has commas in `status_message_raw`
+    let mut orders: Vec<Order> = data(fixtures::ORDERS)?;
+    orders.extend(data::<Vec<Order>>(fixtures::ORDER_HISTORY)?.pop());
+
+    let mut csv = Vec::new();
+    write_orders_csv(&orders, &mut csv)?;
+
+    assert_eq!(String::from_utf8(csv)?, include_str!("golden/orders.csv"));
+
+    Ok(())
+}
+
+#[test]
+fn test_write_trades_csv() -> Result<(), Box<dyn std::error::Error>> {
+    let trades: Vec<Trade> = data(fixtures::TRADES)?;
+
+    let mut csv = Vec::new();
+    write_trades_csv(&trades, &mut csv)?;
+
+    assert_eq!(String::from_utf8(csv)?, include_str!("golden/trades.csv"));
+
+    Ok(())
+}
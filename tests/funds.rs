@@ -0,0 +1,71 @@
+use kite_connect::{
+    orders::{Exchange, PlaceOrderRequest, Product, Variety},
+    test_utils::MockKite,
+};
+use std::time::Duration;
+
+async fn funds_requests(mock: &MockKite) -> usize {
+    mock.server()
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.url.path() == "/user/margins")
+        .count()
+}
+
+#[tokio::test]
+async fn test_funds_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_funds().await;
+    mock.mount_place_order().await;
+    let kite = mock.kite().with_funds_cache(Duration::from_secs(60));
+
+    let funds = kite.get_funds().await?;
+    assert_eq!(kite.get_funds().await?, funds);
+    assert_eq!(funds_requests(&mock).await, 1);
+
+    // Placing an order empties the cache
+    let req = PlaceOrderRequest::builder(Variety::Regular, "INFY", Exchange::NSE)
+        .buy()
+        .quantity(1)
+        .product(Product::CNC)
+        .build()?;
+    kite.place_order(&req).await?;
+    kite.get_funds().await?;
+    assert_eq!(funds_requests(&mock).await, 2);
+
+    // Other requests keep it, even the ones that aren't a GET
+    mock.mount_json(
+        "POST",
+        "/margins/orders",
+        200,
+        r#"{"status": "success", "data": []}"#,
+    )
+    .await;
+    kite.get_order_margins(&[]).await?;
+    kite.get_funds().await?;
+    assert_eq!(funds_requests(&mock).await, 2);
+
+    // Disabled by default
+    let kite = mock.kite();
+    kite.get_funds().await?;
+    kite.get_funds().await?;
+    assert_eq!(funds_requests(&mock).await, 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_funds_cache_expires() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_funds().await;
+    // A zero TTL expires every entry as soon as it is stored
+    let kite = mock.kite().with_funds_cache(Duration::ZERO);
+
+    kite.get_funds().await?;
+    kite.get_funds().await?;
+    assert_eq!(funds_requests(&mock).await, 2);
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use kite_connect::{
+    Error, KiteError,
+    gtt::{GttCondition, GttStatus, GttType, PlaceGttRequest, TriggerId},
+    orders::Exchange,
+    test_utils::{MockKite, fixtures},
+};
+
+#[tokio::test]
+async fn test_get_gtts() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_gtts().await;
+
+    let gtts = mock.kite().get_gtts().await?;
+    assert_eq!(gtts.len(), 4);
+    assert_eq!(gtts[1].orders.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_gtt() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    let gtts: serde_json::Value = serde_json::from_str(fixtures::GTTS)?;
+    let body = serde_json::json!({ "status": "success", "data": gtts["data"][1] });
+    mock.mount_json("GET", "/gtt/triggers/105099", 200, &body.to_string())
+        .await;
+    mock.mount_error(
+        "GET",
+        "/gtt/triggers/1",
+        400,
+        "InputException",
+        "Invalid trigger id",
+    )
+    .await;
+
+    let gtt = mock.kite().get_gtt(TriggerId(105099)).await?;
+    assert_eq!(gtt.id, TriggerId(105099));
+    assert_eq!(gtt.status, GttStatus::Triggered);
+
+    assert!(matches!(
+        mock.kite().get_gtt(TriggerId(1)).await,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_modify_gtt() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_json(
+        "PUT",
+        "/gtt/triggers/112127",
+        200,
+        r#"{"status": "success", "data": {"trigger_id": 112127}}"#,
+    )
+    .await;
+
+    let req = PlaceGttRequest {
+        gtt_type: GttType::Single,
+        condition: GttCondition {
+            exchange: Exchange::NSE,
+            trading_symbol: "INFY".into(),
+            trigger_values: vec![1350.0],
+            last_price: 1412.95,
+        },
+        orders: Vec::new(),
+    };
+    assert_eq!(
+        mock.kite().modify_gtt(TriggerId(112127), &req).await?,
+        TriggerId(112127)
+    );
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    let form: Vec<(String, String)> = serde_urlencoded::from_bytes(&requests[0].body)?;
+    assert_eq!(form[0], ("type".into(), "single".into()));
+    assert!(form[1].1.contains(r#""trigger_values":[1350.0]"#));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_expired_gtts() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_gtts().await;
+    mock.mount_delete_gtt(105099).await;
+    mock.mount_error(
+        "DELETE",
+        "/gtt/triggers/98712",
+        400,
+        "InputException",
+        "Trigger is already expired",
+    )
+    .await;
+    mock.mount_delete_gtt(98713).await;
+
+    let results = mock.kite().delete_expired_gtts().await?;
+
+    // The active trigger expiring in 2099 is kept, one deletion fails
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().ok(), Some(&TriggerId(105099)));
+    assert!(matches!(
+        results[1],
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+    assert_eq!(results[2].as_ref().ok(), Some(&TriggerId(98713)));
+
+    let deleted: Vec<_> = mock
+        .server()
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| r.method.as_str() == "DELETE")
+        .map(|r| r.url.path().to_owned())
+        .collect();
+    assert_eq!(
+        deleted,
+        [
+            "/gtt/triggers/105099",
+            "/gtt/triggers/98712",
+            "/gtt/triggers/98713"
+        ]
+    );
+
+    Ok(())
+}
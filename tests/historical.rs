@@ -0,0 +1,113 @@
+use kite_connect::{
+    Error, KiteError,
+    historical::{HistoricalCandleReq, Interval},
+    test_utils::MockKite,
+};
+
+fn req() -> HistoricalCandleReq {
+    HistoricalCandleReq {
+        from: "2019-12-04 09:15:00".to_string(),
+        to: "2019-12-04 09:16:00".to_string(),
+        continuous: false,
+        oi: true,
+    }
+}
+
+#[tokio::test]
+async fn test_get_historical_data() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_historical(5633, Interval::Minute).await;
+
+    let candles = mock
+        .kite()
+        .get_historical_data(5633, Interval::Minute, req())
+        .await?;
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[0].timestamp, "2019-12-04T09:15:00+0530");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_historical_data_without_data() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_json(
+        "GET",
+        "/instruments/historical/5633/minute",
+        200,
+        r#"{"status": "success", "data": {"candles": []}}"#,
+    )
+    .await;
+    mock.mount_json(
+        "GET",
+        "/instruments/historical/1/minute",
+        400,
+        r#"{"status": "error", "message": "invalid token", "error_type": "InputException"}"#,
+    )
+    .await;
+
+    // No data is an empty `Vec`, a bad request an error
+    let candles = mock
+        .kite()
+        .get_historical_data(5633, Interval::Minute, req())
+        .await?;
+    assert!(candles.is_empty());
+
+    let res = mock
+        .kite()
+        .get_historical_data(1, Interval::Minute, req())
+        .await;
+    assert!(matches!(
+        res,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_historical_data_bulk_cancelled() {
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+    use wiremock::{Mock, ResponseTemplate, matchers};
+
+    let mock = MockKite::start().await;
+    mock.mount_historical(5633, Interval::Minute).await;
+    // The second request never gets an answer before the token is cancelled
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path(format!(
+            "/instruments/historical/408065/{}",
+            Interval::Minute
+        )))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3600)))
+        .mount(mock.server())
+        .await;
+
+    let token = CancellationToken::new();
+    let kite = mock.kite().with_cancellation_token(token.clone());
+    let req = req();
+    let cancel_after_second_request = async {
+        while mock
+            .server()
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .len()
+            < 2
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        token.cancel();
+    };
+
+    let (results, ()) = tokio::join!(
+        kite.get_historical_data_bulk(&[5633, 408065, 738561], Interval::Minute, &req),
+        cancel_after_second_request
+    );
+
+    // The first request completes, the second one is cut short and the third is never sent
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::Cancelled)));
+    assert!(matches!(results[2], Err(Error::Cancelled)));
+    assert_eq!(mock.server().received_requests().await.unwrap().len(), 2);
+}
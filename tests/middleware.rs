@@ -0,0 +1,85 @@
+use http::Extensions;
+use kite_connect::{Error, test_utils::MockKite};
+use reqwest::{Request, Response, header::HeaderValue};
+use reqwest_middleware::{ClientBuilder, Middleware, Next};
+
+struct CorrelationId;
+
+#[async_trait::async_trait]
+impl Middleware for CorrelationId {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        req.headers_mut()
+            .insert("X-Correlation-Id", HeaderValue::from_static("test-42"));
+        next.run(req, extensions).await
+    }
+}
+
+struct Deny;
+
+#[async_trait::async_trait]
+impl Middleware for Deny {
+    async fn handle(
+        &self,
+        _req: Request,
+        _extensions: &mut Extensions,
+        _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        Err(reqwest_middleware::Error::middleware(
+            std::io::Error::other("denied"),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_middleware_client() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_orders().await;
+
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(CorrelationId)
+        .build();
+    let kite = mock.kite().with_middleware_client(client);
+
+    assert!(!kite.get_orders().await?.is_empty());
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    let headers = &requests[0].headers;
+    assert_eq!(headers["X-Correlation-Id"], "test-42");
+    assert_eq!(headers["X-Kite-Version"], "3");
+    assert!(
+        headers["Authorization"]
+            .to_str()?
+            .starts_with(&format!("token {}:", kite.api_key()))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_middleware_error() {
+    let mock = MockKite::start().await;
+    mock.mount_orders().await;
+
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(Deny)
+        .build();
+    let res = mock
+        .kite()
+        .with_middleware_client(client)
+        .get_orders()
+        .await;
+
+    assert!(matches!(res, Err(Error::Middleware(_))));
+    assert!(
+        mock.server()
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .is_empty()
+    );
+}
@@ -0,0 +1,602 @@
+use kite_connect::{
+    Error, KiteError,
+    orders::{
+        Exchange, ModifyIcebergOrderRequest, ModifyOrderRequest, OrderId, OrderStatus,
+        PlaceOrderRequest, Product, Variety,
+    },
+    test_utils::{self, MockKite, fixtures},
+};
+
+fn market_order(variety: Variety) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+        variety,
+        tag: Some("mock".to_string()),
+        ..test_utils::market_order()
+    }
+}
+
+#[tokio::test]
+async fn test_get_orders() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_orders().await;
+
+    let orders = mock.kite().get_orders().await?;
+    assert!(!orders.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_version_header() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_orders().await;
+
+    mock.kite().get_orders().await?;
+    // Kept when authenticating
+    let kite = mock
+        .pending_kite()
+        .with_api_version("3.1")
+        .authenticate_with_access_token("token".into())?;
+    assert_eq!(kite.api_version(), "3.1");
+    kite.get_orders().await?;
+
+    let versions: Vec<_> = mock
+        .server()
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| r.headers["X-Kite-Version"].to_str().map(str::to_owned))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(versions, ["3", "3.1"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_place_order_poll() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_place_order().await;
+
+    let placed = mock
+        .kite()
+        .place_order_poll(&market_order(Variety::Regular))
+        .await?;
+    assert_eq!(placed.order_id, "151220000000000");
+    assert_eq!(placed.idempotency_key, None);
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url.path(), "/orders/regular");
+
+    // No key is generated for an untagged order
+    let untagged = PlaceOrderRequest {
+        tag: None,
+        ..market_order(Variety::Regular)
+    };
+    let placed = mock.kite().place_order_poll(&untagged).await?;
+    assert_eq!(placed.idempotency_key, None);
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    let body = String::from_utf8_lossy(&requests[1].body);
+    assert!(!body.contains("tag="), "{body}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_order_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_place_order().await;
+
+    let kite = mock
+        .kite()
+        .with_default_order_tag("strategy1".to_string())?;
+    let untagged = PlaceOrderRequest {
+        tag: None,
+        ..market_order(Variety::Regular)
+    };
+    kite.place_order_poll(&untagged).await?;
+    kite.place_order_poll(&market_order(Variety::Regular))
+        .await?;
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    let bodies: Vec<_> = requests
+        .iter()
+        .map(|r| String::from_utf8_lossy(&r.body).into_owned())
+        .collect();
+    assert!(bodies[0].ends_with("&tag=strategy1"), "{}", bodies[0]);
+    assert!(bodies[1].ends_with("&tag=mock"), "{}", bodies[1]);
+
+    for tag in ["", "strategy-1", "averyveryverylongtag1"] {
+        assert!(matches!(
+            mock.kite().with_default_order_tag(tag.to_string()),
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_place_order_margin_exception() {
+    let mock = MockKite::start().await;
+    mock.mount_json("POST", "/orders/regular", 400, fixtures::MARGIN_EXCEPTION)
+        .await;
+
+    let res = mock
+        .kite()
+        .place_order_poll(&market_order(Variety::Regular))
+        .await;
+    assert!(matches!(
+        res,
+        Err(Error::KiteError(KiteError::MarginException(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_token_exception() {
+    let mock = MockKite::start().await;
+    mock.mount_json("GET", "/orders", 403, fixtures::TOKEN_EXCEPTION)
+        .await;
+
+    let res = mock.kite().get_orders().await;
+    assert!(matches!(
+        res,
+        Err(Error::KiteError(KiteError::TokenException(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_place_order_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    use std::time::Duration;
+    use wiremock::{Mock, ResponseTemplate, matchers};
+
+    let mock = MockKite::start().await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/orders/regular"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(fixtures::PLACE_ORDER, "application/json")
+                // Longer than the default timeout of the client
+                .set_delay(Duration::from_millis(1500)),
+        )
+        .mount(mock.server())
+        .await;
+    let kite = mock.kite();
+    let req = market_order(Variety::Regular);
+
+    assert!(matches!(
+        kite.place_order(&req).await,
+        Err(Error::RequestTimeOut)
+    ));
+    kite.place_order_fire_and_forget(&req, Duration::from_millis(50))
+        .await?;
+
+    // Validation errors aren't swallowed
+    let invalid = PlaceOrderRequest {
+        tag: Some("not alphanumeric!".into()),
+        ..req
+    };
+    assert!(matches!(
+        kite.place_order_fire_and_forget(&invalid, Duration::from_millis(50))
+            .await,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_place_order_verified() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_place_order().await;
+    mock.mount_order_history("151220000000000").await;
+
+    let order = mock
+        .kite()
+        .place_order_verified(&market_order(Variety::Regular))
+        .await?;
+
+    assert_eq!(order.order_id, "151220000000000");
+    assert_eq!(order.status, OrderStatus::Rejected);
+    assert!(
+        order
+            .status_message_raw
+            .is_some_and(|m| m.starts_with("RMS:Margin Exceeds"))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_order() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_order_history("151220000000000").await;
+    mock.mount_json(
+        "GET",
+        "/orders/100000000000000",
+        200,
+        r#"{"status": "success", "data": []}"#,
+    )
+    .await;
+
+    // The history goes through PUT ORDER REQ RECEIVED and VALIDATION PENDING first
+    let order = mock
+        .kite()
+        .get_order(&OrderId::from("151220000000000"))
+        .await?;
+    assert!(order.is_some_and(|o| o.status == OrderStatus::Rejected));
+
+    assert!(
+        mock.kite()
+            .get_order(&OrderId::from("100000000000000"))
+            .await?
+            .is_none()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_order_history() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_order_history("151220000000000").await;
+
+    let history = mock
+        .kite()
+        .get_order_history(&OrderId::from("151220000000000"))
+        .await?;
+    let statuses: Vec<_> = history.iter().map(|o| o.status.clone()).collect();
+    assert_eq!(
+        statuses,
+        [
+            OrderStatus::PutOrderReqReceived,
+            OrderStatus::ValidationPending,
+            OrderStatus::Rejected,
+        ]
+    );
+    assert!(history.iter().all(|o| o.order_id == "151220000000000"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_trades() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_trades().await;
+
+    let trades = mock.kite().get_trades().await?;
+
+    let summary: Vec<_> = trades
+        .iter()
+        .map(|t| (t.order_id.as_str(), t.exchange, t.product, t.quantity))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("200000000000000", Exchange::NSE, Product::CNC, 1),
+            ("500000000000000", Exchange::CDS, Product::MIS, 1),
+        ]
+    );
+    assert_eq!(trades[1].trading_symbol, "USDINR21JUNFUT");
+    assert_eq!(trades[1].average_price, 72.755);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_order_trades() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_json(
+        "GET",
+        "/orders/200000000000000/trades",
+        200,
+        fixtures::TRADES,
+    )
+    .await;
+
+    let trades = mock
+        .kite()
+        .get_order_trades(&OrderId::from("200000000000000"))
+        .await?;
+
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].order_id, "200000000000000");
+    assert_eq!(trades[0].trade_id, "10000000");
+    assert_eq!(trades[1].trading_symbol, "USDINR21JUNFUT");
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url.path(), "/orders/200000000000000/trades");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_modify_order_routing() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    for variety in ["amo", "co"] {
+        mock.mount_json(
+            "PUT",
+            &format!("/orders/{variety}/151220000000000"),
+            200,
+            r#"{"status": "success", "data": {"order_id": "151220000000000"}}"#,
+        )
+        .await;
+    }
+    let kite = mock.kite();
+
+    let mut req = ModifyOrderRequest {
+        order_type: None,
+        quantity: Some(5),
+        price: Some(1500.5),
+        trigger_price: None,
+        disclosed_quantity: None,
+        validity: None,
+    };
+    kite.modify_order(&OrderId::from("151220000000000"), &Variety::AMO, &req)
+        .await?;
+
+    // Rejected before reaching Kite
+    for variety in [Variety::CO, Variety::Auction, Variety::IceBerg] {
+        let res = kite
+            .modify_order(&OrderId::from("151220000000000"), &variety, &req)
+            .await;
+        assert!(matches!(
+            res,
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+    }
+
+    req.quantity = None;
+    kite.modify_order(&OrderId::from("151220000000000"), &Variety::CO, &req)
+        .await?;
+
+    let requests: Vec<_> = mock
+        .server()
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| {
+            (
+                r.url.path().to_owned(),
+                String::from_utf8_lossy(&r.body).into_owned(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        requests,
+        [
+            (
+                "/orders/amo/151220000000000".to_owned(),
+                "quantity=5&price=1500.5".to_owned()
+            ),
+            (
+                "/orders/co/151220000000000".to_owned(),
+                "price=1500.5".to_owned()
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_cover_order_leg() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_json(
+        "DELETE",
+        "/orders/co/151220000000001",
+        200,
+        r#"{"status": "success", "data": {"order_id": "151220000000001"}}"#,
+    )
+    .await;
+
+    mock.kite()
+        .cancel_cover_order_leg(
+            &OrderId::from("151220000000001"),
+            &OrderId::from("151220000000000"),
+        )
+        .await?;
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    assert_eq!(requests[0].url.path(), "/orders/co/151220000000001");
+    assert_eq!(
+        requests[0].url.query(),
+        Some("parent_order_id=151220000000000")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_exit_cover_order() -> Result<(), Box<dyn std::error::Error>> {
+    let orders: serde_json::Value = serde_json::from_str(fixtures::ORDERS)?;
+    let order = |order_id: &str, parent_order_id: Option<&str>, status: &str| {
+        let mut order = orders["data"][0].clone();
+        order["order_id"] = order_id.into();
+        order["parent_order_id"] = parent_order_id.into();
+        order["status"] = status.into();
+        order["variety"] = "co".into();
+        order
+    };
+    let body = serde_json::json!({
+        "status": "success",
+        "data": [
+            order("100", None, "COMPLETE"),
+            order("101", Some("100"), "TRIGGER PENDING"),
+            order("200", None, "COMPLETE"),
+            order("201", Some("200"), "CANCELLED"),
+        ]
+    });
+
+    let mock = MockKite::start().await;
+    mock.mount_json("GET", "/orders", 200, &body.to_string())
+        .await;
+    mock.mount_json(
+        "DELETE",
+        "/orders/co/101",
+        200,
+        r#"{"status": "success", "data": {"order_id": "101"}}"#,
+    )
+    .await;
+
+    mock.kite().exit_cover_order(&OrderId::from("100")).await?;
+    assert!(matches!(
+        mock.kite().exit_cover_order(&OrderId::from("200")).await,
+        Err(Error::KiteError(KiteError::OrderException(_)))
+    ));
+
+    let cancelled: Vec<_> = mock
+        .server()
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| r.method.as_str() == "DELETE")
+        .map(|r| r.url.to_string())
+        .collect();
+    assert_eq!(cancelled.len(), 1);
+    assert!(cancelled[0].ends_with("/orders/co/101?parent_order_id=100"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_modify_iceberg_order() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_json(
+        "PUT",
+        "/orders/iceberg/151220000000000",
+        200,
+        r#"{"status": "success", "data": {"order_id": "151220000000000"}}"#,
+    )
+    .await;
+
+    let mut req = ModifyIcebergOrderRequest {
+        order_type: None,
+        quantity: Some(400),
+        price: None,
+        trigger_price: None,
+        disclosed_quantity: None,
+        validity: None,
+    };
+    mock.kite()
+        .modify_iceberg_order(&OrderId::from("151220000000000"), 4, &req)
+        .await?;
+
+    // Rejected before reaching Kite
+    req.quantity = Some(401);
+    let res = mock
+        .kite()
+        .modify_iceberg_order(&OrderId::from("151220000000000"), 4, &req)
+        .await;
+    assert!(matches!(
+        res,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(String::from_utf8_lossy(&requests[0].body), "quantity=400");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_token_exception_without_body() {
+    let mock = MockKite::start().await;
+    mock.mount_json("GET", "/orders", 403, "Forbidden").await;
+
+    let res = mock.kite().get_orders().await;
+    assert!(matches!(
+        res,
+        Err(Error::KiteError(KiteError::TokenException(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_place_order_idempotent() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_place_order().await;
+    let orders = fixtures::ORDERS.replacen(r#""tag": null"#, r#""tag": "retry1""#, 1);
+    mock.mount_json("GET", "/orders", 200, &orders).await;
+    let kite = mock.kite();
+    let untagged = PlaceOrderRequest {
+        tag: None,
+        ..market_order(Variety::Regular)
+    };
+
+    // A new key is placed right away
+    let placed = kite.place_order_idempotent(&untagged).await?;
+    assert!(!placed.already_placed);
+    assert_eq!(placed.order_id, "151220000000000");
+    let key = placed.idempotency_key.ok_or("no idempotency key")?;
+    assert!(key.starts_with("ik"));
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    let body = String::from_utf8_lossy(&requests[0].body);
+    assert!(body.ends_with(&format!("&tag={key}")), "{body}");
+
+    // A retry finds the order placed by the first attempt
+    let retry = PlaceOrderRequest {
+        idempotency_key: Some("retry1".into()),
+        ..untagged.clone()
+    };
+    let placed = kite.place_order_idempotent(&retry).await?;
+    assert!(placed.already_placed);
+    assert_eq!(placed.order_id, "100000000000000");
+
+    let fresh = PlaceOrderRequest {
+        idempotency_key: Some("retry2".into()),
+        ..untagged.clone()
+    };
+    assert!(!kite.place_order_idempotent(&fresh).await?.already_placed);
+
+    let methods: Vec<_> = mock
+        .server()
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.method.to_string())
+        .collect();
+    assert_eq!(methods, ["POST", "GET", "GET", "POST"]);
+
+    // The key is sent as the tag, both can't be set
+    let both = PlaceOrderRequest {
+        idempotency_key: Some("retry3".into()),
+        ..market_order(Variety::Regular)
+    };
+    assert!(matches!(
+        kite.place_order_idempotent(&both).await,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    // Nor can the key replace the default order tag
+    let tagged_kite = mock
+        .kite()
+        .with_default_order_tag("strategy1".to_string())?;
+    for res in [
+        tagged_kite.place_order_idempotent(&untagged).await,
+        tagged_kite.place_order_poll(&fresh).await,
+    ] {
+        assert!(matches!(
+            res,
+            Err(Error::KiteError(KiteError::InputException(_)))
+        ));
+    }
+    assert_eq!(
+        mock.server()
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .len(),
+        4
+    );
+
+    Ok(())
+}
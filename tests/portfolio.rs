@@ -0,0 +1,203 @@
+use kite_connect::{
+    Error, KiteError,
+    orders::{Exchange, Product},
+    portfolio::{ConvertPositionReq, PositionType, enrich_holdings_with_history},
+    test_utils::MockKite,
+};
+
+#[tokio::test]
+async fn test_get_holdings() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_holdings().await;
+
+    let holdings = mock.kite().get_holdings().await?;
+    assert!(!holdings.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_positions() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_positions().await;
+
+    let positions = mock.kite().get_positions().await?;
+    assert!(!positions.net.is_empty());
+
+    Ok(())
+}
+
+fn convert_req() -> ConvertPositionReq {
+    ConvertPositionReq {
+        trading_symbol: "INFY".to_string(),
+        exchange: Exchange::NSE,
+        transaction_type: kite_connect::orders::TransactionType::Buy,
+        position_type: PositionType::Day,
+        quantity: 1,
+        old_product: Product::MIS,
+        new_product: Product::CNC,
+    }
+}
+
+#[tokio::test]
+async fn test_convert_position() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_json(
+        "PUT",
+        "/portfolio/positions",
+        200,
+        r#"{"status": "success", "data": true}"#,
+    )
+    .await;
+
+    assert!(mock.kite().convert_position(&convert_req()).await?);
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    assert_eq!(
+        String::from_utf8_lossy(&requests[0].body),
+        "tradingsymbol=INFY&exchange=NSE&transaction_type=BUY&position_type=day&quantity=1&old_product=MIS&new_product=CNC"
+    );
+
+    let mut req = convert_req();
+    req.position_type = PositionType::OverNight;
+    assert_eq!(
+        serde_urlencoded::to_string(&req)?,
+        "tradingsymbol=INFY&exchange=NSE&transaction_type=BUY&position_type=overnight&quantity=1&old_product=MIS&new_product=CNC"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_convert_position_responses() -> Result<(), Box<dyn std::error::Error>> {
+    for body in [
+        r#"{"status": "success", "data": {}}"#,
+        r#"{"status": "success", "data": null}"#,
+        r#"{"status": "success"}"#,
+    ] {
+        let mock = MockKite::start().await;
+        mock.mount_json("PUT", "/portfolio/positions", 200, body)
+            .await;
+        assert!(
+            mock.kite().convert_position(&convert_req()).await?,
+            "{body}"
+        );
+    }
+
+    let mock = MockKite::start().await;
+    mock.mount_json(
+        "PUT",
+        "/portfolio/positions",
+        400,
+        r#"{"status": "error", "message": "Position not found", "error_type": "InputException"}"#,
+    )
+    .await;
+    assert!(matches!(
+        mock.kite().convert_position(&convert_req()).await,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_convert_positions_matching() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_positions().await;
+    mock.mount_json(
+        "PUT",
+        "/portfolio/positions",
+        200,
+        r#"{"status": "success", "data": true}"#,
+    )
+    .await;
+
+    let results = mock
+        .kite()
+        .convert_positions_matching(Product::NRML, Product::MIS)
+        .await?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0.trading_symbol, "LEADMINI17DECFUT");
+    assert!(matches!(results[0].1, Ok(true)));
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    let bodies: Vec<_> = requests
+        .iter()
+        .filter(|r| r.method.as_str() == "PUT")
+        .map(|r| String::from_utf8_lossy(&r.body).into_owned())
+        .collect();
+    assert_eq!(
+        bodies,
+        [
+            "tradingsymbol=LEADMINI17DECFUT&exchange=MCX&transaction_type=BUY&position_type=day&quantity=1&old_product=NRML&new_product=MIS"
+        ]
+    );
+
+    let results = mock
+        .kite()
+        .convert_positions_matching(Product::CNC, Product::MIS)
+        .await?;
+    assert!(results.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_portfolio_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_holdings().await;
+    mock.mount_positions().await;
+    mock.mount_funds().await;
+
+    let snapshot = mock.kite().portfolio_snapshot().await?;
+    assert!(!snapshot.holdings.is_empty());
+    assert!(!snapshot.positions.net.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_enrich_holdings_with_history() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_holdings().await;
+    mock.mount_json(
+        "GET",
+        "/instruments/historical/263681/day",
+        200,
+        r#"{"status": "success", "data": {"candles": [
+            ["2025-01-15T00:00:00+0530", 340, 380, 335, 350, 1000],
+            ["2025-01-16T00:00:00+0530", 350, 360, 320, 352.35, 1200]
+        ]}}"#,
+    )
+    .await;
+    mock.mount_json(
+        "GET",
+        "/instruments/historical/128028676/day",
+        400,
+        r#"{"status": "error", "message": "invalid token", "error_type": "InputException"}"#,
+    )
+    .await;
+
+    let kite = mock.kite();
+    let holdings = kite.get_holdings().await?;
+    let enriched = enrich_holdings_with_history(&kite, &holdings, 365).await;
+    assert_eq!(enriched.len(), 2);
+
+    // AARON at 352.95
+    let history = enriched[0].history.as_ref().map_err(|e| e.to_string())?;
+    assert_eq!(enriched[0].holding.trading_symbol, "AARON");
+    assert_eq!(history.candles.len(), 2);
+    assert_eq!(history.high, Some(380.0));
+    assert_eq!(history.low, Some(320.0));
+    let distance = history.distance_from_high.unwrap_or_default();
+    assert!((distance - (380.0 - 352.95) / 380.0 * 100.0).abs() < 1e-9);
+
+    // The failing instrument doesn't abort the others
+    assert_eq!(enriched[1].holding.trading_symbol, "SBIN");
+    assert!(matches!(
+        enriched[1].history,
+        Err(Error::KiteError(KiteError::InputException(_)))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use kite_connect::{
+    Response,
+    orders::Exchange,
+    quotes::{Quote, QuoteKey},
+    test_utils::{MockKite, fixtures},
+};
+
+#[tokio::test]
+async fn test_get_mixed_quotes() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_quote().await;
+    mock.mount_quote_ltp().await;
+
+    let full = [QuoteKey::symbol(Exchange::NSE, "INFY")];
+    let ltp = [
+        QuoteKey::symbol(Exchange::NSE, "INFY"),
+        QuoteKey::from(408065),
+    ];
+    let quotes = mock.kite().get_mixed_quotes(&full, &ltp).await?;
+
+    assert_eq!(quotes.full["NSE:INFY"].instrument_token, 408065);
+    assert_eq!(quotes.ltp["NSE:INFY"].last_price, 1074.35);
+
+    let requests = mock.server().received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 2);
+
+    let ltp = requests
+        .iter()
+        .find(|r| r.url.path() == "/quote/ltp")
+        .expect("LTP endpoint wasn't called");
+    assert_eq!(ltp.url.query(), Some("i=NSE%3AINFY&i=408065"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_mixed_quotes_skips_empty_sets() -> Result<(), Box<dyn std::error::Error>> {
+    let mock = MockKite::start().await;
+    mock.mount_json("GET", "/quote/ltp", 200, fixtures::QUOTE_LTP)
+        .await;
+
+    let quotes = mock
+        .kite()
+        .get_mixed_quotes(&[], &[QuoteKey::symbol(Exchange::NSE, "INFY")])
+        .await?;
+
+    assert!(quotes.full.is_empty());
+    assert_eq!(quotes.ltp.len(), 1);
+    assert_eq!(
+        mock.server()
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_market_quotes_ordered() -> Result<(), Box<dyn std::error::Error>> {
+    let quote = |instrument_token: u32, last_price: f64| {
+        serde_json::json!({
+            "instrument_token": instrument_token,
+            "timestamp": "2021-06-08 15:45:56",
+            "last_price": last_price,
+            "net_change": 0,
+            "ohlc": {"open": 0, "high": 0, "low": 0, "close": 0}
+        })
+    };
+    let body = serde_json::json!({
+        "status": "success",
+        "data": {
+            "NSE:INFY": quote(408065, 1412.95),
+            "NSE:SBIN": quote(779521, 410.0),
+            "NSE:TCS": quote(2953217, 3250.0),
+        }
+    });
+
+    let mock = MockKite::start().await;
+    mock.mount_json("GET", "/quote", 200, &body.to_string())
+        .await;
+
+    let keys = [
+        QuoteKey::symbol(Exchange::NSE, "SBIN"),
+        QuoteKey::from(408065),
+        QuoteKey::symbol(Exchange::NSE, "UNKNOWN"),
+        QuoteKey::symbol(Exchange::NSE, "TCS"),
+        QuoteKey::symbol(Exchange::NSE, "INFY"),
+    ];
+    let quotes = mock.kite().get_market_quotes_ordered(&keys).await?;
+
+    let order: Vec<_> = quotes
+        .iter()
+        .map(|(key, quote)| (key.as_str(), quote.instrument_token))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            ("NSE:SBIN", 779521),
+            ("408065", 408065),
+            ("NSE:TCS", 2953217),
+            ("NSE:INFY", 408065),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_has_depth() -> Result<(), Box<dyn std::error::Error>> {
+    let mut quotes = serde_json::from_str::<Response<std::collections::HashMap<String, Quote>>>(
+        fixtures::QUOTE,
+    )?
+    .into_result()?;
+    let quote = quotes.remove("NSE:INFY").ok_or("INFY is missing")?;
+
+    // A single sell level at 1412.95
+    assert!(quote.has_depth());
+
+    let mut empty = quote.clone();
+    for level in empty.depth.iter_mut().flat_map(|d| d.sell.iter_mut()) {
+        *level = kite_connect::quotes::Depth {
+            price: 0.0,
+            orders: 0,
+            quantity: 0,
+        };
+    }
+    assert!(!empty.has_depth());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_all_instruments_indices() -> Result<(), Box<dyn std::error::Error>> {
+    use kite_connect::quotes::{InstrumentExchange, Segment};
+    use wiremock::{Mock, ResponseTemplate, matchers};
+
+    let mock = MockKite::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/instruments"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            "instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+256265,1001,NIFTY 50,NIFTY 50,0,,0,0,0,EQ,INDICES,NSE-INDICES
+",
+        ))
+        .mount(mock.server())
+        .await;
+
+    let instruments = mock.kite().get_all_instruments().await?;
+    assert_eq!(instruments[0].exchange, Exchange::NSE);
+    assert_eq!(
+        instruments[1].exchange,
+        InstrumentExchange::Indices(Exchange::NSE)
+    );
+    assert_eq!(instruments[1].exchange.exchange(), Some(Exchange::NSE));
+    assert_eq!(instruments[1].segment, Segment::INDICES);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_all_instruments_cancelled() {
+    use kite_connect::Error;
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+    use wiremock::{Mock, ResponseTemplate, matchers};
+
+    let mock = MockKite::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/instruments"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3600)))
+        .mount(mock.server())
+        .await;
+
+    let token = CancellationToken::new();
+    let kite = mock.kite().with_cancellation_token(token.clone());
+    let cancel_once_sent = async {
+        while mock
+            .server()
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .is_empty()
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        token.cancel();
+    };
+
+    let (res, ()) = tokio::join!(kite.get_all_instruments(), cancel_once_sent);
+    assert!(matches!(res, Err(Error::Cancelled)));
+
+    // Later requests fail right away
+    assert!(matches!(
+        kite.get_exhchange_instruments(Exchange::NSE).await,
+        Err(Error::Cancelled)
+    ));
+}